@@ -0,0 +1,11 @@
+// The parts of the file manager that don't need a window: listing folders,
+// copying, naming, renaming, searching, all on top of a swappable filesystem (`Vfs`).
+pub mod memory;
+pub mod model;
+pub mod ops;
+pub mod rename;
+pub mod search;
+pub mod vfs;
+
+pub use memory::MemoryFs;
+pub use vfs::{LocalFs, Metadata, Vfs};
@@ -0,0 +1,494 @@
+// A filesystem that lives in memory, for tests that shouldn't touch the
+// disk. Paths are absolute; symlinks are followed only as the last
+// component of a path. Every write advances a fake clock by one second so
+// modification times are deterministic.
+use crate::vfs::{Metadata, Vfs};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+// How many links `metadata` follows before giving up, like ELOOP
+const MAX_LINKS: usize = 40;
+
+#[derive(Clone, Debug)]
+enum Node {
+    Dir {
+        modified: SystemTime,
+    },
+    File {
+        contents: Vec<u8>,
+        modified: SystemTime,
+    },
+    Symlink {
+        target: PathBuf,
+    },
+}
+
+struct State {
+    nodes: BTreeMap<PathBuf, Node>,
+    clock: SystemTime,
+}
+
+impl State {
+    fn tick(&mut self) -> SystemTime {
+        self.clock += Duration::from_secs(1);
+        self.clock
+    }
+}
+
+pub struct MemoryFs {
+    state: Mutex<State>,
+}
+
+impl Default for MemoryFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryFs {
+    // An empty filesystem holding only the root folder
+    pub fn new() -> Self {
+        let clock = SystemTime::UNIX_EPOCH;
+        let mut nodes = BTreeMap::new();
+        nodes.insert(PathBuf::from("/"), Node::Dir { modified: clock });
+        Self {
+            state: Mutex::new(State { nodes, clock }),
+        }
+    }
+
+    // Adds a file, creating any missing parent folders
+    pub fn with_file(self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> Self {
+        let path = normalize(path.as_ref());
+        self.create_parents(&path);
+        self.write(&path, &contents.into()).unwrap();
+        self
+    }
+
+    // Adds a folder, creating any missing parent folders
+    pub fn with_dir(self, path: impl AsRef<Path>) -> Self {
+        let path = normalize(path.as_ref());
+        self.create_parents(&path);
+        if !self.exists(&path) {
+            self.create_dir(&path).unwrap();
+        }
+        self
+    }
+
+    pub fn with_symlink(self, target: impl AsRef<Path>, link: impl AsRef<Path>) -> Self {
+        let link = normalize(link.as_ref());
+        self.create_parents(&link);
+        self.symlink(target.as_ref(), &link).unwrap();
+        self
+    }
+
+    // Every path in the filesystem, sorted, for comparing whole trees in tests
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.state.lock().unwrap().nodes.keys().cloned().collect()
+    }
+
+    fn create_parents(&self, path: &Path) {
+        let Some(parent) = path.parent() else {
+            return;
+        };
+        for ancestor in parent.ancestors().collect::<Vec<_>>().into_iter().rev() {
+            if !self.exists(ancestor) {
+                self.create_dir(ancestor).unwrap();
+            }
+        }
+    }
+
+    // Resolves a trailing symlink to the node it points at
+    fn resolve(&self, path: &Path) -> io::Result<PathBuf> {
+        let state = self.state.lock().unwrap();
+        let mut path = normalize(path);
+        for _ in 0..MAX_LINKS {
+            match state.nodes.get(&path) {
+                Some(Node::Symlink { target }) => {
+                    let base = path.parent().unwrap_or(Path::new("/"));
+                    path = normalize(&base.join(target));
+                }
+                Some(_) => return Ok(path),
+                None => return Err(not_found(&path)),
+            }
+        }
+        Err(io::Error::other("too many levels of symbolic links"))
+    }
+
+    fn node(&self, path: &Path) -> io::Result<Node> {
+        let path = normalize(path);
+        self.state
+            .lock()
+            .unwrap()
+            .nodes
+            .get(&path)
+            .cloned()
+            .ok_or_else(|| not_found(&path))
+    }
+
+    // The folder a new entry at `path` would go in must already exist
+    fn check_parent(state: &State, path: &Path) -> io::Result<()> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::AlreadyExists, "the root exists"))?;
+        match state.nodes.get(parent) {
+            Some(Node::Dir { .. }) => Ok(()),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("{} is not a folder", parent.display()),
+            )),
+            None => Err(not_found(parent)),
+        }
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} doesn't exist", path.display()),
+    )
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} already exists", path.display()),
+    )
+}
+
+// Makes `path` absolute and drops `.` and `..`
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => out.push(name),
+            Component::ParentDir => {
+                out.pop();
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn metadata_of(node: &Node) -> Metadata {
+    match node {
+        Node::Dir { modified } => Metadata {
+            is_dir: true,
+            is_symlink: false,
+            len: 0,
+            modified: Some(*modified),
+        },
+        Node::File { contents, modified } => Metadata {
+            is_dir: false,
+            is_symlink: false,
+            len: contents.len() as u64,
+            modified: Some(*modified),
+        },
+        Node::Symlink { target } => Metadata {
+            is_dir: false,
+            is_symlink: true,
+            len: target.as_os_str().len() as u64,
+            modified: None,
+        },
+    }
+}
+
+impl Vfs for MemoryFs {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let path = self.resolve(path)?;
+        self.node(&path).map(|node| metadata_of(&node))
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.node(path).map(|node| metadata_of(&node))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        let path = self.resolve(path)?;
+        let state = self.state.lock().unwrap();
+        if !matches!(state.nodes.get(&path), Some(Node::Dir { .. })) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("{} is not a folder", path.display()),
+            ));
+        }
+        Ok(state
+            .nodes
+            .keys()
+            .filter(|p| p.parent() == Some(&path))
+            .filter_map(|p| p.file_name().map(|n| n.to_os_string()))
+            .collect())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let path = self.resolve(path)?;
+        match self.node(&path)? {
+            Node::File { contents, .. } => Ok(contents),
+            _ => Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                format!("{} is a folder", path.display()),
+            )),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        // Writing through a link writes its target, like the real thing
+        let path = self.resolve(path).unwrap_or_else(|_| normalize(path));
+        let mut state = self.state.lock().unwrap();
+        Self::check_parent(&state, &path)?;
+        if let Some(Node::Dir { .. }) = state.nodes.get(&path) {
+            return Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                format!("{} is a folder", path.display()),
+            ));
+        }
+        let modified = state.tick();
+        state.nodes.insert(
+            path,
+            Node::File {
+                contents: contents.to_vec(),
+                modified,
+            },
+        );
+        Ok(())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut state = self.state.lock().unwrap();
+        if state.nodes.contains_key(&path) {
+            return Err(already_exists(&path));
+        }
+        Self::check_parent(&state, &path)?;
+        let modified = state.tick();
+        state.nodes.insert(path, Node::Dir { modified });
+        Ok(())
+    }
+
+    fn copy_file(&self, source: &Path, destination: &Path) -> io::Result<u64> {
+        let contents = self.read(source)?;
+        self.write(destination, &contents)?;
+        Ok(contents.len() as u64)
+    }
+
+    // Like rename(2): replaces a file at `to`, and an empty folder with a
+    // folder, but nothing else
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (normalize(from), normalize(to));
+        let mut state = self.state.lock().unwrap();
+        let node = state
+            .nodes
+            .get(&from)
+            .cloned()
+            .ok_or_else(|| not_found(&from))?;
+        if to.starts_with(&from) && to != from {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot move a folder into itself",
+            ));
+        }
+        Self::check_parent(&state, &to)?;
+        match (&node, state.nodes.get(&to)) {
+            (_, None) => {}
+            (Node::Dir { .. }, Some(Node::Dir { .. })) => {
+                if state.nodes.keys().any(|p| p.parent() == Some(&to)) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::DirectoryNotEmpty,
+                        format!("{} isn't empty", to.display()),
+                    ));
+                }
+            }
+            (Node::Dir { .. }, Some(_)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotADirectory,
+                    format!("{} is not a folder", to.display()),
+                ))
+            }
+            (_, Some(Node::Dir { .. })) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::IsADirectory,
+                    format!("{} is a folder", to.display()),
+                ))
+            }
+            (_, Some(_)) => {}
+        }
+        let moved: Vec<(PathBuf, Node)> = state
+            .nodes
+            .iter()
+            .filter(|(p, _)| p.starts_with(&from))
+            .map(|(p, n)| (p.clone(), n.clone()))
+            .collect();
+        for (path, _) in &moved {
+            state.nodes.remove(path);
+        }
+        for (path, node) in moved {
+            let relative = path.strip_prefix(&from).unwrap();
+            state.nodes.insert(to.join(relative), node);
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut state = self.state.lock().unwrap();
+        match state.nodes.get(&path) {
+            Some(Node::Dir { .. }) => Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                format!("{} is a folder", path.display()),
+            )),
+            Some(_) => {
+                state.nodes.remove(&path);
+                Ok(())
+            }
+            None => Err(not_found(&path)),
+        }
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut state = self.state.lock().unwrap();
+        match state.nodes.get(&path) {
+            Some(Node::Dir { .. }) if path != Path::new("/") => {
+                state.nodes.retain(|p, _| !p.starts_with(&path));
+                Ok(())
+            }
+            Some(Node::Dir { .. }) => Err(io::Error::other("cannot remove the root")),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("{} is not a folder", path.display()),
+            )),
+            None => Err(not_found(&path)),
+        }
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        match self.node(path)? {
+            Node::Symlink { target } => Ok(target),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a link", path.display()),
+            )),
+        }
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let link = normalize(link);
+        let mut state = self.state.lock().unwrap();
+        if state.nodes.contains_key(&link) {
+            return Err(already_exists(&link));
+        }
+        Self::check_parent(&state, &link)?;
+        state.nodes.insert(
+            link,
+            Node::Symlink {
+                target: target.to_path_buf(),
+            },
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_and_folders() {
+        let fs = MemoryFs::new().with_file("/a/b/c.txt", "hello");
+        assert!(fs.metadata(Path::new("/a/b")).unwrap().is_dir);
+        assert_eq!(fs.read(Path::new("/a/b/c.txt")).unwrap(), b"hello");
+        assert_eq!(fs.metadata(Path::new("/a/b/c.txt")).unwrap().len, 5);
+        assert_eq!(
+            fs.read_dir(Path::new("/a")).unwrap(),
+            vec![OsString::from("b")]
+        );
+        assert_eq!(
+            fs.create_dir(Path::new("/a")).unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(
+            fs.write(Path::new("/missing/x"), b"").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn clock_is_deterministic() {
+        let fs = MemoryFs::new().with_file("/a", "").with_file("/b", "");
+        let a = fs.metadata(Path::new("/a")).unwrap().modified.unwrap();
+        let b = fs.metadata(Path::new("/b")).unwrap().modified.unwrap();
+        assert_eq!(b.duration_since(a).unwrap(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn symlinks() {
+        let fs = MemoryFs::new()
+            .with_file("/data/file.txt", "x")
+            .with_symlink("data", "/link")
+            .with_symlink("/nowhere", "/dangling");
+        assert!(fs.metadata(Path::new("/link")).unwrap().is_dir);
+        assert!(fs.symlink_metadata(Path::new("/link")).unwrap().is_symlink);
+        assert_eq!(
+            fs.read_dir(Path::new("/link")).unwrap(),
+            vec![OsString::from("file.txt")]
+        );
+        assert!(fs.exists(Path::new("/dangling")));
+        assert!(fs.metadata(Path::new("/dangling")).is_err());
+
+        let fs = fs.with_symlink("/loop", "/loop");
+        assert!(fs.metadata(Path::new("/loop")).is_err());
+    }
+
+    #[test]
+    fn rename_moves_subtrees() {
+        let fs = MemoryFs::new()
+            .with_file("/a/one.txt", "1")
+            .with_file("/a/sub/two.txt", "2")
+            .with_dir("/b");
+        fs.rename(Path::new("/a"), Path::new("/b/a")).unwrap();
+        assert_eq!(
+            fs.paths(),
+            [
+                "/",
+                "/b",
+                "/b/a",
+                "/b/a/one.txt",
+                "/b/a/sub",
+                "/b/a/sub/two.txt"
+            ]
+            .map(PathBuf::from)
+        );
+        assert!(fs
+            .rename(Path::new("/b"), Path::new("/b/a/inside"))
+            .is_err());
+    }
+
+    #[test]
+    fn rename_replaces_only_files() {
+        let fs = MemoryFs::new()
+            .with_file("/x", "new")
+            .with_file("/y", "old")
+            .with_file("/full/z", "")
+            .with_dir("/empty")
+            .with_dir("/d");
+        fs.rename(Path::new("/x"), Path::new("/y")).unwrap();
+        assert_eq!(fs.read(Path::new("/y")).unwrap(), b"new");
+        assert!(fs.rename(Path::new("/y"), Path::new("/full")).is_err());
+        assert!(fs.rename(Path::new("/d"), Path::new("/full")).is_err());
+        fs.rename(Path::new("/d"), Path::new("/empty")).unwrap();
+    }
+
+    #[test]
+    fn removing() {
+        let fs = MemoryFs::new().with_file("/a/b/c", "").with_file("/ab", "");
+        assert!(fs.remove_file(Path::new("/a")).is_err());
+        fs.remove_dir_all(Path::new("/a")).unwrap();
+        // "/ab" only shares a prefix with "/a" and must survive
+        assert_eq!(fs.paths(), ["/", "/ab"].map(PathBuf::from));
+        assert!(fs.remove_dir_all(Path::new("/")).is_err());
+    }
+}
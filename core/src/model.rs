@@ -0,0 +1,200 @@
+// What a folder listing holds, independent of how it's drawn
+use crate::vfs::Vfs;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    // Follows symlinks so links to folders can be entered
+    pub is_dir: bool,
+    // Bytes for files, number of entries for folders
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+// Lists `dir` with folders first; entries that vanish while listing are
+// skipped
+pub fn list(fs: &dyn Vfs, dir: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for name in fs.read_dir(dir)? {
+        let path = dir.join(&name);
+        let Ok(meta) = fs.metadata(&path).or_else(|_| fs.symlink_metadata(&path)) else {
+            continue;
+        };
+        let size = if meta.is_dir {
+            fs.read_dir(&path).map(|e| e.len()).unwrap_or(0) as u64
+        } else {
+            meta.len
+        };
+        entries.push(Entry {
+            name: name.to_string_lossy().into_owned(),
+            is_dir: meta.is_dir,
+            size,
+            modified: meta.modified,
+        });
+    }
+    entries.sort_by_key(|e| !e.is_dir);
+    Ok(entries)
+}
+
+pub fn format_file_size(size: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+    const TB: u64 = GB * 1024;
+
+    if size < KB {
+        format!("{} bytes", size)
+    } else if size < MB {
+        format!("{:.2} KiB", size as f64 / KB as f64)
+    } else if size < GB {
+        format!("{:.2} MiB", size as f64 / MB as f64)
+    } else if size < TB {
+        format!("{:.2} GiB", size as f64 / GB as f64)
+    } else {
+        format!("{:.2} TiB", size as f64 / TB as f64)
+    }
+}
+
+// For right-aligned columns in a monospace font: "B" is padded to the width
+// of "KiB" so the numbers line up whatever the unit
+pub fn format_file_size_column(size: u64) -> String {
+    if size < 1024 {
+        format!("{} B  ", size)
+    } else {
+        format_file_size(size)
+    }
+}
+
+// "1,234,567 bytes"
+pub fn format_exact_size(size: u64) -> String {
+    let digits = size.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("{} {}", grouped, if size == 1 { "byte" } else { "bytes" })
+}
+
+// The same name can be spelled with different code points: "é" as one
+// character (NFC, what most systems write) or as "e" plus an accent (NFD,
+// what macOS writes). Names are compared by their NFC form so both spellings
+// count as the same name.
+pub fn same_name(a: &str, b: &str) -> bool {
+    a == b || a.nfc().eq(b.nfc())
+}
+
+// What names sort by: NFC, ignoring case
+pub fn name_sort_key(name: &str) -> String {
+    name.nfc().flat_map(char::to_lowercase).collect()
+}
+
+// Cuts characters out of the middle of `text` for "…" until `fits` accepts
+// it. A little more of the end is kept than of the start, so extensions
+// survive.
+pub fn truncate_middle(text: &str, fits: impl Fn(&str) -> bool) -> String {
+    if fits(text) {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let shortened = |keep: usize| {
+        let head = keep / 2;
+        let mut short: String = chars[..head].iter().collect();
+        short.push('…');
+        short.extend(&chars[chars.len() - (keep - head)..]);
+        short
+    };
+    // The most characters that can be kept
+    let (mut low, mut high) = (0, chars.len().saturating_sub(1));
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if fits(&shortened(mid)) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    shortened(low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocalFs;
+
+    #[test]
+    fn folders_come_first_with_item_counts() {
+        let dir = tempfile::tempdir().unwrap();
+        LocalFs
+            .write(&dir.path().join("file.txt"), b"12345")
+            .unwrap();
+        LocalFs.create_dir(&dir.path().join("folder")).unwrap();
+        LocalFs
+            .write(&dir.path().join("folder/inner"), b"")
+            .unwrap();
+
+        let entries = list(&LocalFs, dir.path()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "folder");
+        assert!(entries[0].is_dir);
+        assert_eq!(entries[0].size, 1);
+        assert_eq!(entries[1].name, "file.txt");
+        assert_eq!(entries[1].size, 5);
+    }
+
+    #[test]
+    fn missing_folder_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(list(&LocalFs, &dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(format_file_size(0), "0 bytes");
+        assert_eq!(format_file_size(1023), "1023 bytes");
+        assert_eq!(format_file_size(1536), "1.50 KiB");
+        assert_eq!(format_file_size(5 * 1024 * 1024), "5.00 MiB");
+        assert_eq!(format_file_size(3 << 40), "3.00 TiB");
+    }
+
+    #[test]
+    fn column_sizes_share_a_unit_width() {
+        assert_eq!(format_file_size_column(12), "12 B  ");
+        assert_eq!(format_file_size_column(1536), "1.50 KiB");
+    }
+
+    #[test]
+    fn exact_sizes() {
+        assert_eq!(format_exact_size(0), "0 bytes");
+        assert_eq!(format_exact_size(1), "1 byte");
+        assert_eq!(format_exact_size(999), "999 bytes");
+        assert_eq!(format_exact_size(1000), "1,000 bytes");
+        assert_eq!(format_exact_size(1234567), "1,234,567 bytes");
+    }
+
+    #[test]
+    fn normalized_names() {
+        let (composed, decomposed) = ("caf\u{e9}.txt", "cafe\u{301}.txt");
+        assert!(same_name(composed, decomposed));
+        assert!(!same_name(composed, "cafe.txt"));
+        assert_eq!(name_sort_key(decomposed), name_sort_key("CAF\u{c9}.TXT"));
+    }
+
+    #[test]
+    fn middle_truncation_keeps_both_ends() {
+        let fits = |max: usize| move |s: &str| s.chars().count() <= max;
+        assert_eq!(truncate_middle("short.txt", fits(20)), "short.txt");
+        assert_eq!(
+            truncate_middle("a_very_long_file_name.txt", fits(12)),
+            "a_ver…me.txt"
+        );
+        assert_eq!(truncate_middle("ünïcödé_nämé", fits(6)), "ün…ämé");
+        assert_eq!(truncate_middle("anything", fits(0)), "…");
+    }
+}
@@ -0,0 +1,359 @@
+// File operations that only need a `Vfs`; progress reporting and jobs are
+// left to the frontends
+use crate::vfs::Vfs;
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
+
+// Where `source` would land in `destination_dir`, refusing to overwrite
+// anything or to put a folder inside itself
+fn destination_in(fs: &dyn Vfs, source: &Path, destination_dir: &Path) -> io::Result<PathBuf> {
+    let name = source
+        .file_name()
+        .ok_or_else(|| io::Error::other("cannot copy a filesystem root"))?;
+    let destination = destination_dir.join(name);
+    if fs.exists(&destination) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", destination.display()),
+        ));
+    }
+    if destination.starts_with(source) {
+        return Err(io::Error::other("cannot copy a folder into itself"));
+    }
+    Ok(destination)
+}
+
+// Copies a file or folder tree into `destination_dir`, keeping its name and
+// preserving symlinks as links. Returns the path of the new copy.
+pub fn copy_into(fs: &dyn Vfs, source: &Path, destination_dir: &Path) -> io::Result<PathBuf> {
+    let destination = destination_in(fs, source, destination_dir)?;
+    copy_recursive(fs, source, &destination)?;
+    Ok(destination)
+}
+
+// Moves a file or folder tree into `destination_dir`. When it can't simply
+// be renamed (e.g. it's on another filesystem) it's copied, and the original
+// removed only once the copy has succeeded.
+pub fn move_into(fs: &dyn Vfs, source: &Path, destination_dir: &Path) -> io::Result<PathBuf> {
+    let destination = destination_in(fs, source, destination_dir)?;
+    if fs.rename(source, &destination).is_ok() {
+        return Ok(destination);
+    }
+    let meta = fs.symlink_metadata(source)?;
+    copy_recursive(fs, source, &destination)?;
+    if meta.is_dir && !meta.is_symlink {
+        fs.remove_dir_all(source)?;
+    } else {
+        fs.remove_file(source)?;
+    }
+    Ok(destination)
+}
+
+pub fn copy_recursive(fs: &dyn Vfs, source: &Path, destination: &Path) -> io::Result<()> {
+    let meta = fs.symlink_metadata(source)?;
+    if meta.is_symlink {
+        match fs.symlink(&fs.read_link(source)?, destination) {
+            // Backends without links get a copy of the target instead
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                copy_followed(fs, source, destination)
+            }
+            result => result,
+        }
+    } else {
+        copy_followed(fs, source, destination)
+    }
+}
+
+fn copy_followed(fs: &dyn Vfs, source: &Path, destination: &Path) -> io::Result<()> {
+    if fs.metadata(source)?.is_dir {
+        fs.create_dir(destination)?;
+        for name in fs.read_dir(source)? {
+            copy_recursive(fs, &source.join(&name), &destination.join(&name))?;
+        }
+        Ok(())
+    } else {
+        fs.copy_file(source, destination).map(|_| ())
+    }
+}
+
+// Whether `dir` holds an item called `name`, including one whose name is
+// spelled with other code points (see `model::same_name`)
+pub fn name_taken(fs: &dyn Vfs, dir: &Path, name: &str) -> bool {
+    taken_names(fs, dir)(name)
+}
+
+// Lists `dir` once for checking many names against it
+fn taken_names<'a>(fs: &'a dyn Vfs, dir: &'a Path) -> impl Fn(&str) -> bool + 'a {
+    let names: HashSet<String> = fs
+        .read_dir(dir)
+        .unwrap_or_default()
+        .iter()
+        .map(|n| n.to_string_lossy().nfc().collect())
+        .collect();
+    move |name| fs.exists(&dir.join(name)) || names.contains(&name.nfc().collect::<String>())
+}
+
+// Appends " (2)", " (3)", … before the extension until the name is free
+pub fn unique_file_name(fs: &dyn Vfs, dir: &Path, name: &str) -> String {
+    let taken = taken_names(fs, dir);
+    if !taken(name) {
+        return name.to_string();
+    }
+    let (stem, ext) = match name.rfind('.') {
+        Some(i) if i > 0 => name.split_at(i),
+        _ => (name, ""),
+    };
+    (2..)
+        .map(|n| format!("{} ({}){}", stem, n, ext))
+        .find(|candidate| !taken(candidate))
+        .unwrap()
+}
+
+// Names a copy made next to its original, e.g. "notes (copy).txt" and then
+// "notes (copy 2).txt". In the patterns `{name}` is the name without its
+// extension, `{ext}` the extension with its dot (none for folders) and `{n}`
+// the number, from 2.
+pub fn copy_name(
+    fs: &dyn Vfs,
+    dir: &Path,
+    name: &str,
+    is_dir: bool,
+    first: &str,
+    numbered: &str,
+) -> String {
+    let (stem, ext) = match name.rfind('.') {
+        Some(i) if i > 0 && !is_dir => name.split_at(i),
+        _ => (name, ""),
+    };
+    let fill = |pattern: &str, n: usize| {
+        pattern
+            .replace("{name}", stem)
+            .replace("{ext}", ext)
+            .replace("{n}", &n.to_string())
+    };
+    let first = fill(first, 1);
+    if first.trim().is_empty() || first == name {
+        return unique_file_name(fs, dir, name);
+    }
+    let taken = taken_names(fs, dir);
+    if !taken(&first) {
+        return first;
+    }
+    // Without a number to count up, fall back to the usual " (2)"
+    if !numbered.contains("{n}") {
+        return unique_file_name(fs, dir, &first);
+    }
+    (2..)
+        .map(|n| fill(numbered, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap()
+}
+
+pub fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LocalFs, MemoryFs};
+
+    #[test]
+    fn copies_trees() {
+        let fs = MemoryFs::new()
+            .with_file("/source/sub/file.txt", "data")
+            .with_dir("/target");
+
+        let copy = copy_into(&fs, Path::new("/source"), Path::new("/target")).unwrap();
+        assert_eq!(copy, Path::new("/target/source"));
+        assert_eq!(fs.read(&copy.join("sub/file.txt")).unwrap(), b"data");
+        // The original is untouched
+        assert_eq!(fs.read(Path::new("/source/sub/file.txt")).unwrap(), b"data");
+    }
+
+    #[test]
+    fn refuses_to_overwrite() {
+        let fs = MemoryFs::new()
+            .with_file("/a/report.txt", "new")
+            .with_file("/b/report.txt", "old");
+        for result in [
+            copy_into(&fs, Path::new("/a/report.txt"), Path::new("/b")),
+            move_into(&fs, Path::new("/a/report.txt"), Path::new("/b")),
+        ] {
+            assert_eq!(result.unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        }
+        assert_eq!(fs.read(Path::new("/a/report.txt")).unwrap(), b"new");
+        assert_eq!(fs.read(Path::new("/b/report.txt")).unwrap(), b"old");
+    }
+
+    #[test]
+    fn refuses_to_copy_into_itself() {
+        let fs = MemoryFs::new().with_dir("/source/inner");
+        assert!(copy_into(&fs, Path::new("/source"), Path::new("/source")).is_err());
+        assert!(move_into(&fs, Path::new("/source"), Path::new("/source/inner")).is_err());
+        assert_eq!(fs.paths().len(), 3);
+    }
+
+    #[test]
+    fn moves_trees() {
+        let fs = MemoryFs::new()
+            .with_file("/source/sub/file.txt", "data")
+            .with_dir("/target");
+        let moved = move_into(&fs, Path::new("/source"), Path::new("/target")).unwrap();
+        assert_eq!(fs.read(&moved.join("sub/file.txt")).unwrap(), b"data");
+        assert!(!fs.exists(Path::new("/source")));
+    }
+
+    // A filesystem whose renames always fail, like moving across devices
+    struct NoRename(MemoryFs);
+
+    impl Vfs for NoRename {
+        fn metadata(&self, path: &Path) -> io::Result<crate::Metadata> {
+            self.0.metadata(path)
+        }
+        fn symlink_metadata(&self, path: &Path) -> io::Result<crate::Metadata> {
+            self.0.symlink_metadata(path)
+        }
+        fn read_dir(&self, path: &Path) -> io::Result<Vec<std::ffi::OsString>> {
+            self.0.read_dir(path)
+        }
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            self.0.read(path)
+        }
+        fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+            self.0.write(path, contents)
+        }
+        fn create_dir(&self, path: &Path) -> io::Result<()> {
+            self.0.create_dir(path)
+        }
+        fn copy_file(&self, source: &Path, destination: &Path) -> io::Result<u64> {
+            self.0.copy_file(source, destination)
+        }
+        fn rename(&self, _from: &Path, _to: &Path) -> io::Result<()> {
+            Err(io::ErrorKind::CrossesDevices.into())
+        }
+        fn remove_file(&self, path: &Path) -> io::Result<()> {
+            self.0.remove_file(path)
+        }
+        fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+            self.0.remove_dir_all(path)
+        }
+        fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+            self.0.read_link(path)
+        }
+        fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+            self.0.symlink(target, link)
+        }
+    }
+
+    #[test]
+    fn moves_by_copying_when_rename_fails() {
+        let fs = NoRename(
+            MemoryFs::new()
+                .with_file("/source/file.txt", "data")
+                .with_symlink("file.txt", "/source/link")
+                .with_dir("/target"),
+        );
+        move_into(&fs, Path::new("/source"), Path::new("/target")).unwrap();
+        assert_eq!(
+            fs.0.paths(),
+            [
+                "/",
+                "/target",
+                "/target/source",
+                "/target/source/file.txt",
+                "/target/source/link"
+            ]
+            .map(PathBuf::from)
+        );
+        assert_eq!(
+            fs.read_link(Path::new("/target/source/link")).unwrap(),
+            Path::new("file.txt")
+        );
+    }
+
+    #[test]
+    fn keeps_symlinks() {
+        let fs = MemoryFs::new().with_symlink("target.txt", "/link");
+        copy_recursive(&fs, Path::new("/link"), Path::new("/copy")).unwrap();
+        assert_eq!(
+            fs.read_link(Path::new("/copy")).unwrap(),
+            Path::new("target.txt")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_symlinks_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("link");
+        LocalFs.symlink(Path::new("target.txt"), &link).unwrap();
+        copy_recursive(&LocalFs, &link, &dir.path().join("copy")).unwrap();
+        assert_eq!(
+            LocalFs.read_link(&dir.path().join("copy")).unwrap(),
+            Path::new("target.txt")
+        );
+    }
+
+    #[test]
+    fn unique_names() {
+        let fs = MemoryFs::new().with_dir("/d");
+        let dir = Path::new("/d");
+        assert_eq!(unique_file_name(&fs, dir, "a.txt"), "a.txt");
+        let fs = fs.with_file("/d/a.txt", "").with_file("/d/a (2).txt", "");
+        assert_eq!(unique_file_name(&fs, dir, "a.txt"), "a (3).txt");
+        let fs = fs.with_file("/d/.hidden", "");
+        assert_eq!(unique_file_name(&fs, dir, ".hidden"), ".hidden (2)");
+        let fs = fs.with_symlink("/nowhere", "/d/dangling");
+        assert_eq!(unique_file_name(&fs, dir, "dangling"), "dangling (2)");
+    }
+
+    #[test]
+    fn differently_spelled_names_are_taken() {
+        let fs = MemoryFs::new().with_file("/d/cafe\u{301}.txt", "");
+        let dir = Path::new("/d");
+        assert!(name_taken(&fs, dir, "caf\u{e9}.txt"));
+        assert_eq!(
+            unique_file_name(&fs, dir, "caf\u{e9}.txt"),
+            "caf\u{e9} (2).txt"
+        );
+    }
+
+    #[test]
+    fn copy_names() {
+        let first = "{name} (copy){ext}";
+        let numbered = "{name} (copy {n}){ext}";
+        let fs = MemoryFs::new()
+            .with_file("/d/a.txt", "")
+            .with_dir("/d/v1.0");
+        let dir = Path::new("/d");
+        let name = |fs: &MemoryFs, name, is_dir| copy_name(fs, dir, name, is_dir, first, numbered);
+        assert_eq!(name(&fs, "a.txt", false), "a (copy).txt");
+        let fs = fs.with_file("/d/a (copy).txt", "");
+        assert_eq!(name(&fs, "a.txt", false), "a (copy 2).txt");
+        let fs = fs.with_file("/d/a (copy 2).txt", "");
+        assert_eq!(name(&fs, "a.txt", false), "a (copy 3).txt");
+        assert_eq!(name(&fs, "v1.0", true), "v1.0 (copy)");
+        assert_eq!(
+            copy_name(&fs, dir, "a.txt", false, "Copy of {name}{ext}", "{name}"),
+            "Copy of a.txt"
+        );
+        assert_eq!(
+            copy_name(&fs, dir, "a.txt", false, "{name}{ext}", numbered),
+            "a (2).txt"
+        );
+    }
+
+    #[test]
+    fn plurals() {
+        assert_eq!(plural(1, "file"), "1 file");
+        assert_eq!(plural(0, "file"), "0 files");
+        assert_eq!(plural(3, "item"), "3 items");
+    }
+}
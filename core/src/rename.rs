@@ -0,0 +1,160 @@
+// Working out new names from old ones. The rename dialog applies a rule to a
+// single item; the same rule can be run over a whole selection.
+use crate::model::{name_sort_key, same_name};
+use crate::ops::{name_taken, unique_file_name};
+use crate::vfs::Vfs;
+use regex::{NoExpand, RegexBuilder};
+use std::io;
+use std::path::Path;
+
+// Replaces every match of `find` in a name. With `regex` set, `find` is a
+// regular expression and `replace` can refer to its groups as `$1` or
+// `${name}`; otherwise both are taken literally.
+#[derive(Clone, Default)]
+pub struct Replace {
+    pub find: String,
+    pub replace: String,
+    pub regex: bool,
+    pub case_sensitive: bool,
+}
+
+impl Replace {
+    pub fn apply(&self, name: &str) -> Result<String, String> {
+        if self.find.is_empty() {
+            return Ok(name.to_string());
+        }
+        let pattern = if self.regex {
+            self.find.clone()
+        } else {
+            regex::escape(&self.find)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let renamed = if self.regex {
+            regex.replace_all(name, self.replace.as_str())
+        } else {
+            regex.replace_all(name, NoExpand(&self.replace))
+        };
+        Ok(renamed.into_owned())
+    }
+}
+
+// Whether `name` can be used for a single item in a folder
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        Err(String::from("The name is empty"))
+    } else if name == "." || name == ".." {
+        Err(format!("\"{}\" can't be used as a name", name))
+    } else if name.contains('/') || (cfg!(windows) && name.contains('\\')) {
+        Err(String::from("Names can't contain slashes"))
+    } else {
+        Ok(())
+    }
+}
+
+// Renames `from` to `to` without replacing anything already there. A change
+// of case or Unicode spelling only ("readme.md" to "README.md") goes through
+// a temporary name: on case-insensitive filesystems such as NTFS and APFS the
+// new name looks taken by the item itself, and a direct rename can quietly do
+// nothing.
+pub fn rename(fs: &dyn Vfs, from: &Path, to: &Path) -> io::Result<()> {
+    let (Some(dir), Some(from_name), Some(to_name)) =
+        (to.parent(), from.file_name(), to.file_name())
+    else {
+        return Err(io::ErrorKind::InvalidInput.into());
+    };
+    let (from_name, to_name) = (from_name.to_string_lossy(), to_name.to_string_lossy());
+    let case_only = from.parent() == Some(dir)
+        && from != to
+        && name_sort_key(&from_name) == name_sort_key(&to_name);
+    let taken = if case_only {
+        // Only an entry other than the item itself is in the way
+        fs.read_dir(dir)?
+            .iter()
+            .any(|n| *n != *from_name && same_name(&n.to_string_lossy(), &to_name))
+    } else {
+        name_taken(fs, dir, &to_name)
+    };
+    if taken {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("\"{}\" already exists", to.display()),
+        ));
+    }
+    if !case_only {
+        return fs.rename(from, to);
+    }
+    let temp = dir.join(unique_file_name(
+        fs,
+        dir,
+        &format!(".{}.renaming", from_name),
+    ));
+    fs.rename(from, &temp)?;
+    fs.rename(&temp, to).inspect_err(|_| {
+        // Put it back under its old name rather than leave the temporary one
+        let _ = fs.rename(&temp, from);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFs;
+
+    fn rule(find: &str, replace: &str, regex: bool) -> Replace {
+        Replace {
+            find: find.to_string(),
+            replace: replace.to_string(),
+            regex,
+            case_sensitive: true,
+        }
+    }
+
+    #[test]
+    fn regex_groups() {
+        let swap = rule(r"(\w+)-(\d+)", "${2}_$1", true);
+        assert_eq!(swap.apply("photo-042.jpg").unwrap(), "042_photo.jpg");
+        assert!(rule("(", "", true).apply("a").is_err());
+    }
+
+    #[test]
+    fn literal_and_case() {
+        assert_eq!(rule(".", "$1", false).apply("a.b.c").unwrap(), "a$1b$1c");
+        let mut upper = rule("JPG", "png", false);
+        assert_eq!(upper.apply("a.jpg").unwrap(), "a.jpg");
+        upper.case_sensitive = false;
+        assert_eq!(upper.apply("a.jpg").unwrap(), "a.png");
+    }
+
+    #[test]
+    fn case_only_renames() {
+        let fs = MemoryFs::new().with_file("/d/readme.md", "text");
+        rename(&fs, Path::new("/d/readme.md"), Path::new("/d/README.md")).unwrap();
+        assert_eq!(fs.read_dir(Path::new("/d")).unwrap(), ["README.md"]);
+        assert_eq!(fs.read(Path::new("/d/README.md")).unwrap(), b"text");
+
+        // Where case matters, another item can already have the new name
+        let fs = fs.with_file("/d/readme.md", "other");
+        let error = rename(&fs, Path::new("/d/readme.md"), Path::new("/d/README.md"));
+        assert_eq!(error.unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs.read(Path::new("/d/README.md")).unwrap(), b"text");
+        assert_eq!(fs.read(Path::new("/d/readme.md")).unwrap(), b"other");
+
+        let fs = MemoryFs::new().with_file("/d/cafe\u{301}", "");
+        rename(&fs, Path::new("/d/cafe\u{301}"), Path::new("/d/caf\u{e9}")).unwrap();
+        assert_eq!(fs.read_dir(Path::new("/d")).unwrap(), ["caf\u{e9}"]);
+        let fs = fs.with_file("/d/other", "");
+        let error = rename(&fs, Path::new("/d/other"), Path::new("/d/cafe\u{301}"));
+        assert_eq!(error.unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn names() {
+        assert!(check_name("notes.txt").is_ok());
+        assert!(check_name(" ").is_err());
+        assert!(check_name("..").is_err());
+        assert!(check_name("a/b").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+// Finding files by name. Patterns are shell-style wildcards: `*` matches any
+// run of characters and `?` matches exactly one. Several patterns can be
+// given separated by `;`.
+use crate::vfs::Vfs;
+use std::path::{Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
+
+pub fn matches_any(patterns: &str, name: &str, case_sensitive: bool) -> bool {
+    patterns
+        .split(';')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .any(|p| wildcard_match(p, name, case_sensitive))
+}
+
+pub fn wildcard_match(pattern: &str, name: &str, case_sensitive: bool) -> bool {
+    // In NFC, so names written on macOS match what's typed
+    let fold = |s: &str| -> Vec<char> {
+        if case_sensitive {
+            s.nfc().collect()
+        } else {
+            s.nfc().flat_map(char::to_lowercase).collect()
+        }
+    };
+    let pattern = fold(pattern);
+    let name = fold(name);
+
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // Let the last `*` swallow one more character and retry
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// Whether a scan should skip `path`, given `;`-separated wildcard patterns
+// matched against the whole path with `/` separators, e.g. `*/node_modules/*`
+// or `*.o`. Folders are matched with a trailing `/`, so a pattern for their
+// contents skips the folder itself too.
+pub fn path_excluded(patterns: &str, path: &Path, is_dir: bool) -> bool {
+    if patterns.trim().is_empty() {
+        return false;
+    }
+    let mut path = path.to_string_lossy().replace('\\', "/");
+    if is_dir {
+        path.push('/');
+    }
+    matches_any(
+        patterns,
+        &path,
+        cfg!(not(any(windows, target_os = "macos"))),
+    )
+}
+
+// Everything under `root` whose name matches, without following links to
+// folders (which could loop)
+pub fn find(fs: &dyn Vfs, root: &Path, patterns: &str, case_sensitive: bool) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(names) = fs.read_dir(&dir) else {
+            continue;
+        };
+        for name in names {
+            let path = dir.join(&name);
+            if matches_any(patterns, &name.to_string_lossy(), case_sensitive) {
+                found.push(path.clone());
+            }
+            if fs.symlink_metadata(&path).is_ok_and(|m| m.is_dir) {
+                pending.push(path);
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocalFs;
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match("*.txt", "notes.txt", true));
+        assert!(!wildcard_match("*.txt", "notes.txt.bak", true));
+        assert!(wildcard_match("a?c", "abc", true));
+        assert!(!wildcard_match("a?c", "ac", true));
+        assert!(wildcard_match("*a*b*", "xxaxxbxx", true));
+        assert!(wildcard_match("*", "", true));
+    }
+
+    #[test]
+    fn case_and_lists() {
+        assert!(!wildcard_match("*.JPG", "photo.jpg", true));
+        assert!(wildcard_match("*.JPG", "photo.jpg", false));
+        assert!(matches_any("*.png; *.jpg", "photo.jpg", true));
+        assert!(!matches_any("*.png;;", "photo.jpg", true));
+        assert!(!matches_any("", "photo.jpg", true));
+    }
+
+    #[test]
+    fn excluded_paths() {
+        let patterns = "*/node_modules/*; *.o";
+        assert!(path_excluded(
+            patterns,
+            Path::new("/src/app/node_modules"),
+            true
+        ));
+        assert!(path_excluded(
+            patterns,
+            Path::new("/src/app/node_modules/x/y.js"),
+            false
+        ));
+        assert!(!path_excluded(
+            patterns,
+            Path::new("/src/app/node_modules.txt"),
+            false
+        ));
+        assert!(path_excluded(patterns, Path::new("/src/main.o"), false));
+        assert!(!path_excluded(patterns, Path::new("/src/main.c"), false));
+        assert!(!path_excluded("", Path::new("/src/main.o"), false));
+    }
+
+    #[test]
+    fn finds_nested_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        LocalFs.create_dir(&dir.path().join("docs")).unwrap();
+        LocalFs.write(&dir.path().join("docs/a.md"), b"").unwrap();
+        LocalFs.write(&dir.path().join("b.md"), b"").unwrap();
+        LocalFs.write(&dir.path().join("c.txt"), b"").unwrap();
+
+        let found = find(&LocalFs, dir.path(), "*.md", true);
+        assert_eq!(
+            found,
+            vec![dir.path().join("b.md"), dir.path().join("docs/a.md")]
+        );
+    }
+}
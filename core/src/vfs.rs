@@ -0,0 +1,221 @@
+// The filesystem as the rest of the core sees it. `LocalFs` is the real disk;
+// other backends (remote shares, archives, tests) implement the same trait.
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metadata {
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+pub trait Vfs {
+    // Follows symlinks
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+    // Describes a symlink itself rather than its target
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata>;
+    // Names of the entries in a folder, in no particular order
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>>;
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+    // Returns the number of bytes copied
+    fn copy_file(&self, source: &Path, destination: &Path) -> io::Result<u64>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    fn read_link(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn symlink(&self, _target: &Path, _link: &Path) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    // Doesn't follow symlinks, so dangling links still count
+    fn exists(&self, path: &Path) -> bool {
+        self.symlink_metadata(path).is_ok()
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct LocalFs;
+
+impl From<fs::Metadata> for Metadata {
+    fn from(meta: fs::Metadata) -> Self {
+        Self {
+            is_dir: meta.is_dir(),
+            is_symlink: meta.file_type().is_symlink(),
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        }
+    }
+}
+
+// Windows refuses ordinary paths longer than 260 characters (MAX_PATH),
+// which deep trees such as node_modules easily pass. The extended-length
+// `\\?\` form has no such limit, so paths get it on their way to the system.
+// Elsewhere they're returned unchanged.
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    if cfg!(windows) {
+        if let Some(extended) = extended_length(path) {
+            return Cow::Owned(extended);
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+// The `\\?\` form of an absolute Windows path. The system doesn't tidy
+// these up, so paths with `.` or `..` in them are left alone.
+fn extended_length(path: &Path) -> Option<PathBuf> {
+    let text = path.to_str()?.replace('/', "\\");
+    if text.starts_with(r"\\?\") || text.starts_with(r"\\.\") {
+        return None;
+    }
+    if text.split('\\').any(|part| part == "." || part == "..") {
+        return None;
+    }
+    if let Some(share) = text.strip_prefix(r"\\") {
+        return Some(PathBuf::from(format!(r"\\?\UNC\{}", share)));
+    }
+    let drive = text.as_bytes();
+    if drive.len() >= 3 && drive[0].is_ascii_alphabetic() && &drive[1..3] == b":\\" {
+        return Some(PathBuf::from(format!(r"\\?\{}", text)));
+    }
+    // Relative, or only relative to the current drive
+    None
+}
+
+// The path as people write it, without the `\\?\` prefix
+pub fn display_path(path: &Path) -> Cow<'_, Path> {
+    let Some(text) = path.to_str() else {
+        return Cow::Borrowed(path);
+    };
+    if let Some(share) = text.strip_prefix(r"\\?\UNC\") {
+        Cow::Owned(PathBuf::from(format!(r"\\{}", share)))
+    } else if let Some(rest) = text.strip_prefix(r"\\?\") {
+        Cow::Owned(PathBuf::from(rest))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+impl Vfs for LocalFs {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        fs::metadata(long_path(path)).map(Metadata::from)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        fs::symlink_metadata(long_path(path)).map(Metadata::from)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        fs::read_dir(long_path(path))?
+            .map(|entry| entry.map(|e| e.file_name()))
+            .collect()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(long_path(path))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(long_path(path), contents)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir(long_path(path))
+    }
+
+    fn copy_file(&self, source: &Path, destination: &Path) -> io::Result<u64> {
+        fs::copy(long_path(source), long_path(destination))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(long_path(from), long_path(to))
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(long_path(path))
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(long_path(path))
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(long_path(path))
+    }
+
+    #[cfg(unix)]
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        std::os::unix::fs::symlink(target, link)
+    }
+
+    // Creating symlinks needs extra privileges on Windows
+    #[cfg(not(unix))]
+    fn symlink(&self, _target: &Path, _link: &Path) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extended_length_paths() {
+        let extend =
+            |p: &str| extended_length(Path::new(p)).map(|p| p.to_string_lossy().into_owned());
+        assert_eq!(extend(r"C:\Users\a").as_deref(), Some(r"\\?\C:\Users\a"));
+        assert_eq!(extend("C:/Users/a").as_deref(), Some(r"\\?\C:\Users\a"));
+        assert_eq!(
+            extend(r"\\server\share\x").as_deref(),
+            Some(r"\\?\UNC\server\share\x")
+        );
+        assert_eq!(extend(r"\\?\C:\x"), None);
+        assert_eq!(extend(r"C:\a\..\b"), None);
+        assert_eq!(extend(r"relative\x"), None);
+        assert_eq!(extend("/home/a"), None);
+
+        for path in [r"C:\Users\a", r"\\server\share\x", "/home/a"] {
+            let extended = extended_length(Path::new(path)).unwrap_or_else(|| PathBuf::from(path));
+            assert_eq!(display_path(&extended), Path::new(path));
+        }
+    }
+
+    #[test]
+    fn local_fs_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        LocalFs.write(&file, b"hello").unwrap();
+        assert_eq!(LocalFs.read(&file).unwrap(), b"hello");
+        assert_eq!(LocalFs.metadata(&file).unwrap().len, 5);
+        assert_eq!(
+            LocalFs.read_dir(dir.path()).unwrap(),
+            vec![OsString::from("a.txt")]
+        );
+
+        LocalFs.rename(&file, &dir.path().join("b.txt")).unwrap();
+        assert!(!LocalFs.exists(&file));
+        assert!(LocalFs.exists(&dir.path().join("b.txt")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dangling_symlink_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("link");
+        LocalFs.symlink(Path::new("missing"), &link).unwrap();
+        assert!(LocalFs.exists(&link));
+        assert!(LocalFs.metadata(&link).is_err());
+        assert!(LocalFs.symlink_metadata(&link).unwrap().is_symlink);
+    }
+}
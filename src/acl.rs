@@ -0,0 +1,215 @@
+// Access control lists, beyond the owner/group/other permission bits. On
+// Unix these are POSIX ACLs read and written through getfacl and setfacl; on
+// Windows the NTFS entries go through icacls. Errors are the tools' own
+// messages.
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+fn run(program: &str, args: &[&std::ffi::OsStr], input: Option<&str>) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Whether the tools are installed; asked once
+pub fn supported() -> bool {
+    static SUPPORTED: OnceLock<bool> = OnceLock::new();
+    *SUPPORTED.get_or_init(|| {
+        let program = if cfg!(windows) { "icacls" } else { "getfacl" };
+        let probe = if cfg!(windows) { "/?" } else { "--version" };
+        Command::new(program)
+            .arg(probe)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok()
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tag {
+    User,
+    Group,
+    // Caps what named users and groups are granted
+    Mask,
+    Other,
+}
+
+impl Tag {
+    pub fn label(self) -> &'static str {
+        match self {
+            Tag::User => "User",
+            Tag::Group => "Group",
+            Tag::Mask => "Mask",
+            Tag::Other => "Others",
+        }
+    }
+
+    fn keyword(self) -> &'static str {
+        match self {
+            Tag::User => "user",
+            Tag::Group => "group",
+            Tag::Mask => "mask",
+            Tag::Other => "other",
+        }
+    }
+}
+
+// One POSIX ACL entry, e.g. `user:bob:r-x` or `default:group::r--`
+#[derive(Clone, PartialEq)]
+pub struct Entry {
+    // Inherited by new items in a folder rather than applying to it
+    pub default: bool,
+    pub tag: Tag,
+    // User or group name; empty for the owner, owning group, mask and others
+    pub qualifier: String,
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Entry {
+    fn parse(line: &str) -> Option<Self> {
+        // Effective rights come after a tab as a comment
+        let line = line.split('#').next()?.trim();
+        let (default, line) = match line.strip_prefix("default:") {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let mut fields = line.split(':');
+        let tag = match fields.next()? {
+            "user" => Tag::User,
+            "group" => Tag::Group,
+            "mask" => Tag::Mask,
+            "other" => Tag::Other,
+            _ => return None,
+        };
+        let qualifier = fields.next()?.to_string();
+        let perms = fields.next()?.as_bytes();
+        Some(Self {
+            default,
+            tag,
+            qualifier,
+            read: perms.first() == Some(&b'r'),
+            write: perms.get(1) == Some(&b'w'),
+            execute: perms.get(2) == Some(&b'x'),
+        })
+    }
+
+    // Owner, owning group, mask and others are always there
+    pub fn is_base(&self) -> bool {
+        self.qualifier.is_empty()
+    }
+}
+
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}:{}:{}{}{}",
+            if self.default { "default:" } else { "" },
+            self.tag.keyword(),
+            self.qualifier,
+            if self.read { 'r' } else { '-' },
+            if self.write { 'w' } else { '-' },
+            if self.execute { 'x' } else { '-' },
+        )
+    }
+}
+
+pub fn read(path: &Path) -> Result<Vec<Entry>, String> {
+    let output = run(
+        "getfacl",
+        &[
+            "--omit-header".as_ref(),
+            "--absolute-names".as_ref(),
+            path.as_os_str(),
+        ],
+        None,
+    )?;
+    Ok(output.lines().filter_map(Entry::parse).collect())
+}
+
+// Replaces the whole ACL. The mask is recalculated unless one is given.
+pub fn write(path: &Path, entries: &[Entry]) -> Result<(), String> {
+    let spec: String = entries.iter().map(|e| format!("{}\n", e)).collect();
+    run(
+        "setfacl",
+        &["--set-file=-".as_ref(), path.as_os_str()],
+        Some(&spec),
+    )
+    .map(|_| ())
+}
+
+// One NTFS access control entry as icacls prints it, e.g.
+// `BUILTIN\Users:(OI)(CI)(RX)`
+#[derive(Clone, PartialEq)]
+pub struct Ace {
+    pub principal: String,
+    pub rights: String,
+    // Comes from a parent folder, so it can only be changed there
+    pub inherited: bool,
+}
+
+fn parse_icacls(output: &str, path: &Path) -> Vec<Ace> {
+    let path = path.to_string_lossy();
+    output
+        .lines()
+        .map(|line| line.strip_prefix(path.as_ref()).unwrap_or(line).trim())
+        .filter_map(|line| {
+            let (principal, rights) = line.split_once(":(")?;
+            let rights = format!("({}", rights);
+            Some(Ace {
+                principal: principal.to_string(),
+                // (I) marks inherited entries; (OI) and (CI) are about
+                // what children inherit
+                inherited: rights.contains("(I)"),
+                rights,
+            })
+        })
+        .collect()
+}
+
+pub fn read_ntfs(path: &Path) -> Result<Vec<Ace>, String> {
+    run("icacls", &[path.as_os_str()], None).map(|output| parse_icacls(&output, path))
+}
+
+// `rights` is an icacls level such as "R", "RX", "M" or "F"
+pub fn grant_ntfs(path: &Path, principal: &str, rights: &str) -> Result<(), String> {
+    let grant = format!("{}:({})", principal, rights);
+    run(
+        "icacls",
+        &[path.as_os_str(), "/grant".as_ref(), grant.as_ref()],
+        None,
+    )
+    .map(|_| ())
+}
+
+pub fn remove_ntfs(path: &Path, principal: &str) -> Result<(), String> {
+    run(
+        "icacls",
+        &[path.as_os_str(), "/remove".as_ref(), principal.as_ref()],
+        None,
+    )
+    .map(|_| ())
+}
@@ -0,0 +1,122 @@
+use crate::config::CustomAction;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub fn mime_type(path: &Path) -> String {
+    if path.is_dir() {
+        return String::from("inode/directory");
+    }
+    mime_guess::from_path(path)
+        .first()
+        .map(|m| m.essence_str().to_string())
+        .unwrap_or_else(|| String::from("application/octet-stream"))
+}
+
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(top_level) => mime.split('/').next() == Some(top_level),
+        None => pattern == "*" || pattern.eq_ignore_ascii_case(mime),
+    }
+}
+
+// Whether every target matches one of the MIME patterns (empty matches all)
+pub fn mime_filter_matches(mime_types: &[String], targets: &[PathBuf]) -> bool {
+    if mime_types.is_empty() {
+        return true;
+    }
+    targets.iter().all(|target| {
+        let mime = mime_type(target);
+        mime_types.iter().any(|p| mime_matches(p, &mime))
+    })
+}
+
+impl CustomAction {
+    pub fn applies_to(&self, targets: &[PathBuf]) -> bool {
+        mime_filter_matches(&self.mime_types, targets)
+    }
+
+    // The command runs once per target when it uses %f or %n, otherwise once
+    // for the whole selection
+    pub fn run(&self, targets: &[PathBuf], current_dir: &Path) -> std::io::Result<()> {
+        let per_file = self.command.contains("%f") || self.command.contains("%n");
+        if per_file {
+            for target in targets {
+                self.spawn(
+                    &expand(&self.command, Some(target), targets, current_dir),
+                    current_dir,
+                )?;
+            }
+            Ok(())
+        } else {
+            self.spawn(
+                &expand(&self.command, None, targets, current_dir),
+                current_dir,
+            )
+        }
+    }
+
+    fn spawn(&self, command_line: &str, current_dir: &Path) -> std::io::Result<()> {
+        tracing::info!("Running action \"{}\": {}", self.name, command_line);
+        shell_command(command_line)
+            .current_dir(current_dir)
+            .spawn()
+            .map(|_| ())
+    }
+}
+
+pub fn shell_command(command_line: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    let (shell, flag) = ("cmd", "/C");
+    #[cfg(not(target_os = "windows"))]
+    let (shell, flag) = ("sh", "-c");
+
+    let mut command = Command::new(shell);
+    command.arg(flag).arg(command_line);
+    command
+}
+
+pub fn expand(template: &str, file: Option<&Path>, all: &[PathBuf], current_dir: &Path) -> String {
+    let mut out = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('f') => out.push_str(
+                &file
+                    .map(|f| quote(&f.to_string_lossy()))
+                    .unwrap_or_default(),
+            ),
+            Some('n') => out.push_str(
+                &file
+                    .and_then(|f| f.file_name())
+                    .map(|n| quote(&n.to_string_lossy()))
+                    .unwrap_or_default(),
+            ),
+            Some('F') => {
+                let quoted: Vec<String> = all.iter().map(|p| quote(&p.to_string_lossy())).collect();
+                out.push_str(&quoted.join(" "));
+            }
+            Some('d') => out.push_str(&quote(&current_dir.to_string_lossy())),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+#[cfg(target_os = "windows")]
+pub fn quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('"', "\"\""))
+}
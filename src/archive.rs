@@ -0,0 +1,428 @@
+use sevenz_rust::{Password, SevenZArchiveEntry, SevenZMethod, SevenZWriter};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{AesMode, CompressionMethod, ZipArchive, ZipWriter};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Zip,
+    SevenZip,
+    Rar,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+    // A single compressed file rather than an archive
+    Gz,
+    Xz,
+    Zst,
+}
+
+impl Format {
+    // Tarballs come before the plain compressors so "x.tar.gz" isn't
+    // mistaken for a single gzipped file
+    const ALL: &'static [Format] = &[
+        Format::TarGz,
+        Format::TarXz,
+        Format::TarZst,
+        Format::Tar,
+        Format::Zip,
+        Format::SevenZip,
+        Format::Rar,
+        Format::Gz,
+        Format::Xz,
+        Format::Zst,
+    ];
+
+    // The formats new archives can be created in
+    pub fn writable() -> &'static [Format] {
+        &[Format::Zip, Format::SevenZip]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Format::Zip => "Zip",
+            Format::SevenZip => "7z",
+            Format::Rar => "RAR",
+            Format::Tar => "Tar",
+            Format::TarGz => "Tar (gzip)",
+            Format::TarXz => "Tar (xz)",
+            Format::TarZst => "Tar (zstd)",
+            Format::Gz => "gzip",
+            Format::Xz => "xz",
+            Format::Zst => "zstd",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        self.suffixes()[0]
+    }
+
+    fn suffixes(&self) -> &'static [&'static str] {
+        match self {
+            Format::Zip => &["zip"],
+            Format::SevenZip => &["7z"],
+            Format::Rar => &["rar"],
+            Format::Tar => &["tar"],
+            Format::TarGz => &["tar.gz", "tgz"],
+            Format::TarXz => &["tar.xz", "txz"],
+            Format::TarZst => &["tar.zst", "tzst"],
+            Format::Gz => &["gz"],
+            Format::Xz => &["xz"],
+            Format::Zst => &["zst"],
+        }
+    }
+
+    // Extracting these gives one file instead of a folder
+    pub fn is_single_file(&self) -> bool {
+        matches!(self, Format::Gz | Format::Xz | Format::Zst)
+    }
+
+    pub fn of(path: &Path) -> Option<Format> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        Format::ALL.iter().copied().find(|format| {
+            format
+                .suffixes()
+                .iter()
+                .any(|suffix| name.ends_with(&format!(".{}", suffix)))
+        })
+    }
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    Format::of(path).is_some()
+}
+
+// "photos.tar.gz" → "photos": the name to extract into
+pub fn extracted_name(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let suffix_len = Format::of(path)
+        .and_then(|format| {
+            let lower = name.to_lowercase();
+            format
+                .suffixes()
+                .iter()
+                .find(|suffix| lower.ends_with(&format!(".{}", suffix)))
+                .map(|suffix| suffix.len() + 1)
+        })
+        .unwrap_or(0);
+    name[..name.len() - suffix_len].to_string()
+}
+
+// Packs files and folders into a new zip archive. Folders are stored with
+// their own name as the top-level entry, like "Compress" in other file
+// managers.
+pub fn create_zip(targets: &[impl AsRef<Path>], destination: &Path) -> io::Result<()> {
+    create(targets, destination, Format::Zip, None)
+}
+
+// Entries are encrypted with AES-256 when a password is given
+pub fn create(
+    targets: &[impl AsRef<Path>],
+    destination: &Path,
+    format: Format,
+    password: Option<&str>,
+) -> io::Result<()> {
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(destination)?;
+    let result = match format {
+        Format::Zip => write_zip(file, targets, password),
+        Format::SevenZip => write_7z(file, targets, password),
+        _ => Err(io::Error::other(
+            "archives can only be created as zip or 7z",
+        )),
+    };
+    if result.is_err() {
+        // Don't leave a truncated archive behind
+        let _ = fs::remove_file(destination);
+    }
+    result
+}
+
+// Every file and folder below the targets, with the name it gets inside the
+// archive
+fn archive_entries(
+    targets: &[impl AsRef<Path>],
+) -> impl Iterator<Item = io::Result<(walkdir::DirEntry, String)>> + '_ {
+    targets.iter().flat_map(|target| {
+        let target = target.as_ref();
+        let base = target.parent().unwrap_or(Path::new("")).to_path_buf();
+        WalkDir::new(target).into_iter().map(move |entry| {
+            let entry = entry.map_err(io::Error::other)?;
+            let name = entry
+                .path()
+                .strip_prefix(&base)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .replace('\\', "/");
+            Ok((entry, name))
+        })
+    })
+}
+
+fn write_zip(
+    file: fs::File,
+    targets: &[impl AsRef<Path>],
+    password: Option<&str>,
+) -> io::Result<()> {
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let options = match password {
+        Some(password) => options.with_aes_encryption(AesMode::Aes256, password),
+        None => options,
+    };
+
+    for entry in archive_entries(targets) {
+        let (entry, name) = entry?;
+        let options = match entry.metadata().ok().and_then(|m| m.modified().ok()) {
+            Some(modified) => options.last_modified_time(zip_time(modified)),
+            None => options,
+        };
+        if entry.file_type().is_dir() {
+            zip.add_directory(name, options)?;
+        } else {
+            zip.start_file(name, options)?;
+            io::copy(&mut fs::File::open(entry.path())?, &mut zip)?;
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn write_7z(
+    file: fs::File,
+    targets: &[impl AsRef<Path>],
+    password: Option<&str>,
+) -> io::Result<()> {
+    let mut archive = SevenZWriter::new(file).map_err(io::Error::other)?;
+    if let Some(password) = password {
+        archive.set_content_methods(vec![
+            sevenz_rust::AesEncoderOptions::new(Password::from(password)).into(),
+            SevenZMethod::LZMA2.into(),
+        ]);
+        // Hide the file names too
+        archive.set_encrypt_header(true);
+    }
+
+    for entry in archive_entries(targets) {
+        let (entry, name) = entry?;
+        let archive_entry = SevenZArchiveEntry::from_path(entry.path(), name);
+        let result = if entry.file_type().is_dir() {
+            archive.push_archive_entry::<fs::File>(archive_entry, None)
+        } else {
+            archive.push_archive_entry(archive_entry, Some(fs::File::open(entry.path())?))
+        };
+        result.map_err(io::Error::other)?;
+    }
+
+    archive.finish()?;
+    Ok(())
+}
+
+// Whether extracting needs a password, so it can be asked for up front
+pub fn needs_password(path: &Path) -> io::Result<bool> {
+    match Format::of(path) {
+        Some(Format::Zip) => {
+            let mut zip = ZipArchive::new(fs::File::open(path)?)?;
+            for i in 0..zip.len() {
+                if zip.by_index_raw(i)?.encrypted() {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Some(Format::SevenZip) => match sevenz_rust::Archive::open(path) {
+            // The file list itself is encrypted
+            Err(sevenz_rust::Error::PasswordRequired) => Ok(true),
+            Err(e) => Err(io::Error::other(e)),
+            Ok(archive) => Ok(archive.folders.iter().any(|folder| {
+                folder
+                    .coders
+                    .iter()
+                    .any(|c| c.decompression_method_id() == SevenZMethod::ID_AES256SHA256)
+            })),
+        },
+        Some(Format::Rar) => match unrar::Archive::new(path).open_for_listing() {
+            Err(e) if e.code == unrar::error::Code::MissingPassword => Ok(true),
+            Err(e) => Err(io::Error::other(e)),
+            Ok(listing) => {
+                if listing.has_encrypted_headers() {
+                    return Ok(true);
+                }
+                for entry in listing {
+                    if entry.map_err(io::Error::other)?.is_encrypted() {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        },
+        _ => Ok(false),
+    }
+}
+
+fn wrong_password() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "wrong password")
+}
+
+// Extracts into `destination`: a new folder, or for single compressed files
+// the decompressed file itself. It must not exist yet and is removed again
+// when extraction fails.
+pub fn extract(archive: &Path, destination: &Path, password: Option<&str>) -> io::Result<()> {
+    let format = Format::of(archive).ok_or_else(|| io::Error::other("not a supported archive"))?;
+    if format.is_single_file() {
+        let mut out = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(destination)?;
+        let result = io::copy(
+            &mut decompressor(format, fs::File::open(archive)?)?,
+            &mut out,
+        );
+        if result.is_err() {
+            let _ = fs::remove_file(destination);
+        }
+        return result.map(|_| ());
+    }
+
+    fs::create_dir(destination)?;
+    let result = match format {
+        Format::Zip => extract_zip(archive, destination, password),
+        Format::SevenZip => extract_7z(archive, destination, password),
+        Format::Rar => extract_rar(archive, destination, password),
+        // unpack() refuses entries that would land outside the destination
+        _ => tar::Archive::new(decompressor(format, fs::File::open(archive)?)?).unpack(destination),
+    };
+    if result.is_err() {
+        let _ = fs::remove_dir_all(destination);
+    }
+    result
+}
+
+fn decompressor(format: Format, file: fs::File) -> io::Result<Box<dyn Read>> {
+    let file = io::BufReader::new(file);
+    Ok(match format {
+        Format::TarGz | Format::Gz => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        Format::TarXz | Format::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(file)),
+        Format::TarZst | Format::Zst => Box::new(
+            ruzstd::StreamingDecoder::new(file)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        ),
+        _ => Box::new(file),
+    })
+}
+
+// unrar only reads archives; multi-volume sets have to be opened at their
+// first part
+fn extract_rar(archive: &Path, destination: &Path, password: Option<&str>) -> io::Result<()> {
+    let rar = match password {
+        Some(password) => unrar::Archive::with_password(archive, password),
+        None => unrar::Archive::new(archive),
+    };
+    let map_error = |e: unrar::error::UnrarError| match e.code {
+        unrar::error::Code::MissingPassword | unrar::error::Code::BadPassword => wrong_password(),
+        _ => io::Error::other(e),
+    };
+    let mut rar = rar
+        .as_first_part()
+        .open_for_processing()
+        .map_err(map_error)?;
+    while let Some(header) = rar.read_header().map_err(map_error)? {
+        rar = if header.entry().is_file() {
+            header.extract_with_base(destination).map_err(map_error)?
+        } else {
+            header.skip().map_err(map_error)?
+        };
+    }
+    Ok(())
+}
+
+fn extract_zip(archive: &Path, destination: &Path, password: Option<&str>) -> io::Result<()> {
+    let mut zip = ZipArchive::new(fs::File::open(archive)?)?;
+    for i in 0..zip.len() {
+        let mut file = match password {
+            Some(password) => zip.by_index_decrypt(i, password.as_bytes()),
+            None => zip.by_index(i),
+        }
+        .map_err(|e| match e {
+            zip::result::ZipError::InvalidPassword => wrong_password(),
+            e => e.into(),
+        })?;
+        // Skips names that would land outside the destination (../, /etc)
+        let Some(name) = file.enclosed_name() else {
+            continue;
+        };
+        let path = destination.join(name);
+        if file.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut out = fs::File::create(&path)?;
+        // AES entries are checked at the end, so a bad password shows up here
+        io::copy(&mut file, &mut out).map_err(|e| match password {
+            Some(_) if e.kind() == io::ErrorKind::InvalidData => wrong_password(),
+            _ => e,
+        })?;
+        #[cfg(unix)]
+        if let Some(mode) = file.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o7777))?;
+        }
+    }
+    Ok(())
+}
+
+fn extract_7z(archive: &Path, destination: &Path, password: Option<&str>) -> io::Result<()> {
+    let password = password.map(Password::from).unwrap_or_else(Password::empty);
+    sevenz_rust::decompress_with_extract_fn_and_password(
+        fs::File::open(archive)?,
+        destination,
+        password,
+        |entry, reader, path| {
+            if !is_enclosed(entry.name()) {
+                return Ok(true);
+            }
+            sevenz_rust::default_entry_extract_fn(entry, reader, path)
+        },
+    )
+    .map_err(|e| match e {
+        sevenz_rust::Error::PasswordRequired | sevenz_rust::Error::MaybeBadPassword(_) => {
+            wrong_password()
+        }
+        e => io::Error::other(e),
+    })
+}
+
+// Same rule as zip's enclosed_name: only plain relative paths
+fn is_enclosed(name: &str) -> bool {
+    PathBuf::from(name)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+// Zip stores local DOS timestamps; anything before 1980 is clamped to it
+fn zip_time(time: std::time::SystemTime) -> zip::DateTime {
+    use chrono::{Datelike, Timelike};
+    let local: chrono::DateTime<chrono::Local> = time.into();
+    zip::DateTime::from_date_and_time(
+        local.year().clamp(1980, 2107) as u16,
+        local.month() as u8,
+        local.day() as u8,
+        local.hour() as u8,
+        local.minute() as u8,
+        local.second() as u8,
+    )
+    .unwrap_or_default()
+}
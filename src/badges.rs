@@ -0,0 +1,77 @@
+// Small markers shown after a name for attributes that change how a file
+// behaves: runnable, not writable, hard-linked, or hidden/system on Windows
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy)]
+pub struct Badge {
+    pub icon: &'static str,
+    pub tooltip: &'static str,
+}
+
+const EXECUTABLE: Badge = Badge {
+    icon: "⚙",
+    tooltip: "Executable: double-clicking asks whether to run it",
+};
+
+const READ_ONLY: Badge = Badge {
+    icon: "🔒",
+    tooltip: "Read-only: you don't have permission to change it",
+};
+
+const HARD_LINKED: Badge = Badge {
+    icon: "⛓",
+    tooltip: "Has other hard links: edits show up under every name",
+};
+
+pub fn badges(path: &Path, meta: &fs::Metadata) -> Vec<Badge> {
+    let mut badges = Vec::new();
+    if meta.is_file() && crate::launch::is_executable(path) {
+        badges.push(EXECUTABLE);
+    }
+    if is_read_only(path, meta) {
+        badges.push(READ_ONLY);
+    }
+    if meta.is_file() && crate::links::hard_link_count(meta).is_some_and(|n| n > 1) {
+        badges.push(HARD_LINKED);
+    }
+    windows_attributes(meta, &mut badges);
+    badges
+}
+
+// Going by the permission bits alone would miss files owned by someone else
+#[cfg(unix)]
+fn is_read_only(path: &Path, _meta: &fs::Metadata) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) != 0 }
+}
+
+#[cfg(not(unix))]
+fn is_read_only(_path: &Path, meta: &fs::Metadata) -> bool {
+    meta.permissions().readonly()
+}
+
+#[cfg(windows)]
+fn windows_attributes(meta: &fs::Metadata, badges: &mut Vec<Badge>) {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+    if meta.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0 {
+        badges.push(Badge {
+            icon: "H",
+            tooltip: "Hidden: Explorer doesn't show it unless told to",
+        });
+    }
+    if meta.file_attributes() & FILE_ATTRIBUTE_SYSTEM != 0 {
+        badges.push(Badge {
+            icon: "S",
+            tooltip: "System: used by Windows; changing it may break things",
+        });
+    }
+}
+
+#[cfg(not(windows))]
+fn windows_attributes(_meta: &fs::Metadata, _badges: &mut Vec<Badge>) {}
@@ -0,0 +1,115 @@
+// `file_manager --bench <path>` times each step of listing every folder under
+// `path`: reading the folder, looking up details on the metadata pool, and
+// sorting the rows, then prints a report. Steps run one after another rather
+// than overlapped as in the window, so each shows on its own. Runs against a
+// warm cache are the comparable ones; run twice.
+use crate::{browser, listing};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+// Folders listed in the slowest section
+const SLOWEST: usize = 5;
+
+struct Step {
+    name: String,
+    time: Duration,
+}
+
+pub fn run(root: &Path) -> Result<(), String> {
+    if !root.is_dir() {
+        return Err(format!("{} is not a folder", root.display()));
+    }
+    let mut steps: Vec<Step> = Vec::new();
+    let mut add = |name: &str, time: Duration| match steps.iter_mut().find(|s| s.name == name) {
+        Some(step) => step.time += time,
+        None => steps.push(Step {
+            name: name.to_string(),
+            time,
+        }),
+    };
+    let mut folders = 0;
+    let mut entries = 0;
+    let mut unreadable = 0;
+    let mut slowest: Vec<(Duration, PathBuf, usize)> = Vec::new();
+
+    let dirs = WalkDir::new(root)
+        .into_iter()
+        .flatten()
+        .filter(|e| e.file_type().is_dir());
+    for dir in dirs {
+        let dir = dir.path();
+        let start = Instant::now();
+        let listed = match listing::read(dir) {
+            Ok(listed) => listed,
+            Err(_) => {
+                unreadable += 1;
+                continue;
+            }
+        };
+        let read = start.elapsed();
+        add("read_dir", read);
+
+        let start = Instant::now();
+        let details = listing::resolve(dir, &listed);
+        let resolved = start.elapsed();
+        add("metadata", resolved);
+
+        let count = listed.len();
+        let mut total = read + resolved;
+        for (column, time) in browser::time_sorts(listed.into_iter().zip(details).collect()) {
+            add(&format!("sort by {}", column.to_lowercase()), time);
+            total += time;
+        }
+
+        folders += 1;
+        entries += count;
+        slowest.push((total, dir.to_path_buf(), count));
+        slowest.sort_by_key(|s| std::cmp::Reverse(s.0));
+        slowest.truncate(SLOWEST);
+    }
+
+    println!("Listing benchmark of {}", root.display());
+    println!(
+        "{} folders, {} entries{}",
+        folders,
+        entries,
+        if unreadable > 0 {
+            format!(", {} folders unreadable", unreadable)
+        } else {
+            String::new()
+        }
+    );
+    println!();
+    for step in &steps {
+        println!(
+            "  {:<18} {:>10}  {:>10}/entry",
+            step.name,
+            duration(step.time),
+            duration(step.time / entries.max(1) as u32)
+        );
+    }
+    let total: Duration = steps.iter().map(|s| s.time).sum();
+    println!("  {:<18} {:>10}", "total", duration(total));
+    if !slowest.is_empty() {
+        println!();
+        println!("Slowest folders:");
+        for (time, dir, count) in &slowest {
+            println!(
+                "  {:>10}  {} ({} entries)",
+                duration(*time),
+                dir.display(),
+                count
+            );
+        }
+    }
+    Ok(())
+}
+
+fn duration(time: Duration) -> String {
+    match time.as_secs_f64() {
+        s if s >= 1.0 => format!("{:.2} s", s),
+        s if s >= 0.001 => format!("{:.2} ms", s * 1e3),
+        s => format!("{:.2} µs", s * 1e6),
+    }
+}
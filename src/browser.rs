@@ -0,0 +1,6927 @@
+#[cfg(feature = "profiling")]
+use crate::profiler;
+use crate::{
+    actions, archive, badges, capabilities, clipboard, config, convert, dates, dnd, download,
+    drives, filters, folder_icons, gpg, history, icon_theme, iostats, iso, jobs, launch, links,
+    listing, locks, logging, network, notify, ops, owners, picker, plugins, processes, properties,
+    recent, recovery, repaint, resume, scripting, settings, share, shortcuts, split, taskbar,
+    templates, thumbnails, timestamps, toasts, trash, udisks, uri, user_dirs,
+};
+use capabilities::Capabilities;
+use clipboard::PastedContent;
+use config::{Config, CustomAction};
+use eframe::egui;
+use file_manager_core::model::{
+    format_exact_size, format_file_size, format_file_size_column, name_sort_key, truncate_middle,
+};
+use file_manager_core::ops::name_taken;
+use file_manager_core::rename::{self, Replace};
+use file_manager_core::{search, LocalFs};
+use folder_icons::FolderIcon;
+use jobs::Jobs;
+use links::LinkKind;
+use plugins::{ColumnValue, FsEntries, Plugin, PluginColumn, PreviewRenderer, VfsBackend};
+use properties::{BulkPropertiesDialog, PropertiesDialog};
+use repaint::Repaint;
+use scripting::{ScriptCommand, ScriptHost};
+use settings::SettingsWindow;
+use shortcuts::Shortcut;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use templates::Template;
+
+type SelectionCallback = Box<dyn FnMut(&[PathBuf])>;
+type OpenCallback = Box<dyn FnMut(&Path) -> bool>;
+
+pub struct FileBrowserWidget {
+    // Salts the ids of this browser's panels, windows and widgets, so that
+    // several can be shown in one app
+    id: egui::Id,
+    config: Config,
+    settings: SettingsWindow,
+    logs: logging::LogsWindow,
+    history: history::HistoryWindow,
+    processes: processes::ProcessesWindow,
+    io_stats: iostats::Monitor,
+    // Date column text, formatted as rows are drawn
+    dates: dates::DateLabels,
+    current_dir: PathBuf,
+    entries: Vec<FileEntry>,
+    selected: HashSet<PathBuf>,
+    // Always at least one; the active tab's entry is only brought up to date
+    // when switching away from it
+    tabs: Vec<Tab>,
+    active_tab: usize,
+    // The window this browser is shown in
+    viewport: egui::ViewportId,
+    // Tabs dragged out into windows of their own
+    windows: Vec<TabWindow>,
+    windows_opened: u64,
+    // Shown in one of those windows
+    detached: bool,
+    // The main window's folder as last saved for starting there next time
+    saved_location: PathBuf,
+    // Confirmations of what was just done, with Undo where it can be
+    toasts: toasts::Toasts,
+    // Whether the high-contrast visuals are in place, so switching them off
+    // puts egui's own back
+    high_contrast: bool,
+    // Debug → Profiler, in the main window only
+    #[cfg(feature = "profiling")]
+    profiler: profiler::ProfilerWindow,
+    // The folder dragged files are held over, to open it after a moment
+    spring: dnd::Spring,
+    // The tab and folder a drag of files started from
+    drag_origin: Option<(usize, PathBuf)>,
+    // A tab dropped outside this window, and where on the screen
+    torn_off: Option<(usize, Option<egui::Pos2>)>,
+    select_pattern: Option<SelectPatternDialog>,
+    paste_dialog: Option<PasteDialog>,
+    // Ctrl+V was pressed with text on the clipboard, see `handle_shortcuts`
+    text_pasted: bool,
+    // Items picked up with Edit → Copy, used by Paste Special
+    file_clipboard: Vec<PathBuf>,
+    // The clipboard files get moved rather than copied on paste
+    clipboard_cut: bool,
+    link_dialog: Option<LinkDialog>,
+    new_item_dialog: Option<NewItemDialog>,
+    rename_dialog: Option<RenameDialog>,
+    run_dialog: Option<RunDialog>,
+    download_dialog: Option<DownloadDialog>,
+    location_dialog: Option<LocationDialog>,
+    delete_confirm: Option<DeleteConfirm>,
+    trash_confirm: Option<TrashConfirm>,
+    // Item count and size for the sidebar, recounted after each job
+    trash_summary: Option<trash::Summary>,
+    trash_rx: Option<Receiver<trash::Summary>>,
+    // Showing a trash folder, where deleting is for good
+    in_trash: bool,
+    // The items of the trash folder being shown, by name
+    trashed: HashMap<String, trash::Trashed>,
+    restore_dialog: Option<RestoreDialog>,
+    // What was going on when the app last crashed
+    recovered: Option<recovery::Session>,
+    encrypt_dialog: Option<EncryptDialog>,
+    compress_dialog: Option<CompressDialog>,
+    // Archive waiting for its password before extraction
+    extract_password: Option<(PathBuf, String)>,
+    image_browser: Option<ImageBrowser>,
+    // A plugin's rendering of a file
+    preview: Option<PreviewWindow>,
+    fs_browser: Option<FsBrowser>,
+    split_dialog: Option<SplitDialog>,
+    convert_dialog: Option<ConvertDialog>,
+    timestamp_dialog: Option<TimestampDialog>,
+    // Drives listed in the sidebar, kept current by a watcher thread
+    devices: drives::Devices,
+    devices_rx: Receiver<drives::Devices>,
+    device_error: Option<String>,
+    device_task: Option<DeviceTask>,
+    unlock_dialog: Option<UnlockDialog>,
+    // Network browser, searching for as long as it's open
+    network: Option<Result<network::Discovery, String>>,
+    // Set when running as another app's open/save dialog
+    picker: Option<picker::Picker>,
+    // File name typed into a save picker
+    picker_name: String,
+    // Picked files that already exist, waiting for the user to confirm
+    replace_confirm: Option<ReplaceConfirm>,
+    symlink_confirm: Option<SymlinkConfirm>,
+    // Reported one at a time
+    partial_failures: Vec<PartialFailure>,
+    // Left behind by an earlier run that closed or crashed mid-transfer
+    interrupted: Vec<resume::Checkpoint>,
+    jobs: Jobs,
+    // Wakes the UI from background threads; nothing polls on a timer
+    repaint: Repaint,
+    properties: Vec<PropertiesDialog>,
+    // Properties of several items at once
+    bulk_properties: Vec<BulkPropertiesDialog>,
+    share_window: Option<ShareWindow>,
+    // Shortcut icons by icon name; None when the icon couldn't be loaded
+    icon_textures: HashMap<String, Option<egui::TextureHandle>>,
+    thumbnails: thumbnails::Thumbnails,
+    plugins: Vec<Plugin>,
+    plugin_manager_open: bool,
+    // Plugin column cells keyed by (column index, path), filled in as the
+    // background commands finish
+    plugin_cells: HashMap<(usize, PathBuf), String>,
+    plugin_cells_rx: Option<Receiver<ColumnValue>>,
+    // Set while the current folder is being listed
+    listing: Option<Receiver<listing::Loaded>>,
+    // Nothing is listed until the builder methods have picked where to start
+    unlisted: bool,
+    // Shows what matches this under the current folder instead of its
+    // contents
+    search: Option<String>,
+    // The search box
+    search_text: String,
+    search_scope: SearchScope,
+    // Paths to skip for this window's searches instead of the configured ones
+    scan_exclude: Option<String>,
+    // Patterns to select by among the entries still to come
+    pending_select: Vec<(String, bool)>,
+    // An item to select and scroll to once its folder is listed
+    reveal: Option<PathBuf>,
+    // recent:// is open, listing recently used files in place of the folder
+    showing_recent: bool,
+    sort: SortColumn,
+    sort_descending: bool,
+    filters: filters::Filters,
+    // Showing the drives overview instead of a folder
+    overview: bool,
+    // Nothing can be deleted, moved or pasted, for kiosks and demos
+    read_only: bool,
+    scripts: ScriptHost,
+    // What the script hooks last saw, to fire them only on changes
+    hooked_dir: Option<PathBuf>,
+    hooked_selection: HashSet<PathBuf>,
+    // Set by applications embedding the browser
+    on_selection_changed: Option<SelectionCallback>,
+    on_open: Option<OpenCallback>,
+}
+
+// What a tab shows; the folder is listed again on switching to it
+#[derive(Clone)]
+struct Tab {
+    dir: PathBuf,
+    selected: HashSet<PathBuf>,
+    overview: bool,
+    pinned: bool,
+}
+
+// A tab dragged out into a window of its own
+struct TabWindow {
+    id: egui::ViewportId,
+    browser: FileBrowserWidget,
+    // Where on the screen it was dropped
+    position: Option<egui::Pos2>,
+}
+
+// egui keys windows by their title, so a browser's are salted with its id to
+// keep two browsers from sharing a dialog's position and state
+fn salted_window(salt: egui::Id, title: impl Into<egui::WidgetText>) -> egui::Window<'static> {
+    let title = title.into();
+    let id = salt.with(title.text());
+    egui::Window::new(title).id(id)
+}
+
+fn tab_title(dir: &Path, overview: bool) -> String {
+    if overview {
+        return String::from("This Computer");
+    }
+    dir.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| dir.display().to_string())
+}
+
+struct SelectPatternDialog {
+    pattern: String,
+    case_sensitive: bool,
+    // Add to the current selection instead of replacing it
+    extend: bool,
+}
+
+struct FileEntry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    // Dates are formatted as rows are drawn rather than stored as text, as
+    // folders can hold millions of entries
+    modified: Option<std::time::SystemTime>,
+    created: Option<std::time::SystemTime>,
+    accessed: Option<std::time::SystemTime>,
+    owner: Option<owners::Ownership>,
+    badges: Box<[badges::Badge]>,
+    // Parsed .desktop / .lnk contents; boxed like the folder icon since few
+    // entries have one
+    shortcut: Option<Box<Shortcut>>,
+    // Custom icon and emblem of a folder
+    folder_icon: Option<Box<FolderIcon>>,
+    // Where an item in the trash came from, and when it was trashed
+    original: Option<PathBuf>,
+    deleted: Option<std::time::SystemTime>,
+    // False until the size, date and badges have been fetched
+    loaded: bool,
+}
+
+impl FileEntry {
+    // Until its details arrive
+    fn listed(listed: listing::Listed) -> Self {
+        Self {
+            name: listed.name,
+            is_dir: listed.is_dir,
+            size: 0,
+            modified: None,
+            created: None,
+            accessed: None,
+            owner: None,
+            badges: Box::default(),
+            shortcut: None,
+            folder_icon: None,
+            original: None,
+            deleted: None,
+            loaded: false,
+        }
+    }
+
+    fn apply(&mut self, details: listing::Details) {
+        self.is_dir = details.is_dir;
+        self.size = details.size;
+        self.modified = details.modified;
+        self.created = details.created;
+        self.accessed = details.accessed;
+        self.owner = details.owner;
+        self.badges = details.badges.into_boxed_slice();
+        self.shortcut = details.shortcut.map(Box::new);
+        self.folder_icon = details.folder_icon.map(Box::new);
+        self.loaded = true;
+    }
+
+    // Blank until the details arrive
+    fn modified_text(&self, dates: &mut dates::DateLabels) -> String {
+        match self.modified {
+            Some(time) => dates.label(time).to_string(),
+            None if self.loaded => String::from("Unknown"),
+            None => String::new(),
+        }
+    }
+}
+
+// Builds the rows a folder would show and times sorting them by each of the
+// default columns, the way the listing sorts; for --bench
+pub(crate) fn time_sorts(
+    entries: Vec<(listing::Listed, listing::Details)>,
+) -> Vec<(&'static str, Duration)> {
+    let rows: Vec<FileEntry> = entries
+        .into_iter()
+        .map(|(listed, details)| {
+            let mut entry = FileEntry::listed(listed);
+            entry.apply(details);
+            entry
+        })
+        .collect();
+    [SortColumn::Name, SortColumn::Size, SortColumn::Modified]
+        .into_iter()
+        .map(|column| {
+            let mut rows: Vec<&FileEntry> = rows.iter().collect();
+            let start = Instant::now();
+            rows.sort_by(|a, b| column.compare(a, b));
+            (column.title(), start.elapsed())
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SortColumn {
+    Name,
+    Size,
+    Modified,
+    Created,
+    Accessed,
+    Owner,
+    Group,
+    Original,
+    Deleted,
+}
+
+impl SortColumn {
+    fn title(self) -> &'static str {
+        match self {
+            SortColumn::Name => "Name",
+            SortColumn::Size => "Size",
+            SortColumn::Modified => "Modified",
+            SortColumn::Created => "Created",
+            SortColumn::Accessed => "Accessed",
+            SortColumn::Owner => "Owner",
+            SortColumn::Group => "Group",
+            SortColumn::Original => "Original Location",
+            SortColumn::Deleted => "Deleted",
+        }
+    }
+
+    // Folders always come before files, whatever the column
+    fn compare(self, a: &FileEntry, b: &FileEntry) -> std::cmp::Ordering {
+        let by_column = match self {
+            SortColumn::Name => name_sort_key(&a.name).cmp(&name_sort_key(&b.name)),
+            SortColumn::Size => a.size.cmp(&b.size),
+            SortColumn::Modified => a.modified.cmp(&b.modified),
+            SortColumn::Created => a.created.cmp(&b.created),
+            SortColumn::Accessed => a.accessed.cmp(&b.accessed),
+            SortColumn::Owner => {
+                let name = |e: &FileEntry| e.owner.map(|o| owners::user_label(o.uid));
+                name(a).cmp(&name(b))
+            }
+            SortColumn::Group => {
+                let name = |e: &FileEntry| e.owner.map(|o| owners::group_label(o.gid));
+                name(a).cmp(&name(b))
+            }
+            SortColumn::Original => a.original.cmp(&b.original),
+            SortColumn::Deleted => a.deleted.cmp(&b.deleted),
+        };
+        b.is_dir.cmp(&a.is_dir).then(by_column)
+    }
+}
+
+struct PasteDialog {
+    content: PastedContent,
+    name: String,
+    error: Option<String>,
+}
+
+struct LinkDialog {
+    targets: Vec<PathBuf>,
+    kind: LinkKind,
+    relative: bool,
+    errors: Vec<String>,
+}
+
+enum NewItem {
+    Folder,
+    File,
+    Template(PathBuf),
+}
+
+struct NewItemDialog {
+    item: NewItem,
+    name: String,
+    error: Option<String>,
+}
+
+struct RenameDialog {
+    path: PathBuf,
+    name: String,
+    // Find and replace within the current name instead of typing a new one
+    replace: Option<Replace>,
+    error: Option<String>,
+}
+
+impl RenameDialog {
+    fn new_name(&self) -> Result<String, String> {
+        let name = match &self.replace {
+            Some(replace) => replace.apply(&file_name(&self.path))?,
+            None => self.name.trim().to_string(),
+        };
+        rename::check_name(&name)?;
+        Ok(name)
+    }
+}
+
+struct RunDialog {
+    path: PathBuf,
+    is_script: bool,
+    // Set when the file carries a "downloaded from the internet" marker
+    origin: Option<String>,
+    trusted: bool,
+    error: Option<String>,
+    dont_ask: bool,
+}
+
+// Items waiting for the user to confirm their deletion
+struct DeleteConfirm {
+    targets: Vec<PathBuf>,
+    // Overwrite the contents before unlinking
+    secure: bool,
+    // Files that have other names too, with their link count
+    hard_linked: Vec<(PathBuf, u64)>,
+    dont_ask: bool,
+}
+
+struct RestoreDialog {
+    targets: Vec<PathBuf>,
+    folder: String,
+}
+
+// Waiting for the user to confirm a trash operation
+enum TrashConfirm {
+    Move {
+        targets: Vec<PathBuf>,
+        dont_ask: bool,
+    },
+    Empty {
+        dont_ask: bool,
+    },
+}
+
+// A job that skipped items it couldn't handle, offering to run it again on
+// just those
+struct PartialFailure {
+    title: String,
+    folder: Option<PathBuf>,
+    failures: Vec<jobs::Failure>,
+    batch: jobs::Batch,
+}
+
+// Copying links to a drive that can't hold them
+struct SymlinkConfirm {
+    transfer: ops::Transfer,
+    capabilities: Capabilities,
+}
+
+struct ReplaceConfirm {
+    paths: Vec<PathBuf>,
+    // The ones that are there already
+    existing: Vec<PathBuf>,
+    dont_ask: bool,
+}
+
+impl DeleteConfirm {
+    fn new(targets: Vec<PathBuf>, secure: bool) -> Self {
+        let hard_linked = targets
+            .iter()
+            .filter_map(|t| {
+                let meta = fs::symlink_metadata(t).ok().filter(|m| m.is_file())?;
+                let count = links::hard_link_count(&meta).filter(|&n| n > 1)?;
+                Some((t.clone(), count))
+            })
+            .collect();
+        Self {
+            targets,
+            secure,
+            hard_linked,
+            dont_ask: false,
+        }
+    }
+}
+
+struct CompressDialog {
+    targets: Vec<PathBuf>,
+    // Without the extension, which follows the format
+    name: String,
+    format: archive::Format,
+    use_password: bool,
+    password: String,
+    confirm: String,
+}
+
+struct EncryptDialog {
+    targets: Vec<PathBuf>,
+    keys: Result<Vec<gpg::Key>, String>,
+    recipient: Option<usize>,
+    // Encrypt to an untrusted recipient
+    trust_anyway: bool,
+}
+
+struct DownloadDialog {
+    url: String,
+    error: Option<String>,
+}
+
+struct ShareWindow {
+    folder: PathBuf,
+    use_password: bool,
+    password: String,
+    share: Option<share::Share>,
+    error: Option<String>,
+}
+
+struct SplitDialog {
+    path: PathBuf,
+    chunk_mib: u64,
+}
+
+struct TimestampDialog {
+    targets: Vec<PathBuf>,
+    // Shift each item's time instead of setting one for all
+    shift: bool,
+    datetime: String,
+    // Signed offset, split up for easier entry
+    days: i64,
+    hours: i64,
+    minutes: i64,
+    seconds: i64,
+    modified: bool,
+    created: bool,
+    error: Option<String>,
+}
+
+// A drive being mounted, unmounted, unlocked or removed on its own thread,
+// since udisks may wait on a password prompt or on writes being flushed
+struct DeviceTask {
+    // e.g. "Mounting STICK…"
+    label: String,
+    // Errors go to the unlock dialog if it's still open
+    unlocking: bool,
+    // The folder to go to once done, if any
+    done: Receiver<Result<Option<PathBuf>, String>>,
+}
+
+// Passphrase prompt for a LUKS-encrypted volume
+struct UnlockDialog {
+    device: udisks::BlockDevice,
+    passphrase: String,
+    error: Option<String>,
+}
+
+struct ConvertDialog {
+    targets: Vec<PathBuf>,
+    format: convert::ImageFormat,
+    quality: u8,
+    resize: bool,
+    max_side: u32,
+}
+
+// Read-only view into a disk image, walked folder by folder
+struct ImageBrowser {
+    path: PathBuf,
+    image: iso::IsoImage,
+    // Folders opened from the root down; the last one is being shown
+    trail: Vec<iso::IsoEntry>,
+    entries: Vec<iso::IsoEntry>,
+    // Indices into `entries`
+    selected: HashSet<usize>,
+    error: Option<String>,
+}
+
+// Filled in once the renderer, which may be slow, has finished
+struct PreviewWindow {
+    path: PathBuf,
+    renderer: String,
+    text: Option<Result<String, String>>,
+    rendered: Receiver<Result<String, String>>,
+}
+
+// A plugin's filesystem, walked folder by folder like a disk image
+struct FsBrowser {
+    backend: Arc<dyn VfsBackend>,
+    dir: PathBuf,
+    entries: FsEntries,
+    // Indices into `entries`
+    selected: HashSet<usize>,
+    error: Option<String>,
+    listing: Option<Receiver<std::io::Result<FsEntries>>>,
+}
+
+// Context menu choices that need `&mut self`, applied once the entry list is
+// no longer borrowed
+enum EntryAction {
+    Copy(Vec<PathBuf>),
+    Cut(Vec<PathBuf>),
+    CreateLink(Vec<PathBuf>),
+    RunCustom(CustomAction, Vec<PathBuf>),
+    ConfirmRun(PathBuf),
+    Properties(Vec<PathBuf>),
+    Rename(PathBuf),
+    Trash(Vec<PathBuf>),
+    // Into the folder if given, otherwise where they came from
+    Restore(Vec<PathBuf>, Option<PathBuf>),
+    RestoreTo(Vec<PathBuf>),
+    Delete(Vec<PathBuf>, bool),
+    Encrypt(Vec<PathBuf>),
+    Compress(Vec<PathBuf>),
+    Extract(PathBuf),
+    BrowseImage(PathBuf),
+    MountImage(PathBuf),
+    Split(PathBuf),
+    Timestamps(Vec<PathBuf>),
+    Join(PathBuf),
+    Gpg(Vec<PathBuf>, gpg::Operation),
+    SendTo(Vec<PathBuf>, SendTarget),
+    Preview(PathBuf),
+    // Go to the folder holding a search result, in a new tab if set
+    Reveal(PathBuf, bool),
+}
+
+// Sidebar device clicks, applied once the device list is no longer borrowed
+enum DeviceAction {
+    Open(PathBuf),
+    Mount(udisks::BlockDevice),
+    Unmount(udisks::BlockDevice),
+    Unlock(udisks::BlockDevice),
+    Eject(udisks::BlockDevice),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SearchScope {
+    Folder,
+    Subfolders,
+    Drive,
+    Bookmarks,
+    Everywhere,
+}
+
+impl SearchScope {
+    const ALL: [SearchScope; 5] = [
+        SearchScope::Folder,
+        SearchScope::Subfolders,
+        SearchScope::Drive,
+        SearchScope::Bookmarks,
+        SearchScope::Everywhere,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SearchScope::Folder => "This folder",
+            SearchScope::Subfolders => "With subfolders",
+            SearchScope::Drive => "This drive",
+            SearchScope::Bookmarks => "Bookmarks",
+            SearchScope::Everywhere => "Everywhere",
+        }
+    }
+}
+
+// Entries of the Go menu
+#[derive(Clone, Copy)]
+enum Location {
+    Home,
+    Desktop,
+    Documents,
+    Downloads,
+    Root,
+    Trash,
+    Recent,
+    Network,
+    Enter,
+}
+
+impl Location {
+    const ALL: [Location; 9] = [
+        Location::Home,
+        Location::Desktop,
+        Location::Documents,
+        Location::Downloads,
+        Location::Root,
+        Location::Trash,
+        Location::Recent,
+        Location::Network,
+        Location::Enter,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Location::Home => "Home",
+            Location::Desktop => "Desktop",
+            Location::Documents => "Documents",
+            Location::Downloads => "Downloads",
+            Location::Root => "Root",
+            Location::Trash => "Trash",
+            Location::Recent => "Recent Files",
+            Location::Network => "Network",
+            Location::Enter => "Enter Location…",
+        }
+    }
+
+    // Mostly Finder's, which other file managers share
+    fn shortcut(self) -> egui::KeyboardShortcut {
+        use egui::{Key, KeyboardShortcut, Modifiers};
+        let command_shift = Modifiers::COMMAND | Modifiers::SHIFT;
+        match self {
+            Location::Home => KeyboardShortcut::new(Modifiers::ALT, Key::Home),
+            Location::Desktop => KeyboardShortcut::new(command_shift, Key::D),
+            Location::Documents => KeyboardShortcut::new(command_shift, Key::O),
+            Location::Downloads => {
+                KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::ALT, Key::L)
+            }
+            Location::Root => KeyboardShortcut::new(command_shift, Key::R),
+            Location::Trash => KeyboardShortcut::new(command_shift, Key::T),
+            Location::Recent => KeyboardShortcut::new(command_shift, Key::F),
+            Location::Network => KeyboardShortcut::new(command_shift, Key::K),
+            Location::Enter => KeyboardShortcut::new(Modifiers::COMMAND, Key::L),
+        }
+    }
+}
+
+struct LocationDialog {
+    path: String,
+    error: Option<String>,
+}
+
+enum SendTarget {
+    Archive,
+    Email,
+    Folder(PathBuf),
+}
+
+impl ImageBrowser {
+    fn load(&mut self) {
+        self.selected.clear();
+        let dir = self.trail.last().unwrap().clone();
+        match self.image.read_dir(&dir) {
+            Ok(entries) => {
+                self.entries = entries;
+                self.error = None;
+            }
+            Err(e) => {
+                self.entries.clear();
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+impl FsBrowser {
+    fn new(backend: Arc<dyn VfsBackend>, repaint: Repaint) -> Self {
+        let mut browser = Self {
+            backend,
+            dir: PathBuf::from("/"),
+            entries: Vec::new(),
+            selected: HashSet::new(),
+            error: None,
+            listing: None,
+        };
+        browser.load(repaint);
+        browser
+    }
+
+    // Plugin commands may go over the network, so folders are listed on a
+    // thread of their own
+    fn load(&mut self, repaint: Repaint) {
+        self.selected.clear();
+        self.entries.clear();
+        self.error = None;
+        let (tx, rx) = std::sync::mpsc::channel();
+        let backend = self.backend.clone();
+        let dir = self.dir.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(backend.list(&dir));
+            repaint.request();
+        });
+        self.listing = Some(rx);
+    }
+
+    fn poll(&mut self) {
+        let Some(result) = self.listing.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.listing = None;
+        match result {
+            Ok(entries) => self.entries = entries,
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+}
+
+impl Default for FileBrowserWidget {
+    fn default() -> Self {
+        let config = Config::load();
+        let plugins = plugins::load_all(&config.disabled_plugins);
+        let repaint = Repaint::default();
+        let read_only = config.read_only;
+        // The shell's folder may have been deleted since it started us
+        let current_dir = std::env::current_dir()
+            .ok()
+            .or_else(user_dirs::home_dir)
+            .unwrap_or_else(|| PathBuf::from("/"));
+        Self {
+            id: egui::Id::new("file_browser"),
+            config,
+            settings: SettingsWindow::default(),
+            logs: logging::LogsWindow::default(),
+            history: history::HistoryWindow::default(),
+            processes: processes::ProcessesWindow::default(),
+            io_stats: iostats::Monitor::default(),
+            dates: dates::DateLabels::default(),
+            tabs: vec![Tab {
+                dir: current_dir.clone(),
+                selected: HashSet::new(),
+                overview: false,
+                pinned: false,
+            }],
+            active_tab: 0,
+            viewport: egui::ViewportId::ROOT,
+            windows: Vec::new(),
+            windows_opened: 0,
+            detached: false,
+            saved_location: PathBuf::new(),
+            toasts: toasts::Toasts::default(),
+            #[cfg(feature = "profiling")]
+            profiler: profiler::ProfilerWindow::default(),
+            high_contrast: false,
+            spring: dnd::Spring::default(),
+            drag_origin: None,
+            torn_off: None,
+            current_dir,
+            entries: Vec::new(),
+            selected: HashSet::new(),
+            select_pattern: None,
+            paste_dialog: None,
+            text_pasted: false,
+            file_clipboard: Vec::new(),
+            clipboard_cut: false,
+            link_dialog: None,
+            new_item_dialog: None,
+            rename_dialog: None,
+            run_dialog: None,
+            download_dialog: None,
+            location_dialog: None,
+            delete_confirm: None,
+            trash_confirm: None,
+            trash_summary: None,
+            trash_rx: None,
+            in_trash: false,
+            trashed: HashMap::new(),
+            restore_dialog: None,
+            recovered: recovery::take_crashed(),
+            encrypt_dialog: None,
+            compress_dialog: None,
+            extract_password: None,
+            image_browser: None,
+            preview: None,
+            fs_browser: None,
+            split_dialog: None,
+            convert_dialog: None,
+            timestamp_dialog: None,
+            devices: drives::Devices::default(),
+            devices_rx: drives::watch(repaint.clone()),
+            device_error: None,
+            device_task: None,
+            unlock_dialog: None,
+            network: None,
+            picker: None,
+            picker_name: String::new(),
+            replace_confirm: None,
+            symlink_confirm: None,
+            partial_failures: Vec::new(),
+            interrupted: resume::interrupted(),
+            jobs: Jobs::with_repaint(repaint.clone()),
+            repaint: repaint.clone(),
+            properties: Vec::new(),
+            bulk_properties: Vec::new(),
+            share_window: None,
+            icon_textures: HashMap::new(),
+            thumbnails: thumbnails::Thumbnails::new(repaint),
+            plugins,
+            plugin_manager_open: false,
+            plugin_cells: HashMap::new(),
+            plugin_cells_rx: None,
+            listing: None,
+            unlisted: true,
+            search: None,
+            search_text: String::new(),
+            search_scope: SearchScope::Subfolders,
+            scan_exclude: None,
+            pending_select: Vec::new(),
+            reveal: None,
+            showing_recent: false,
+            sort: SortColumn::Name,
+            sort_descending: false,
+            filters: filters::Filters::default(),
+            overview: false,
+            read_only,
+            scripts: ScriptHost::load(read_only),
+            hooked_dir: None,
+            hooked_selection: HashSet::new(),
+            on_selection_changed: None,
+            on_open: None,
+        }
+    }
+}
+
+impl FileBrowserWidget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Needed for each browser when an app shows more than one, as they'd
+    // otherwise share their panels' sizes, dialogs and search box
+    pub fn with_id_salt(mut self, salt: impl std::hash::Hash) -> Self {
+        self.id = egui::Id::new(salt);
+        self
+    }
+
+    pub fn with_directory(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.navigate(dir);
+        self
+    }
+
+    // Starts on "This Computer" rather than in a folder
+    pub fn with_overview(mut self) -> Self {
+        self.overview = true;
+        self.unlisted = false;
+        self
+    }
+
+    // Opens the folder holding `path` with it selected and scrolled to
+    pub fn with_revealed(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let Some(parent) = path.parent().filter(|p| p.is_dir()) else {
+            return self.with_startup();
+        };
+        self.navigate(parent);
+        self.reveal = Some(path);
+        self
+    }
+
+    // Where Settings says to start when nothing was asked for
+    pub fn with_startup(self) -> Self {
+        let startup = &self.config.startup;
+        let dir = match startup.folder {
+            config::StartupFolder::Overview => None,
+            config::StartupFolder::Home => user_dirs::home_dir(),
+            config::StartupFolder::Last => config::last_location(),
+            config::StartupFolder::Path => Some(startup.path.clone()),
+        };
+        match dir.filter(|d| d.is_dir()) {
+            Some(dir) => self.with_directory(dir),
+            None => self.with_overview(),
+        }
+    }
+
+    // Hides and blocks destructive operations regardless of the config
+    pub fn with_read_only(mut self) -> Self {
+        self.read_only = true;
+        self.scripts.read_only = true;
+        self
+    }
+
+    // Turns the browser into a file chooser with an accept bar at the bottom
+    pub fn with_picker(mut self, picker: picker::Picker) -> Self {
+        if let Some(folder) = picker.folder.clone().filter(|f| f.is_dir()) {
+            self.navigate(folder);
+        }
+        if let picker::PickMode::Save { name } = &picker.mode {
+            self.picker_name = name.clone();
+        }
+        self.picker = Some(picker);
+        self
+    }
+
+    // Called with the selected paths, in listing order, whenever they change
+    pub fn on_selection_changed(mut self, callback: impl FnMut(&[PathBuf]) + 'static) -> Self {
+        self.on_selection_changed = Some(Box::new(callback));
+        self
+    }
+
+    // Called when a file is double-clicked; returning true means the
+    // application handled it and the file isn't opened with its default app
+    pub fn on_open(mut self, callback: impl FnMut(&Path) -> bool + 'static) -> Self {
+        self.on_open = Some(Box::new(callback));
+        self
+    }
+
+    pub fn current_dir(&self) -> &Path {
+        &self.current_dir
+    }
+
+    pub fn bookmarks(&self) -> &[PathBuf] {
+        &self.config.bookmarks
+    }
+
+    // Whether Settings asks for a tray icon, which is up to the application
+    // to show
+    pub fn wants_tray(&self) -> bool {
+        self.config.tray
+    }
+
+    pub fn navigate(&mut self, dir: impl Into<PathBuf>) {
+        if self.pinned() {
+            self.open_tab(dir.into(), true);
+            return;
+        }
+        self.current_dir = dir.into();
+        self.selected.clear();
+        self.search = None;
+        self.showing_recent = false;
+        self.read_dir();
+    }
+
+    // Goes to `dir` in this tab, or in a new one when this tab is pinned
+    fn go_to(&mut self, dir: PathBuf) {
+        if self.pinned() && (dir != self.current_dir || self.overview || self.showing_recent) {
+            self.open_tab(dir, true);
+        } else {
+            self.current_dir = dir;
+            self.search = None;
+            self.showing_recent = false;
+            self.read_dir();
+        }
+    }
+
+    fn show_recent(&mut self) {
+        if self.pinned() {
+            self.open_tab(self.current_dir.clone(), true);
+        }
+        self.search = None;
+        self.showing_recent = true;
+        self.read_dir();
+    }
+
+    // The current location as the path bar shows it, with recent:// and
+    // trash:// for those
+    fn address(&self) -> String {
+        if self.showing_recent {
+            return String::from("recent://");
+        }
+        let in_trash = trash::files_dir().and_then(|files| {
+            self.current_dir
+                .strip_prefix(files)
+                .ok()
+                .map(uri::trash_address)
+        });
+        in_trash.unwrap_or_else(|| self.current_dir.display().to_string())
+    }
+
+    fn search_spec(&self, query: &str) -> listing::Search {
+        let exclude = &self.config.search.exclude;
+        let here = vec![self.current_dir.clone()];
+        let (roots, exclude) = match self.search_scope {
+            SearchScope::Folder => (here, ""),
+            SearchScope::Subfolders => (here, exclude.subfolders.as_str()),
+            SearchScope::Drive => (vec![self.drive_root()], exclude.drive.as_str()),
+            SearchScope::Bookmarks => (self.config.bookmarks.clone(), exclude.bookmarks.as_str()),
+            SearchScope::Everywhere => {
+                // The current drive too, as the root filesystem of a
+                // container or live system isn't listed among the drives
+                let mut roots = vec![self.drive_root()];
+                for mount_point in self.devices.mount_points() {
+                    if !roots.iter().any(|r| r == mount_point) {
+                        roots.push(mount_point.to_path_buf());
+                    }
+                }
+                (roots, exclude.everywhere.as_str())
+            }
+        };
+        listing::Search {
+            roots,
+            base: self.current_dir.clone(),
+            patterns: search_patterns(query),
+            exclude: exclude.to_string(),
+            skip: self
+                .scan_exclude
+                .clone()
+                .unwrap_or_else(|| self.config.scan_exclude.clone()),
+            shallow: self.search_scope == SearchScope::Folder,
+            one_filesystem: matches!(
+                self.search_scope,
+                SearchScope::Drive | SearchScope::Everywhere
+            ),
+        }
+    }
+
+    // Where the drive the current folder is on is mounted
+    fn drive_root(&self) -> PathBuf {
+        self.devices
+            .mount_points()
+            .filter(|m| self.current_dir.starts_with(m))
+            .max_by_key(|m| m.components().count())
+            .map(Path::to_path_buf)
+            .or_else(|| self.current_dir.ancestors().last().map(Path::to_path_buf))
+            .unwrap_or_default()
+    }
+
+    fn start_search(&mut self) {
+        let query = self.search_text.trim();
+        self.search = (!query.is_empty()).then(|| query.to_string());
+        self.read_dir();
+    }
+
+    fn close_search(&mut self) {
+        self.search = None;
+        self.read_dir();
+    }
+
+    fn show_overview(&mut self) {
+        if self.pinned() && !self.overview {
+            self.open_tab(self.current_dir.clone(), true);
+        }
+        self.overview = true;
+    }
+
+    // A pinned tab stays on its folder and can't be closed
+    fn pinned(&self) -> bool {
+        self.tabs[self.active_tab].pinned
+    }
+
+    pub fn selection(&self) -> Vec<PathBuf> {
+        self.selection_in_order()
+    }
+
+    // Opens `dir` in a new tab after the current one, leaving the current
+    // one showing unless `switch` is set
+    fn open_tab(&mut self, dir: PathBuf, switch: bool) {
+        self.tabs.insert(
+            self.active_tab + 1,
+            Tab {
+                dir,
+                selected: HashSet::new(),
+                overview: false,
+                pinned: false,
+            },
+        );
+        if switch {
+            self.switch_tab(self.active_tab + 1);
+        }
+    }
+
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.active_tab || index >= self.tabs.len() {
+            return;
+        }
+        self.save_tab();
+        self.active_tab = index;
+        self.load_tab();
+    }
+
+    fn save_tab(&mut self) {
+        self.tabs[self.active_tab] = Tab {
+            dir: self.current_dir.clone(),
+            selected: self.selected.clone(),
+            overview: self.overview,
+            pinned: self.pinned(),
+        };
+    }
+
+    fn load_tab(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        self.current_dir = tab.dir.clone();
+        self.selected = std::mem::take(&mut tab.selected);
+        let overview = tab.overview;
+        self.search = None;
+        self.showing_recent = false;
+        self.read_dir();
+        self.overview = overview;
+    }
+
+    // The last tab stays open
+    fn close_tab(&mut self, index: usize) {
+        if self.tabs.len() < 2 || index >= self.tabs.len() {
+            return;
+        }
+        if index == self.active_tab {
+            // Closing the current tab shows its right-hand neighbour, or the
+            // left one at the end
+            let next = if index + 1 < self.tabs.len() {
+                index + 1
+            } else {
+                index - 1
+            };
+            self.switch_tab(next);
+        }
+        self.tabs.remove(index);
+        if self.active_tab > index {
+            self.active_tab -= 1;
+        }
+    }
+
+    fn move_tab(&mut self, from: usize, to: usize) {
+        let tab = self.tabs.remove(from);
+        self.tabs.insert(to, tab);
+        if self.active_tab == from {
+            self.active_tab = to;
+        } else if from < self.active_tab && to >= self.active_tab {
+            self.active_tab -= 1;
+        } else if from > self.active_tab && to <= self.active_tab {
+            self.active_tab += 1;
+        }
+    }
+
+    // Removes a tab to go to another window. A window's only tab is handed
+    // over too, but stays until the window is closed.
+    fn take_tab(&mut self, index: usize) -> Tab {
+        self.save_tab();
+        let tab = self.tabs[index].clone();
+        self.close_tab(index);
+        tab
+    }
+
+    // Tabs as the crash snapshot keeps them, with those of detached windows
+    fn saved_tabs(&self) -> Vec<recovery::SavedTab> {
+        let own = self.tabs.iter().enumerate().filter_map(|(i, tab)| {
+            let (dir, overview) = if i == self.active_tab {
+                (&self.current_dir, self.overview)
+            } else {
+                (&tab.dir, tab.overview)
+            };
+            (!overview).then(|| recovery::SavedTab {
+                dir: dir.clone(),
+                pinned: tab.pinned,
+            })
+        });
+        let detached = self.windows.iter().flat_map(|w| w.browser.saved_tabs());
+        own.chain(detached).collect()
+    }
+
+    fn add_tab(&mut self, tab: Tab) {
+        self.tabs.push(tab);
+        self.switch_tab(self.tabs.len() - 1);
+    }
+
+    fn tab_bar(&mut self, ui: &mut egui::Ui) {
+        let mut switch = None;
+        let mut close = None;
+        let mut toggle_pin = None;
+        let mut dropped = None;
+        let mut dropped_files = None;
+        let mut rects = Vec::new();
+        ui.horizontal_wrapped(|ui| {
+            for (i, tab) in self.tabs.iter().enumerate() {
+                let active = i == self.active_tab;
+                let mut title = if active {
+                    tab_title(&self.current_dir, self.overview)
+                } else {
+                    tab_title(&tab.dir, tab.overview)
+                };
+                if tab.pinned {
+                    title = format!("📌 {}", title);
+                }
+                let response = ui
+                    .selectable_label(active, &title)
+                    .interact(egui::Sense::drag())
+                    .on_hover_text("Drag to reorder or out of the window to detach");
+                rects.push(response.rect);
+                if response.clicked() {
+                    switch = Some(i);
+                }
+                if response.middle_clicked() && !tab.pinned {
+                    close = Some(i);
+                }
+                response.context_menu(|ui| {
+                    let pin = if tab.pinned { "Unpin Tab" } else { "Pin Tab" };
+                    if ui.button(pin).clicked() {
+                        toggle_pin = Some(i);
+                        ui.close_menu();
+                    }
+                    if !tab.pinned && self.tabs.len() > 1 && ui.button("Close Tab").clicked() {
+                        close = Some(i);
+                        ui.close_menu();
+                    }
+                });
+                if response.dragged() {
+                    egui::show_tooltip_at_pointer(
+                        ui.ctx(),
+                        ui.layer_id(),
+                        response.id.with("dragged"),
+                        |ui| ui.label(&title),
+                    );
+                }
+                if response.drag_stopped() {
+                    dropped = Some(i);
+                }
+                let (dir, overview) = if active {
+                    (&self.current_dir, self.overview)
+                } else {
+                    (&tab.dir, tab.overview)
+                };
+                if !overview {
+                    if let Some(files) = dnd::drop_target(ui, &response) {
+                        dropped_files = Some((files, dir.clone()));
+                    } else if !active && dnd::held(&response) && self.spring.due(ui.ctx(), dir) {
+                        switch = Some(i);
+                    }
+                }
+                if !tab.pinned && ui.small_button("×").on_hover_text("Close tab").clicked() {
+                    close = Some(i);
+                }
+                ui.separator();
+            }
+        });
+        if let Some(index) = toggle_pin {
+            self.tabs[index].pinned = !self.tabs[index].pinned;
+        }
+        if let Some((files, destination)) = dropped_files {
+            self.drop_files(ui.ctx(), files, destination);
+        }
+        if let Some(index) = switch {
+            self.switch_tab(index);
+        }
+        if let Some(index) = close {
+            self.close_tab(index);
+        }
+        if let Some(index) = dropped {
+            let pos = ui.input(|i| i.pointer.latest_pos());
+            if let Some(to) = pos.and_then(|p| rects.iter().position(|r| r.contains(p))) {
+                if to != index {
+                    self.move_tab(index, to);
+                }
+            } else if !pos.is_some_and(|p| ui.ctx().screen_rect().contains(p))
+                && self.picker.is_none()
+            {
+                // Dropped outside the window; where on the screen, when the
+                // platform tells window positions
+                let window = ui.input(|i| i.viewport().inner_rect);
+                let at = pos.zip(window).map(|(p, w)| w.min + p.to_vec2());
+                self.torn_off = Some((index, at));
+            }
+        }
+    }
+
+    // Shows the windows of detached tabs, and moves tabs dropped outside
+    // their window to the window under them or a new one
+    fn show_windows(&mut self, ctx: &egui::Context) {
+        let salt = self.id;
+        self.windows.retain_mut(|window| {
+            let title = tab_title(&window.browser.current_dir, window.browser.overview);
+            let mut builder = egui::ViewportBuilder::default()
+                .with_title(title.clone())
+                .with_inner_size([900.0, 600.0]);
+            if let Some(position) = window.position {
+                builder = builder.with_position(position);
+            }
+            let mut open = true;
+            ctx.show_viewport_immediate(window.id, builder, |ctx, class| {
+                if class == egui::ViewportClass::Embedded {
+                    // No native windows on this platform
+                    egui::Window::new(title)
+                        .id(salt.with(window.id))
+                        .open(&mut open)
+                        .show(ctx, |ui| window.browser.show(ui));
+                } else {
+                    egui::CentralPanel::default().show(ctx, |ui| window.browser.show(ui));
+                    if ctx.input(|i| i.viewport().close_requested()) {
+                        open = false;
+                    }
+                }
+            });
+            open
+        });
+
+        let mut dropped = Vec::new();
+        if let Some(torn_off) = self.torn_off.take() {
+            dropped.push((self.viewport, torn_off));
+        }
+        for window in &mut self.windows {
+            if let Some(torn_off) = window.browser.torn_off.take() {
+                dropped.push((window.id, torn_off));
+            }
+        }
+        for (source, (index, at)) in dropped {
+            self.hand_over_tab(ctx, source, index, at);
+        }
+    }
+
+    fn tab_browser(&mut self, viewport: egui::ViewportId) -> Option<&mut FileBrowserWidget> {
+        if viewport == self.viewport {
+            return Some(self);
+        }
+        self.windows
+            .iter_mut()
+            .find(|w| w.id == viewport)
+            .map(|w| &mut w.browser)
+    }
+
+    fn hand_over_tab(
+        &mut self,
+        ctx: &egui::Context,
+        source: egui::ViewportId,
+        index: usize,
+        at: Option<egui::Pos2>,
+    ) {
+        let target = at.and_then(|at| {
+            std::iter::once(self.viewport)
+                .chain(self.windows.iter().map(|w| w.id))
+                .filter(|&id| id != source)
+                .find(|&id| {
+                    ctx.input_for(id, |i| i.viewport().inner_rect)
+                        .is_some_and(|r| r.contains(at))
+                })
+        });
+        let Some(from) = self.tab_browser(source) else {
+            return;
+        };
+        // A window's only tab can join another window but there's no point
+        // in giving it a new one
+        let last = from.tabs.len() == 1;
+        if last && target.is_none() {
+            return;
+        }
+        let tab = from.take_tab(index);
+        match target.and_then(|id| self.tab_browser(id)) {
+            Some(to) => to.add_tab(tab),
+            None => {
+                self.windows_opened += 1;
+                let id = self.id.with(("tab_window", self.windows_opened));
+                let window = TabWindow {
+                    id: egui::ViewportId(id),
+                    browser: self.detached(tab, id),
+                    position: at,
+                };
+                self.windows.push(window);
+            }
+        }
+        if last {
+            self.windows.retain(|w| w.id != source);
+        }
+        ctx.request_repaint();
+    }
+
+    // A browser for a window of its own, showing `tab`
+    fn detached(&self, tab: Tab, id: egui::Id) -> Self {
+        let mut browser = Self {
+            id,
+            tabs: vec![tab],
+            detached: true,
+            ..Self::default()
+        };
+        if self.read_only {
+            browser = browser.with_read_only();
+        }
+        browser.load_tab();
+        browser
+    }
+
+    // Starts listing the current folder; entries stream in through
+    // `poll_listing`
+    fn read_dir(&mut self) {
+        self.unlisted = false;
+        self.overview = false;
+        self.entries.clear();
+        self.plugin_cells.clear();
+        self.plugin_cells_rx = None;
+        self.thumbnails.retain_in(&self.current_dir);
+        self.in_trash = trash::contains(&self.current_dir);
+        self.trashed = if self.in_trash {
+            trash::list()
+                .into_iter()
+                .filter(|t| t.path.parent() == Some(self.current_dir.as_path()))
+                .filter_map(|t| Some((t.path.file_name()?.to_string_lossy().into_owned(), t)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        // The trash columns are gone outside the trash
+        if !self.columns().contains(&self.sort) {
+            self.sort = SortColumn::Name;
+        }
+        self.listing = Some(match &self.search {
+            Some(query) => listing::search(self.search_spec(query), self.repaint.clone()),
+            None if self.showing_recent => listing::paths(recent::files, self.repaint.clone()),
+            None => listing::load(self.current_dir.clone(), self.repaint.clone()),
+        });
+        self.pending_select.clear();
+        if self.reveal.as_ref().and_then(|r| r.parent()) != Some(self.current_dir.as_path()) {
+            self.reveal = None;
+        }
+    }
+
+    fn finish_listing(&mut self) {
+        self.sort_entries();
+
+        // Drop selected paths that no longer exist in this listing
+        let current: HashSet<PathBuf> = self
+            .entries
+            .iter()
+            .map(|e| self.current_dir.join(&e.name))
+            .collect();
+        self.selected.retain(|p| current.contains(p));
+        if let Some(reveal) = &self.reveal {
+            if current.contains(reveal) {
+                self.selected = HashSet::from([reveal.clone()]);
+            } else {
+                self.reveal = None;
+            }
+        }
+
+        self.start_plugin_columns();
+    }
+
+    fn sort_entries(&mut self) {
+        profile_scope!("sort entries");
+        // Details arrive by index while loading, so the listing gets sorted
+        // once it's done instead
+        if self.listing.is_some() {
+            return;
+        }
+        let (column, descending) = (self.sort, self.sort_descending);
+        self.entries.sort_by(|a, b| {
+            let order = column.compare(a, b);
+            // Reversing keeps folders first
+            if descending && a.is_dir == b.is_dir {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+    }
+
+    // Clicking the sorted column again flips the direction
+    fn sort_by(&mut self, column: SortColumn) {
+        if self.sort == column {
+            self.sort_descending = !self.sort_descending;
+        } else {
+            self.sort = column;
+            self.sort_descending = false;
+        }
+        self.sort_entries();
+    }
+
+    // Built-in columns shown, in order
+    fn columns(&self) -> Vec<SortColumn> {
+        let mut columns = vec![SortColumn::Name, SortColumn::Size, SortColumn::Modified];
+        if self.config.view.show_created {
+            columns.push(SortColumn::Created);
+        }
+        if self.config.view.show_accessed {
+            columns.push(SortColumn::Accessed);
+        }
+        if self.config.view.show_owner {
+            columns.push(SortColumn::Owner);
+            columns.push(SortColumn::Group);
+        }
+        if !self.trashed.is_empty() {
+            columns.push(SortColumn::Original);
+            columns.push(SortColumn::Deleted);
+        }
+        columns
+    }
+
+    fn plugin_columns(&self) -> Vec<PluginColumn> {
+        self.plugins
+            .iter()
+            .filter(|p| p.enabled)
+            .flat_map(|p| p.manifest.columns.iter().cloned())
+            .collect()
+    }
+
+    fn start_plugin_columns(&mut self) {
+        self.plugin_cells.clear();
+        let columns = self.plugin_columns();
+        self.plugin_cells_rx = if columns.is_empty() {
+            None
+        } else {
+            let paths = self
+                .entries
+                .iter()
+                .map(|e| self.current_dir.join(&e.name))
+                .collect();
+            Some(plugins::compute_columns(
+                columns,
+                paths,
+                self.current_dir.clone(),
+                self.repaint.clone(),
+            ))
+        };
+    }
+
+    fn poll_plugin_columns(&mut self) {
+        let Some(rx) = &self.plugin_cells_rx else {
+            return;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(cell) => {
+                    self.plugin_cells
+                        .insert((cell.column, cell.path), cell.value);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.plugin_cells_rx = None;
+                    break;
+                }
+            }
+        }
+    }
+
+    fn poll_listing(&mut self) {
+        profile_scope!("poll listing");
+        let Some(rx) = &self.listing else {
+            return;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(listing::Loaded::Entries(listed)) => {
+                    for (patterns, case_sensitive) in &self.pending_select {
+                        for l in &listed {
+                            if search::matches_any(patterns, &l.name, *case_sensitive) {
+                                self.selected.insert(self.current_dir.join(&l.name));
+                            }
+                        }
+                    }
+                    let trashed = &self.trashed;
+                    self.entries.extend(listed.into_iter().map(|l| {
+                        let trashed = trashed.get(&l.name);
+                        FileEntry {
+                            original: trashed.map(|t| t.original.clone()),
+                            deleted: trashed.and_then(|t| t.deleted_time()),
+                            ..FileEntry::listed(l)
+                        }
+                    }));
+                }
+                Ok(listing::Loaded::Details(index, details)) => {
+                    if let Some(entry) = self.entries.get_mut(index) {
+                        entry.apply(*details);
+                    }
+                }
+                Ok(listing::Loaded::Failed(e)) => {
+                    tracing::error!("Failed to list {}: {}", self.current_dir.display(), e);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.listing = None;
+                    self.finish_listing();
+                    break;
+                }
+            }
+        }
+    }
+
+    // The row of the item to reveal, once, when the listing is complete
+    fn reveal_row(&mut self, visible: Option<&[usize]>) -> Option<usize> {
+        if self.listing.is_some() {
+            return None;
+        }
+        let reveal = self.reveal.take()?;
+        let index = self
+            .entries
+            .iter()
+            .position(|e| self.current_dir.join(&e.name) == reveal)?;
+        match visible {
+            Some(visible) => visible.iter().position(|&i| i == index),
+            None => Some(index),
+        }
+    }
+
+    fn selection_in_order(&self) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .map(|e| self.current_dir.join(&e.name))
+            .filter(|p| self.selected.contains(p))
+            .collect()
+    }
+
+    fn run_script_hooks(&mut self) {
+        if self.hooked_dir.as_ref() != Some(&self.current_dir) {
+            self.hooked_dir = Some(self.current_dir.clone());
+            let commands = self
+                .scripts
+                .on_open_directory(&self.current_dir, &self.selection_in_order());
+            self.apply_script_commands(commands);
+        }
+        if self.hooked_selection != self.selected {
+            let selection = self.selection_in_order();
+            let commands = self.scripts.on_select(&self.current_dir, &selection);
+            self.apply_script_commands(commands);
+            let selection = self.selection_in_order();
+            if let Some(callback) = &mut self.on_selection_changed {
+                callback(&selection);
+            }
+        }
+        // Changes made by the hooks themselves shouldn't trigger them again
+        self.hooked_dir = Some(self.current_dir.clone());
+        self.hooked_selection = self.selected.clone();
+    }
+
+    fn apply_script_commands(&mut self, commands: Vec<ScriptCommand>) {
+        for command in commands {
+            match command {
+                ScriptCommand::Navigate(dir) => {
+                    if dir.is_dir() {
+                        self.go_to(dir);
+                    }
+                }
+                ScriptCommand::Select(pattern) => self.select_matching(&pattern, false, true),
+                ScriptCommand::ClearSelection => self.selected.clear(),
+                ScriptCommand::Run(_) if self.read_only => {
+                    tracing::warn!("Scripts can't run commands in read-only mode");
+                }
+                ScriptCommand::Run(command_line) => {
+                    let result = actions::shell_command(&command_line)
+                        .current_dir(&self.current_dir)
+                        .spawn();
+                    if let Err(e) = result {
+                        tracing::error!("Failed to run \"{}\": {}", command_line, e);
+                    }
+                }
+                ScriptCommand::Refresh => self.read_dir(),
+            }
+        }
+    }
+
+    fn scripts_menu(&mut self, ui: &mut egui::Ui) {
+        let mut chosen = None;
+        for (index, script) in self.scripts.scripts.iter().enumerate() {
+            let functions = script.menu_functions();
+            if functions.is_empty() {
+                continue;
+            }
+            ui.menu_button(&script.name, |ui| {
+                for function in functions {
+                    if ui.button(&function).clicked() {
+                        chosen = Some((index, function));
+                    }
+                }
+            });
+        }
+        if chosen.is_some() {
+            ui.close_menu();
+        }
+        if let Some((index, function)) = chosen {
+            let commands = self.scripts.run_function(
+                index,
+                &function,
+                &self.current_dir,
+                &self.selection_in_order(),
+            );
+            self.apply_script_commands(commands);
+        }
+
+        ui.separator();
+        if ui.button("Reload Scripts").clicked() {
+            self.scripts = ScriptHost::load(self.read_only);
+            ui.close_menu();
+        }
+        if let Some(dir) = scripting::scripts_dir() {
+            if ui.button("Open Scripts Folder").clicked() {
+                let _ = fs::create_dir_all(&dir);
+                if let Err(e) = open_file(&dir, "") {
+                    tracing::error!("Failed to open scripts folder: {}", e);
+                }
+                ui.close_menu();
+            }
+        }
+    }
+
+    fn show_share_window(&mut self, ctx: &egui::Context) {
+        let Some(window) = &mut self.share_window else {
+            return;
+        };
+        let mut open = true;
+
+        salted_window(self.id, "Share folder")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Folder: {}", window.folder.display()));
+                ui.weak("Read-only. Anyone on your network with the link can download files.");
+                ui.separator();
+
+                match &window.share {
+                    None => {
+                        ui.checkbox(&mut window.use_password, "Require a password");
+                        if window.use_password {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut window.password)
+                                    .password(true)
+                                    .hint_text("Password"),
+                            );
+                        }
+                        if let Some(error) = &window.error {
+                            ui.colored_label(ui.visuals().error_fg_color, error);
+                        }
+                        let ready = !window.use_password || !window.password.is_empty();
+                        if ui
+                            .add_enabled(ready, egui::Button::new("Start Sharing"))
+                            .clicked()
+                        {
+                            let password = window.use_password.then(|| window.password.clone());
+                            match share::Share::start(window.folder.clone(), password) {
+                                Ok(share) => window.share = Some(share),
+                                Err(e) => window.error = Some(e),
+                            }
+                        }
+                    }
+                    Some(share) => {
+                        ui.horizontal(|ui| {
+                            ui.hyperlink(&share.url);
+                            if ui.small_button("Copy").clicked() {
+                                ui.ctx().copy_text(share.url.clone());
+                            }
+                        });
+                        if let Some((width, modules)) = share::qr_modules(&share.url) {
+                            draw_qr_code(ui, width, &modules);
+                        }
+                        if ui.button("Stop Sharing").clicked() {
+                            window.share = None;
+                        }
+                    }
+                }
+            });
+
+        // Closing the window shuts the server down
+        if !open {
+            self.share_window = None;
+        }
+    }
+
+    fn show_plugin_manager(&mut self, ctx: &egui::Context) {
+        let mut open = self.plugin_manager_open;
+        let mut changed = false;
+        let mut reload = false;
+
+        salted_window(self.id, "Plugins")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if self.plugins.is_empty() {
+                    ui.label("No plugins installed.");
+                }
+                for plugin in &mut self.plugins {
+                    ui.horizontal(|ui| {
+                        if ui.checkbox(&mut plugin.enabled, "").changed() {
+                            changed = true;
+                        }
+                        ui.strong(&plugin.manifest.name);
+                        if !plugin.manifest.version.is_empty() {
+                            ui.weak(&plugin.manifest.version);
+                        }
+                    });
+                    if !plugin.manifest.description.is_empty() {
+                        ui.label(&plugin.manifest.description);
+                    }
+                    ui.weak(format!(
+                        "{} actions, {} columns, {} previewers, {} filesystems — {}",
+                        plugin.manifest.actions.len(),
+                        plugin.manifest.columns.len(),
+                        plugin.previewers.len(),
+                        plugin.filesystems.len(),
+                        plugin.dir.display()
+                    ));
+                    ui.separator();
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Reload").clicked() {
+                        reload = true;
+                    }
+                    if let Some(dir) = plugins::plugins_dir() {
+                        if ui.button("Open Plugins Folder").clicked() {
+                            let _ = fs::create_dir_all(&dir);
+                            if let Err(e) = open_file(&dir, "") {
+                                tracing::error!("Failed to open plugins folder: {}", e);
+                            }
+                        }
+                    }
+                });
+            });
+
+        if changed {
+            self.config.disabled_plugins = self
+                .plugins
+                .iter()
+                .filter(|p| !p.enabled)
+                .map(|p| p.id.clone())
+                .collect();
+            self.config.save();
+        }
+        if reload {
+            self.plugins = plugins::load_all(&self.config.disabled_plugins);
+        }
+        if changed || reload {
+            self.start_plugin_columns();
+        }
+        self.plugin_manager_open = open;
+    }
+
+    fn can_navigate_up(&self) -> bool {
+        self.current_dir.parent().is_some()
+    }
+
+    // Items a context menu action applies to: the whole selection when the
+    // clicked item is part of it, otherwise just the clicked item
+    fn action_targets(&self, clicked: &Path) -> Vec<PathBuf> {
+        if !self.selected.contains(clicked) {
+            return vec![clicked.to_path_buf()];
+        }
+        self.entries
+            .iter()
+            .map(|e| self.current_dir.join(&e.name))
+            .filter(|p| self.selected.contains(p))
+            .collect()
+    }
+
+    fn entry_context_menu(&self, ui: &mut egui::Ui, clicked: &Path) -> Option<EntryAction> {
+        let targets = self.action_targets(clicked);
+
+        let restorable = targets.iter().all(|t| {
+            t.file_name()
+                .is_some_and(|n| self.trashed.contains_key(&*n.to_string_lossy()))
+        });
+        if restorable && !self.read_only {
+            if ui.button("Restore").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Restore(targets, None));
+            }
+            if ui.button("Restore To…").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::RestoreTo(targets));
+            }
+            ui.separator();
+        }
+        if self.search.is_some() || self.showing_recent {
+            if ui.button("Open Containing Folder").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Reveal(clicked.to_path_buf(), false));
+            }
+            if ui.button("Open Containing Folder in New Tab").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Reveal(clicked.to_path_buf(), true));
+            }
+            ui.separator();
+        }
+        if !self.read_only && ui.button("Cut").clicked() {
+            ui.ctx().copy_text(paths_text(&targets));
+            ui.close_menu();
+            return Some(EntryAction::Cut(targets));
+        }
+        if ui.button("Copy").clicked() {
+            ui.ctx().copy_text(paths_text(&targets));
+            ui.close_menu();
+            return Some(EntryAction::Copy(targets));
+        }
+        if !self.read_only {
+            if ui.button("Create Link…").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::CreateLink(targets));
+            }
+            if ui.button("Compress…").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Compress(targets));
+            }
+        }
+        if let [archive] = targets.as_slice() {
+            if !self.read_only
+                && archive::is_archive(archive)
+                && ui.button("Extract Here").clicked()
+            {
+                ui.close_menu();
+                return Some(EntryAction::Extract(archive.clone()));
+            }
+            if iso::is_iso(archive) && ui.button("Browse Image").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::BrowseImage(archive.clone()));
+            }
+            if iso::is_disk_image(archive) && ui.button("Mount").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::MountImage(archive.clone()));
+            }
+            if !self.read_only
+                && split::part_number(archive).is_some()
+                && ui.button("Join Parts").clicked()
+            {
+                ui.close_menu();
+                return Some(EntryAction::Join(archive.clone()));
+            }
+            if !self.read_only && archive.is_file() && ui.button("Split…").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Split(archive.clone()));
+            }
+        }
+        ui.separator();
+
+        let copied = if ui.button("Copy Full Path").clicked() {
+            Some(paths_text(&targets))
+        } else if ui.button("Copy Name").clicked() {
+            Some(join_lines(targets.iter().map(|p| {
+                p.file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            })))
+        } else if ui.button("Copy as file:// URI").clicked() {
+            Some(join_lines(targets.iter().map(|p| uri::to_file_uri(p))))
+        } else {
+            None
+        };
+
+        if let Some(text) = copied {
+            ui.ctx().copy_text(text);
+            ui.close_menu();
+        }
+
+        if !self.read_only {
+            let mut send_target = None;
+            ui.menu_button("Send To", |ui| {
+                send_target = self.send_to_menu(ui);
+            });
+            if let Some(target) = send_target {
+                ui.close_menu();
+                return Some(EntryAction::SendTo(targets, target));
+            }
+        }
+
+        if gpg::available() {
+            let mut chosen = None;
+            ui.menu_button("Encryption", |ui| {
+                // Only verifying leaves no new file behind
+                if !self.read_only {
+                    if ui.button("Encrypt…").clicked() {
+                        chosen = Some(None);
+                    }
+                    if ui.button("Sign").clicked() {
+                        chosen = Some(Some(gpg::Operation::Sign));
+                    }
+                    if targets.iter().all(|t| gpg::is_encrypted(t))
+                        && ui.button("Decrypt").clicked()
+                    {
+                        chosen = Some(Some(gpg::Operation::Decrypt));
+                    }
+                }
+                if targets.iter().all(|t| gpg::is_signature(t))
+                    && ui.button("Verify Signature").clicked()
+                {
+                    chosen = Some(Some(gpg::Operation::Verify));
+                }
+            });
+            if let Some(operation) = chosen {
+                ui.close_menu();
+                return Some(match operation {
+                    Some(operation) => EntryAction::Gpg(targets, operation),
+                    None => EntryAction::Encrypt(targets),
+                });
+            }
+        }
+
+        ui.separator();
+        if !self.read_only {
+            if self.can_trash() && ui.button("Move to Trash").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Trash(targets));
+            }
+            if ui.button("Delete Permanently").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Delete(targets, false));
+            }
+            if ui.button("Securely Delete…").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Delete(targets, true));
+            }
+            if ui.button("Change Timestamps…").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Timestamps(targets));
+            }
+            if targets.len() == 1 && ui.button("Rename…").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Rename(clicked.to_path_buf()));
+            }
+        }
+        if let [file] = targets.as_slice() {
+            if self.previewer(file).is_some() && ui.button("Preview").clicked() {
+                ui.close_menu();
+                return Some(EntryAction::Preview(file.clone()));
+            }
+        }
+        if ui.button("Properties").clicked() {
+            ui.close_menu();
+            return Some(EntryAction::Properties(targets));
+        }
+
+        let plugin_actions = self
+            .plugins
+            .iter()
+            .filter(|p| p.enabled)
+            .flat_map(|p| &p.manifest.actions);
+        let custom: Vec<&CustomAction> = self
+            .config
+            .actions
+            .iter()
+            .chain(plugin_actions)
+            // They run arbitrary commands, so read-only mode leaves them out
+            .filter(|a| !self.read_only && a.applies_to(&targets))
+            .collect();
+        if !custom.is_empty() {
+            ui.separator();
+        }
+        for custom_action in custom {
+            if ui.button(&custom_action.name).clicked() {
+                ui.close_menu();
+                return Some(EntryAction::RunCustom(custom_action.clone(), targets));
+            }
+        }
+        None
+    }
+
+    fn send_to_menu(&self, ui: &mut egui::Ui) -> Option<SendTarget> {
+        let mut chosen = None;
+        if ui.button("🗜 Compressed (zip) Archive").clicked() {
+            chosen = Some(SendTarget::Archive);
+        }
+        if ui.button("✉ Email Recipient").clicked() {
+            chosen = Some(SendTarget::Email);
+        }
+
+        let bookmarks: Vec<&PathBuf> = self
+            .config
+            .bookmarks
+            .iter()
+            .filter(|b| **b != self.current_dir)
+            .collect();
+        if !bookmarks.is_empty() {
+            ui.separator();
+        }
+        for bookmark in bookmarks {
+            if ui
+                .button(format!("📁 {}", folder_label(bookmark)))
+                .on_hover_text(bookmark.display().to_string())
+                .clicked()
+            {
+                chosen = Some(SendTarget::Folder(bookmark.clone()));
+            }
+        }
+
+        let removable = drives::removable();
+        if !removable.is_empty() {
+            ui.separator();
+        }
+        for drive in removable {
+            if ui
+                .button(format!("💾 {}", drive.label()))
+                .on_hover_text(drive.description())
+                .clicked()
+            {
+                chosen = Some(SendTarget::Folder(drive.mount_point));
+            }
+        }
+        chosen
+    }
+
+    fn send_to(&mut self, targets: Vec<PathBuf>, target: SendTarget) {
+        match target {
+            SendTarget::Archive => {
+                let name = match targets.as_slice() {
+                    [single] => format!(
+                        "{}.zip",
+                        single
+                            .file_name()
+                            .map(|n| n.to_string_lossy().into_owned())
+                            .unwrap_or_default()
+                    ),
+                    _ => String::from("Archive.zip"),
+                };
+                let destination = self
+                    .current_dir
+                    .join(unique_file_name(&self.current_dir, &name));
+                let folder = Some(self.current_dir.clone());
+                self.jobs
+                    .spawn_in(format!("Compressing {}", name), folder, move |_| {
+                        let result =
+                            archive::create_zip(&targets, &destination).map_err(|e| e.to_string());
+                        history::logged(
+                            history::Operation::Compress,
+                            &targets,
+                            Some(&destination),
+                            result,
+                        )
+                    });
+            }
+            SendTarget::Email => {
+                if let Err(e) = launch::send_by_email(&targets) {
+                    tracing::error!("Failed to open the mail client: {}", e);
+                }
+            }
+            SendTarget::Folder(folder) => {
+                let title = format!(
+                    "Sending {} to {}",
+                    items_label(&targets),
+                    file_name(&folder)
+                );
+                self.jobs.spawn_in(title, Some(folder.clone()), move |job| {
+                    job.set_unit(jobs::ProgressUnit::Items);
+                    let mut result = Ok(());
+                    for (done, source) in targets.iter().enumerate() {
+                        if job.is_cancelled() {
+                            result = Err(String::from("cancelled"));
+                            break;
+                        }
+                        job.set_progress(done as u64, Some(targets.len() as u64));
+                        job.set_message(file_name(source));
+                        if let Err(e) = ops::copy_into(source, &folder) {
+                            result = Err(format!("{}: {}", source.display(), e));
+                            break;
+                        }
+                    }
+                    if result.is_ok() {
+                        job.set_progress(targets.len() as u64, Some(targets.len() as u64));
+                    }
+                    history::logged(history::Operation::Copy, &targets, Some(&folder), result)
+                });
+            }
+        }
+    }
+
+    fn toggle_bookmark(&mut self) {
+        if let Some(index) = self
+            .config
+            .bookmarks
+            .iter()
+            .position(|b| *b == self.current_dir)
+        {
+            self.config.bookmarks.remove(index);
+        } else {
+            self.config.bookmarks.push(self.current_dir.clone());
+        }
+        self.config.save();
+    }
+
+    fn bookmarks_menu(&mut self, ui: &mut egui::Ui) {
+        let bookmarked = self.config.bookmarks.contains(&self.current_dir);
+        let label = if bookmarked {
+            "Remove Bookmark"
+        } else {
+            "Bookmark This Folder"
+        };
+        if ui.button(label).clicked() {
+            self.toggle_bookmark();
+            ui.close_menu();
+        }
+        if !self.config.bookmarks.is_empty() {
+            ui.separator();
+        }
+        let mut chosen = None;
+        for bookmark in &self.config.bookmarks {
+            if ui
+                .button(format!("📁 {}", folder_label(bookmark)))
+                .on_hover_text(bookmark.display().to_string())
+                .clicked()
+            {
+                chosen = Some(bookmark.clone());
+            }
+        }
+        if let Some(dir) = chosen {
+            self.go_to(dir);
+            ui.close_menu();
+        }
+    }
+
+    fn apply_entry_action(&mut self, action: EntryAction) {
+        let destructive = matches!(
+            action,
+            EntryAction::Cut(_)
+                | EntryAction::CreateLink(_)
+                | EntryAction::Rename(_)
+                | EntryAction::Compress(_)
+                | EntryAction::Extract(_)
+                | EntryAction::Split(_)
+                | EntryAction::Join(_)
+                | EntryAction::SendTo(..)
+                | EntryAction::Encrypt(_)
+                | EntryAction::Gpg(
+                    _,
+                    gpg::Operation::Encrypt(..) | gpg::Operation::Sign | gpg::Operation::Decrypt
+                )
+                | EntryAction::Trash(_)
+                | EntryAction::Restore(..)
+                | EntryAction::RestoreTo(_)
+                | EntryAction::Delete(..)
+                | EntryAction::Timestamps(_)
+                | EntryAction::RunCustom(..)
+        );
+        if destructive && self.read_only {
+            return;
+        }
+        match action {
+            EntryAction::Copy(targets) => {
+                self.toasts.push(
+                    format!("{} copied", ops::plural(targets.len(), "item")),
+                    None,
+                );
+                self.file_clipboard = targets;
+                self.clipboard_cut = false;
+            }
+            EntryAction::Cut(targets) => {
+                self.toasts
+                    .push(format!("{} cut", ops::plural(targets.len(), "item")), None);
+                self.file_clipboard = targets;
+                self.clipboard_cut = true;
+            }
+            EntryAction::CreateLink(targets) => self.open_link_dialog(targets),
+            EntryAction::RunCustom(custom_action, targets) => {
+                if let Err(e) = custom_action.run(&targets, &self.current_dir) {
+                    tracing::error!("Failed to run \"{}\": {}", custom_action.name, e);
+                }
+            }
+            EntryAction::SendTo(targets, target) => self.send_to(targets, target),
+            EntryAction::Reveal(path, new_tab) => {
+                if let Some(folder) = path.parent().map(Path::to_path_buf) {
+                    if new_tab {
+                        self.open_tab(folder, true);
+                    } else {
+                        self.go_to(folder);
+                    }
+                    self.selected.insert(path);
+                }
+            }
+            EntryAction::Properties(mut targets) => {
+                if targets.len() == 1 {
+                    self.properties.push(PropertiesDialog::new(
+                        targets.remove(0),
+                        self.repaint.clone(),
+                        self.read_only,
+                    ));
+                } else if !targets.is_empty() {
+                    self.bulk_properties.push(BulkPropertiesDialog::new(
+                        targets,
+                        self.repaint.clone(),
+                        self.read_only,
+                    ));
+                }
+            }
+            EntryAction::Rename(path) => self.open_rename_dialog(path),
+            EntryAction::Encrypt(targets) => {
+                self.encrypt_dialog = Some(EncryptDialog {
+                    targets,
+                    keys: gpg::encryption_keys(),
+                    recipient: None,
+                    trust_anyway: false,
+                });
+            }
+            EntryAction::Gpg(targets, operation) => self.run_gpg(targets, operation),
+            EntryAction::Preview(path) => self.open_preview(path),
+            EntryAction::Compress(targets) => {
+                let name = match targets.as_slice() {
+                    [single] if single.is_dir() => items_label(&targets),
+                    [single] => single
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    _ => String::from("Archive"),
+                };
+                self.compress_dialog = Some(CompressDialog {
+                    targets,
+                    name,
+                    format: archive::Format::Zip,
+                    use_password: false,
+                    password: String::new(),
+                    confirm: String::new(),
+                });
+            }
+            EntryAction::Extract(path) => match archive::needs_password(&path) {
+                Ok(true) => self.extract_password = Some((path, String::new())),
+                Ok(false) => self.extract(path, None),
+                Err(e) => tracing::error!("Failed to read {}: {}", path.display(), e),
+            },
+            EntryAction::BrowseImage(path) => match iso::IsoImage::open(&path) {
+                Ok(image) => {
+                    let mut browser = ImageBrowser {
+                        path,
+                        trail: vec![image.root()],
+                        image,
+                        entries: Vec::new(),
+                        selected: HashSet::new(),
+                        error: None,
+                    };
+                    browser.load();
+                    self.image_browser = Some(browser);
+                }
+                Err(e) => tracing::error!("Failed to open {}: {}", path.display(), e),
+            },
+            EntryAction::Split(path) => {
+                self.split_dialog = Some(SplitDialog {
+                    path,
+                    chunk_mib: 100,
+                });
+            }
+            EntryAction::Timestamps(targets) => {
+                // Start from the first item's time, the usual one to correct
+                let datetime = targets
+                    .first()
+                    .and_then(|t| fs::metadata(t).and_then(|m| m.modified()).ok())
+                    .map(chrono::DateTime::<chrono::Local>::from)
+                    .unwrap_or_else(chrono::Local::now);
+                self.timestamp_dialog = Some(TimestampDialog {
+                    targets,
+                    shift: false,
+                    datetime: datetime.format(TIMESTAMP_FORMAT).to_string(),
+                    days: 0,
+                    hours: 0,
+                    minutes: 0,
+                    seconds: 0,
+                    modified: true,
+                    created: false,
+                    error: None,
+                });
+            }
+            EntryAction::Join(path) => {
+                self.jobs.spawn(
+                    format!("Joining {}", items_label(std::slice::from_ref(&path))),
+                    move |job| split::join(&path, job).map(|_| ()),
+                );
+            }
+            EntryAction::MountImage(path) => match iso::mount(&path) {
+                Ok(mount_point) => {
+                    self.go_to(mount_point);
+                }
+                Err(e) => tracing::error!("Failed to mount {}: {}", path.display(), e),
+            },
+            EntryAction::Trash(targets) => self.request_trash(targets),
+            EntryAction::Restore(targets, folder) => {
+                let title = match &folder {
+                    Some(folder) => format!(
+                        "Restoring {} to {}",
+                        items_label(&targets),
+                        folder.display()
+                    ),
+                    None => format!("Restoring {}", items_label(&targets)),
+                };
+                self.selected.clear();
+                self.jobs.spawn(title, move |job| {
+                    trash::restore(&targets, folder.as_deref(), job)
+                });
+            }
+            EntryAction::RestoreTo(targets) => {
+                // Starts from where the first one came from
+                let folder = targets
+                    .first()
+                    .and_then(|t| self.trashed.get(&*t.file_name()?.to_string_lossy()))
+                    .and_then(|t| t.original.parent())
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default();
+                self.restore_dialog = Some(RestoreDialog { targets, folder });
+            }
+            EntryAction::Delete(targets, secure) => self.request_delete(targets, secure),
+            EntryAction::ConfirmRun(path) => {
+                let origin = launch::download_origin(&path);
+                if origin.is_none() && !self.config.confirmations.run_executables {
+                    if let Err(e) = launch::run(&path) {
+                        tracing::error!("Failed to run {}: {}", path.display(), e);
+                    }
+                    return;
+                }
+                self.run_dialog = Some(RunDialog {
+                    is_script: launch::is_script(&path),
+                    origin,
+                    trusted: false,
+                    error: None,
+                    dont_ask: false,
+                    path,
+                });
+            }
+        }
+    }
+
+    // The paths also go on the system clipboard as text, both so they can be
+    // pasted elsewhere and because egui only reports Ctrl+V when there is text
+    fn copy_selection(&mut self, ctx: &egui::Context, cut: bool) {
+        if cut && self.read_only {
+            return;
+        }
+        if !self.selected.is_empty() {
+            self.file_clipboard = self
+                .entries
+                .iter()
+                .map(|e| self.current_dir.join(&e.name))
+                .filter(|p| self.selected.contains(p))
+                .collect();
+            self.clipboard_cut = cut;
+            ctx.copy_text(paths_text(&self.file_clipboard));
+            let count = ops::plural(self.file_clipboard.len(), "item");
+            self.toasts.push(
+                format!("{} {}", count, if cut { "cut" } else { "copied" }),
+                None,
+            );
+        }
+    }
+
+    // Whether pasted text is still what copy_selection put there, meaning the
+    // copied files should be pasted rather than the text
+    fn is_file_clipboard(&self, text: &str) -> bool {
+        !self.file_clipboard.is_empty() && text == paths_text(&self.file_clipboard)
+    }
+
+    // Escape during a drag: back out of the folders it opened on the way,
+    // without dropping
+    fn cancel_drag(&mut self, ctx: &egui::Context) {
+        dnd::cancel(ctx);
+        let Some((tab, dir)) = self.drag_origin.take() else {
+            return;
+        };
+        self.switch_tab(tab);
+        if self.current_dir != dir || self.overview {
+            self.current_dir = dir;
+            self.read_dir();
+        }
+    }
+
+    // Moves or copies files dragged onto a folder
+    fn drop_files(&mut self, ctx: &egui::Context, sources: Vec<PathBuf>, destination: PathBuf) {
+        if self.read_only {
+            return;
+        }
+        // Dropping items where they already are, or a folder into itself,
+        // does nothing
+        let sources: Vec<PathBuf> = sources
+            .into_iter()
+            .filter(|s| s.parent() != Some(destination.as_path()) && !destination.starts_with(s))
+            .collect();
+        if sources.is_empty() {
+            return;
+        }
+        let mode = dnd::mode(ctx.input(|i| i.modifiers), &sources, &destination);
+        self.start_transfer(ops::Transfer {
+            sources,
+            destination,
+            mode,
+            options: self.config.transfers.clone(),
+            resume: None,
+        });
+    }
+
+    // Checks first whether links would get lost on the way
+    fn start_transfer(&mut self, transfer: ops::Transfer) {
+        if transfer.options.preserve_symlinks {
+            let capabilities = Capabilities::detect(&transfer.destination);
+            if !capabilities.symlinks && has_symlinks(&transfer.sources) {
+                self.symlink_confirm = Some(SymlinkConfirm {
+                    transfer,
+                    capabilities,
+                });
+                return;
+            }
+        }
+        self.run_transfer(transfer);
+    }
+
+    fn run_transfer(&mut self, transfer: ops::Transfer) {
+        let folder = Some(transfer.destination.clone());
+        let count = ops::plural(transfer.sources.len(), "item");
+        let to = file_name(&transfer.destination);
+        match transfer.mode {
+            ops::TransferMode::Copy => {
+                let text = format!("{} copied to {}", count, to);
+                self.toasts.after_job(transfer.title(), text, None);
+            }
+            ops::TransferMode::Move => {
+                let moved = transfer
+                    .sources
+                    .iter()
+                    .filter_map(|s| Some((transfer.destination.join(s.file_name()?), s.clone())))
+                    .collect();
+                let text = format!("{} moved to {}", count, to);
+                self.toasts
+                    .after_job(transfer.title(), text, Some(toasts::Undo::Move(moved)));
+            }
+        }
+        let sources = transfer.sources.clone();
+        let title = transfer.title();
+        let batch: jobs::Batch = Arc::new(move |job, sources| {
+            let transfer = ops::Transfer {
+                sources: sources.to_vec(),
+                ..transfer.clone()
+            };
+            history::logged_transfer(&transfer, transfer.run(job))
+        });
+        self.jobs.spawn_batch(title, folder, sources, batch);
+    }
+
+    fn show_partial_failure(&mut self, ctx: &egui::Context) {
+        let Some(report) = self.partial_failures.first() else {
+            return;
+        };
+        let mut open = true;
+        let mut retry = false;
+        let mut skip = false;
+
+        egui::Window::new("Some items were skipped")
+            .id(self.id.with("partial_failure"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{}: {} couldn't be handled. Everything else went through.",
+                    report.title,
+                    ops::plural(report.failures.len(), "item")
+                ));
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("partial_failures")
+                            .num_columns(2)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for failure in &report.failures {
+                                    ui.label(failure.path.display().to_string());
+                                    ui.vertical(|ui| {
+                                        ui.weak(&failure.error);
+                                        if !failure.held_by.is_empty() {
+                                            ui.label(format!(
+                                                "In use by {}",
+                                                failure.held_by.join(", ")
+                                            ));
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                ui.weak("Close the programs using them or check their permissions, then retry.");
+                ui.horizontal(|ui| {
+                    if ui.button("Retry Skipped").clicked() {
+                        retry = true;
+                    }
+                    if ui.button("Skip").clicked() {
+                        skip = true;
+                    }
+                });
+            });
+
+        if retry {
+            let report = self.partial_failures.remove(0);
+            let mut targets: Vec<PathBuf> = Vec::new();
+            for failure in report.failures {
+                if !targets.contains(&failure.target) {
+                    targets.push(failure.target);
+                }
+            }
+            self.jobs
+                .spawn_batch(report.title, report.folder, targets, report.batch);
+        } else if skip || !open {
+            self.partial_failures.remove(0);
+        }
+    }
+
+    fn show_symlink_confirm(&mut self, ctx: &egui::Context) {
+        let Some(confirm) = &self.symlink_confirm else {
+            return;
+        };
+        let mut open = true;
+        let mut go_ahead = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Links can't be copied")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} can't store links. The files and folders they point to will be copied in their place.",
+                    confirm.capabilities.drive_label()
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Copy Anyway").clicked() {
+                        go_ahead = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if go_ahead {
+            let confirm = self.symlink_confirm.take().unwrap();
+            self.run_transfer(confirm.transfer);
+        } else if cancel || !open {
+            self.symlink_confirm = None;
+        }
+    }
+
+    fn show_interrupted(&mut self, ctx: &egui::Context) {
+        if self.interrupted.is_empty() {
+            return;
+        }
+        let mut open = true;
+        let mut resumed = None;
+        let mut discarded = None;
+
+        salted_window(self.id, "Interrupted Transfers")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label("These transfers didn't finish the last time the app ran.");
+                for (i, checkpoint) in self.interrupted.iter().enumerate() {
+                    ui.separator();
+                    ui.label(checkpoint.describe());
+                    ui.horizontal(|ui| {
+                        if ui.button("Resume").clicked() {
+                            resumed = Some(i);
+                        }
+                        if ui.button("Discard").clicked() {
+                            discarded = Some(i);
+                        }
+                    });
+                }
+            });
+
+        if let Some(i) = resumed {
+            let checkpoint = self.interrupted.remove(i);
+            // The resumed transfer writes a checkpoint of its own
+            checkpoint.remove();
+            self.start_transfer(checkpoint.transfer());
+        } else if let Some(i) = discarded {
+            self.interrupted.remove(i).remove();
+        } else if !open {
+            // Still offered on the next start
+            self.interrupted.clear();
+        }
+    }
+
+    // Long jobs that ended while the user was in another window
+    fn notify_ended(&self, ctx: &egui::Context, ended: Vec<jobs::Ended>) {
+        let options = &self.config.notifications;
+        if !options.enabled || ctx.input(|i| i.viewport().focused) != Some(false) {
+            return;
+        }
+        for job in ended {
+            if job.took.as_secs() < options.after_secs {
+                continue;
+            }
+            let body = match job.state {
+                jobs::JobState::Finished => String::from("Finished"),
+                jobs::JobState::Failed(e) => format!("Failed: {}", e),
+                _ => continue,
+            };
+            notify::send(job.title, body, job.folder, self.repaint.clone());
+        }
+    }
+
+    fn paste_files(&mut self) {
+        if self.read_only {
+            return;
+        }
+        let transfer = ops::Transfer {
+            sources: self.file_clipboard.clone(),
+            destination: self.current_dir.clone(),
+            mode: if self.clipboard_cut {
+                ops::TransferMode::Move
+            } else {
+                ops::TransferMode::Copy
+            },
+            options: self.config.transfers.clone(),
+            resume: None,
+        };
+        // Cut files are gone from their old place after one paste
+        if self.clipboard_cut {
+            self.file_clipboard.clear();
+            self.clipboard_cut = false;
+        }
+        self.start_transfer(transfer);
+    }
+
+    fn paste(&mut self, content: PastedContent) {
+        if self.read_only {
+            return;
+        }
+        match content {
+            PastedContent::Text(text) if self.is_file_clipboard(&text) => self.paste_files(),
+            content => self.open_paste_dialog(content),
+        }
+    }
+
+    fn show_download_dialog(&mut self, ctx: &egui::Context) {
+        if self.read_only {
+            self.download_dialog = None;
+            return;
+        }
+        let Some(dialog) = &mut self.download_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut start = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Download from URL")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Save to: {}", self.current_dir.display()));
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut dialog.url)
+                        .hint_text("https://example.com/file.zip")
+                        .desired_width(360.0),
+                );
+                response.request_focus();
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    start = true;
+                }
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Download").clicked() {
+                        start = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if start {
+            let url = dialog.url.trim().to_string();
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                dialog.error = Some(String::from("Only http and https URLs are supported"));
+                return;
+            }
+            let name = download::file_name_from_url(&url);
+            let destination = self
+                .current_dir
+                .join(unique_file_name(&self.current_dir, &name));
+            self.jobs
+                .spawn(format!("Downloading {}", name), move |job| {
+                    download::download(&url, &destination, job).map(|_| ())
+                });
+            self.download_dialog = None;
+        } else if cancel || !open {
+            self.download_dialog = None;
+        }
+    }
+
+    // The folder a Go menu entry leads to; None for the ones that aren't a
+    // folder or aren't there on this system
+    fn location_dir(&self, location: Location) -> Option<PathBuf> {
+        match location {
+            Location::Home => user_dirs::home_dir(),
+            Location::Desktop => user_dirs::desktop_dir(),
+            Location::Documents => user_dirs::documents_dir(),
+            Location::Downloads => user_dirs::downloads_dir(),
+            // The root of the current drive on Windows
+            Location::Root => self.current_dir.ancestors().last().map(Path::to_path_buf),
+            Location::Trash => trash::files_dir(),
+            Location::Recent | Location::Network | Location::Enter => None,
+        }
+        .filter(|dir| dir.is_dir())
+    }
+
+    fn go_menu(&mut self, ui: &mut egui::Ui) {
+        for location in Location::ALL {
+            if matches!(location, Location::Network | Location::Enter) {
+                ui.separator();
+            }
+            let available = match location {
+                Location::Recent | Location::Network | Location::Enter => true,
+                _ => self.location_dir(location).is_some(),
+            };
+            let button = egui::Button::new(location.label())
+                .shortcut_text(ui.ctx().format_shortcut(&location.shortcut()));
+            if ui.add_enabled(available, button).clicked() {
+                self.go(location);
+                ui.close_menu();
+            }
+        }
+    }
+
+    fn go(&mut self, location: Location) {
+        match location {
+            Location::Network => {
+                if self.network.is_none() {
+                    self.network = Some(network::Discovery::start());
+                }
+            }
+            Location::Recent => self.show_recent(),
+            Location::Enter => {
+                self.location_dialog = Some(LocationDialog {
+                    path: self.address(),
+                    error: None,
+                });
+            }
+            _ => match self.location_dir(location) {
+                Some(dir) => self.go_to(dir),
+                None => tracing::warn!("There's no {} folder here", location.label()),
+            },
+        }
+    }
+
+    fn show_location_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.location_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut go = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Enter Location")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut dialog.path)
+                        .hint_text("~/Documents")
+                        .desired_width(360.0),
+                );
+                response.request_focus();
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    go = true;
+                }
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Go").clicked() {
+                        go = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if go {
+            // file:// URIs as browsers copy them, and the virtual locations
+            let typed = match uri::parse_address(&dialog.path) {
+                Ok(uri::Address::Path(path)) => path,
+                Ok(uri::Address::Trash(relative)) => match trash::files_dir() {
+                    Some(files) => files.join(relative),
+                    None => {
+                        dialog.error = Some(String::from("There's no trash here"));
+                        return;
+                    }
+                },
+                Ok(uri::Address::Recent) => {
+                    self.location_dialog = None;
+                    self.show_recent();
+                    return;
+                }
+                Err(e) => {
+                    dialog.error = Some(e);
+                    return;
+                }
+            };
+            let path = match typed.strip_prefix("~") {
+                Ok(rest) => match user_dirs::home_dir() {
+                    Some(home) => home.join(rest),
+                    None => typed,
+                },
+                Err(_) => typed,
+            };
+            if path.is_dir() {
+                self.location_dialog = None;
+                self.go_to(path);
+            } else if let (true, Some(parent)) = (path.is_file(), path.parent()) {
+                // A file opens its folder with the file selected
+                self.location_dialog = None;
+                self.go_to(parent.to_path_buf());
+                self.selected.insert(path);
+            } else {
+                dialog.error = Some(format!("{} doesn't exist", path.display()));
+            }
+        } else if cancel || !open {
+            self.location_dialog = None;
+        }
+    }
+
+    fn show_run_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.run_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut close = false;
+        let name = dialog
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut ran = false;
+        salted_window(self.id, "Run executable file?")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if dialog.is_script {
+                    ui.label(format!(
+                        "\"{}\" is an executable script. Do you want to run it or open it for editing?",
+                        name
+                    ));
+                } else {
+                    ui.label(format!("\"{}\" is an executable program.", name));
+                }
+
+                if let Some(origin) = &dialog.origin {
+                    ui.separator();
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "⚠ This file was downloaded from {}. Only run it if you trust its source.",
+                            origin
+                        ),
+                    );
+                    ui.checkbox(&mut dialog.trusted, "I trust this file");
+                }
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                // Downloaded files keep asking
+                if dialog.origin.is_none() {
+                    ui.checkbox(&mut dialog.dont_ask, "Don't ask again");
+                }
+
+                let can_run = dialog.origin.is_none() || dialog.trusted;
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(can_run, egui::Button::new("Run")).clicked() {
+                        match launch::run(&dialog.path) {
+                            Ok(()) => ran = true,
+                            Err(e) => dialog.error = Some(format!("Failed to run: {}", e)),
+                        }
+                    }
+                    if ui
+                        .add_enabled(can_run, egui::Button::new("Run in Terminal"))
+                        .clicked()
+                    {
+                        match launch::run_in_terminal(&dialog.path) {
+                            Ok(()) => ran = true,
+                            Err(e) => dialog.error = Some(format!("Failed to run: {}", e)),
+                        }
+                    }
+                    if dialog.is_script && ui.button("Open for Editing").clicked() {
+                        match launch::open_in_editor(&dialog.path) {
+                            Ok(()) => close = true,
+                            Err(e) => dialog.error = Some(format!("Failed to open: {}", e)),
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close = true;
+                    }
+                });
+            });
+
+        if ran && dialog.dont_ask {
+            self.config.confirmations.run_executables = false;
+            self.config.save();
+        }
+        if ran || close || !open {
+            self.run_dialog = None;
+        }
+    }
+
+    fn open_link_dialog(&mut self, targets: Vec<PathBuf>) {
+        self.link_dialog = Some(LinkDialog {
+            targets,
+            kind: LinkKind::Symbolic,
+            relative: false,
+            errors: Vec::new(),
+        });
+    }
+
+    fn show_link_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.link_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut create = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Create link here")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if let [target] = dialog.targets.as_slice() {
+                    ui.label(format!("Target: {}", target.display()));
+                } else {
+                    ui.label(format!("{} targets", dialog.targets.len()));
+                }
+                ui.label(format!("Link location: {}", self.current_dir.display()));
+                ui.separator();
+                for kind in LinkKind::all() {
+                    ui.radio_value(&mut dialog.kind, *kind, kind.label());
+                }
+                ui.add_enabled(
+                    dialog.kind.supports_relative(),
+                    egui::Checkbox::new(&mut dialog.relative, "Relative target path"),
+                );
+                for error in &dialog.errors {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Create").clicked() {
+                        create = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if create {
+            dialog.errors.clear();
+            let mut created = Vec::new();
+            for target in &dialog.targets {
+                let mut name = dialog.kind.link_name(target);
+                if target.parent() == Some(self.current_dir.as_path()) {
+                    name = format!("Link to {}", name);
+                }
+                let link = self
+                    .current_dir
+                    .join(unique_file_name(&self.current_dir, &name));
+                let result = links::create_link(target, &link, dialog.kind, dialog.relative)
+                    .map_err(|e| e.to_string());
+                history::record(
+                    history::Operation::Link,
+                    std::slice::from_ref(target),
+                    Some(&link),
+                    &result,
+                );
+                match result {
+                    Ok(()) => created.push(link),
+                    Err(e) => dialog.errors.push(format!("{}: {}", target.display(), e)),
+                }
+            }
+            if dialog.errors.is_empty() {
+                self.link_dialog = None;
+            }
+            self.read_dir();
+            self.selected = created.into_iter().collect();
+        } else if cancel || !open {
+            self.link_dialog = None;
+        }
+    }
+
+    // Indices of the entries the filters let through
+    fn visible_entries(&self) -> Vec<usize> {
+        let matcher = self.filters.matcher();
+        (0..self.entries.len())
+            .filter(|&i| {
+                let entry = &self.entries[i];
+                let file_size = (entry.loaded && !entry.is_dir).then_some(entry.size);
+                matcher.matches(entry.modified, file_size)
+            })
+            .collect()
+    }
+
+    // Only what's shown, so nothing hidden by a filter gets caught up in
+    // a following delete or move
+    fn select_all(&mut self) {
+        self.selected = self
+            .visible_entries()
+            .into_iter()
+            .map(|i| self.current_dir.join(&self.entries[i].name))
+            .collect();
+    }
+
+    fn invert_selection(&mut self) {
+        self.selected = self
+            .visible_entries()
+            .into_iter()
+            .map(|i| self.current_dir.join(&self.entries[i].name))
+            .filter(|p| !self.selected.contains(p))
+            .collect();
+    }
+
+    fn select_matching(&mut self, patterns: &str, case_sensitive: bool, extend: bool) {
+        if !extend {
+            self.selected.clear();
+        }
+        for entry in &self.entries {
+            if search::matches_any(patterns, &entry.name, case_sensitive) {
+                self.selected.insert(self.current_dir.join(&entry.name));
+            }
+        }
+        if self.listing.is_some() {
+            self.pending_select
+                .push((patterns.to_string(), case_sensitive));
+        }
+    }
+
+    // Which browser keys go to when an app shows several in one window: the
+    // one under the pointer, or else the one last clicked. Both are kept in
+    // egui's memory, where every browser can see them.
+    fn has_keyboard(&self, ui: &egui::Ui) -> bool {
+        let viewport = ui.ctx().viewport_id();
+        let focused_key = egui::Id::new(("file_browser_focused", viewport));
+        let hovered_key = egui::Id::new(("file_browser_hovered", viewport));
+        let hovered = ui.rect_contains_pointer(ui.max_rect());
+        let clicked = hovered && ui.input(|i| i.pointer.any_pressed());
+        ui.ctx().data_mut(|data| {
+            let focused = data.get_temp::<egui::Id>(focused_key);
+            if clicked || focused.is_none() {
+                data.insert_temp(focused_key, self.id);
+            }
+            let other_hovered = data
+                .get_temp::<egui::Id>(hovered_key)
+                .is_some_and(|id| id != self.id);
+            if hovered {
+                data.insert_temp(hovered_key, self.id);
+            } else if !other_hovered {
+                data.remove::<egui::Id>(hovered_key);
+            }
+            hovered || (focused.is_none_or(|id| id == self.id) && !other_hovered)
+        })
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        use egui::{Key, KeyboardShortcut, Modifiers};
+
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        // Before the rest, as Ctrl+Shift+D would also count as Ctrl+D
+        for location in Location::ALL {
+            if ctx.input_mut(|i| i.consume_shortcut(&location.shortcut())) {
+                self.go(location);
+            }
+        }
+        let select_all = KeyboardShortcut::new(Modifiers::COMMAND, Key::A);
+        let invert = KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::I);
+        let by_pattern = KeyboardShortcut::new(Modifiers::COMMAND, Key::S);
+
+        if ctx.input_mut(|i| i.consume_shortcut(&select_all)) {
+            self.select_all();
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&invert)) {
+            self.invert_selection();
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&by_pattern)) {
+            self.open_select_pattern();
+        }
+        let search = KeyboardShortcut::new(Modifiers::COMMAND, Key::F);
+        if ctx.input_mut(|i| i.consume_shortcut(&search)) {
+            ctx.memory_mut(|m| m.request_focus(self.id.with("search")));
+        }
+        let refresh = KeyboardShortcut::new(Modifiers::COMMAND, Key::R);
+        if ctx.input_mut(|i| i.consume_shortcut(&refresh) || i.key_pressed(Key::F5)) {
+            self.read_dir();
+        }
+        let bookmark = KeyboardShortcut::new(Modifiers::COMMAND, Key::D);
+        if ctx.input_mut(|i| i.consume_shortcut(&bookmark)) {
+            self.toggle_bookmark();
+        }
+        if ctx.input(|i| i.key_pressed(Key::Escape)) {
+            if dnd::dragged(ctx).is_some() {
+                self.cancel_drag(ctx);
+            } else {
+                self.selected.clear();
+            }
+        }
+        if ctx.input(|i| i.key_pressed(Key::F2)) && self.selected.len() == 1 && !self.read_only {
+            if let Some(path) = self.selected.iter().next().cloned() {
+                self.open_rename_dialog(path);
+            }
+        }
+        if ctx.input(|i| i.key_pressed(Key::Delete)) && !self.selected.is_empty() && !self.read_only
+        {
+            // Shift+Delete skips the trash
+            if ctx.input(|i| i.modifiers.shift) || !self.can_trash() {
+                self.request_delete(self.selection_in_order(), false);
+            } else {
+                self.request_trash(self.selection_in_order());
+            }
+        }
+
+        if ctx.input(|i| i.events.contains(&egui::Event::Copy)) {
+            self.copy_selection(ctx, false);
+        }
+        if ctx.input(|i| i.events.contains(&egui::Event::Cut)) {
+            self.copy_selection(ctx, true);
+        }
+
+        // egui only turns Ctrl+V into an event when the clipboard holds text,
+        // and swallows the key press otherwise. Letting go of V with Ctrl
+        // still down does come through, so anything else is read then.
+        let pasted_text = ctx.input(|i| {
+            i.events.iter().find_map(|e| match e {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            })
+        });
+        let released = ctx.input(|i| {
+            i.events.iter().any(|e| {
+                matches!(e, egui::Event::Key { key: Key::V, pressed: false, modifiers, .. }
+                    if modifiers.command)
+            })
+        });
+        if let Some(text) = pasted_text {
+            self.text_pasted = true;
+            self.paste(PastedContent::Text(text));
+        }
+        if released && !std::mem::take(&mut self.text_pasted) {
+            self.paste_clipboard();
+        }
+    }
+
+    // Whatever the clipboard holds, or else the files copied in the app
+    fn paste_clipboard(&mut self) {
+        match clipboard::read() {
+            Some(content) => self.paste(content),
+            None if !self.file_clipboard.is_empty() => self.paste_files(),
+            None => {}
+        }
+    }
+
+    fn open_paste_dialog(&mut self, content: PastedContent) {
+        let name = unique_file_name(&self.current_dir, content.default_name());
+        self.paste_dialog = Some(PasteDialog {
+            content,
+            name,
+            error: None,
+        });
+    }
+
+    fn new_menu(&mut self, ui: &mut egui::Ui) {
+        let mut chosen = None;
+        if ui.button("Folder…").clicked() {
+            chosen = Some(NewItem::Folder);
+        }
+        if ui.button("Empty File…").clicked() {
+            chosen = Some(NewItem::File);
+        }
+
+        let templates = templates::load();
+        if !templates.is_empty() {
+            ui.separator();
+            if let Some(path) = template_menu(ui, &templates) {
+                chosen = Some(NewItem::Template(path));
+            }
+        }
+
+        if let Some(item) = chosen {
+            self.open_new_item_dialog(item);
+            ui.close_menu();
+        }
+    }
+
+    fn open_new_item_dialog(&mut self, item: NewItem) {
+        let default_name = match &item {
+            NewItem::Folder => String::from("New Folder"),
+            NewItem::File => String::from("New File"),
+            NewItem::Template(path) => path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        self.new_item_dialog = Some(NewItemDialog {
+            name: unique_file_name(&self.current_dir, &default_name),
+            item,
+            error: None,
+        });
+    }
+
+    fn show_new_item_dialog(&mut self, ctx: &egui::Context) {
+        if self.read_only {
+            self.new_item_dialog = None;
+            return;
+        }
+        let Some(dialog) = &mut self.new_item_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut create = false;
+        let mut cancel = false;
+
+        let title = match &dialog.item {
+            NewItem::Folder => "New folder",
+            NewItem::File => "New file",
+            NewItem::Template(_) => "New document",
+        };
+        salted_window(self.id, title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    let edit = ui.text_edit_singleline(&mut dialog.name);
+                    if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        create = true;
+                    }
+                });
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Create").clicked() {
+                        create = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if create {
+            let name = dialog.name.trim();
+            let path = self.current_dir.join(name);
+            let result = if name.is_empty() {
+                Err(String::from("Please enter a name"))
+            } else if name_taken(&LocalFs, &self.current_dir, name) {
+                Err(format!("\"{}\" already exists", name))
+            } else {
+                let result = match &dialog.item {
+                    NewItem::Folder => fs::create_dir(&path),
+                    NewItem::File => fs::OpenOptions::new()
+                        .write(true)
+                        .create_new(true)
+                        .open(&path)
+                        .map(|_| ()),
+                    NewItem::Template(template) => fs::copy(template, &path).map(|_| ()),
+                }
+                .map_err(|e| e.to_string());
+                history::logged(
+                    history::Operation::Create,
+                    std::slice::from_ref(&path),
+                    None,
+                    result,
+                )
+            };
+            match result {
+                Ok(()) => {
+                    self.new_item_dialog = None;
+                    self.read_dir();
+                    self.selected.clear();
+                    self.selected.insert(path);
+                }
+                Err(e) => dialog.error = Some(e),
+            }
+        } else if cancel || !open {
+            self.new_item_dialog = None;
+        }
+    }
+
+    fn open_rename_dialog(&mut self, path: PathBuf) {
+        self.rename_dialog = Some(RenameDialog {
+            name: file_name(&path),
+            path,
+            replace: None,
+            error: None,
+        });
+    }
+
+    fn show_rename_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.rename_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut confirm = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Rename")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let mut use_regex = dialog.replace.is_some();
+                if ui
+                    .checkbox(&mut use_regex, "Find and replace with a regular expression")
+                    .changed()
+                {
+                    dialog.replace = use_regex.then(|| Replace {
+                        regex: true,
+                        ..Replace::default()
+                    });
+                }
+                let mut enter = false;
+                match &mut dialog.replace {
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.label("Name:");
+                            let edit = ui.text_edit_singleline(&mut dialog.name);
+                            enter |= edit.lost_focus();
+                        });
+                    }
+                    Some(replace) => {
+                        egui::Grid::new("rename_regex")
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                ui.label("Find:");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut replace.find)
+                                        .hint_text(r"e.g. (\w+)-(\d+)"),
+                                );
+                                ui.end_row();
+                                ui.label("Replace with:");
+                                let edit = ui.add(
+                                    egui::TextEdit::singleline(&mut replace.replace)
+                                        .hint_text("e.g. $2 $1"),
+                                );
+                                enter |= edit.lost_focus();
+                                ui.end_row();
+                            });
+                        ui.checkbox(&mut replace.case_sensitive, "Match case");
+                    }
+                }
+                if enter && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    confirm = true;
+                }
+                if dialog.replace.is_some() {
+                    match dialog.new_name() {
+                        Ok(name) => ui.label(format!("New name: {}", name)),
+                        Err(e) => ui.colored_label(ui.visuals().error_fg_color, e),
+                    };
+                }
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Rename").clicked() {
+                        confirm = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if confirm {
+            let result = dialog.new_name().and_then(|name| {
+                let destination = dialog.path.with_file_name(&name);
+                if destination == dialog.path {
+                    return Ok(destination);
+                }
+                let result = rename::rename(&LocalFs, &dialog.path, &destination).map_err(|e| {
+                    match e.kind() {
+                        std::io::ErrorKind::AlreadyExists => format!("\"{}\" already exists", name),
+                        _ => locks::describe_error(&dialog.path, &e),
+                    }
+                });
+                history::logged(
+                    history::Operation::Rename,
+                    std::slice::from_ref(&dialog.path),
+                    Some(&destination),
+                    result,
+                )
+                .map(|()| destination)
+            });
+            match result {
+                Ok(destination) => {
+                    if destination != dialog.path {
+                        let undo = toasts::Undo::Rename {
+                            from: destination.clone(),
+                            to: dialog.path.clone(),
+                        };
+                        let text = format!("Renamed to \"{}\"", file_name(&destination));
+                        self.toasts.push(text, Some(undo));
+                    }
+                    self.rename_dialog = None;
+                    self.read_dir();
+                    self.selected.clear();
+                    self.selected.insert(destination);
+                }
+                Err(e) => dialog.error = Some(e),
+            }
+        } else if cancel || !open {
+            self.rename_dialog = None;
+        }
+    }
+
+    fn show_paste_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.paste_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut save = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Save clipboard contents")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(dialog.content.describe());
+                ui.horizontal(|ui| {
+                    ui.label("File name:");
+                    let edit = ui.text_edit_singleline(&mut dialog.name);
+                    if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        save = true;
+                    }
+                });
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        save = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if save {
+            let path = self.current_dir.join(dialog.name.trim());
+            let result = if dialog.name.trim().is_empty() {
+                Err(String::from("Please enter a file name"))
+            } else if name_taken(&LocalFs, &self.current_dir, dialog.name.trim()) {
+                Err(format!("\"{}\" already exists", dialog.name.trim()))
+            } else {
+                dialog.content.save(&path).map_err(|e| e.to_string())
+            };
+            match result {
+                Ok(()) => {
+                    self.paste_dialog = None;
+                    self.read_dir();
+                    self.selected.clear();
+                    self.selected.insert(path);
+                }
+                Err(e) => dialog.error = Some(e),
+            }
+        } else if cancel || !open {
+            self.paste_dialog = None;
+        }
+    }
+
+    fn open_select_pattern(&mut self) {
+        self.select_pattern = Some(SelectPatternDialog {
+            pattern: String::from("*"),
+            case_sensitive: false,
+            extend: false,
+        });
+    }
+
+    // Extracts next to the archive, which among search results may not be
+    // the current folder, into a folder named after it
+    fn extract(&mut self, path: PathBuf, password: Option<String>) {
+        let name = archive::extracted_name(&path);
+        let folder = path.parent().unwrap_or(&self.current_dir).to_path_buf();
+        let destination = folder.join(unique_file_name(&folder, &name));
+        self.jobs.spawn_in(
+            format!("Extracting {}", items_label(std::slice::from_ref(&path))),
+            Some(folder),
+            move |_| {
+                let result = archive::extract(&path, &destination, password.as_deref())
+                    .map_err(|e| e.to_string());
+                history::logged(
+                    history::Operation::Extract,
+                    std::slice::from_ref(&path),
+                    Some(&destination),
+                    result,
+                )
+            },
+        );
+    }
+
+    fn show_image_browser(&mut self, ctx: &egui::Context) {
+        let Some(browser) = &mut self.image_browser else {
+            return;
+        };
+        let mut open = true;
+        let mut extract = None;
+
+        let title = browser
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        salted_window(self.id, format!("{} (read-only)", title))
+            .open(&mut open)
+            .default_size([500.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(browser.trail.len() > 1, egui::Button::new("⬆ Up"))
+                        .clicked()
+                    {
+                        browser.trail.pop();
+                        browser.load();
+                    }
+                    let names: Vec<&str> =
+                        browser.trail[1..].iter().map(|e| e.name.as_str()).collect();
+                    ui.label(format!("/{}", names.join("/")));
+                });
+                if let Some(error) = &browser.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.separator();
+
+                let mut opened = None;
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("image_entries")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (i, entry) in browser.entries.iter().enumerate() {
+                                    let icon = if entry.is_dir { "📁" } else { "📄" };
+                                    let selected = browser.selected.contains(&i);
+                                    let response = ui.selectable_label(
+                                        selected,
+                                        format!("{} {}", icon, entry.name),
+                                    );
+                                    if response.double_clicked() && entry.is_dir {
+                                        opened = Some(entry.clone());
+                                    } else if response.clicked() && !selected {
+                                        browser.selected.insert(i);
+                                    } else if response.clicked() {
+                                        browser.selected.remove(&i);
+                                    }
+                                    if entry.is_dir {
+                                        ui.label("");
+                                    } else {
+                                        ui.label(format_file_size(entry.size));
+                                    }
+                                    ui.label(
+                                        entry
+                                            .modified
+                                            .map(|m| m.format("%Y-%m-%d %H:%M").to_string())
+                                            .unwrap_or_default(),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                if let Some(dir) = opened {
+                    browser.trail.push(dir);
+                    browser.load();
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let selected: Vec<iso::IsoEntry> = browser
+                        .selected
+                        .iter()
+                        .map(|&i| browser.entries[i].clone())
+                        .collect();
+                    if ui
+                        .add_enabled(!selected.is_empty(), egui::Button::new("Extract Selected"))
+                        .clicked()
+                    {
+                        extract = Some(selected);
+                    }
+                    if ui.button("Extract All").clicked() {
+                        extract = Some(browser.entries.clone());
+                    }
+                });
+            });
+
+        if let Some(entries) = extract {
+            let image_path = browser.path.clone();
+            self.extract_from_image(image_path, entries);
+        }
+        if !open {
+            self.image_browser = None;
+        }
+    }
+
+    // The first enabled plugin previewer that can show `path`
+    fn previewer(&self, path: &Path) -> Option<Arc<dyn PreviewRenderer>> {
+        self.plugins
+            .iter()
+            .filter(|p| p.enabled)
+            .flat_map(|p| &p.previewers)
+            .find(|r| r.handles(path))
+            .cloned()
+    }
+
+    fn open_preview(&mut self, path: PathBuf) {
+        let Some(renderer) = self.previewer(&path) else {
+            return;
+        };
+        let (tx, rx) = std::sync::mpsc::channel();
+        let repaint = self.repaint.clone();
+        let file = path.clone();
+        let name = renderer.name().to_string();
+        std::thread::spawn(move || {
+            let _ = tx.send(renderer.render(&file));
+            repaint.request();
+        });
+        self.preview = Some(PreviewWindow {
+            path,
+            renderer: name,
+            text: None,
+            rendered: rx,
+        });
+    }
+
+    fn show_preview(&mut self, ctx: &egui::Context) {
+        let Some(preview) = &mut self.preview else {
+            return;
+        };
+        if let Ok(result) = preview.rendered.try_recv() {
+            preview.text = Some(result);
+        }
+        let mut open = true;
+
+        let title = preview
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        salted_window(self.id, format!("Preview of {}", title))
+            .open(&mut open)
+            .default_size([500.0, 400.0])
+            .show(ctx, |ui| {
+                ui.weak(format!("Rendered by {}", preview.renderer));
+                ui.separator();
+                match &preview.text {
+                    None => {
+                        ui.spinner();
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                    Some(Ok(text)) => {
+                        egui::ScrollArea::both().show(ui, |ui| {
+                            ui.add(egui::Label::new(egui::RichText::new(text).monospace()));
+                        });
+                    }
+                }
+            });
+
+        if !open {
+            self.preview = None;
+        }
+    }
+
+    fn show_fs_browser(&mut self, ctx: &egui::Context) {
+        let Some(browser) = &mut self.fs_browser else {
+            return;
+        };
+        browser.poll();
+        let mut open = true;
+        let mut reload = false;
+        let mut copy = None;
+
+        salted_window(self.id, browser.backend.name().to_string())
+            .open(&mut open)
+            .default_size([500.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(browser.dir.parent().is_some(), egui::Button::new("⬆ Up"))
+                        .clicked()
+                    {
+                        browser.dir.pop();
+                        reload = true;
+                    }
+                    ui.label(browser.dir.to_string_lossy());
+                    if browser.listing.is_some() {
+                        ui.spinner();
+                    }
+                });
+                if let Some(error) = &browser.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.separator();
+
+                let mut opened = None;
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("plugin_fs_entries")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (i, (name, metadata)) in browser.entries.iter().enumerate() {
+                                    let icon = if metadata.is_dir { "📁" } else { "📄" };
+                                    let selected = browser.selected.contains(&i);
+                                    let response =
+                                        ui.selectable_label(selected, format!("{} {}", icon, name));
+                                    if response.double_clicked() && metadata.is_dir {
+                                        opened = Some(name.clone());
+                                    } else if response.clicked() && !selected {
+                                        browser.selected.insert(i);
+                                    } else if response.clicked() {
+                                        browser.selected.remove(&i);
+                                    }
+                                    if metadata.is_dir {
+                                        ui.label("");
+                                    } else {
+                                        ui.label(format_file_size(metadata.len));
+                                    }
+                                    ui.label(
+                                        metadata
+                                            .modified
+                                            .map(|m| {
+                                                chrono::DateTime::<chrono::Local>::from(m)
+                                                    .format("%Y-%m-%d %H:%M")
+                                                    .to_string()
+                                            })
+                                            .unwrap_or_default(),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                if let Some(name) = opened {
+                    browser.dir.push(name);
+                    reload = true;
+                }
+
+                // Copying out writes into the current folder
+                if !self.read_only {
+                    ui.separator();
+                    let mut selected: Vec<usize> = browser.selected.iter().copied().collect();
+                    selected.sort_unstable();
+                    if ui
+                        .add_enabled(!selected.is_empty(), egui::Button::new("Copy Here"))
+                        .clicked()
+                    {
+                        copy = Some(
+                            selected
+                                .into_iter()
+                                .map(|i| browser.entries[i].0.clone())
+                                .collect::<Vec<_>>(),
+                        );
+                    }
+                }
+            });
+
+        if reload {
+            browser.load(self.repaint.clone());
+        }
+        if let Some(names) = copy {
+            let backend = browser.backend.clone();
+            let dir = browser.dir.clone();
+            self.copy_from_backend(backend, dir, names);
+        }
+        if !open {
+            self.fs_browser = None;
+        }
+    }
+
+    fn copy_from_backend(
+        &mut self,
+        backend: Arc<dyn VfsBackend>,
+        dir: PathBuf,
+        names: Vec<String>,
+    ) {
+        if self.read_only {
+            return;
+        }
+        let mut taken = HashSet::new();
+        let items: Vec<(PathBuf, PathBuf)> = names
+            .into_iter()
+            .map(|name| {
+                let mut unique = unique_file_name(&self.current_dir, &name);
+                while !taken.insert(unique.clone()) {
+                    unique = format!("{} (copy)", unique);
+                }
+                (dir.join(name), self.current_dir.join(unique))
+            })
+            .collect();
+        let title = format!("Copying from {}", backend.name());
+        self.jobs
+            .spawn_in(title, Some(self.current_dir.clone()), move |job| {
+                job.set_unit(jobs::ProgressUnit::Items);
+                for (done, (from, to)) in items.iter().enumerate() {
+                    if job.is_cancelled() {
+                        return Err(String::from("cancelled"));
+                    }
+                    job.set_progress(done as u64, Some(items.len() as u64));
+                    job.set_message(from.to_string_lossy());
+                    plugins::copy_out(backend.as_ref(), from, to)
+                        .map_err(|e| format!("{}: {}", from.display(), e))?;
+                }
+                job.set_progress(items.len() as u64, Some(items.len() as u64));
+                Ok(())
+            });
+    }
+
+    fn show_split_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.split_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut split = false;
+        let mut cancel = false;
+
+        const MIB: u64 = 1024 * 1024;
+        let size = fs::metadata(&dialog.path).map(|m| m.len()).unwrap_or(0);
+        salted_window(self.id, "Split File")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "\"{}\" ({})",
+                    dialog
+                        .path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy(),
+                    format_file_size(size)
+                ));
+                ui.horizontal(|ui| {
+                    ui.label("Chunk size:");
+                    ui.add(
+                        egui::DragValue::new(&mut dialog.chunk_mib)
+                            .range(1..=split::FAT32_LIMIT / MIB)
+                            .suffix(" MiB"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    for (label, mib) in [
+                        ("FAT32 maximum", split::FAT32_LIMIT / MIB),
+                        ("CD (700 MiB)", 700),
+                        ("25 MiB", 25),
+                    ] {
+                        if ui.small_button(label).clicked() {
+                            dialog.chunk_mib = mib;
+                        }
+                    }
+                });
+                let count = size.div_ceil(dialog.chunk_mib * MIB).max(1);
+                ui.weak(format!(
+                    "Makes {} parts and a .sha256 file to check the joined file against",
+                    count
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Split").clicked() {
+                        split = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if split {
+            let SplitDialog { path, chunk_mib } = self.split_dialog.take().unwrap();
+            self.jobs.spawn(
+                format!("Splitting {}", items_label(std::slice::from_ref(&path))),
+                move |job| split::split(&path, chunk_mib * MIB, job),
+            );
+        } else if cancel || !open {
+            self.split_dialog = None;
+        }
+    }
+
+    fn show_timestamp_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.timestamp_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Change Timestamps")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(items_label(&dialog.targets));
+                ui.radio_value(&mut dialog.shift, false, "Set to:");
+                ui.add_enabled(
+                    !dialog.shift,
+                    egui::TextEdit::singleline(&mut dialog.datetime).hint_text(TIMESTAMP_FORMAT),
+                );
+                ui.radio_value(&mut dialog.shift, true, "Shift by:");
+                ui.add_enabled_ui(dialog.shift, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut dialog.days).suffix(" d"));
+                        ui.add(egui::DragValue::new(&mut dialog.hours).suffix(" h"));
+                        ui.add(egui::DragValue::new(&mut dialog.minutes).suffix(" min"));
+                        ui.add(egui::DragValue::new(&mut dialog.seconds).suffix(" s"));
+                    });
+                    ui.weak("Use negative values to move times back.");
+                });
+                ui.separator();
+                ui.checkbox(&mut dialog.modified, "Modified time");
+                ui.add_enabled(
+                    timestamps::can_set_created(),
+                    egui::Checkbox::new(&mut dialog.created, "Created time"),
+                )
+                .on_disabled_hover_text("This system doesn't allow changing creation times");
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    let any = dialog.modified || dialog.created;
+                    if ui.add_enabled(any, egui::Button::new("Apply")).clicked() {
+                        apply = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if apply {
+            let change = if dialog.shift {
+                let offset = chrono::Duration::days(dialog.days)
+                    + chrono::Duration::hours(dialog.hours)
+                    + chrono::Duration::minutes(dialog.minutes)
+                    + chrono::Duration::seconds(dialog.seconds);
+                Ok(timestamps::Change::Shift(offset))
+            } else {
+                chrono::NaiveDateTime::parse_from_str(dialog.datetime.trim(), TIMESTAMP_FORMAT)
+                    .map(timestamps::Change::Set)
+                    .map_err(|_| format!("Enter the time as {}", TIMESTAMP_FORMAT))
+            };
+            match change {
+                Ok(change) => {
+                    let options = timestamps::TimestampOptions {
+                        change,
+                        modified: dialog.modified,
+                        created: dialog.created,
+                    };
+                    let targets = self.timestamp_dialog.take().unwrap().targets;
+                    self.jobs.spawn(
+                        format!("Changing timestamps of {}", items_label(&targets)),
+                        move |job| timestamps::apply(&targets, options, job),
+                    );
+                }
+                Err(e) => dialog.error = Some(e),
+            }
+        } else if cancel || !open {
+            self.timestamp_dialog = None;
+        }
+    }
+
+    fn show_convert_dialog(&mut self, ctx: &egui::Context) {
+        if self.read_only {
+            self.convert_dialog = None;
+            return;
+        }
+        let Some(dialog) = &mut self.convert_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut convert = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Convert Images")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(items_label(&dialog.targets));
+                ui.horizontal(|ui| {
+                    ui.label("Format:");
+                    for format in convert::ImageFormat::ALL {
+                        ui.radio_value(&mut dialog.format, format, format.label());
+                    }
+                });
+                if dialog.format == convert::ImageFormat::Jpeg {
+                    ui.add(egui::Slider::new(&mut dialog.quality, 1..=100).text("Quality"));
+                }
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut dialog.resize, "Shrink to fit within");
+                    ui.add_enabled(
+                        dialog.resize,
+                        egui::DragValue::new(&mut dialog.max_side)
+                            .range(16..=16384)
+                            .suffix(" px"),
+                    );
+                });
+                ui.weak("Converted copies are saved next to the originals.");
+                ui.horizontal(|ui| {
+                    if ui.button("Convert").clicked() {
+                        convert = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if convert {
+            let dialog = self.convert_dialog.take().unwrap();
+            let options = convert::ConvertOptions {
+                format: dialog.format,
+                quality: dialog.quality,
+                max_side: dialog.resize.then_some(dialog.max_side),
+            };
+            let targets = dialog.targets;
+            self.jobs.spawn(
+                format!("Converting {}", items_label(&targets)),
+                move |job| convert::convert_all(&targets, options, job),
+            );
+        } else if cancel || !open {
+            self.convert_dialog = None;
+        }
+    }
+
+    fn sidebar(&mut self, ui: &mut egui::Ui) {
+        if let Some(devices) = self.devices_rx.try_iter().last() {
+            self.devices = devices;
+        }
+
+        if ui
+            .selectable_label(self.overview, "🖥 This Computer")
+            .on_hover_text("All drives and how full they are")
+            .clicked()
+        {
+            self.show_overview();
+        }
+        ui.add_space(8.0);
+
+        let mut chosen = None;
+        let mut dropped = None;
+        if !self.config.bookmarks.is_empty() {
+            ui.strong("Bookmarks");
+            for bookmark in &self.config.bookmarks {
+                let response = ui
+                    .selectable_label(
+                        self.current_dir == *bookmark,
+                        format!("📁 {}", folder_label(bookmark)),
+                    )
+                    .on_hover_text(bookmark.display().to_string());
+                if response.clicked() {
+                    chosen = Some(bookmark.clone());
+                }
+                if let Some(files) = dnd::drop_target(ui, &response) {
+                    dropped = Some((files, bookmark.clone()));
+                } else if dnd::held(&response) && self.spring.due(ui.ctx(), bookmark) {
+                    chosen = Some(bookmark.clone());
+                }
+            }
+            ui.add_space(8.0);
+        }
+
+        ui.strong("Devices");
+        if !self.devices.block.is_empty() {
+            let mut action = None;
+            let idle = self.device_task.is_none();
+            for device in &self.devices.block {
+                let icon = device_icon(device);
+                let open = device
+                    .mount_point
+                    .as_ref()
+                    .is_some_and(|m| self.current_dir.starts_with(m) && m.as_os_str() != "/");
+                ui.horizontal(|ui| {
+                    if !idle {
+                        ui.disable();
+                    }
+                    let mut text =
+                        egui::RichText::new(format!("{} {}", icon, device.display_name()));
+                    if device.mount_point.is_none() {
+                        text = text.weak();
+                    }
+                    let response = ui.selectable_label(open, text).on_hover_text(format!(
+                        "{} · {} · {}",
+                        device.path, device.fs_type, device.size
+                    ));
+                    if response.clicked() {
+                        action = Some(match &device.mount_point {
+                            Some(mount) => DeviceAction::Open(mount.clone()),
+                            None if device.locked => DeviceAction::Unlock(device.clone()),
+                            None => DeviceAction::Mount(device.clone()),
+                        });
+                    }
+                    response.context_menu(|ui| {
+                        let chosen = if device.locked {
+                            ui.button("Unlock…")
+                                .clicked()
+                                .then(|| DeviceAction::Unlock(device.clone()))
+                        } else if device.mount_point.is_some() {
+                            ui.button("Unmount")
+                                .clicked()
+                                .then(|| DeviceAction::Unmount(device.clone()))
+                        } else {
+                            ui.button("Mount")
+                                .clicked()
+                                .then(|| DeviceAction::Mount(device.clone()))
+                        };
+                        let eject = device.removable && ui.button("Safely Remove").clicked();
+                        if let Some(chosen) =
+                            chosen.or(eject.then(|| DeviceAction::Eject(device.clone())))
+                        {
+                            action = Some(chosen);
+                            ui.close_menu();
+                        }
+                    });
+                    if device.removable
+                        && ui
+                            .small_button("⏏")
+                            .on_hover_text("Unmount and power off so it can be unplugged")
+                            .clicked()
+                    {
+                        action = Some(DeviceAction::Eject(device.clone()));
+                    }
+                });
+            }
+            if let Some(action) = action {
+                self.apply_device_action(action);
+            }
+        } else {
+            for mount in &self.devices.mounts {
+                let icon = if mount.removable { "💾" } else { "🖴" };
+                if ui
+                    .selectable_label(
+                        self.current_dir.starts_with(&mount.mount_point),
+                        format!("{} {}", icon, mount.label()),
+                    )
+                    .on_hover_text(mount.description())
+                    .clicked()
+                {
+                    chosen = Some(mount.mount_point.clone());
+                }
+            }
+        }
+        if let Some(task) = &self.device_task {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.weak(&task.label);
+            });
+        }
+        if let Some(error) = &self.device_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        if let Some(files) = trash::files_dir() {
+            ui.add_space(8.0);
+            let response = ui.selectable_label(self.in_trash, "🗑 Trash");
+            let response = match &self.trash_summary {
+                Some(summary) => response.on_hover_text(summary.to_string()),
+                None => response,
+            };
+            if response.clicked() {
+                chosen = Some(files);
+            }
+            if !self.read_only {
+                response.context_menu(|ui| {
+                    let any = self.trash_summary.is_some_and(|s| s.items > 0);
+                    if ui
+                        .add_enabled(any, egui::Button::new("Empty Trash"))
+                        .clicked()
+                    {
+                        self.request_empty_trash();
+                        ui.close_menu();
+                    }
+                });
+            }
+            if let Some(summary) = &self.trash_summary {
+                ui.weak(summary.to_string());
+            }
+        }
+
+        ui.add_space(8.0);
+        if ui
+            .selectable_label(self.network.is_some(), "🌐 Network")
+            .on_hover_text("Shares advertised on the local network")
+            .clicked()
+        {
+            self.network = match self.network {
+                Some(_) => None,
+                None => Some(network::Discovery::start()),
+            };
+        }
+        if let Some(dir) = chosen {
+            self.go_to(dir);
+        }
+        if let Some((files, destination)) = dropped {
+            self.drop_files(ui.ctx(), files, destination);
+        }
+    }
+
+    // "This Computer": every volume as a card showing how full it is
+    fn overview(&mut self, ui: &mut egui::Ui) {
+        ui.heading("This Computer");
+        ui.add_space(8.0);
+        let mut action = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for device in &self.devices.block {
+                        let mount_point = device.mount_point.as_deref();
+                        let space = mount_point.and_then(|m| self.devices.space.get(m));
+                        let details = [device.fs_type.as_str(), &device.path]
+                            .into_iter()
+                            .chain(mount_point.and_then(|m| m.to_str()))
+                            .filter(|d| !d.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(" · ");
+                        let name = format!("{} {}", device_icon(device), device.display_name());
+                        if volume_card(ui, &name, &details, space) {
+                            action = Some(match &device.mount_point {
+                                Some(mount) => DeviceAction::Open(mount.clone()),
+                                None if device.locked => DeviceAction::Unlock(device.clone()),
+                                None => DeviceAction::Mount(device.clone()),
+                            });
+                        }
+                    }
+                    for mount in &self.devices.mounts {
+                        let icon = if mount.removable { "💾" } else { "🖴" };
+                        let name = format!("{} {}", icon, mount.label());
+                        let space = self.devices.space.get(&mount.mount_point);
+                        if volume_card(ui, &name, &mount.description(), space) {
+                            action = Some(DeviceAction::Open(mount.mount_point.clone()));
+                        }
+                    }
+                });
+            });
+        if let Some(action) = action {
+            self.apply_device_action(action);
+        }
+    }
+
+    // Right after a device action, rather than waiting for the watcher
+    // Checked every frame, since egui resets the visuals when the system
+    // switches between light and dark
+    fn apply_contrast(&mut self, ctx: &egui::Context) {
+        let dark = ctx.style().visuals.dark_mode;
+        if self.config.accessibility.high_contrast {
+            let visuals = high_contrast_visuals(dark);
+            if ctx.style().visuals != visuals {
+                ctx.set_visuals(visuals);
+            }
+            self.high_contrast = true;
+        } else if std::mem::take(&mut self.high_contrast) {
+            ctx.set_visuals(if dark {
+                egui::Visuals::dark()
+            } else {
+                egui::Visuals::light()
+            });
+        }
+    }
+
+    fn refresh_devices(&mut self) {
+        self.devices = drives::devices();
+    }
+
+    fn apply_device_action(&mut self, action: DeviceAction) {
+        match action {
+            DeviceAction::Open(mount) => self.go_to(mount),
+            DeviceAction::Mount(device) => {
+                let label = format!("Mounting {}…", device.display_name());
+                self.start_device_task(label, false, move || udisks::mount(&device.path).map(Some));
+            }
+            DeviceAction::Unlock(device) => {
+                self.unlock_dialog = Some(UnlockDialog {
+                    device,
+                    passphrase: String::new(),
+                    error: None,
+                });
+            }
+            DeviceAction::Unmount(device) => {
+                self.leave(device.mount_point.as_deref());
+                let label = format!("Unmounting {}…", device.display_name());
+                self.start_device_task(label, false, move || {
+                    udisks::unmount(&device.path).map(|_| None)
+                });
+            }
+            DeviceAction::Eject(device) => {
+                self.leave(device.mount_point.as_deref());
+                let label = format!("Preparing {} for removal…", device.display_name());
+                self.start_device_task(label, false, move || udisks::eject(&device).map(|_| None));
+            }
+        }
+    }
+
+    fn start_device_task(
+        &mut self,
+        label: String,
+        unlocking: bool,
+        task: impl FnOnce() -> Result<Option<PathBuf>, String> + Send + 'static,
+    ) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let repaint = self.repaint.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(task());
+            repaint.request();
+        });
+        self.device_error = None;
+        self.device_task = Some(DeviceTask {
+            label,
+            unlocking,
+            done: rx,
+        });
+    }
+
+    fn poll_device_task(&mut self) {
+        let Some(task) = &self.device_task else {
+            return;
+        };
+        let result = match task.done.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(String::from("The drive action failed")),
+        };
+        let unlocking = task.unlocking;
+        self.device_task = None;
+        match result {
+            Ok(mount) => {
+                if unlocking {
+                    self.unlock_dialog = None;
+                }
+                if let Some(mount) = mount {
+                    self.go_to(mount);
+                }
+            }
+            Err(e) => match self.unlock_dialog.as_mut().filter(|_| unlocking) {
+                Some(dialog) => {
+                    dialog.passphrase.clear();
+                    dialog.error = Some(e);
+                }
+                None => self.device_error = Some(e),
+            },
+        }
+        self.refresh_devices();
+    }
+
+    // Steps out of a drive that's about to go away, which would otherwise
+    // keep it busy
+    fn leave(&mut self, mount_point: Option<&Path>) {
+        if mount_point.is_some_and(|m| self.current_dir.starts_with(m)) {
+            self.current_dir = user_dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+            self.search = None;
+            self.showing_recent = false;
+            self.read_dir();
+        }
+    }
+
+    fn show_recovery_prompt(&mut self, ctx: &egui::Context) {
+        let Some(session) = &self.recovered else {
+            return;
+        };
+        let mut go_there = false;
+        let mut reopen = false;
+        let mut dismiss = false;
+        salted_window(self.id, "File Manager closed unexpectedly")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "The last session crashed on {}.",
+                    session.crashed_at
+                ));
+                ui.horizontal(|ui| {
+                    ui.label(format!("You were in {}", session.current_dir.display()));
+                    go_there = session.current_dir.is_dir() && ui.button("Go There").clicked();
+                });
+                if session.tabs.len() > 1 {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} tabs were open", session.tabs.len()));
+                        reopen = ui.button("Reopen Tabs").clicked();
+                    });
+                }
+                if !session.jobs.is_empty() {
+                    ui.separator();
+                    ui.label("These jobs were still running and may have stopped partway:");
+                    for job in &session.jobs {
+                        let progress = match job.total {
+                            Some(total) if total > 0 => {
+                                format!("{}%", job.done.saturating_mul(100) / total)
+                            }
+                            _ => String::from("started"),
+                        };
+                        ui.label(format!("• {} ({})", job.title, progress));
+                        if !job.message.is_empty() {
+                            ui.weak(&job.message);
+                        }
+                    }
+                }
+                ui.collapsing("Details", |ui| {
+                    ui.monospace(&session.panic);
+                });
+                ui.horizontal(|ui| {
+                    dismiss = ui.button("Dismiss").clicked();
+                    if ui.button("Show Logs").clicked() {
+                        self.logs.open = true;
+                    }
+                });
+            });
+        if reopen {
+            let tabs = session.tabs.clone();
+            let open = tabs.into_iter().filter(|tab| tab.dir.is_dir());
+            self.tabs.extend(open.map(|tab| Tab {
+                dir: tab.dir,
+                selected: HashSet::new(),
+                overview: false,
+                pinned: tab.pinned,
+            }));
+        } else if go_there {
+            let dir = session.current_dir.clone();
+            self.navigate(dir);
+        }
+        if go_there || reopen || dismiss {
+            self.recovered = None;
+        }
+    }
+
+    fn picker_bar(&mut self, ui: &mut egui::Ui) {
+        let Some(picker) = self.picker.clone() else {
+            return;
+        };
+        let selected = self.selection_in_order();
+        let mut accept = false;
+        ui.horizontal(|ui| {
+            match &picker.mode {
+                picker::PickMode::Save { .. } => {
+                    ui.label("Name:");
+                    let response = ui.text_edit_singleline(&mut self.picker_name);
+                    accept = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if self.current_dir.join(self.picker_name.trim()).is_file() {
+                        ui.colored_label(ui.visuals().warn_fg_color, "Replaces the existing file");
+                    }
+                }
+                picker::PickMode::SaveFiles { names } => {
+                    ui.label(format!(
+                        "Saves {} here",
+                        items_label(&names.iter().map(PathBuf::from).collect::<Vec<_>>())
+                    ));
+                }
+                picker::PickMode::Open {
+                    directory: true, ..
+                } => {
+                    ui.weak("Select a folder, or open this one");
+                }
+                picker::PickMode::Open { .. } => {}
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let result = picker.result(&self.current_dir, &selected, &self.picker_name);
+                if ui
+                    .add_enabled(result.is_some(), egui::Button::new(picker.accept_label()))
+                    .clicked()
+                {
+                    accept = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+        });
+        if accept {
+            self.accept_pick(ui.ctx(), None);
+        }
+    }
+
+    // Hands the result back to the app that asked and closes. `opened` is a
+    // file double-clicked in an open picker.
+    fn accept_pick(&mut self, ctx: &egui::Context, opened: Option<PathBuf>) {
+        let Some(picker) = &self.picker else {
+            return;
+        };
+        let result = match opened {
+            Some(path) => Some(vec![path]),
+            None => picker.result(
+                &self.current_dir,
+                &self.selection_in_order(),
+                &self.picker_name,
+            ),
+        };
+        let Some(paths) = result else {
+            return;
+        };
+        let saving = !matches!(picker.mode, picker::PickMode::Open { .. });
+        let existing: Vec<PathBuf> = paths.iter().filter(|p| p.is_file()).cloned().collect();
+        if saving && !existing.is_empty() && self.config.confirmations.overwrite {
+            self.replace_confirm = Some(ReplaceConfirm {
+                paths,
+                existing,
+                dont_ask: false,
+            });
+            return;
+        }
+        picker::Picker::finish(&paths);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+
+    fn show_replace_confirm(&mut self, ctx: &egui::Context) {
+        let Some(confirm) = &mut self.replace_confirm else {
+            return;
+        };
+        let mut open = true;
+        let mut replace = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Replace")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                match confirm.existing.as_slice() {
+                    [path] => ui.label(format!(
+                        "\"{}\" already exists. Replace it?",
+                        path.file_name().unwrap_or_default().to_string_lossy()
+                    )),
+                    existing => ui.label(format!(
+                        "{} already exist. Replace them?",
+                        ops::plural(existing.len(), "file")
+                    )),
+                };
+                ui.checkbox(&mut confirm.dont_ask, "Don't ask again");
+                ui.horizontal(|ui| {
+                    if ui.button("Replace").clicked() {
+                        replace = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if replace {
+            let ReplaceConfirm {
+                paths, dont_ask, ..
+            } = self.replace_confirm.take().unwrap();
+            if dont_ask {
+                self.config.confirmations.overwrite = false;
+                self.config.save();
+            }
+            picker::Picker::finish(&paths);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        } else if cancel || !open {
+            self.replace_confirm = None;
+        }
+    }
+
+    fn show_network(&mut self, ctx: &egui::Context) {
+        let Some(discovery) = &mut self.network else {
+            return;
+        };
+        let mut open = true;
+
+        salted_window(self.id, "Network")
+            .open(&mut open)
+            .default_size([420.0, 300.0])
+            .show(ctx, |ui| match discovery {
+                Err(e) => {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("Can't search: {}", e));
+                }
+                Ok(discovery) => {
+                    discovery.poll();
+                    ctx.request_repaint_after(std::time::Duration::from_millis(500));
+                    let hosts = discovery.hosts();
+                    if hosts.is_empty() {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Looking for shared folders…");
+                        });
+                    }
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (host, services) in hosts {
+                            ui.strong(format!("🖥 {}", host));
+                            for service in services {
+                                let url = service.url();
+                                ui.horizontal(|ui| {
+                                    ui.label(&service.name).on_hover_text(format!(
+                                        "{}
+{}",
+                                        service.kind.label(),
+                                        url
+                                    ));
+                                    ui.weak(service.kind.label());
+                                    if ui.small_button("Open").clicked() {
+                                        if let Err(e) = launch::open_with_system(url.as_ref()) {
+                                            tracing::error!("Failed to open {}: {}", url, e);
+                                        }
+                                    }
+                                    if ui.small_button("Copy Address").clicked() {
+                                        ui.ctx().copy_text(url.clone());
+                                    }
+                                });
+                            }
+                            ui.add_space(4.0);
+                        }
+                    });
+                    ui.separator();
+                    ui.weak(
+                        "Only machines that advertise over mDNS (Avahi, Bonjour) show up. \
+                         Windows PCs usually don't.",
+                    );
+                }
+            });
+
+        if !open {
+            self.network = None;
+        }
+    }
+
+    fn show_unlock_dialog(&mut self, ctx: &egui::Context) {
+        let busy = self.device_task.is_some();
+        let Some(dialog) = &mut self.unlock_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut unlock = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Unlock Volume")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Enter the passphrase for {} ({}).",
+                    dialog.device.display_name(),
+                    dialog.device.path
+                ));
+                let response = ui.add_enabled(
+                    !busy,
+                    egui::TextEdit::singleline(&mut dialog.passphrase).password(true),
+                );
+                if !busy {
+                    response.request_focus();
+                }
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    unlock = !busy;
+                }
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!busy, egui::Button::new("Unlock")).clicked() {
+                        unlock = true;
+                    }
+                    if busy {
+                        ui.spinner();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if unlock {
+            // Unlock, then mount the filesystem inside
+            dialog.error = None;
+            let path = dialog.device.path.clone();
+            let passphrase = dialog.passphrase.clone();
+            let label = format!("Unlocking {}…", dialog.device.display_name());
+            self.start_device_task(label, true, move || {
+                udisks::unlock(&path, &passphrase).map(Some)
+            });
+        } else if cancel || !open {
+            self.unlock_dialog = None;
+        }
+    }
+
+    // Copies entries out of a disk image into the current folder
+    fn extract_from_image(&mut self, image_path: PathBuf, entries: Vec<iso::IsoEntry>) {
+        let mut taken = HashSet::new();
+        let items: Vec<(iso::IsoEntry, PathBuf)> = entries
+            .into_iter()
+            .map(|entry| {
+                let mut name = unique_file_name(&self.current_dir, &entry.name);
+                while !taken.insert(name.clone()) {
+                    name = format!("{} (copy)", name);
+                }
+                let destination = self.current_dir.join(name);
+                (entry, destination)
+            })
+            .collect();
+        self.jobs.spawn(
+            format!(
+                "Extracting from {}",
+                items_label(std::slice::from_ref(&image_path))
+            ),
+            move |job| {
+                let mut image = iso::IsoImage::open(&image_path).map_err(|e| e.to_string())?;
+                job.set_unit(jobs::ProgressUnit::Items);
+                for (done, (entry, destination)) in items.iter().enumerate() {
+                    if job.is_cancelled() {
+                        return Err(String::from("cancelled"));
+                    }
+                    job.set_progress(done as u64, Some(items.len() as u64));
+                    job.set_message(&entry.name);
+                    image
+                        .extract(entry, destination)
+                        .map_err(|e| format!("{}: {}", entry.name, e))?;
+                }
+                job.set_progress(items.len() as u64, Some(items.len() as u64));
+                Ok(())
+            },
+        );
+    }
+
+    fn show_extract_password(&mut self, ctx: &egui::Context) {
+        let Some((path, password)) = &mut self.extract_password else {
+            return;
+        };
+        let mut open = true;
+        let mut extract = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Password required")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "\"{}\" is encrypted.",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ));
+                let response = ui.add(egui::TextEdit::singleline(password).password(true));
+                response.request_focus();
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    extract = true;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Extract").clicked() {
+                        extract = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if extract {
+            let (path, password) = self.extract_password.take().unwrap();
+            self.extract(path, Some(password));
+        } else if cancel || !open {
+            self.extract_password = None;
+        }
+    }
+
+    fn show_compress_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.compress_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut create = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Compress")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut dialog.name);
+                    ui.label(format!(".{}", dialog.format.extension()));
+                });
+                ui.horizontal(|ui| {
+                    for format in archive::Format::writable() {
+                        ui.radio_value(&mut dialog.format, *format, format.label());
+                    }
+                });
+                ui.checkbox(&mut dialog.use_password, "Encrypt with a password (AES-256)");
+                if dialog.use_password {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut dialog.password)
+                            .password(true)
+                            .hint_text("Password"),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(&mut dialog.confirm)
+                            .password(true)
+                            .hint_text("Repeat password"),
+                    );
+                    if !dialog.confirm.is_empty() && dialog.password != dialog.confirm {
+                        ui.colored_label(ui.visuals().error_fg_color, "Passwords don't match");
+                    }
+                    ui.weak("The password is not saved anywhere. Without it the archive can't be opened.");
+                }
+                let valid = !dialog.name.trim().is_empty()
+                    && (!dialog.use_password
+                        || (!dialog.password.is_empty() && dialog.password == dialog.confirm));
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(valid, egui::Button::new("Create")).clicked() {
+                        create = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if create {
+            let dialog = self.compress_dialog.take().unwrap();
+            let name = format!("{}.{}", dialog.name.trim(), dialog.format.extension());
+            let destination = self
+                .current_dir
+                .join(unique_file_name(&self.current_dir, &name));
+            let password = dialog.use_password.then_some(dialog.password);
+            let (targets, format) = (dialog.targets, dialog.format);
+            let folder = Some(self.current_dir.clone());
+            self.jobs
+                .spawn_in(format!("Compressing {}", name), folder, move |_| {
+                    let result =
+                        archive::create(&targets, &destination, format, password.as_deref())
+                            .map_err(|e| e.to_string());
+                    history::logged(
+                        history::Operation::Compress,
+                        &targets,
+                        Some(&destination),
+                        result,
+                    )
+                });
+        } else if cancel || !open {
+            self.compress_dialog = None;
+        }
+    }
+
+    fn run_gpg(&mut self, targets: Vec<PathBuf>, operation: gpg::Operation) {
+        let verb = match operation {
+            gpg::Operation::Encrypt(..) => "Encrypting",
+            gpg::Operation::Decrypt => "Decrypting",
+            gpg::Operation::Sign => "Signing",
+            gpg::Operation::Verify => "Verifying",
+        };
+        self.jobs
+            .spawn(format!("{} {}", verb, items_label(&targets)), move |job| {
+                let mut results = Vec::new();
+                let mut errors = Vec::new();
+                for file in &targets {
+                    match gpg::run(&operation, file) {
+                        Ok(result) => results.push(result),
+                        Err(e) => errors.push(format!("{}: {}", file.display(), e)),
+                    }
+                }
+                job.set_message(results.join("; "));
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors.join("; "))
+                }
+            });
+    }
+
+    fn show_encrypt_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.encrypt_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut encrypt = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Encrypt")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Encrypt {} for:", items_label(&dialog.targets)));
+                match &dialog.keys {
+                    Ok(keys) if keys.is_empty() => {
+                        ui.weak("No public keys that can encrypt. Import one with gpg --import.");
+                    }
+                    Ok(keys) => {
+                        egui::ScrollArea::vertical()
+                            .max_height(240.0)
+                            .show(ui, |ui| {
+                                for (i, key) in keys.iter().enumerate() {
+                                    let mut text = egui::RichText::new(&key.user_id);
+                                    if !key.trusted {
+                                        text = text.weak();
+                                    }
+                                    ui.radio_value(&mut dialog.recipient, Some(i), text)
+                                        .on_hover_text(&key.fingerprint);
+                                }
+                            });
+                        let untrusted = dialog
+                            .recipient
+                            .and_then(|i| keys.get(i))
+                            .is_some_and(|key| !key.trusted);
+                        if untrusted {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                "This key hasn't been certified, so it may not belong to who it \
+                                 says. Check its fingerprint with the owner first.",
+                            );
+                            ui.checkbox(&mut dialog.trust_anyway, "Encrypt to it anyway");
+                        }
+                    }
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                }
+                ui.horizontal(|ui| {
+                    let ready = match (&dialog.keys, dialog.recipient) {
+                        (Ok(keys), Some(i)) => keys
+                            .get(i)
+                            .is_some_and(|k| k.trusted || dialog.trust_anyway),
+                        _ => false,
+                    };
+                    if ui
+                        .add_enabled(ready, egui::Button::new("Encrypt"))
+                        .clicked()
+                    {
+                        encrypt = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if encrypt {
+            let dialog = self.encrypt_dialog.take().unwrap();
+            let key = dialog
+                .keys
+                .ok()
+                .zip(dialog.recipient)
+                .and_then(|(keys, i)| keys.into_iter().nth(i));
+            if let Some(key) = key {
+                let trust_anyway = !key.trusted && dialog.trust_anyway;
+                self.run_gpg(
+                    dialog.targets,
+                    gpg::Operation::Encrypt(key.fingerprint, trust_anyway),
+                );
+            }
+        } else if cancel || !open {
+            self.encrypt_dialog = None;
+        }
+    }
+
+    fn show_delete_confirm(&mut self, ctx: &egui::Context) {
+        let Some(confirm) = &mut self.delete_confirm else {
+            return;
+        };
+        let (title, verb) = if confirm.secure {
+            ("Secure delete", "Securely delete")
+        } else {
+            ("Delete", "Permanently delete")
+        };
+        let mut open = true;
+        let mut delete = false;
+        let mut cancel = false;
+
+        salted_window(self.id, title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                match confirm.targets.as_slice() {
+                    [target] => ui.label(format!(
+                        "{} \"{}\"?",
+                        verb,
+                        target.file_name().unwrap_or_default().to_string_lossy()
+                    )),
+                    targets => ui.label(format!("{} {} items?", verb, targets.len())),
+                };
+                ui.weak("Folders are deleted with everything inside them. This can't be undone.");
+                if confirm.secure {
+                    ui.add_space(4.0);
+                    ui.label(
+                        "File contents are overwritten first. That does not reliably \
+                         erase data on:",
+                    );
+                    ui.label("• SSDs, SD cards and USB sticks, which remap writes");
+                    ui.label("• Copy-on-write filesystems (Btrfs, ZFS, APFS)");
+                    ui.label("• Snapshots, backups and synced copies of the files");
+                    ui.weak("Full-disk encryption is the dependable protection there.");
+                }
+                if !confirm.hard_linked.is_empty() {
+                    ui.add_space(4.0);
+                    let warning = if confirm.secure {
+                        "⚠ These files have other hard links. Shredding overwrites the \
+                         contents every link shares:"
+                    } else {
+                        "⚠ These files have other hard links, which keep the contents:"
+                    };
+                    ui.colored_label(ui.visuals().warn_fg_color, warning);
+                    for (path, count) in confirm.hard_linked.iter().take(5) {
+                        ui.label(format!(
+                            "• {} ({} links)",
+                            path.file_name().unwrap_or_default().to_string_lossy(),
+                            count
+                        ));
+                    }
+                    if confirm.hard_linked.len() > 5 {
+                        ui.label(format!("• and {} more", confirm.hard_linked.len() - 5));
+                    }
+                }
+                ui.checkbox(&mut confirm.dont_ask, "Don't ask again");
+                ui.horizontal(|ui| {
+                    if ui.button("Delete").clicked() {
+                        delete = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if delete {
+            let DeleteConfirm {
+                targets,
+                secure,
+                dont_ask,
+                ..
+            } = self.delete_confirm.take().unwrap();
+            if dont_ask {
+                self.config.confirmations.permanent_delete = false;
+                self.config.save();
+            }
+            self.delete(targets, secure);
+        } else if cancel || !open {
+            self.delete_confirm = None;
+        }
+    }
+
+    // Asks first unless that was switched off in Settings
+    fn request_delete(&mut self, targets: Vec<PathBuf>, secure: bool) {
+        if self.config.confirmations.permanent_delete {
+            self.delete_confirm = Some(DeleteConfirm::new(targets, secure));
+        } else {
+            self.delete(targets, secure);
+        }
+    }
+
+    fn can_trash(&self) -> bool {
+        trash::supported() && !self.in_trash
+    }
+
+    fn request_trash(&mut self, targets: Vec<PathBuf>) {
+        if self.config.confirmations.trash {
+            self.trash_confirm = Some(TrashConfirm::Move {
+                targets,
+                dont_ask: false,
+            });
+        } else {
+            self.move_to_trash(targets);
+        }
+    }
+
+    fn move_to_trash(&mut self, targets: Vec<PathBuf>) {
+        let title = format!("Moving {} to the trash", items_label(&targets));
+        // Only trashes the app can list can be restored from
+        let undo = trash::browsable().then(|| toasts::Undo::Trash(targets.clone()));
+        let text = format!("{} moved to the trash", ops::plural(targets.len(), "item"));
+        self.toasts.after_job(title.clone(), text, undo);
+        self.selected.clear();
+        let batch: jobs::Batch = Arc::new(|job, targets| {
+            history::logged(
+                history::Operation::Trash,
+                targets,
+                None,
+                trash::trash(targets, job),
+            )
+        });
+        self.jobs.spawn_batch(title, None, targets, batch);
+    }
+
+    fn undo(&mut self, undo: toasts::Undo) {
+        if self.read_only {
+            return;
+        }
+        match undo {
+            toasts::Undo::Trash(originals) => {
+                let title = format!("Restoring {} from the trash", items_label(&originals));
+                self.jobs.spawn(title, move |job| {
+                    trash::restore(&trash::latest_trashed(&originals), None, job)
+                });
+            }
+            // Back to each item's old folder, one move per folder
+            toasts::Undo::Move(moved) => {
+                let mut by_folder: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+                for (current, original) in moved {
+                    let Some(folder) = original.parent() else {
+                        continue;
+                    };
+                    match by_folder.iter_mut().find(|(f, _)| f == folder) {
+                        Some((_, sources)) => sources.push(current),
+                        None => by_folder.push((folder.to_path_buf(), vec![current])),
+                    }
+                }
+                for (destination, sources) in by_folder {
+                    self.run_transfer(ops::Transfer {
+                        sources,
+                        destination,
+                        mode: ops::TransferMode::Move,
+                        options: self.config.transfers.clone(),
+                        resume: None,
+                    });
+                }
+            }
+            toasts::Undo::Rename { from, to } => {
+                let result = rename::rename(&LocalFs, &from, &to).map_err(|e| e.to_string());
+                let result = history::logged(
+                    history::Operation::Rename,
+                    std::slice::from_ref(&from),
+                    Some(&to),
+                    result,
+                );
+                if let Err(e) = result {
+                    tracing::error!("Failed to rename {} back: {}", from.display(), e);
+                }
+                self.read_dir();
+            }
+        }
+    }
+
+    fn request_empty_trash(&mut self) {
+        if self.config.confirmations.empty_trash {
+            self.trash_confirm = Some(TrashConfirm::Empty { dont_ask: false });
+        } else {
+            self.empty_trash();
+        }
+    }
+
+    fn empty_trash(&mut self) {
+        self.jobs.spawn("Emptying the trash", move |job| {
+            let items = trash::list();
+            let originals: Vec<PathBuf> = items.iter().map(|t| t.original.clone()).collect();
+            history::logged(
+                history::Operation::Delete,
+                &originals,
+                None,
+                trash::remove(&items, job),
+            )
+        });
+    }
+
+    // The first count also empties out old items, unless only looking.
+    // Waits for the first frame, when read-only mode is known.
+    fn count_trash(&mut self) {
+        let purge_after = (self.config.trash.auto_empty && !self.read_only && !self.detached)
+            .then_some(self.config.trash.auto_empty_days);
+        self.trash_rx = Some(trash::summarize(purge_after, self.repaint.clone()));
+    }
+
+    fn refresh_trash(&mut self) {
+        if trash::browsable() {
+            self.trash_rx = Some(trash::summarize(None, self.repaint.clone()));
+        }
+    }
+
+    fn show_restore_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.restore_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut restore = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Restore To")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Restore {} into:", items_label(&dialog.targets)));
+                ui.add(egui::TextEdit::singleline(&mut dialog.folder).desired_width(320.0));
+                ui.weak("Missing folders are created.");
+                let valid = Path::new(dialog.folder.trim()).is_absolute();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(valid, egui::Button::new("Restore"))
+                        .clicked()
+                    {
+                        restore = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if restore {
+            let RestoreDialog { targets, folder } = self.restore_dialog.take().unwrap();
+            let folder = PathBuf::from(folder.trim());
+            self.apply_entry_action(EntryAction::Restore(targets, Some(folder)));
+        } else if cancel || !open {
+            self.restore_dialog = None;
+        }
+    }
+
+    fn show_trash_confirm(&mut self, ctx: &egui::Context) {
+        let Some(confirm) = &mut self.trash_confirm else {
+            return;
+        };
+        let mut open = true;
+        let mut go = false;
+        let mut cancel = false;
+        let (title, button) = match confirm {
+            TrashConfirm::Move { .. } => ("Move to Trash", "Move to Trash"),
+            TrashConfirm::Empty { .. } => ("Empty Trash", "Empty Trash"),
+        };
+
+        salted_window(self.id, title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let dont_ask = match confirm {
+                    TrashConfirm::Move { targets, dont_ask } => {
+                        ui.label(format!("Move {} to the trash?", items_label(targets)));
+                        dont_ask
+                    }
+                    TrashConfirm::Empty { dont_ask } => {
+                        let summary = self.trash_summary.unwrap_or_default();
+                        ui.label(format!(
+                            "Delete {} in the trash for good?",
+                            ops::plural(summary.items, "item")
+                        ));
+                        ui.weak("This can't be undone.");
+                        dont_ask
+                    }
+                };
+                ui.checkbox(dont_ask, "Don't ask again");
+                ui.horizontal(|ui| {
+                    if ui.button(button).clicked() {
+                        go = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if go {
+            match self.trash_confirm.take().unwrap() {
+                TrashConfirm::Move { targets, dont_ask } => {
+                    if dont_ask {
+                        self.config.confirmations.trash = false;
+                        self.config.save();
+                    }
+                    self.move_to_trash(targets);
+                }
+                TrashConfirm::Empty { dont_ask } => {
+                    if dont_ask {
+                        self.config.confirmations.empty_trash = false;
+                        self.config.save();
+                    }
+                    self.empty_trash();
+                }
+            }
+        } else if cancel || !open {
+            self.trash_confirm = None;
+        }
+    }
+
+    fn delete(&mut self, targets: Vec<PathBuf>, secure: bool) {
+        let verb = if secure { "Shredding" } else { "Deleting" };
+        let title = format!("{} {}", verb, items_label(&targets));
+        self.selected.clear();
+        let batch: jobs::Batch = Arc::new(move |job, targets| {
+            if secure {
+                history::logged(
+                    history::Operation::Shred,
+                    targets,
+                    None,
+                    ops::shred(targets, job),
+                )
+            } else {
+                history::logged(
+                    history::Operation::Delete,
+                    targets,
+                    None,
+                    ops::delete(targets, job),
+                )
+            }
+        });
+        self.jobs.spawn_batch(title, None, targets, batch);
+    }
+
+    fn show_select_pattern(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.select_pattern else {
+            return;
+        };
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+
+        salted_window(self.id, "Select by pattern")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Wildcards: * matches anything, ? matches one character. Separate patterns with ;");
+                let edit = ui.text_edit_singleline(&mut dialog.pattern);
+                edit.request_focus();
+                if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    apply = true;
+                }
+                ui.checkbox(&mut dialog.case_sensitive, "Case sensitive");
+                ui.checkbox(&mut dialog.extend, "Add to current selection");
+                ui.horizontal(|ui| {
+                    if ui.button("Select").clicked() {
+                        apply = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if apply {
+            let dialog = self.select_pattern.take().unwrap();
+            self.select_matching(&dialog.pattern, dialog.case_sensitive, dialog.extend);
+        } else if cancel || !open {
+            self.select_pattern = None;
+        }
+    }
+}
+
+impl FileBrowserWidget {
+    // Draws the whole browser (menus, sidebar, listing and its windows)
+    // inside `ui`, which would normally be a CentralPanel
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let ctx = &ui.ctx().clone();
+        let area = ui.max_rect();
+        #[cfg(feature = "profiling")]
+        if !self.detached {
+            self.profiler.begin_frame();
+        }
+        self.repaint.set(ctx);
+        self.viewport = ctx.viewport_id();
+        if self.trash_rx.is_none() && trash::browsable() {
+            self.count_trash();
+        }
+        if self.has_keyboard(ui) {
+            self.handle_shortcuts(ctx);
+        }
+        self.apply_contrast(ctx);
+        self.dates.refresh(ctx, self.config.view.relative_dates);
+        if self.unlisted {
+            self.read_dir();
+        }
+        self.poll_listing();
+        self.poll_plugin_columns();
+        {
+            profile_scope!("poll thumbnails");
+            self.thumbnails.poll(ctx);
+        }
+        let ended = self.jobs.take_ended();
+        if !ended.is_empty() {
+            for job in &ended {
+                self.toasts.job_ended(job);
+                if let (jobs::JobState::Failed(_), Some(batch), false) =
+                    (&job.state, &job.batch, job.failures.is_empty())
+                {
+                    self.partial_failures.push(PartialFailure {
+                        title: job.title.clone(),
+                        folder: job.folder.clone(),
+                        failures: job.failures.clone(),
+                        batch: batch.clone(),
+                    });
+                }
+            }
+            self.read_dir();
+            self.refresh_trash();
+            self.notify_ended(ctx, ended);
+        }
+        if let Some(folder) = notify::take_shown() {
+            self.navigate(folder);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+        if let Some(summary) = self.trash_rx.as_ref().and_then(|rx| rx.try_iter().last()) {
+            self.trash_summary = Some(summary);
+        }
+
+        egui::TopBottomPanel::top(self.id.with("menu_bar")).show_inside(ui, |ui| {
+            egui::menu::bar(ui, |ui| {
+                // Everything in it writes into the current folder
+                if !self.read_only {
+                    ui.menu_button("File", |ui| {
+                        ui.menu_button("New", |ui| self.new_menu(ui));
+                        if ui.button("Download from URL…").clicked() {
+                            self.download_dialog = Some(DownloadDialog {
+                                url: String::new(),
+                                error: None,
+                            });
+                            ui.close_menu();
+                        }
+                    });
+                }
+                ui.menu_button("Edit", |ui| {
+                    if !self.read_only && ui.button("Cut").clicked() {
+                        self.copy_selection(ui.ctx(), true);
+                        ui.close_menu();
+                    }
+                    if ui.button("Copy").clicked() {
+                        self.copy_selection(ui.ctx(), false);
+                        ui.close_menu();
+                    }
+                    if !self.read_only {
+                        if ui.button("Paste").clicked() {
+                            self.paste_clipboard();
+                            ui.close_menu();
+                        }
+                        ui.menu_button("Paste Special", |ui| {
+                            let enabled = !self.file_clipboard.is_empty();
+                            if ui
+                                .add_enabled(enabled, egui::Button::new("Create Link Here…"))
+                                .clicked()
+                            {
+                                self.open_link_dialog(self.file_clipboard.clone());
+                                ui.close_menu();
+                            }
+                        });
+                        let any = !self.selected.is_empty();
+                        if self.can_trash()
+                            && ui
+                                .add_enabled(any, egui::Button::new("Move to Trash"))
+                                .clicked()
+                        {
+                            self.request_trash(self.selection_in_order());
+                            ui.close_menu();
+                        }
+                        if ui
+                            .add_enabled(any, egui::Button::new("Delete Permanently"))
+                            .clicked()
+                        {
+                            self.request_delete(self.selection_in_order(), false);
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    if ui.button("Select All").clicked() {
+                        self.select_all();
+                        ui.close_menu();
+                    }
+                    if ui.button("Select by Pattern…").clicked() {
+                        self.open_select_pattern();
+                        ui.close_menu();
+                    }
+                    if ui.button("Invert Selection").clicked() {
+                        self.invert_selection();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Settings…").clicked() {
+                        self.settings.open = true;
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("View", |ui| {
+                    let view = &mut self.config.view;
+                    let mut changed = ui
+                        .checkbox(&mut view.show_created, "Created Column")
+                        .changed();
+                    changed |= ui
+                        .checkbox(&mut view.show_accessed, "Accessed Column")
+                        .changed();
+                    changed |= ui
+                        .checkbox(&mut view.show_owner, "Owner and Group Columns")
+                        .changed();
+                    changed |= ui
+                        .checkbox(&mut view.wrap_names, "Wrap Long Names")
+                        .on_hover_text("Show long names on two lines instead of shortening them")
+                        .changed();
+                    changed |= ui
+                        .checkbox(&mut view.size_heat, "Colour Large Files")
+                        .on_hover_text("Over 10 MB in yellow, 100 MB in orange and 1 GB in red")
+                        .changed();
+                    if changed {
+                        self.config.save();
+                        // Hiding the sorted column goes back to sorting by name
+                        if !self.columns().contains(&self.sort) {
+                            self.sort = SortColumn::Name;
+                            self.sort_descending = false;
+                            self.sort_entries();
+                        }
+                    }
+                    ui.separator();
+                    ui.menu_button("Sort By", |ui| {
+                        for column in self.columns() {
+                            if ui.radio(self.sort == column, column.title()).clicked() {
+                                if self.sort != column {
+                                    self.sort_by(column);
+                                }
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        if ui.radio(self.sort_descending, "Descending").clicked() {
+                            self.sort_by(self.sort);
+                            ui.close_menu();
+                        }
+                    });
+                });
+                ui.menu_button("Go", |ui| self.go_menu(ui));
+                ui.menu_button("Bookmarks", |ui| self.bookmarks_menu(ui));
+                ui.menu_button("Tools", |ui| {
+                    if ui.button("Plugins…").clicked() {
+                        self.plugin_manager_open = true;
+                        ui.close_menu();
+                    }
+                    let filesystems: Vec<Arc<dyn VfsBackend>> = self
+                        .plugins
+                        .iter()
+                        .filter(|p| p.enabled)
+                        .flat_map(|p| p.filesystems.iter().cloned())
+                        .collect();
+                    if !filesystems.is_empty() {
+                        ui.menu_button("Plugin Filesystems", |ui| {
+                            for backend in filesystems {
+                                if ui.button(backend.name()).clicked() {
+                                    self.fs_browser =
+                                        Some(FsBrowser::new(backend, self.repaint.clone()));
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    }
+                    ui.menu_button("Scripts", |ui| self.scripts_menu(ui));
+                    if ui.button("Logs").clicked() {
+                        self.logs.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("History").clicked() {
+                        self.history.open();
+                        ui.close_menu();
+                    }
+                    if ui.button("Open Process Folder…").clicked() {
+                        self.processes.open();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    let images: Vec<PathBuf> = self
+                        .selected
+                        .iter()
+                        .filter(|p| convert::is_image(p))
+                        .cloned()
+                        .collect();
+                    if !self.read_only
+                        && ui
+                            .add_enabled(!images.is_empty(), egui::Button::new("Convert Images…"))
+                            .on_disabled_hover_text("Select some PNG, JPEG or WebP images first")
+                            .clicked()
+                    {
+                        self.convert_dialog = Some(ConvertDialog {
+                            targets: images,
+                            format: convert::ImageFormat::Jpeg,
+                            quality: 85,
+                            resize: false,
+                            max_side: 1920,
+                        });
+                        ui.close_menu();
+                    }
+                    if ui.button("Share This Folder…").clicked() {
+                        self.share_window = Some(ShareWindow {
+                            folder: self.current_dir.clone(),
+                            use_password: false,
+                            password: String::new(),
+                            share: None,
+                            error: None,
+                        });
+                        ui.close_menu();
+                    }
+                });
+                #[cfg(feature = "profiling")]
+                if !self.detached {
+                    ui.menu_button("Debug", |ui| {
+                        if ui.checkbox(&mut self.profiler.open, "Profiler").clicked() {
+                            ui.close_menu();
+                        }
+                    });
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if self.read_only {
+                        ui.weak("🔒 Read-only");
+                    }
+                    if !self.selected.is_empty() {
+                        ui.label(format!("{} selected", self.selected.len()));
+                    }
+                    if self.jobs.any_running() {
+                        if let Some(throughput) = self.io_stats.sample(&self.current_dir) {
+                            ui.weak(format!("🖴 {}", throughput.label()))
+                                .on_hover_text("Throughput of the disk this folder is on");
+                        }
+                        ui.ctx().request_repaint_after(Duration::from_secs(1));
+                    } else {
+                        self.io_stats.reset();
+                    }
+                });
+            });
+        });
+
+        match dnd::dragged(ctx) {
+            None => self.drag_origin = None,
+            // Drags can cross browsers, but only the one under the pointer
+            // labels it
+            Some(files)
+                if ctx
+                    .input(|i| i.pointer.hover_pos())
+                    .is_some_and(|p| area.contains(p)) =>
+            {
+                egui::show_tooltip_at_pointer(
+                    ctx,
+                    egui::LayerId::background(),
+                    self.id.with("dragged_files"),
+                    |ui| {
+                        ui.label(items_label(&files.0));
+                        ui.weak("Ctrl to copy, Shift to move, Escape to cancel");
+                    },
+                );
+            }
+            Some(_) => {}
+        }
+
+        self.show_select_pattern(ctx);
+        self.show_paste_dialog(ctx);
+        self.show_link_dialog(ctx);
+        self.show_new_item_dialog(ctx);
+        self.show_rename_dialog(ctx);
+        self.show_run_dialog(ctx);
+        self.show_download_dialog(ctx);
+        self.show_location_dialog(ctx);
+        self.show_delete_confirm(ctx);
+        self.show_trash_confirm(ctx);
+        self.show_restore_dialog(ctx);
+        self.show_replace_confirm(ctx);
+        self.show_symlink_confirm(ctx);
+        self.show_partial_failure(ctx);
+        self.show_interrupted(ctx);
+        self.show_encrypt_dialog(ctx);
+        self.show_compress_dialog(ctx);
+        self.show_extract_password(ctx);
+        self.show_image_browser(ctx);
+        self.show_preview(ctx);
+        self.show_fs_browser(ctx);
+        self.show_split_dialog(ctx);
+        self.show_convert_dialog(ctx);
+        self.show_timestamp_dialog(ctx);
+        self.poll_device_task();
+        self.show_unlock_dialog(ctx);
+        self.show_network(ctx);
+        let salt = self.id;
+        self.properties.retain_mut(|dialog| dialog.show(ctx, salt));
+        self.bulk_properties
+            .retain_mut(|dialog| dialog.show(ctx, salt));
+        if self
+            .properties
+            .iter_mut()
+            .any(|dialog| std::mem::take(&mut dialog.folder_icon_changed))
+        {
+            self.read_dir();
+        }
+        self.show_plugin_manager(ctx);
+        self.show_share_window(ctx);
+        self.logs.show(ctx, self.id);
+        #[cfg(feature = "profiling")]
+        self.profiler.show(ctx);
+        if let Some(dir) = self.history.show(ctx, self.id) {
+            self.navigate(dir);
+        }
+        if let Some(exe) = self.processes.show(ctx, self.id) {
+            if let Some(folder) = exe.parent().map(Path::to_path_buf) {
+                self.go_to(folder);
+                self.selected.insert(exe);
+            }
+        }
+        self.show_recovery_prompt(ctx);
+        if let Some(undo) = self.toasts.show(ctx, self.id) {
+            self.undo(undo);
+        }
+        if self.settings.show(ctx, self.id, &mut self.config) {
+            self.config.save();
+        }
+
+        if self.picker.is_some() {
+            egui::TopBottomPanel::bottom(self.id.with("picker"))
+                .show_inside(ui, |ui| self.picker_bar(ui));
+        }
+        if !self.jobs.is_empty() {
+            egui::TopBottomPanel::bottom(self.id.with("jobs")).show_inside(ui, |ui| {
+                profile_scope!("jobs panel");
+                self.jobs.show(ui);
+            });
+        }
+
+        egui::SidePanel::left(self.id.with("sidebar"))
+            .resizable(true)
+            .default_width(160.0)
+            .show_inside(ui, |ui| {
+                profile_scope!("sidebar");
+                self.sidebar(ui);
+            });
+
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            if self.tabs.len() > 1 || self.detached || self.pinned() {
+                self.tab_bar(ui);
+                ui.separator();
+            }
+            if self.overview {
+                self.overview(ui);
+                return;
+            }
+            ui.horizontal(|ui| {
+                if self.showing_recent {
+                    ui.label("🕘 Recent Files");
+                    return;
+                }
+                // Inside the trash the crumbs start from the trash itself
+                let trash = trash::files_dir().filter(|files| self.current_dir.starts_with(files));
+                let in_trash = trash.is_some();
+                let (mut path_so_far, relative, mut crumbs) = match trash {
+                    Some(files) => {
+                        let relative = self.current_dir.strip_prefix(&files).unwrap().to_path_buf();
+                        (
+                            files.clone(),
+                            relative,
+                            vec![(String::from("🗑 Trash"), files)],
+                        )
+                    }
+                    None => (PathBuf::new(), self.current_dir.clone(), Vec::new()),
+                };
+                for component in relative.components() {
+                    if let Some(component_str) = component.as_os_str().to_str() {
+                        path_so_far.push(component_str);
+                        crumbs.push((component_str.to_string(), path_so_far.clone()));
+                    }
+                }
+                // Deep paths fold their middle into a "…" menu, keeping the
+                // first crumb and at least the last two; names too long even
+                // then scroll, with the end in view
+                let hidden = hidden_crumbs(ui, &crumbs);
+                egui::ScrollArea::horizontal()
+                    .id_source("breadcrumbs")
+                    .stick_to_right(true)
+                    .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysHidden)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            for (i, (label, path)) in crumbs.iter().enumerate() {
+                                if hidden.contains(&i) && i != hidden.start {
+                                    continue;
+                                }
+                                // None after the root, which is a separator itself
+                                if i > 0 && (i != 1 || in_trash) {
+                                    ui.label("/");
+                                }
+                                if hidden.contains(&i) {
+                                    if let Some(path) = folded_crumbs(ui, &crumbs[hidden.clone()]) {
+                                        self.go_to(path);
+                                    }
+                                    continue;
+                                }
+                                let btn = ui.selectable_label(false, label);
+                                if btn.clicked() {
+                                    self.go_to(path.clone());
+                                }
+                                if let Some(files) = dnd::drop_target(ui, &btn) {
+                                    self.drop_files(ui.ctx(), files, path.clone());
+                                } else if dnd::held(&btn)
+                                    && *path != self.current_dir
+                                    && self.spring.due(ui.ctx(), path)
+                                {
+                                    self.go_to(path.clone());
+                                }
+                            }
+                        });
+                    });
+            });
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                // Navigate to the parent directory
+                if self.can_navigate_up() && ui.button("Up").clicked() {
+                    if let Some(parent) = self.current_dir.parent().map(Path::to_path_buf) {
+                        self.go_to(parent);
+                    }
+                }
+                if ui.button("⟳").on_hover_text("Refresh (F5)").clicked() {
+                    self.read_dir();
+                }
+                let pinned = &mut self.tabs[self.active_tab].pinned;
+                ui.toggle_value(pinned, "📌").on_hover_text(
+                    "Pin this tab to keep it on this folder; going elsewhere opens a new tab",
+                );
+                if self.in_trash && !self.read_only && ui.button("Empty Trash").clicked() {
+                    self.request_empty_trash();
+                }
+                ui.separator();
+                self.filters.show(ui);
+                ui.separator();
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.search_text)
+                        .id(self.id.with("search"))
+                        .hint_text("Search (Ctrl+F)")
+                        .desired_width(160.0),
+                );
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    self.start_search();
+                }
+                let scope = self.search_scope;
+                egui::ComboBox::from_id_source("search_scope")
+                    .selected_text(scope.label())
+                    .show_ui(ui, |ui| {
+                        for option in SearchScope::ALL {
+                            ui.selectable_value(&mut self.search_scope, option, option.label());
+                        }
+                    });
+                let mut rescan = self.search_scope != scope;
+                let skip_label = if self.scan_exclude.is_some() {
+                    "Skip*"
+                } else {
+                    "Skip"
+                };
+                ui.menu_button(skip_label, |ui| {
+                    ui.label("Paths to skip, separated by ; (wildcards allowed):");
+                    let skip = self
+                        .scan_exclude
+                        .get_or_insert_with(|| self.config.scan_exclude.clone());
+                    let response = ui.text_edit_singleline(skip);
+                    rescan |= response.lost_focus();
+                    if ui
+                        .add_enabled(
+                            self.scan_exclude.as_ref() != Some(&self.config.scan_exclude),
+                            egui::Button::new("Use Settings"),
+                        )
+                        .clicked()
+                    {
+                        self.scan_exclude = None;
+                        rescan = true;
+                        ui.close_menu();
+                    }
+                })
+                .response
+                .on_hover_text("Paths searches skip, such as */node_modules/*");
+                // Only differences from the settings are kept, so later
+                // changes there still apply
+                if self.scan_exclude.as_ref() == Some(&self.config.scan_exclude) {
+                    self.scan_exclude = None;
+                }
+                if rescan && self.search.is_some() {
+                    self.read_dir();
+                }
+                if self.listing.is_some() {
+                    ui.spinner();
+                    let found = if self.search.is_some() {
+                        "found"
+                    } else {
+                        "so far"
+                    };
+                    ui.weak(format!("Loading… {} items {}", self.entries.len(), found));
+                }
+            });
+            if let Some(query) = &self.search {
+                let mut close = false;
+                ui.horizontal(|ui| {
+                    let place = match self.search_scope {
+                        SearchScope::Folder => folder_label(&self.current_dir),
+                        SearchScope::Subfolders => {
+                            format!("{} and its subfolders", folder_label(&self.current_dir))
+                        }
+                        SearchScope::Drive => format!("drive {}", self.drive_root().display()),
+                        SearchScope::Bookmarks => String::from("all bookmarks"),
+                        SearchScope::Everywhere => String::from("all drives"),
+                    };
+                    ui.label(format!(
+                        "🔍 {} matching “{}” in {}",
+                        ops::plural(self.entries.len(), "item"),
+                        query,
+                        place
+                    ));
+                    close = ui.button("Close Search").clicked();
+                });
+                if close {
+                    self.close_search();
+                }
+            }
+            // Filtering is skipped entirely when off, as folders can be huge
+            let visible = self.filters.is_active().then(|| self.visible_entries());
+            if let Some(visible) = &visible {
+                ui.weak(format!(
+                    "Showing {} of {}",
+                    visible.len(),
+                    ops::plural(self.entries.len(), "item")
+                ));
+            }
+
+            let columns = self.columns();
+            let plugin_columns = self.plugin_columns();
+            let column_count = columns.len() + plugin_columns.len();
+
+            let column_width = ui.available_width() / column_count as f32;
+            egui::Grid::new("file_manager_header")
+                .min_col_width(column_width)
+                .show(ui, |ui| {
+                    let mut clicked = None;
+                    for &column in &columns {
+                        let sorted = self.sort == column;
+                        let title = match (sorted, self.sort_descending) {
+                            (true, false) => format!("{} ⏶", column.title()),
+                            (true, true) => format!("{} ⏷", column.title()),
+                            (false, _) => column.title().to_string(),
+                        };
+                        let header = |ui: &mut egui::Ui| ui.selectable_label(sorted, title);
+                        let response = if column == SortColumn::Size {
+                            right_aligned(ui, column_width, header)
+                        } else {
+                            header(ui)
+                        };
+                        if response.on_hover_text("Sort by this column").clicked() {
+                            clicked = Some(column);
+                        }
+                    }
+                    if let Some(column) = clicked {
+                        self.sort_by(column);
+                    }
+                    for column in &plugin_columns {
+                        ui.label(&column.name);
+                    }
+                    ui.end_row();
+                });
+
+            // Only the visible rows are laid out, so huge folders stay fast
+            let row_count = visible.as_ref().map_or(self.entries.len(), Vec::len);
+            let wrap_names = self.config.view.wrap_names;
+            let row_height = if wrap_names {
+                let lines = 2.0 * ui.text_style_height(&egui::TextStyle::Button);
+                (lines + 2.0 * ui.spacing().button_padding.y).max(ui.spacing().interact_size.y)
+            } else {
+                ui.spacing().interact_size.y
+            };
+            let mut scroll = egui::ScrollArea::vertical().auto_shrink([false; 2]);
+            if let Some(row) = self.reveal_row(visible.as_deref()) {
+                // Brought to the middle of the list
+                let offset = row as f32 * (row_height + ui.spacing().item_spacing.y);
+                scroll =
+                    scroll.vertical_scroll_offset((offset - ui.available_height() / 2.0).max(0.0));
+            }
+            scroll.show_rows(ui, row_height, row_count, |ui, rows| {
+                profile_scope!("file grid");
+                egui::Grid::new("file_manager_grid")
+                    .striped(true)
+                    .start_row(rows.start)
+                    .min_col_width(column_width)
+                    .show(ui, |ui| {
+                        let mut clicked_dir: Option<PathBuf> = None;
+                        let mut new_tab: Option<PathBuf> = None;
+                        let mut dropped = None;
+                        let mut action: Option<EntryAction> = None;
+                        let mut picked = None;
+                        let modifiers = ui.input(|i| i.modifiers);
+
+                        let indices: Vec<usize> = match &visible {
+                            Some(visible) => visible[rows].to_vec(),
+                            None => rows.collect(),
+                        };
+                        for entry in indices.iter().map(|&i| &self.entries[i]) {
+                            let path = self.current_dir.join(&entry.name);
+                            let is_selected = self.selected.contains(&path);
+                            let shortcut_icon = entry
+                                .shortcut
+                                .as_ref()
+                                .and_then(|s| s.icon())
+                                .and_then(|icon| {
+                                    load_icon(&mut self.icon_textures, ui.ctx(), icon)
+                                });
+                            let folder_icon = entry
+                                .folder_icon
+                                .as_ref()
+                                .and_then(|f| f.icon.as_deref())
+                                .and_then(|icon| {
+                                    load_icon(&mut self.icon_textures, ui.ctx(), icon)
+                                });
+                            let thumbnail =
+                                (entry.loaded && !entry.is_dir && convert::is_image(&path))
+                                    .then(|| {
+                                        self.thumbnails.get(
+                                            &path,
+                                            entry.modified,
+                                            self.config.view.thumbnail_cache_mb * 1024 * 1024,
+                                        )
+                                    })
+                                    .flatten();
+                            let type_icon = self
+                                .config
+                                .view
+                                .theme_icons
+                                .then(|| icon_theme::for_item(&entry.name, entry.is_dir))
+                                .flatten()
+                                .and_then(|icon| {
+                                    load_icon(&mut self.icon_textures, ui.ctx(), &icon)
+                                });
+                            let row_icon =
+                                shortcut_icon.or(folder_icon).or(thumbnail).or(type_icon);
+                            let icon = match entry.shortcut.as_deref() {
+                                _ if row_icon.is_some() => "",
+                                _ if entry.is_dir => "📁",
+                                Some(Shortcut::Internet(_)) => "🌐",
+                                Some(_) => "🔗",
+                                None => "📄",
+                            };
+                            let display_name = entry
+                                .shortcut
+                                .as_ref()
+                                .and_then(|s| s.display_name())
+                                .unwrap_or(&entry.name);
+                            // Selected rows get a tick when colour alone
+                            // shouldn't tell them apart
+                            let mut marks = String::new();
+                            if is_selected && self.config.accessibility.shape_indicators {
+                                marks.push_str("✔ ");
+                            }
+                            if let Some(emblem) =
+                                entry.folder_icon.as_ref().and_then(|f| f.emblem.as_deref())
+                            {
+                                marks.push_str(emblem);
+                                marks.push(' ');
+                            }
+                            let marked_name = format!("{}{}", marks, display_name);
+                            let display_name = if marks.is_empty() {
+                                display_name
+                            } else {
+                                &marked_name
+                            };
+                            let label = format!("{} {}", icon, display_name);
+                            // Room left for the name beside the
+                            // thumbnail and badges
+                            let spacing = ui.spacing().item_spacing.x;
+                            let mut name_width = column_width
+                                - 2.0 * ui.spacing().button_padding.x
+                                - entry.badges.len() as f32 * (16.0 + spacing);
+                            if row_icon.is_some() {
+                                name_width -= 16.0 + spacing;
+                            }
+                            let nme_lbl = ui
+                                .horizontal(|ui| {
+                                    ui.set_min_height(row_height);
+                                    let response = match row_icon {
+                                        Some(texture) => {
+                                            ui.add(
+                                                egui::Image::new(&texture)
+                                                    .fit_to_exact_size(egui::vec2(16.0, 16.0)),
+                                            );
+                                            let name =
+                                                name_text(ui, display_name, name_width, wrap_names);
+                                            ui.selectable_label(is_selected, name)
+                                        }
+                                        None => {
+                                            let name =
+                                                name_text(ui, &label, name_width, wrap_names);
+                                            ui.selectable_label(is_selected, name)
+                                        }
+                                    };
+                                    for badge in &entry.badges {
+                                        ui.weak(badge.icon).on_hover_text(badge.tooltip);
+                                    }
+                                    response
+                                })
+                                .inner;
+                            // Full name, path, size and dates once the
+                            // pointer has rested on the row a moment
+                            if nme_lbl.hovered() {
+                                let delay = self.config.view.details_delay_ms as f32 / 1000.0;
+                                let still = ui.input(|i| i.pointer.time_since_last_movement());
+                                if still >= delay {
+                                    egui::show_tooltip_at_pointer(
+                                        ui.ctx(),
+                                        ui.layer_id(),
+                                        nme_lbl.id.with("details"),
+                                        |ui| details_tooltip(ui, entry, &path),
+                                    );
+                                } else {
+                                    ui.ctx().request_repaint_after_secs(delay - still);
+                                }
+                            }
+                            let nme_lbl = if self.read_only {
+                                nme_lbl
+                            } else {
+                                nme_lbl.interact(egui::Sense::drag())
+                            };
+                            if nme_lbl.drag_started() {
+                                dnd::start(ui.ctx(), self.action_targets(&path));
+                                self.drag_origin =
+                                    Some((self.active_tab, self.current_dir.clone()));
+                            }
+                            if entry.is_dir {
+                                // Held over a folder other than the
+                                // dragged ones, it opens after a moment
+                                let held = nme_lbl
+                                    .dnd_hover_payload::<dnd::DraggedFiles>()
+                                    .is_some_and(|files| !files.0.contains(&path));
+                                if let Some(files) = dnd::drop_target(ui, &nme_lbl) {
+                                    dropped = Some((files, path.clone()));
+                                } else if held && self.spring.due(ui.ctx(), &path) {
+                                    clicked_dir = Some(path.clone());
+                                }
+                            }
+
+                            if nme_lbl.clicked() {
+                                if modifiers.command {
+                                    if !self.selected.remove(&path) {
+                                        self.selected.insert(path.clone());
+                                    }
+                                } else if entry.is_dir {
+                                    clicked_dir = Some(path.clone());
+                                } else {
+                                    self.selected.clear();
+                                    self.selected.insert(path.clone());
+                                }
+                            }
+
+                            // Like links in a web browser, middle-click or
+                            // Ctrl+double-click opens a folder in a tab
+                            // behind this one
+                            let to_tab = nme_lbl.middle_clicked()
+                                || (modifiers.command && nme_lbl.double_clicked());
+                            if to_tab && entry.is_dir {
+                                new_tab = Some(path.clone());
+                            }
+
+                            if nme_lbl.secondary_clicked() && !is_selected {
+                                self.selected.clear();
+                                self.selected.insert(path.clone());
+                            }
+                            nme_lbl.context_menu(|ui| {
+                                if let Some(a) = self.entry_context_menu(ui, &path) {
+                                    action = Some(a);
+                                }
+                            });
+
+                            let picking_files = matches!(
+                                self.picker.as_ref().map(|p| &p.mode),
+                                Some(picker::PickMode::Open {
+                                    directory: false,
+                                    ..
+                                })
+                            );
+                            let opened = nme_lbl.double_clicked() && !entry.is_dir;
+                            if opened && picking_files {
+                                picked = Some(path.clone());
+                            } else if opened
+                                && self.on_open.as_mut().is_some_and(|on_open| on_open(&path))
+                            {
+                                // Handled by the embedding application
+                            } else if opened {
+                                let trusted_shortcut = match entry.shortcut.as_deref() {
+                                    Some(Shortcut::Desktop(_)) => {
+                                        shortcuts::is_trusted_desktop_file(&path)
+                                    }
+                                    Some(Shortcut::WindowsLink(_)) => true,
+                                    Some(Shortcut::Internet(shortcut)) => shortcut.is_web(),
+                                    None => false,
+                                };
+                                if let (Some(shortcut), true) =
+                                    (entry.shortcut.as_deref(), trusted_shortcut)
+                                {
+                                    if let Err(e) = shortcut.launch(&path) {
+                                        tracing::error!("Failed to launch {}: {}", entry.name, e);
+                                    }
+                                } else if launch::is_executable(&path) {
+                                    action = Some(EntryAction::ConfirmRun(path.clone()));
+                                } else {
+                                    tracing::info!("Opening file: {}", entry.name);
+                                    let result = open_with_opener(
+                                        &self.config.openers,
+                                        &self.current_dir,
+                                        &entry.name,
+                                    );
+                                    if let Err(e) = result {
+                                        tracing::error!("Failed to open file: {}", e);
+                                    }
+                                }
+                            }
+
+                            // File or directory size
+                            right_aligned(ui, column_width, |ui| {
+                                if !entry.loaded {
+                                    ui.weak("…");
+                                } else if entry.is_dir {
+                                    ui.label(format!("{} items", entry.size));
+                                } else {
+                                    let mut text =
+                                        egui::RichText::new(format_file_size_column(entry.size))
+                                            .monospace();
+                                    let heat = size_heat(entry.size)
+                                        .filter(|_| self.config.view.size_heat);
+                                    if let Some((color, _)) = heat {
+                                        text = text.color(color);
+                                    }
+                                    ui.label(text).on_hover_text(format_exact_size(entry.size));
+                                    // Left of the size, so the sizes stay lined up
+                                    if let (Some((color, level)), true) =
+                                        (heat, self.config.accessibility.shape_indicators)
+                                    {
+                                        ui.colored_label(color, "■".repeat(level));
+                                    }
+                                }
+                            });
+
+                            // Modified date
+                            let modified = entry.modified_text(&mut self.dates);
+                            let recency = entry
+                                .modified
+                                .filter(|_| self.config.view.highlight_recent)
+                                .and_then(recency);
+                            match recency {
+                                Some((color, shape, hint)) => {
+                                    let shape = if self.config.accessibility.shape_indicators {
+                                        shape
+                                    } else {
+                                        "●"
+                                    };
+                                    ui.label(
+                                        egui::RichText::new(format!("{} {}", shape, modified))
+                                            .color(color),
+                                    )
+                                    .on_hover_text(hint);
+                                }
+                                None => {
+                                    ui.label(modified);
+                                }
+                            }
+
+                            for column in &columns[3..] {
+                                let value = match column {
+                                    SortColumn::Created => {
+                                        entry.created.map(|t| self.dates.label(t).to_string())
+                                    }
+                                    SortColumn::Accessed => {
+                                        entry.accessed.map(|t| self.dates.label(t).to_string())
+                                    }
+                                    SortColumn::Owner => {
+                                        entry.owner.map(|o| owners::user_label(o.uid))
+                                    }
+                                    SortColumn::Group => {
+                                        entry.owner.map(|o| owners::group_label(o.gid))
+                                    }
+                                    SortColumn::Original => entry
+                                        .original
+                                        .as_deref()
+                                        .and_then(Path::parent)
+                                        .map(|p| p.display().to_string()),
+                                    SortColumn::Deleted => {
+                                        entry.deleted.map(|t| self.dates.label(t).to_string())
+                                    }
+                                    _ => None,
+                                };
+                                match value {
+                                    Some(value) => {
+                                        ui.label(value);
+                                    }
+                                    None if entry.loaded => {
+                                        ui.weak("—").on_hover_text(
+                                            "Not recorded by this system or filesystem",
+                                        );
+                                    }
+                                    None => {
+                                        ui.weak("…");
+                                    }
+                                }
+                            }
+
+                            for column in 0..plugin_columns.len() {
+                                let key = (column, self.current_dir.join(&entry.name));
+                                ui.label(self.plugin_cells.get(&key).map_or("", |v| v));
+                            }
+                            ui.end_row();
+                        }
+
+                        if let Some(action) = action {
+                            self.apply_entry_action(action);
+                        }
+
+                        if let Some(new_dir) = clicked_dir {
+                            self.go_to(new_dir);
+                        }
+                        if let Some(dir) = new_tab {
+                            self.open_tab(dir, false);
+                        }
+                        if let Some((files, destination)) = dropped {
+                            self.drop_files(ui.ctx(), files, destination);
+                        }
+                        if picked.is_some() {
+                            self.accept_pick(ui.ctx(), picked);
+                        }
+                    });
+            });
+        });
+
+        self.run_script_hooks();
+        // The main window's session is the one offered back after a crash
+        if !self.detached {
+            let jobs = self.windows.iter().map(|w| &w.browser.jobs);
+            recovery::update(
+                &self.current_dir,
+                self.saved_tabs(),
+                std::iter::once(&self.jobs).chain(jobs),
+            );
+            let windows = self.windows.iter().map(|w| &w.browser.jobs);
+            taskbar::update(jobs::overall(std::iter::once(&self.jobs).chain(windows)));
+            if !self.overview && self.picker.is_none() && self.saved_location != self.current_dir {
+                self.saved_location = self.current_dir.clone();
+                config::save_last_location(&self.current_dir);
+            }
+        }
+        self.show_windows(ctx);
+        #[cfg(feature = "profiling")]
+        if !self.detached {
+            self.profiler.end_frame();
+        }
+    }
+}
+
+// How the timestamp tool shows and reads dates
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// What's typed in the search box as wildcard patterns; plain words match
+// anywhere in a name
+fn search_patterns(query: &str) -> String {
+    query
+        .split(';')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            if part.contains(['*', '?']) {
+                part.to_string()
+            } else {
+                format!("*{}*", part)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+// Names too long for their column are cut short in the middle, keeping the
+// start and the extension, or wrapped onto a second line
+fn name_text(ui: &egui::Ui, name: &str, max_width: f32, wrap: bool) -> egui::WidgetText {
+    let font_id = egui::TextStyle::Button.resolve(ui.style());
+    let format = egui::TextFormat::simple(font_id.clone(), egui::Color32::PLACEHOLDER);
+    let text = if wrap {
+        name.to_string()
+    } else {
+        let fits = |text: &str| {
+            let galley = ui.fonts(|f| {
+                f.layout_no_wrap(
+                    text.to_string(),
+                    font_id.clone(),
+                    egui::Color32::PLACEHOLDER,
+                )
+            });
+            galley.size().x <= max_width
+        };
+        truncate_middle(name, fits)
+    };
+    let mut job = egui::text::LayoutJob::single_section(text, format);
+    if wrap {
+        job.wrap = egui::text::TextWrapping {
+            max_width,
+            max_rows: 2,
+            break_anywhere: true,
+            overflow_character: Some('…'),
+        };
+    }
+    ui.fonts(|f| f.layout_job(job)).into()
+}
+
+// Everything about a row the columns may cut short or round
+fn details_tooltip(ui: &mut egui::Ui, entry: &FileEntry, path: &Path) {
+    ui.strong(&entry.name);
+    ui.weak(path.display().to_string());
+    if let Some(target) = entry.shortcut.as_ref().and_then(|s| s.target()) {
+        ui.label(format!("→ {}", target));
+    }
+    if !entry.loaded {
+        return;
+    }
+    ui.separator();
+    egui::Grid::new("details_tooltip")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.weak("Size");
+            if entry.is_dir {
+                ui.label(ops::plural(entry.size as usize, "item"));
+            } else {
+                ui.label(format_exact_size(entry.size));
+            }
+            ui.end_row();
+            let times = [
+                ("Modified", entry.modified),
+                ("Created", entry.created),
+                ("Accessed", entry.accessed),
+            ];
+            for (label, time) in times {
+                if let Some(time) = time {
+                    let time: chrono::DateTime<chrono::Local> = time.into();
+                    ui.weak(label);
+                    ui.label(time.format(TIMESTAMP_FORMAT).to_string());
+                    ui.end_row();
+                }
+            }
+        });
+}
+
+// Warmer colours for bigger files, to spot what's taking up the space, and
+// the level (1 to 3) for those who can't tell the colours apart
+fn size_heat(size: u64) -> Option<(egui::Color32, usize)> {
+    const MB: u64 = 1024 * 1024;
+    match size {
+        s if s >= 1024 * MB => Some((egui::Color32::from_rgb(220, 60, 50), 3)),
+        s if s >= 100 * MB => Some((egui::Color32::from_rgb(230, 130, 40), 2)),
+        s if s >= 10 * MB => Some((egui::Color32::from_rgb(200, 170, 40), 1)),
+        _ => None,
+    }
+}
+
+// Highlight colour for items modified today or in the past week, with a
+// shape that tells the two apart without the colour
+fn recency(modified: std::time::SystemTime) -> Option<(egui::Color32, &'static str, &'static str)> {
+    let modified: chrono::DateTime<chrono::Local> = modified.into();
+    let today = chrono::Local::now().date_naive();
+    let day = modified.date_naive();
+    let fresh = egui::Color32::from_rgb(70, 170, 90);
+    if day == today {
+        Some((fresh, "⏺", "Modified today"))
+    } else if day < today && today - day < chrono::Duration::days(7) {
+        Some((fresh.gamma_multiply(0.6), "○", "Modified this week"))
+    } else {
+        None
+    }
+}
+
+// Stark text and outlines on a black or white background, with the
+// selection in a bright accent
+fn high_contrast_visuals(dark: bool) -> egui::Visuals {
+    use egui::{Color32, Stroke};
+    let (mut visuals, fg, bg, accent) = if dark {
+        let accent = Color32::from_rgb(255, 210, 0);
+        (
+            egui::Visuals::dark(),
+            Color32::WHITE,
+            Color32::BLACK,
+            accent,
+        )
+    } else {
+        let accent = Color32::from_rgb(0, 60, 200);
+        (
+            egui::Visuals::light(),
+            Color32::BLACK,
+            Color32::WHITE,
+            accent,
+        )
+    };
+    visuals.panel_fill = bg;
+    visuals.window_fill = bg;
+    visuals.extreme_bg_color = bg;
+    visuals.faint_bg_color = if dark {
+        Color32::from_gray(40)
+    } else {
+        Color32::from_gray(225)
+    };
+    visuals.window_stroke = Stroke::new(2.0, fg);
+    visuals.hyperlink_color = accent;
+    visuals.selection.bg_fill = accent;
+    visuals.selection.stroke = Stroke::new(2.0, bg);
+    let widgets = &mut visuals.widgets;
+    for widget in [
+        &mut widgets.noninteractive,
+        &mut widgets.inactive,
+        &mut widgets.hovered,
+        &mut widgets.active,
+        &mut widgets.open,
+    ] {
+        widget.fg_stroke = Stroke::new(widget.fg_stroke.width.max(1.5), fg);
+        widget.bg_stroke = Stroke::new(1.0, fg);
+    }
+    widgets.inactive.bg_fill = bg;
+    widgets.inactive.weak_bg_fill = bg;
+    widgets.hovered.bg_stroke = Stroke::new(2.0, accent);
+    widgets.active.bg_stroke = Stroke::new(2.0, accent);
+    visuals
+}
+
+fn template_menu(ui: &mut egui::Ui, items: &[Template]) -> Option<PathBuf> {
+    let mut chosen = None;
+    for item in items {
+        match item {
+            Template::File { label, path } => {
+                if ui.button(label).clicked() {
+                    chosen = Some(path.clone());
+                }
+            }
+            Template::Group { label, items } => {
+                ui.menu_button(label, |ui| {
+                    if let Some(path) = template_menu(ui, items) {
+                        chosen = Some(path);
+                    }
+                });
+            }
+        }
+    }
+    chosen
+}
+
+fn draw_qr_code(ui: &mut egui::Ui, width: usize, modules: &[bool]) {
+    const MODULE: f32 = 5.0;
+    // The spec asks for a four-module light border around the code
+    const QUIET_ZONE: usize = 4;
+
+    let side = (width + 2 * QUIET_ZONE) as f32 * MODULE;
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::WHITE);
+    for (i, dark) in modules.iter().enumerate() {
+        if *dark {
+            let x = (i % width + QUIET_ZONE) as f32 * MODULE;
+            let y = (i / width + QUIET_ZONE) as f32 * MODULE;
+            let module =
+                egui::Rect::from_min_size(rect.min + egui::vec2(x, y), egui::vec2(MODULE, MODULE));
+            painter.rect_filled(module, 0.0, egui::Color32::BLACK);
+        }
+    }
+}
+
+// Lays out a grid cell's contents against its right edge
+// Which crumbs, after the first, go into the "…" menu so the rest fit the
+// width left; never the last two
+fn hidden_crumbs(ui: &egui::Ui, crumbs: &[(String, PathBuf)]) -> std::ops::Range<usize> {
+    let spacing = ui.spacing().item_spacing.x;
+    let padding = 2.0 * ui.spacing().button_padding.x;
+    let text_width = |text: &str, style: egui::TextStyle| {
+        let font = style.resolve(ui.style());
+        ui.fonts(|f| {
+            f.layout_no_wrap(text.to_string(), font, egui::Color32::WHITE)
+                .size()
+                .x
+        })
+    };
+    let separator = text_width("/", egui::TextStyle::Body) + spacing;
+    let widths: Vec<f32> = crumbs
+        .iter()
+        .map(|(label, _)| {
+            text_width(label, egui::TextStyle::Button) + padding + spacing + separator
+        })
+        .collect();
+    let available = ui.available_width();
+    if crumbs.len() <= 3 || widths.iter().sum::<f32>() <= available {
+        return 1..1;
+    }
+    let mut used =
+        widths[0] + text_width("…", egui::TextStyle::Button) + padding + spacing + separator;
+    let mut first_shown = crumbs.len();
+    while first_shown > 1 {
+        let width = widths[first_shown - 1];
+        let must_show = crumbs.len() - first_shown < 2;
+        if !must_show && used + width > available {
+            break;
+        }
+        used += width;
+        first_shown -= 1;
+    }
+    1..first_shown
+}
+
+// The "…" crumb, listing the folded folders from the top down
+fn folded_crumbs(ui: &mut egui::Ui, folded: &[(String, PathBuf)]) -> Option<PathBuf> {
+    let mut chosen = None;
+    ui.menu_button("…", |ui| {
+        for (label, path) in folded {
+            if ui.button(format!("📁 {}", label)).clicked() {
+                chosen = Some(path.clone());
+                ui.close_menu();
+            }
+        }
+    })
+    .response
+    .on_hover_text(
+        folded
+            .last()
+            .map(|(_, path)| path.display().to_string())
+            .unwrap_or_default(),
+    );
+    chosen
+}
+
+fn right_aligned<R>(ui: &mut egui::Ui, width: f32, add: impl FnOnce(&mut egui::Ui) -> R) -> R {
+    ui.allocate_ui_with_layout(
+        egui::vec2(width, ui.spacing().interact_size.y),
+        egui::Layout::right_to_left(egui::Align::Center),
+        add,
+    )
+    .inner
+}
+
+fn device_icon(device: &udisks::BlockDevice) -> &'static str {
+    match () {
+        _ if device.locked => "🔒",
+        _ if device.removable => "💾",
+        _ => "🖴",
+    }
+}
+
+// One volume on the overview; returns true when it was clicked
+fn volume_card(
+    ui: &mut egui::Ui,
+    name: &str,
+    details: &str,
+    space: Option<&drives::Space>,
+) -> bool {
+    const WIDTH: f32 = 220.0;
+    let response = egui::Frame::group(ui.style())
+        .show(ui, |ui| {
+            ui.set_width(WIDTH);
+            ui.strong(name);
+            match space {
+                Some(space) => {
+                    let used = space.used_fraction();
+                    let mut bar = egui::ProgressBar::new(used).text(format!(
+                        "{} free of {}",
+                        format_file_size(space.available),
+                        format_file_size(space.total)
+                    ));
+                    // Nearly full drives stand out
+                    if used > 0.9 {
+                        bar = bar.fill(ui.visuals().error_fg_color);
+                    }
+                    ui.add(bar);
+                }
+                None => {
+                    ui.weak("Not mounted");
+                }
+            }
+            ui.weak(details);
+        })
+        .response
+        .interact(egui::Sense::click());
+    response
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+        .clicked()
+}
+
+fn folder_label(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+// Only looks through the first few thousand items, so a huge tree doesn't
+// hold up the window
+fn has_symlinks(sources: &[PathBuf]) -> bool {
+    sources
+        .iter()
+        .flat_map(|s| walkdir::WalkDir::new(s).into_iter().flatten())
+        .take(5000)
+        .any(|e| e.path_is_symlink())
+}
+
+// Just the last part, which is empty for roots
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn load_icon(
+    cache: &mut HashMap<String, Option<egui::TextureHandle>>,
+    ctx: &egui::Context,
+    icon: &str,
+) -> Option<egui::TextureHandle> {
+    cache
+        .entry(icon.to_string())
+        .or_insert_with(|| {
+            let image = match icon_theme::bundled(icon) {
+                Some(bytes) => image::load_from_memory(bytes).ok()?,
+                None => image::open(shortcuts::find_icon_file(icon)?).ok()?,
+            }
+            .into_rgba8();
+            let size = [image.width() as usize, image.height() as usize];
+            let pixels = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+            Some(ctx.load_texture(icon, pixels, egui::TextureOptions::LINEAR))
+        })
+        .clone()
+}
+
+// Appends " (2)", " (3)", … before the extension until the name is free
+pub(crate) fn unique_file_name(dir: &Path, name: &str) -> String {
+    file_manager_core::ops::unique_file_name(&LocalFs, dir, name)
+}
+
+// "report.pdf" for a single item, "3 items" for several
+fn items_label(paths: &[PathBuf]) -> String {
+    match paths {
+        [path] => path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string()),
+        paths => format!("{} items", paths.len()),
+    }
+}
+
+fn paths_text(paths: &[PathBuf]) -> String {
+    join_lines(paths.iter().map(|p| p.display().to_string()))
+}
+
+fn join_lines(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join("\n")
+}
+
+fn open_file(current_dir: &Path, file_name: &str) -> std::io::Result<()> {
+    let file_path = current_dir.join(file_name);
+    launch::open_with_system(file_path.as_os_str())
+}
+
+// Opens with the per-extension command from the settings when there is one,
+// otherwise with the system default application
+fn open_with_opener(
+    openers: &std::collections::BTreeMap<String, String>,
+    current_dir: &Path,
+    file_name: &str,
+) -> std::io::Result<()> {
+    let file_path = current_dir.join(file_name);
+    let extension = file_path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase());
+    match extension.and_then(|e| openers.get(&e)) {
+        Some(command) => {
+            let targets = [file_path.clone()];
+            let command_line = actions::expand(command, Some(&file_path), &targets, current_dir);
+            actions::shell_command(&command_line)
+                .current_dir(current_dir)
+                .spawn()
+                .map(|_| ())
+        }
+        None => open_file(current_dir, file_name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detached_windows_stay_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("a");
+        fs::write(&from, "").unwrap();
+        let browser = FileBrowserWidget::new()
+            .with_directory(dir.path())
+            .with_read_only();
+        let tab = Tab {
+            dir: dir.path().to_path_buf(),
+            selected: HashSet::new(),
+            overview: false,
+            pinned: false,
+        };
+        let window = browser.detached(tab, egui::Id::new("window"));
+        let script = format!(
+            "rename({:?}, {:?})",
+            from.display().to_string(),
+            dir.path().join("b").display().to_string()
+        );
+        assert!(window.scripts.run_source(&script, dir.path()).is_err());
+        assert!(from.exists());
+    }
+}
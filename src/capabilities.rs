@@ -0,0 +1,123 @@
+// What the filesystem holding a path can store, so the UI can leave out what
+// wouldn't stick: permissions on FAT32, links on exFAT. Known filesystem
+// types answer from a table; case sensitivity is also checked against the
+// folder's own entries, as it's a per-volume (or even per-folder) setting on
+// APFS, NTFS and others.
+use crate::drives;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, PartialEq)]
+pub struct Capabilities {
+    // As the system names it, e.g. "ext4" or "vfat"; empty when unknown
+    pub fs_type: String,
+    pub symlinks: bool,
+    // Unix permission bits and ACLs, or NTFS ACLs on Windows
+    pub permissions: bool,
+    pub xattrs: bool,
+    pub case_sensitive: bool,
+}
+
+impl Capabilities {
+    pub fn detect(path: &Path) -> Self {
+        let fs_type = drives::fs_type(path).unwrap_or_default();
+        let mut capabilities = Self::of_type(&fs_type);
+        let dir = if path.is_dir() {
+            Some(path)
+        } else {
+            path.parent()
+        };
+        if let Some(case_sensitive) = dir.and_then(probe_case) {
+            capabilities.case_sensitive = case_sensitive;
+        }
+        capabilities
+    }
+
+    fn of_type(fs_type: &str) -> Self {
+        let (symlinks, permissions, xattrs, case_sensitive) = match fs_type {
+            "vfat" | "msdos" | "fat" | "fat32" | "exfat" => (false, false, false, false),
+            "ntfs" | "ntfs3" | "fuseblk" => (true, cfg!(windows), false, false),
+            "cifs" | "smbfs" | "smb3" => (false, false, false, false),
+            "iso9660" | "udf" | "cd9660" => (false, false, false, true),
+            "hfs" | "apfs" => (true, true, true, false),
+            // Nothing but Administrators creates links on Windows
+            _ if cfg!(windows) => (false, true, false, false),
+            _ => (true, true, true, !cfg!(target_os = "macos")),
+        };
+        Self {
+            fs_type: fs_type.to_string(),
+            symlinks,
+            permissions,
+            xattrs,
+            case_sensitive,
+        }
+    }
+
+    // "exfat drives", for notes about what's missing
+    pub fn drive_label(&self) -> String {
+        if self.fs_type.is_empty() {
+            String::from("This drive")
+        } else {
+            format!("{} drives", self.fs_type)
+        }
+    }
+
+    // What doesn't fit, for the Properties window
+    pub fn describe(&self) -> String {
+        let mut missing = Vec::new();
+        if !self.symlinks {
+            missing.push("links");
+        }
+        if !self.permissions {
+            missing.push("permissions");
+        }
+        if !self.xattrs {
+            missing.push("extended attributes");
+        }
+        let mut notes = Vec::new();
+        if !missing.is_empty() {
+            notes.push(format!("no {}", missing.join(", ")));
+        }
+        if !self.case_sensitive {
+            notes.push(String::from("names ignore case"));
+        }
+        match (self.fs_type.as_str(), notes.is_empty()) {
+            ("", true) => String::from("Unknown"),
+            ("", false) => notes.join("; "),
+            (fs_type, true) => fs_type.to_string(),
+            (fs_type, false) => format!("{} ({})", fs_type, notes.join("; ")),
+        }
+    }
+}
+
+// Looks up an entry of `dir` under its name with the case flipped: finding it
+// means names ignore case. Nothing is written, but it needs an entry with
+// letters in its name.
+fn probe_case(dir: &Path) -> Option<bool> {
+    let names: Vec<String> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .take(200)
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.iter().find_map(|name| {
+        let flipped: String = name
+            .chars()
+            .map(|c| {
+                if c.is_uppercase() {
+                    c.to_lowercase().next().unwrap_or(c)
+                } else {
+                    c.to_uppercase().next().unwrap_or(c)
+                }
+            })
+            .collect();
+        if flipped == *name {
+            return None;
+        }
+        // Both spellings being separate entries settles it too
+        if names.contains(&flipped) {
+            return Some(true);
+        }
+        Some(fs::symlink_metadata(dir.join(&flipped)).is_err())
+    })
+}
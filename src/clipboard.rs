@@ -0,0 +1,61 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub enum PastedContent {
+    Text(String),
+    Image {
+        width: usize,
+        height: usize,
+        rgba: Vec<u8>,
+    },
+}
+
+impl PastedContent {
+    pub fn default_name(&self) -> &'static str {
+        match self {
+            PastedContent::Text(_) => "Pasted text.txt",
+            PastedContent::Image { .. } => "Pasted image.png",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            PastedContent::Text(text) => format!("Text, {} characters", text.chars().count()),
+            PastedContent::Image { width, height, .. } => format!("Image, {}×{}", width, height),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        match self {
+            PastedContent::Text(text) => fs::write(path, text),
+            PastedContent::Image {
+                width,
+                height,
+                rgba,
+            } => {
+                let image = image::RgbaImage::from_raw(*width as u32, *height as u32, rgba.clone())
+                    .ok_or_else(|| io::Error::other("clipboard image has an invalid size"))?;
+                image.save(path).map_err(io::Error::other)
+            }
+        }
+    }
+}
+
+// Reads an image or text from the system clipboard, preferring the image when
+// both are present (screenshots often carry a text fallback)
+pub fn read() -> Option<PastedContent> {
+    let mut clipboard = arboard::Clipboard::new().ok()?;
+    if let Ok(image) = clipboard.get_image() {
+        return Some(PastedContent::Image {
+            width: image.width,
+            height: image.height,
+            rgba: image.bytes.into_owned(),
+        });
+    }
+    clipboard
+        .get_text()
+        .ok()
+        .filter(|text| !text.is_empty())
+        .map(PastedContent::Text)
+}
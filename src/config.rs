@@ -0,0 +1,349 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // Kiosk and demo setups: nothing can be deleted, moved, pasted or
+    // otherwise changed. Only settable in the file (or with --read-only) so
+    // it can't be switched off from inside the app.
+    pub read_only: bool,
+    pub actions: Vec<CustomAction>,
+    // Folder names of plugins switched off in the plugin manager
+    pub disabled_plugins: Vec<String>,
+    // Lower-case extension → command line overriding the system default
+    // application, e.g. `mp4 = "mpv %f"`
+    pub openers: BTreeMap<String, String>,
+    pub bookmarks: Vec<PathBuf>,
+    pub transfers: TransferOptions,
+    pub view: ViewOptions,
+    pub confirmations: Confirmations,
+    pub trash: TrashOptions,
+    pub search: SearchOptions,
+    // Paths recursive scans skip unless changed for a run, as `;`-separated
+    // wildcard patterns matched against the whole path, e.g.
+    // `scan_exclude = "*/node_modules/*; *.o"`
+    pub scan_exclude: String,
+    pub notifications: NotificationOptions,
+    // A tray icon with bookmarks and recent folders (Linux); closing the
+    // window then minimises it
+    pub tray: bool,
+    pub startup: StartupOptions,
+    pub accessibility: AccessibilityOptions,
+}
+
+//   [accessibility]
+//   high_contrast = true
+//   shape_indicators = true
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityOptions {
+    // Black or white backgrounds, stark text and outlines
+    pub high_contrast: bool,
+    // Shapes and ticks alongside the colours that mark selected items,
+    // recently modified dates and large files
+    pub shape_indicators: bool,
+}
+
+// Where a window opens when it isn't given a folder
+//
+//   [startup]
+//   folder = "path"
+//   path = "/srv/media"
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StartupOptions {
+    pub folder: StartupFolder,
+    // For `folder = "path"`
+    pub path: PathBuf,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartupFolder {
+    // "This Computer"
+    #[default]
+    Overview,
+    Home,
+    // Where the main window was when it closed
+    Last,
+    Path,
+}
+
+fn last_location_file() -> Option<PathBuf> {
+    crate::user_dirs::data_dir().map(|d| d.join("last-location"))
+}
+
+pub fn last_location() -> Option<PathBuf> {
+    let text = fs::read_to_string(last_location_file()?).ok()?;
+    Some(PathBuf::from(text.trim_end_matches('\n')))
+}
+
+pub fn save_last_location(dir: &std::path::Path) {
+    let Some(file) = last_location_file() else {
+        return;
+    };
+    let result = file
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(&file, format!("{}\n", dir.display())));
+    if let Err(e) = result {
+        tracing::warn!("Failed to save {}: {}", file.display(), e);
+    }
+}
+
+// How the file list looks
+//
+//   [view]
+//   highlight_recent = true
+//   relative_dates = false
+//   show_created = true
+//   size_heat = true
+//   show_owner = true
+//   thumbnail_cache_mb = 256
+//   details_delay_ms = 500
+//   wrap_names = true
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewOptions {
+    // Colour the dates of items modified today and this week
+    pub highlight_recent: bool,
+    // "Today at" and "Yesterday at" instead of the date for the last two days
+    pub relative_dates: bool,
+    // Optional date columns
+    pub show_created: bool,
+    pub show_accessed: bool,
+    // Owner and Group columns
+    pub show_owner: bool,
+    // Colour sizes by how big the file is
+    pub size_heat: bool,
+    // Size the thumbnail cache is trimmed back to
+    pub thumbnail_cache_mb: u64,
+    // How long the pointer rests on a row before its details tooltip shows
+    pub details_delay_ms: u64,
+    // Long names over two lines rather than cut short in the middle
+    pub wrap_names: bool,
+    // File type and folder icons from the icon theme instead of emoji
+    pub theme_icons: bool,
+}
+
+impl Default for ViewOptions {
+    fn default() -> Self {
+        Self {
+            highlight_recent: false,
+            relative_dates: true,
+            show_created: false,
+            show_accessed: false,
+            show_owner: false,
+            size_heat: false,
+            thumbnail_cache_mb: 256,
+            details_delay_ms: 700,
+            wrap_names: false,
+            theme_icons: true,
+        }
+    }
+}
+
+// How copy and move jobs write their files
+//
+//   [transfers]
+//   verify = true
+//   preserve_xattrs = false
+//   copy_name = "Copy of {name}{ext}"
+//   per_disk = 2
+//   numbered_copy_name = "Copy {n} of {name}{ext}"
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransferOptions {
+    // Re-read every copied file and compare its SHA-256 with the source
+    pub verify: bool,
+    pub preserve_timestamps: bool,
+    pub preserve_permissions: bool,
+    pub preserve_xattrs: bool,
+    // Copy symlinks as links; when off, what they point to gets copied
+    pub preserve_symlinks: bool,
+    // Names for copies pasted into the folder of their original, see
+    // `file_manager_core::ops::copy_name`
+    pub copy_name: String,
+    pub numbered_copy_name: String,
+    // Transfers running at once on one hard disk, others wait their turn;
+    // 0 for no limit. SSDs aren't limited.
+    pub per_disk: usize,
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        Self {
+            verify: false,
+            preserve_timestamps: true,
+            preserve_permissions: true,
+            preserve_xattrs: true,
+            preserve_symlinks: true,
+            copy_name: String::from("{name} (copy){ext}"),
+            numbered_copy_name: String::from("{name} (copy {n}){ext}"),
+            per_disk: 1,
+        }
+    }
+}
+
+// Which operations ask before going ahead. Each prompt also has a "Don't
+// ask again" checkbox that turns its entry off.
+//
+//   [confirmations]
+//   permanent_delete = false
+//   trash = true
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Confirmations {
+    // Moving to the trash, which can be undone
+    pub trash: bool,
+    // Deleting for good, shredding included
+    pub permanent_delete: bool,
+    // Saving over an existing file from the file chooser
+    pub overwrite: bool,
+    // Opening programs and executable scripts; files downloaded from the
+    // internet always ask
+    pub run_executables: bool,
+    pub empty_trash: bool,
+}
+
+impl Default for Confirmations {
+    fn default() -> Self {
+        Self {
+            trash: false,
+            permanent_delete: true,
+            overwrite: true,
+            run_executables: true,
+            empty_trash: true,
+        }
+    }
+}
+
+//   [notifications]
+//   enabled = true
+//   after_secs = 30
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationOptions {
+    // Tell the desktop when a job ends while the window isn't in focus
+    pub enabled: bool,
+    // Quicker jobs end before anyone looks away
+    pub after_secs: u64,
+}
+
+impl Default for NotificationOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            after_secs: 10,
+        }
+    }
+}
+
+//   [trash]
+//   auto_empty = true
+//   auto_empty_days = 30
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashOptions {
+    // Delete items trashed more than `auto_empty_days` ago, checked at startup
+    pub auto_empty: bool,
+    pub auto_empty_days: u32,
+}
+
+impl Default for TrashOptions {
+    fn default() -> Self {
+        Self {
+            auto_empty: false,
+            auto_empty_days: 30,
+        }
+    }
+}
+
+//   [search.exclude]
+//   subfolders = "node_modules; .git"
+//   everywhere = "node_modules; .git; .cache; .Trash*"
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub exclude: SearchExcludes,
+}
+
+// Folders each search scope doesn't look into, by name, as `;`-separated
+// wildcard patterns. Searching just the current folder skips nothing.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchExcludes {
+    pub subfolders: String,
+    pub drive: String,
+    pub bookmarks: String,
+    pub everywhere: String,
+}
+
+impl Default for SearchExcludes {
+    fn default() -> Self {
+        Self {
+            subfolders: String::from("node_modules; .git"),
+            drive: String::from("node_modules; .git; .Trash*; Trash"),
+            bookmarks: String::from("node_modules; .git"),
+            everywhere: String::from("node_modules; .git; .Trash*; Trash"),
+        }
+    }
+}
+
+// A user-defined context menu command, e.g.
+//
+//   [[actions]]
+//   name = "Optimize PNG"
+//   command = "optipng %f"
+//   mime_types = ["image/png"]
+//
+// Placeholders: %f file path (runs once per selected item), %F all selected
+// paths, %n file name, %d current directory, %% a literal percent sign.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomAction {
+    pub name: String,
+    pub command: String,
+    // Patterns such as "image/png" or "image/*"; empty applies to everything.
+    // Folders have the type "inode/directory".
+    pub mime_types: Vec<String>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        crate::user_dirs::config_dir().map(|d| d.join("config.toml"))
+    }
+
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                tracing::warn!("Invalid config file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        let result = toml::to_string_pretty(self)
+            .map_err(std::io::Error::other)
+            .and_then(|contents| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(&path, contents)
+            });
+        if let Err(e) = result {
+            tracing::error!("Failed to save config file {}: {}", path.display(), e);
+        }
+    }
+}
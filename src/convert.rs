@@ -0,0 +1,132 @@
+// Batch conversion between image formats, with optional downscaling. The
+// converted copies go next to the originals, which are never touched.
+use crate::jobs::{JobHandle, ProgressUnit};
+use crate::ops::plural;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    WebP,
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 3] = [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "PNG",
+            ImageFormat::Jpeg => "JPEG",
+            // The image crate only writes lossless WebP
+            ImageFormat::WebP => "WebP (lossless)",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::WebP => "webp",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ConvertOptions {
+    pub format: ImageFormat,
+    // 1–100, JPEG only
+    pub quality: u8,
+    // Shrink so neither side is longer than this, keeping the aspect ratio
+    pub max_side: Option<u32>,
+}
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| matches!(e.as_str(), "png" | "jpg" | "jpeg" | "webp"))
+}
+
+// Converts every source, carrying on past failures and listing them at the end
+pub fn convert_all(
+    sources: &[PathBuf],
+    options: ConvertOptions,
+    job: &JobHandle,
+) -> Result<(), String> {
+    job.set_unit(ProgressUnit::Items);
+    let total = sources.len() as u64;
+    let mut failed = Vec::new();
+    for (done, source) in sources.iter().enumerate() {
+        if job.is_cancelled() {
+            return Err(String::from("cancelled"));
+        }
+        job.set_progress(done as u64, Some(total));
+        job.set_message(source.display().to_string());
+        if let Err(e) = convert(source, options) {
+            failed.push(format!("{}: {}", source.display(), e));
+        }
+    }
+    job.set_progress(total, Some(total));
+
+    if failed.is_empty() {
+        job.set_message(format!("Converted {}", plural(sources.len(), "image")));
+        return Ok(());
+    }
+    job.set_message("");
+    let more = match failed.len() {
+        n if n > 3 => format!(" and {} more", n - 3),
+        _ => String::new(),
+    };
+    Err(format!(
+        "{} couldn't be converted: {}{}",
+        plural(failed.len(), "image"),
+        failed[..failed.len().min(3)].join("; "),
+        more
+    ))
+}
+
+fn convert(source: &Path, options: ConvertOptions) -> Result<PathBuf, String> {
+    let mut image = image::open(source).map_err(|e| e.to_string())?;
+    if let Some(max) = options.max_side {
+        if image.width() > max || image.height() > max {
+            image = image.resize(max, max, FilterType::Lanczos3);
+        }
+    }
+
+    let dir = source.parent().unwrap_or(Path::new("."));
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let name = format!("{}.{}", stem, options.format.extension());
+    let destination = dir.join(crate::unique_file_name(dir, &name));
+
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&destination)
+        .map_err(|e| e.to_string())?;
+    let result = encode(&image, options, BufWriter::new(file));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&destination);
+        return Err(e.to_string());
+    }
+    Ok(destination)
+}
+
+fn encode(image: &DynamicImage, options: ConvertOptions, mut out: impl Write) -> io::Result<()> {
+    let result = match options.format {
+        // JPEG has no alpha channel
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut out, options.quality)),
+        ImageFormat::Png => image.write_with_encoder(PngEncoder::new(&mut out)),
+        ImageFormat::WebP => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut out)),
+    };
+    result.map_err(io::Error::other)?;
+    out.flush()
+}
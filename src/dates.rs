@@ -0,0 +1,97 @@
+// Dates as the file list shows them, "Today at 14:05" or "03/02/2024 at
+// 18:00". Formatted the first time a row needs one and kept by the minute,
+// since a folder's dates repeat and rows are redrawn every frame. Everything
+// is dropped when the day, the time zone or the setting changes, as "Today"
+// would be wrong from then on.
+use chrono::{DateTime, Local, NaiveDate};
+use eframe::egui;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Scrolling through a huge folder shouldn't grow this without end
+const MAX_CACHED: usize = 10_000;
+
+// What the labels depend on besides the time itself
+#[derive(Clone, Copy, PartialEq)]
+struct Basis {
+    today: NaiveDate,
+    // Seconds east of UTC, which changes with daylight saving
+    offset: i32,
+    relative: bool,
+}
+
+impl Basis {
+    fn now(relative: bool) -> Self {
+        let now = Local::now();
+        Self {
+            today: now.date_naive(),
+            offset: now.offset().local_minus_utc(),
+            relative,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DateLabels {
+    basis: Option<Basis>,
+    // By minutes since the epoch
+    labels: HashMap<i64, String>,
+}
+
+impl DateLabels {
+    // Once a frame, before any labels are asked for. Wakes the UI at
+    // midnight so "Today" moves on even when nothing else happens.
+    pub fn refresh(&mut self, ctx: &egui::Context, relative: bool) {
+        let basis = Basis::now(relative);
+        if self.basis != Some(basis) {
+            self.labels.clear();
+            self.basis = Some(basis);
+        }
+        if relative {
+            ctx.request_repaint_after(until_midnight());
+        }
+    }
+
+    pub fn label(&mut self, time: SystemTime) -> &str {
+        let relative = self.basis.is_none_or(|b| b.relative);
+        if self.labels.len() >= MAX_CACHED {
+            self.labels.clear();
+        }
+        self.labels
+            .entry(minute(time))
+            .or_insert_with(|| format(time, relative))
+    }
+}
+
+fn minute(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => (since.as_secs() / 60) as i64,
+        Err(e) => -(e.duration().as_secs().div_ceil(60) as i64),
+    }
+}
+
+// "Today at 14:05", "Yesterday at 09:30" or "03/02/2024 at 18:00"
+pub fn format(time: SystemTime, relative: bool) -> String {
+    let datetime: DateTime<Local> = time.into();
+    let today = Local::now().date_naive();
+    if relative && datetime.date_naive() == today {
+        format!("Today at {}", datetime.format("%H:%M"))
+    } else if relative && Some(datetime.date_naive()) == today.pred_opt() {
+        format!("Yesterday at {}", datetime.format("%H:%M"))
+    } else {
+        datetime.format("%d/%m/%Y at %H:%M").to_string()
+    }
+}
+
+fn until_midnight() -> Duration {
+    let now = Local::now();
+    let midnight = now
+        .date_naive()
+        .succ_opt()
+        .and_then(|tomorrow| tomorrow.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest());
+    midnight
+        .and_then(|midnight| (midnight - now).to_std().ok())
+        .unwrap_or(Duration::from_secs(60))
+        + Duration::from_secs(1)
+}
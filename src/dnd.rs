@@ -0,0 +1,90 @@
+// Dragging files within the app: onto folders, breadcrumbs, bookmarks and
+// tabs to move or copy them there. Holding the drag over a folder for a
+// moment opens it, so a drop can go several levels deep in one gesture.
+use crate::ops::{self, TransferMode};
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Seconds dragged files hover over a folder before it opens
+const SPRING_DELAY: f64 = 1.0;
+
+// What's carried by a drag that started on the file list
+pub struct DraggedFiles(pub Vec<PathBuf>);
+
+pub fn start(ctx: &egui::Context, files: Vec<PathBuf>) {
+    egui::DragAndDrop::set_payload(ctx, DraggedFiles(files));
+}
+
+pub fn dragged(ctx: &egui::Context) -> Option<Arc<DraggedFiles>> {
+    egui::DragAndDrop::payload(ctx)
+}
+
+// Escape backs out of a drag without dropping anything
+pub fn cancel(ctx: &egui::Context) {
+    egui::DragAndDrop::clear_payload(ctx);
+}
+
+// Whether dragged files are over `response`
+pub fn held(response: &egui::Response) -> bool {
+    response.dnd_hover_payload::<DraggedFiles>().is_some()
+}
+
+// Outlines `response` while files are dragged over it, and returns them
+// once they're dropped there
+pub fn drop_target(ui: &egui::Ui, response: &egui::Response) -> Option<Vec<PathBuf>> {
+    response.dnd_hover_payload::<DraggedFiles>()?;
+    ui.painter()
+        .rect_stroke(response.rect, 2.0, ui.visuals().selection.stroke);
+    response
+        .dnd_release_payload::<DraggedFiles>()
+        .map(|files| files.0.clone())
+}
+
+// Like most file managers, drops move within a drive and copy across
+// drives; Ctrl copies and Shift moves regardless
+pub fn mode(modifiers: egui::Modifiers, sources: &[PathBuf], destination: &Path) -> TransferMode {
+    if modifiers.command {
+        TransferMode::Copy
+    } else if modifiers.shift
+        || sources
+            .iter()
+            .all(|source| ops::same_device(source, destination))
+    {
+        TransferMode::Move
+    } else {
+        TransferMode::Copy
+    }
+}
+
+// The folder dragged files are being held over, and since when
+#[derive(Default)]
+pub struct Spring {
+    folder: Option<(PathBuf, f64, u64)>,
+}
+
+impl Spring {
+    // Called every frame dragged files are over `folder`; true once they've
+    // been there long enough to open it
+    pub fn due(&mut self, ctx: &egui::Context, folder: &Path) -> bool {
+        let (now, frame) = (ctx.input(|i| i.time), ctx.frame_nr());
+        match &mut self.folder {
+            // Still over it since the last frame
+            Some((held, since, seen)) if held == folder && *seen + 1 >= frame => {
+                *seen = frame;
+                if now - *since >= SPRING_DELAY {
+                    self.folder = None;
+                    return true;
+                }
+                ctx.request_repaint_after(std::time::Duration::from_secs_f64(
+                    SPRING_DELAY - (now - *since),
+                ));
+            }
+            _ => {
+                self.folder = Some((folder.to_path_buf(), now, frame));
+                ctx.request_repaint_after(std::time::Duration::from_secs_f64(SPRING_DELAY));
+            }
+        }
+        false
+    }
+}
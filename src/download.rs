@@ -0,0 +1,199 @@
+use crate::jobs::JobHandle;
+use reqwest::header::{
+    HeaderMap, CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use reqwest::StatusCode;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+// The file name a URL saves as: its last path segment, without the query
+pub fn file_name_from_url(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or("");
+    let path = path.split_once("://").map(|(_, rest)| rest).unwrap_or(path);
+    path.split_once('/')
+        .map(|(_, path)| path)
+        .and_then(|path| path.rsplit('/').next())
+        .map(crate::uri::percent_decode)
+        .filter(|name| !name.is_empty() && name != "." && name != "..")
+        .map(|name| name.replace(['/', '\\'], "_"))
+        .unwrap_or_else(|| String::from("download"))
+}
+
+// Data goes to "<name>.part" first, with the server's ETag or Last-Modified
+// in "<name>.part.validator". If a previous attempt left both behind, only
+// the missing bytes are requested, with If-Range so a file that changed on
+// the server since is downloaded again from the start.
+pub fn download(url: &str, destination: &Path, job: &JobHandle) -> Result<PathBuf, String> {
+    let part = with_suffix(destination, ".part");
+    let validator_file = with_suffix(&part, ".validator");
+    let existing = fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+    let validator = fs::read_to_string(&validator_file)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| existing > 0 && !v.is_empty());
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(None)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client.get(url);
+    if let Some(validator) = &validator {
+        request = request
+            .header(RANGE, format!("bytes={}-", existing))
+            .header(IF_RANGE, validator);
+    }
+    let mut response = request.send().map_err(|e| e.to_string())?;
+
+    let status = response.status();
+    if status == StatusCode::RANGE_NOT_SATISFIABLE && validator.is_some() {
+        // Unchanged, and the partial file already holds everything
+        return finish(&part, &validator_file, destination);
+    }
+    if !status.is_success() {
+        return Err(format!("server replied {}", status));
+    }
+    // A 200 means the file changed or the server can't resume, so the data
+    // starts over
+    let resumed = status == StatusCode::PARTIAL_CONTENT;
+    if resumed {
+        let start = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(range_start);
+        if start != Some(existing) {
+            let _ = fs::remove_file(&part);
+            let _ = fs::remove_file(&validator_file);
+            return Err(String::from(
+                "the server resumed from the wrong place, try again",
+            ));
+        }
+    } else {
+        match validator_of(response.headers()) {
+            Some(validator) => fs::write(&validator_file, validator),
+            None => fs::remove_file(&validator_file).or_else(|e| match e.kind() {
+                io::ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            }),
+        }
+        .map_err(|e| e.to_string())?;
+    }
+
+    let length = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    // Content-Range: bytes 100-999/1000 carries the full size when resuming
+    let total = if resumed {
+        response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|v| v.parse::<u64>().ok())
+            .or(length.map(|l| l + existing))
+    } else {
+        length
+    };
+
+    let mut file = if resumed {
+        job.set_message("Resuming");
+        fs::OpenOptions::new().append(true).open(&part)
+    } else {
+        fs::File::create(&part)
+    }
+    .map_err(|e| e.to_string())?;
+
+    let mut done = if resumed { existing } else { 0 };
+    job.set_progress(done, total);
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        if job.is_cancelled() {
+            // Keep the .part file so the download can be resumed
+            return Err(String::from("cancelled"));
+        }
+        let read = match response.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.to_string()),
+        };
+        file.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
+        done += read as u64;
+        job.set_progress(done, total);
+    }
+    file.flush().map_err(|e| e.to_string())?;
+    drop(file);
+
+    if total.is_some_and(|total| done < total) {
+        return Err(String::from(
+            "connection closed before the download finished",
+        ));
+    }
+    finish(&part, &validator_file, destination)
+}
+
+fn finish(part: &Path, validator_file: &Path, destination: &Path) -> Result<PathBuf, String> {
+    fs::rename(part, destination).map_err(|e| e.to_string())?;
+    let _ = fs::remove_file(validator_file);
+    Ok(destination.to_path_buf())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// What If-Range can check the partial data against. Weak ETags aren't
+// allowed there, so Last-Modified is used instead.
+fn validator_of(headers: &HeaderMap) -> Option<String> {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    header(ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+        .map(str::to_string)
+}
+
+// "bytes 100-999/1000" → 100
+fn range_start(content_range: &str) -> Option<u64> {
+    let range = content_range.trim().strip_prefix("bytes ")?;
+    range.split_once('-')?.0.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn picks_a_strong_validator() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_static("Tue, 01 Oct 2024 10:00:00 GMT"),
+        );
+        assert_eq!(
+            validator_of(&headers).as_deref(),
+            Some("Tue, 01 Oct 2024 10:00:00 GMT")
+        );
+        headers.insert(ETAG, HeaderValue::from_static("W/\"abc\""));
+        assert_eq!(
+            validator_of(&headers).as_deref(),
+            Some("Tue, 01 Oct 2024 10:00:00 GMT")
+        );
+        headers.insert(ETAG, HeaderValue::from_static("\"abc\""));
+        assert_eq!(validator_of(&headers).as_deref(), Some("\"abc\""));
+        assert_eq!(validator_of(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn reads_where_a_range_starts() {
+        assert_eq!(range_start("bytes 100-999/1000"), Some(100));
+        assert_eq!(range_start("bytes 0-9/*"), Some(0));
+        assert_eq!(range_start("bytes */1000"), None);
+        assert_eq!(range_start("items 1-2/3"), None);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::repaint::Repaint;
+use crate::udisks;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+#[derive(Clone, PartialEq)]
+pub struct Mount {
+    pub device: String,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    pub removable: bool,
+}
+
+impl Mount {
+    pub fn label(&self) -> String {
+        self.mount_point
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.mount_point.display().to_string())
+    }
+
+    pub fn description(&self) -> String {
+        let mut parts = vec![self.mount_point.display().to_string()];
+        if !self.device.is_empty() {
+            parts.push(self.device.clone());
+        }
+        if !self.fs_type.is_empty() {
+            parts.push(self.fs_type.clone());
+        }
+        parts.join(" · ")
+    }
+}
+
+// /proc/mounts escapes spaces and a few other characters as octal (\040)
+#[cfg(target_os = "linux")]
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() {
+            if let Ok(value) = u8::from_str_radix(&field[i + 1..i + 4], 8) {
+                out.push(value);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Whether the block device behind /dev/sdb1 (etc.) reports itself removable
+#[cfg(target_os = "linux")]
+fn is_removable_device(device: &str) -> bool {
+    let Some(name) = device.strip_prefix("/dev/") else {
+        return false;
+    };
+    // Partitions live below their disk in sysfs: /sys/class/block/sdb1/..
+    let class = std::path::Path::new("/sys/class/block").join(name);
+    let disk = std::fs::canonicalize(&class).ok().and_then(|p| {
+        let partition = p.join("partition").exists();
+        if partition {
+            p.parent().map(|d| d.to_path_buf())
+        } else {
+            Some(p)
+        }
+    });
+    disk.and_then(|d| std::fs::read_to_string(d.join("removable")).ok())
+        .is_some_and(|v| v.trim() == "1")
+}
+
+#[cfg(target_os = "linux")]
+pub fn mounts() -> Vec<Mount> {
+    const VIRTUAL: &[&str] = &[
+        "proc",
+        "sysfs",
+        "devtmpfs",
+        "devpts",
+        "tmpfs",
+        "cgroup",
+        "cgroup2",
+        "securityfs",
+        "pstore",
+        "debugfs",
+        "tracefs",
+        "configfs",
+        "fusectl",
+        "mqueue",
+        "hugetlbfs",
+        "bpf",
+        "autofs",
+        "binfmt_misc",
+        "efivarfs",
+        "overlay",
+        "squashfs",
+        "nsfs",
+        "ramfs",
+        "rpc_pipefs",
+        "fuse.portal",
+        "fuse.gvfsd-fuse",
+    ];
+    let Ok(contents) = std::fs::read_to_string("/proc/mounts") else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = unescape_mount_field(fields.next()?);
+            let mount_point = PathBuf::from(unescape_mount_field(fields.next()?));
+            let fs_type = fields.next()?.to_string();
+            if VIRTUAL.contains(&fs_type.as_str()) || mount_point.starts_with("/snap") {
+                return None;
+            }
+            let user_mount =
+                mount_point.starts_with("/media") || mount_point.starts_with("/run/media");
+            Some(Mount {
+                removable: user_mount || is_removable_device(&device),
+                device,
+                mount_point,
+                fs_type,
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+pub fn mounts() -> Vec<Mount> {
+    // Every volume except the boot disk shows up in /Volumes; the boot disk is
+    // a symlink to /
+    let Ok(read) = std::fs::read_dir("/Volumes") else {
+        return Vec::new();
+    };
+    read.flatten()
+        .map(|entry| {
+            let mount_point = entry.path();
+            let is_boot = std::fs::read_link(&mount_point).is_ok();
+            Mount {
+                device: String::new(),
+                mount_point: if is_boot {
+                    PathBuf::from("/")
+                } else {
+                    mount_point
+                },
+                fs_type: String::new(),
+                removable: !is_boot,
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+pub fn mounts() -> Vec<Mount> {
+    let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| String::from("C:"));
+    (b'A'..=b'Z')
+        .map(|letter| format!("{}:\\", letter as char))
+        .filter(|root| std::path::Path::new(root).exists())
+        .map(|root| Mount {
+            device: root.trim_end_matches('\\').to_string(),
+            removable: !root.starts_with(&system_drive),
+            mount_point: PathBuf::from(root),
+            fs_type: String::new(),
+        })
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn mounts() -> Vec<Mount> {
+    Vec::new()
+}
+
+// The type of the filesystem `path` is on, e.g. "ext4" or "exfat", virtual
+// ones included
+#[cfg(target_os = "linux")]
+pub fn fs_type(path: &Path) -> Option<String> {
+    let path = std::fs::canonicalize(path).ok()?;
+    let contents = std::fs::read_to_string("/proc/mounts").ok()?;
+    // Later mounts over the same point hide earlier ones
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            fields.next()?;
+            let mount_point = PathBuf::from(unescape_mount_field(fields.next()?));
+            Some((mount_point, fields.next()?.to_string()))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+        .map(|(_, fs_type)| fs_type)
+}
+
+#[cfg(target_os = "macos")]
+pub fn fs_type(path: &Path) -> Option<String> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+// "NTFS", "FAT32" and so on, lower-cased to match the other systems
+#[cfg(windows)]
+pub fn fs_type(path: &Path) -> Option<String> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetVolumePathNameW(file: *const u16, volume: *mut u16, length: u32) -> i32;
+        fn GetVolumeInformationW(
+            root: *const u16,
+            name: *mut u16,
+            name_size: u32,
+            serial: *mut u32,
+            max_component_length: *mut u32,
+            flags: *mut u32,
+            fs_name: *mut u16,
+            fs_name_size: u32,
+        ) -> i32;
+    }
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut root = [0u16; 261];
+    if unsafe { GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root.len() as u32) } == 0 {
+        return None;
+    }
+    let mut name = [0u16; 32];
+    let null = std::ptr::null_mut;
+    if unsafe {
+        GetVolumeInformationW(
+            root.as_ptr(),
+            null(),
+            0,
+            null(),
+            null(),
+            null(),
+            name.as_mut_ptr(),
+            name.len() as u32,
+        )
+    } == 0
+    {
+        return None;
+    }
+    let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+    Some(String::from_utf16_lossy(&name[..len]).to_lowercase())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn fs_type(_path: &Path) -> Option<String> {
+    None
+}
+
+pub fn removable() -> Vec<Mount> {
+    mounts().into_iter().filter(|m| m.removable).collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Space {
+    pub total: u64,
+    // What the current user can still write; root may have a reserve on top
+    pub available: u64,
+}
+
+impl Space {
+    pub fn used_fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        1.0 - self.available as f32 / self.total as f32
+    }
+}
+
+#[cfg(unix)]
+pub fn space(path: &Path) -> io::Result<Space> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let block = stat.f_frsize as u64;
+    Ok(Space {
+        total: stat.f_blocks as u64 * block,
+        available: stat.f_bavail as u64 * block,
+    })
+}
+
+#[cfg(windows)]
+pub fn space(path: &Path) -> io::Result<Space> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            free_to_caller: *mut u64,
+            total: *mut u64,
+            total_free: *mut u64,
+        ) -> i32;
+    }
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let (mut available, mut total, mut free) = (0, 0, 0);
+    if unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, &mut total, &mut free) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Space { total, available })
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn space(_path: &Path) -> io::Result<Space> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+// What the sidebar lists. Without udisks (other systems, or Linux without
+// udev) it falls back to what's mounted.
+#[derive(Clone, Default, PartialEq)]
+pub struct Devices {
+    pub block: Vec<udisks::BlockDevice>,
+    pub mounts: Vec<Mount>,
+    // By mount point
+    pub space: BTreeMap<PathBuf, Space>,
+}
+
+pub fn devices() -> Devices {
+    let block = if cfg!(target_os = "linux") {
+        udisks::block_devices()
+    } else {
+        Vec::new()
+    };
+    let mounts = if block.is_empty() {
+        mounts()
+    } else {
+        Vec::new()
+    };
+    let mut devices = Devices {
+        block,
+        mounts,
+        space: BTreeMap::new(),
+    };
+    devices.space = devices
+        .mount_points()
+        .filter_map(|dir| Some((dir.to_path_buf(), space(dir).ok()?)))
+        .collect();
+    devices
+}
+
+impl Devices {
+    // Where filesystems are mounted, whether found through udisks or not
+    pub fn mount_points(&self) -> impl Iterator<Item = &Path> {
+        self.block
+            .iter()
+            .filter_map(|device| device.mount_point.as_deref())
+            .chain(self.mounts.iter().map(|mount| mount.mount_point.as_path()))
+    }
+}
+
+// Re-reads the devices every few seconds on a background thread and only
+// sends (and wakes the UI) when something was plugged in, removed, mounted
+// or unmounted, or free space changed. Stops once the receiver is dropped.
+pub fn watch(repaint: Repaint) -> Receiver<Devices> {
+    const INTERVAL: Duration = Duration::from_secs(3);
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut last = None;
+        loop {
+            let current = devices();
+            if last.as_ref() != Some(&current) {
+                if sender.send(current.clone()).is_err() {
+                    return;
+                }
+                repaint.request();
+                last = Some(current);
+            }
+            std::thread::sleep(INTERVAL);
+        }
+    });
+    receiver
+}
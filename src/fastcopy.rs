@@ -0,0 +1,83 @@
+// Filesystem shortcuts for copying: reflinks share the source's blocks
+// until either copy changes (Btrfs, XFS, APFS), and sparse files keep their
+// holes instead of filling them with zeros
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Creates `destination` as a clone of `source`. Fails when the filesystem
+// can't do it or the two are on different filesystems.
+#[cfg(target_os = "linux")]
+pub fn reflink(source: &Path, destination: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let source = fs::File::open(source)?;
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(destination)?;
+    if unsafe { libc::ioctl(file.as_raw_fd(), libc::FICLONE as _, source.as_raw_fd()) } == 0 {
+        return Ok(());
+    }
+    let error = io::Error::last_os_error();
+    drop(file);
+    let _ = fs::remove_file(destination);
+    Err(error)
+}
+
+#[cfg(target_os = "macos")]
+pub fn reflink(source: &Path, destination: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let source = CString::new(source.as_os_str().as_bytes())?;
+    let destination = CString::new(destination.as_os_str().as_bytes())?;
+    if unsafe { libc::clonefile(source.as_ptr(), destination.as_ptr(), 0) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn reflink(_source: &Path, _destination: &Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+// The (start, end) byte ranges of a sparse file that hold data. None for
+// files without holes or when the filesystem can't tell.
+#[cfg(target_os = "linux")]
+pub fn data_ranges(file: &fs::File, len: u64) -> Option<Vec<(u64, u64)>> {
+    use std::os::unix::fs::MetadataExt;
+    use std::os::unix::io::AsRawFd;
+
+    // Every byte has a block behind it, so there are no holes to keep
+    if file.metadata().ok()?.blocks() * 512 >= len {
+        return None;
+    }
+    let fd = file.as_raw_fd();
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    while (offset as u64) < len {
+        let data = unsafe { libc::lseek(fd, offset, libc::SEEK_DATA) };
+        if data < 0 {
+            // ENXIO: only a hole is left until the end of the file
+            return match io::Error::last_os_error().raw_os_error() {
+                Some(libc::ENXIO) => Some(ranges),
+                _ => None,
+            };
+        }
+        let hole = unsafe { libc::lseek(fd, data, libc::SEEK_HOLE) };
+        if hole < 0 {
+            return None;
+        }
+        ranges.push((data as u64, (hole as u64).min(len)));
+        offset = hole;
+    }
+    Some(ranges)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn data_ranges(_file: &fs::File, _len: u64) -> Option<Vec<(u64, u64)>> {
+    None
+}
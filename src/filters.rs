@@ -0,0 +1,182 @@
+// Narrows the file list down without touching the folder: only the entries
+// modified within the chosen period, or files past a size, stay visible
+use chrono::{Datelike, Days, Local, NaiveDate, TimeZone};
+use eframe::egui;
+use std::time::SystemTime;
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Period {
+    #[default]
+    AnyTime,
+    Today,
+    ThisWeek,
+    ThisMonth,
+    ThisYear,
+    Custom,
+}
+
+impl Period {
+    const ALL: [Period; 6] = [
+        Period::AnyTime,
+        Period::Today,
+        Period::ThisWeek,
+        Period::ThisMonth,
+        Period::ThisYear,
+        Period::Custom,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Period::AnyTime => "Any time",
+            Period::Today => "Today",
+            Period::ThisWeek => "This week",
+            Period::ThisMonth => "This month",
+            Period::ThisYear => "This year",
+            Period::Custom => "Between…",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum MinSize {
+    #[default]
+    Any,
+    Over10Mb,
+    Over100Mb,
+    Over1Gb,
+}
+
+impl MinSize {
+    const ALL: [MinSize; 4] = [
+        MinSize::Any,
+        MinSize::Over10Mb,
+        MinSize::Over100Mb,
+        MinSize::Over1Gb,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            MinSize::Any => "Any size",
+            MinSize::Over10Mb => "Larger than 10 MB",
+            MinSize::Over100Mb => "Larger than 100 MB",
+            MinSize::Over1Gb => "Larger than 1 GB",
+        }
+    }
+
+    fn bytes(self) -> Option<u64> {
+        const MB: u64 = 1024 * 1024;
+        match self {
+            MinSize::Any => None,
+            MinSize::Over10Mb => Some(10 * MB),
+            MinSize::Over100Mb => Some(100 * MB),
+            MinSize::Over1Gb => Some(1024 * MB),
+        }
+    }
+}
+
+pub struct Filters {
+    pub modified: Period,
+    pub size: MinSize,
+    // Both days included; only used for `Period::Custom`
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        let today = Local::now().date_naive();
+        Self {
+            modified: Period::AnyTime,
+            size: MinSize::Any,
+            from: today - Days::new(7),
+            to: today,
+        }
+    }
+}
+
+// Start of a day in local time
+fn midnight(date: NaiveDate) -> Option<SystemTime> {
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+// What the filters let through, worked out once per frame
+pub struct Matcher {
+    modified: Option<(SystemTime, SystemTime)>,
+    min_size: Option<u64>,
+}
+
+impl Matcher {
+    // `file_size` is None for folders, which a size filter hides
+    pub fn matches(&self, modified: Option<SystemTime>, file_size: Option<u64>) -> bool {
+        let modified = match (self.modified, modified) {
+            (None, _) => true,
+            (Some((start, end)), Some(time)) => start <= time && time < end,
+            // Still loading, or unknown
+            (Some(_), None) => false,
+        };
+        let size = match (self.min_size, file_size) {
+            (None, _) => true,
+            (Some(min), Some(size)) => size > min,
+            (Some(_), None) => false,
+        };
+        modified && size
+    }
+}
+
+impl Filters {
+    pub fn is_active(&self) -> bool {
+        self.modified != Period::AnyTime || self.size != MinSize::Any
+    }
+
+    pub fn matcher(&self) -> Matcher {
+        let today = Local::now().date_naive();
+        let first_day = match self.modified {
+            Period::AnyTime => None,
+            Period::Today => Some(today),
+            Period::ThisWeek => Some(today.week(chrono::Weekday::Mon).first_day()),
+            Period::ThisMonth => today.with_day(1),
+            Period::ThisYear => today.with_ordinal(1),
+            Period::Custom => Some(self.from.min(self.to)),
+        };
+        let last_day = match self.modified {
+            Period::Custom => self.from.max(self.to),
+            _ => today,
+        };
+        let modified = first_day.and_then(|first| {
+            Some((
+                midnight(first)?,
+                midnight(last_day.checked_add_days(Days::new(1))?)?,
+            ))
+        });
+        Matcher {
+            modified,
+            min_size: self.size.bytes(),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.label("Modified:");
+        egui::ComboBox::from_id_source("modified_filter")
+            .selected_text(self.modified.label())
+            .show_ui(ui, |ui| {
+                for period in Period::ALL {
+                    ui.selectable_value(&mut self.modified, period, period.label());
+                }
+            });
+        if self.modified == Period::Custom {
+            ui.add(egui_extras::DatePickerButton::new(&mut self.from).id_source("modified_from"));
+            ui.label("–");
+            ui.add(egui_extras::DatePickerButton::new(&mut self.to).id_source("modified_to"));
+        }
+        egui::ComboBox::from_id_source("size_filter")
+            .selected_text(self.size.label())
+            .show_ui(ui, |ui| {
+                for size in MinSize::ALL {
+                    ui.selectable_value(&mut self.size, size, size.label());
+                }
+            });
+    }
+}
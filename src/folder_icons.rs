@@ -0,0 +1,217 @@
+// Custom folder icons, kept where the desktop itself looks for them: the Icon
+// key of a `.directory` file (KDE Dolphin, also used on other Unix systems)
+// or IconResource in a Windows desktop.ini. Emblems, an emoji shown before
+// the name, are this app's own and go in the same file under their own key.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Default, PartialEq)]
+pub struct FolderIcon {
+    // Icon theme name or image path
+    pub icon: Option<String>,
+    pub emblem: Option<String>,
+}
+
+impl FolderIcon {
+    pub fn is_empty(&self) -> bool {
+        self.icon.is_none() && self.emblem.is_none()
+    }
+}
+
+#[cfg(not(windows))]
+pub const FILE_NAME: &str = ".directory";
+#[cfg(not(windows))]
+const ICON: (&str, &str) = ("Desktop Entry", "Icon");
+#[cfg(not(windows))]
+const EMBLEM: (&str, &str) = ("Desktop Entry", "X-FileManager-Emblem");
+
+#[cfg(windows)]
+pub const FILE_NAME: &str = "desktop.ini";
+#[cfg(windows)]
+const ICON: (&str, &str) = (".ShellClassInfo", "IconResource");
+#[cfg(windows)]
+const EMBLEM: (&str, &str) = ("FileManager", "Emblem");
+
+pub fn read(dir: &Path) -> Option<FolderIcon> {
+    let contents = read_text(&dir.join(FILE_NAME)).ok()?;
+    let icon = value(&contents, ICON).map(|icon| resolve(dir, icon));
+    let emblem = value(&contents, EMBLEM).map(str::to_string);
+    let folder = FolderIcon { icon, emblem };
+    (!folder.is_empty()).then_some(folder)
+}
+
+// Other keys in the file are kept; the file goes once nothing is left in it
+pub fn write(dir: &Path, folder: &FolderIcon) -> io::Result<()> {
+    let path = dir.join(FILE_NAME);
+    let contents = match read_text(&path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        contents => contents?,
+    };
+    let contents = set_value(
+        &contents,
+        ICON,
+        icon_value(folder.icon.as_deref()).as_deref(),
+    );
+    let contents = set_value(&contents, EMBLEM, folder.emblem.as_deref());
+    let empty = contents
+        .lines()
+        .all(|line| line.trim().is_empty() || line.trim().starts_with('['));
+    if empty {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        };
+    }
+    write_text(dir, &path, &contents)
+}
+
+fn value<'a>(contents: &'a str, (section, key): (&str, &str)) -> Option<&'a str> {
+    let mut in_section = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.eq_ignore_ascii_case(section);
+        } else if let (true, Some((k, v))) = (in_section, line.split_once('=')) {
+            if k.trim().eq_ignore_ascii_case(key) && !v.trim().is_empty() {
+                return Some(v.trim());
+            }
+        }
+    }
+    None
+}
+
+// Replaces the key's line, adds it to its section, or removes it for None
+fn set_value(contents: &str, (section, key): (&str, &str), value: Option<&str>) -> String {
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let mut in_section = false;
+    let mut section_end = None;
+    let mut found = None;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.eq_ignore_ascii_case(section);
+            if in_section {
+                section_end = Some(i + 1);
+            }
+        } else if in_section {
+            if trimmed
+                .split_once('=')
+                .is_some_and(|(k, _)| k.trim().eq_ignore_ascii_case(key))
+            {
+                found = Some(i);
+            }
+            if !trimmed.is_empty() {
+                section_end = Some(i + 1);
+            }
+        }
+    }
+    let line = value.map(|value| format!("{}={}", key, value));
+    match (found, line, section_end) {
+        (Some(i), Some(line), _) => lines[i] = line,
+        (Some(i), None, _) => {
+            lines.remove(i);
+        }
+        (None, Some(line), Some(end)) => lines.insert(end, line),
+        (None, Some(line), None) => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", section));
+            lines.push(line);
+        }
+        (None, None, _) => {}
+    }
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    contents
+}
+
+// Relative image paths are relative to the folder
+fn resolve(dir: &Path, icon: &str) -> String {
+    // desktop.ini adds the icon's index within the file
+    let icon = if cfg!(windows) {
+        icon.rsplit_once(',')
+            .filter(|(_, index)| index.trim().parse::<i32>().is_ok())
+            .map_or(icon, |(file, _)| file)
+    } else {
+        icon
+    };
+    let path = Path::new(icon);
+    if path.is_relative()
+        && (icon.contains('/') || icon.contains('\\') || path.extension().is_some())
+    {
+        return dir.join(path).to_string_lossy().into_owned();
+    }
+    icon.to_string()
+}
+
+fn icon_value(icon: Option<&str>) -> Option<String> {
+    if cfg!(windows) {
+        icon.map(|icon| format!("{},0", icon))
+    } else {
+        icon.map(str::to_string)
+    }
+}
+
+// desktop.ini is often UTF-16 with a byte order mark
+fn read_text(path: &Path) -> io::Result<String> {
+    let bytes = fs::read(path)?;
+    Ok(match bytes.strip_prefix(&[0xFF, 0xFE]) {
+        Some(wide) => {
+            let units: Vec<u16> = wide
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => String::from_utf8_lossy(&bytes).into_owned(),
+    })
+}
+
+#[cfg(not(windows))]
+fn write_text(_dir: &Path, path: &Path, contents: &str) -> io::Result<()> {
+    fs::write(path, contents)
+}
+
+// Explorer only reads desktop.ini when it is hidden and a system file, and
+// the folder is marked read-only. Written as UTF-16 so emblems survive.
+#[cfg(windows)]
+fn write_text(dir: &Path, path: &Path, contents: &str) -> io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetFileAttributesW(file: *const u16) -> u32;
+        fn SetFileAttributesW(file: *const u16, attributes: u32) -> i32;
+    }
+    const FILE_ATTRIBUTE_READONLY: u32 = 0x1;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+    const FILE_ATTRIBUTE_NORMAL: u32 = 0x80;
+    const INVALID_FILE_ATTRIBUTES: u32 = u32::MAX;
+
+    let wide =
+        |path: &Path| -> Vec<u16> { path.as_os_str().encode_wide().chain(Some(0)).collect() };
+    let file = wide(path);
+    // Hidden files can't be overwritten without first unhiding them
+    if path.exists() {
+        unsafe { SetFileAttributesW(file.as_ptr(), FILE_ATTRIBUTE_NORMAL) };
+    }
+    let mut bytes = vec![0xFF, 0xFE];
+    for unit in contents.replace('\n', "\r\n").encode_utf16() {
+        bytes.extend(unit.to_le_bytes());
+    }
+    fs::write(path, bytes)?;
+    let folder = wide(dir);
+    unsafe {
+        SetFileAttributesW(file.as_ptr(), FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM);
+        let attributes = GetFileAttributesW(folder.as_ptr());
+        if attributes != INVALID_FILE_ATTRIBUTES
+            && SetFileAttributesW(folder.as_ptr(), attributes | FILE_ATTRIBUTE_READONLY) == 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
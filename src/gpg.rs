@@ -0,0 +1,310 @@
+// OpenPGP through the gpg command line tool. Passphrases are never seen
+// here: gpg-agent asks for them through pinentry, which can also offer to
+// remember them in the desktop keyring. Results are read from gpg's status
+// lines rather than its messages, which are translated.
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+#[derive(Clone)]
+pub struct Key {
+    pub fingerprint: String,
+    pub user_id: String,
+    // Certified by the user or someone they trust. gpg refuses to encrypt to
+    // other keys unless told to trust them anyway.
+    pub trusted: bool,
+}
+
+#[derive(Clone)]
+pub enum Operation {
+    // Encrypt to the key with this fingerprint, and whether to do so even if
+    // it isn't trusted
+    Encrypt(String, bool),
+    Decrypt,
+    Sign,
+    Verify,
+}
+
+pub fn available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        Command::new("gpg")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    })
+}
+
+// Files that look like OpenPGP data rather than something to encrypt
+pub fn is_encrypted(path: &Path) -> bool {
+    has_extension(path, &["gpg", "pgp", "asc"])
+}
+
+pub fn is_signature(path: &Path) -> bool {
+    has_extension(path, &["sig", "asc"])
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| extensions.contains(&e.as_str()))
+}
+
+// Public keys that can currently encrypt, from the colon-separated listing
+pub fn encryption_keys() -> Result<Vec<Key>, String> {
+    let output = Command::new("gpg")
+        .args(["--list-keys", "--with-colons"])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(last_line(&output.stderr));
+    }
+
+    let mut keys = Vec::new();
+    // (usable, trusted, fingerprint, user id)
+    type Partial = (bool, bool, Option<String>, Option<String>);
+    let mut current: Option<Partial> = None;
+    let mut push = |current: Option<Partial>| {
+        if let Some((true, trusted, Some(fingerprint), Some(user_id))) = current {
+            keys.push(Key {
+                fingerprint,
+                user_id,
+                trusted,
+            });
+        }
+    };
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields.first() {
+            Some(&"pub") => {
+                push(current.take());
+                // Field 2 is the validity (r = revoked, e = expired, f and u
+                // fully and ultimately trusted) and field 12 the key's
+                // capabilities, upper case for the whole key
+                let validity = fields.get(1).copied().unwrap_or_default();
+                let valid = !matches!(validity, "r" | "e" | "d");
+                let trusted = matches!(validity, "f" | "u");
+                let encrypts = fields.get(11).is_some_and(|c| c.contains('E'));
+                current = Some((valid && encrypts, trusted, None, None));
+            }
+            Some(&"fpr") => {
+                if let Some((_, _, fingerprint @ None, _)) = &mut current {
+                    *fingerprint = fields.get(9).map(|f| f.to_string());
+                }
+            }
+            Some(&"uid") => {
+                if let Some((_, _, _, user_id @ None)) = &mut current {
+                    *user_id = fields.get(9).map(|u| unescape(u));
+                }
+            }
+            _ => {}
+        }
+    }
+    push(current);
+    Ok(keys)
+}
+
+// User IDs escape colons and other special bytes as \xHH
+fn unescape(text: &str) -> String {
+    let mut out = Vec::new();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b'x') && i + 3 < bytes.len() {
+            if let Ok(value) = u8::from_str_radix(&text[i + 2..i + 4], 16) {
+                out.push(value);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Runs the operation on one file, returning a short description of what
+// happened. Output files are written next to the input.
+pub fn run(operation: &Operation, file: &Path) -> Result<String, String> {
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // --batch keeps gpg from asking anything on a terminal, which it would
+    // otherwise do for an untrusted recipient; pinentry still asks for
+    // passphrases
+    let mut command = Command::new("gpg");
+    command
+        .stdin(Stdio::null())
+        .args(["--batch", "--status-fd", "1"]);
+
+    let output_file = match operation {
+        Operation::Encrypt(fingerprint, trust_anyway) => {
+            let output = dir.join(crate::unique_file_name(dir, &format!("{}.gpg", name)));
+            if *trust_anyway {
+                command.args(["--trust-model", "always"]);
+            }
+            command
+                .args(["--encrypt", "--recipient", fingerprint, "--output"])
+                .arg(&output)
+                .arg(file);
+            Some(output)
+        }
+        Operation::Decrypt => {
+            let stem = file
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let output = dir.join(crate::unique_file_name(dir, &stem));
+            command
+                .arg("--output")
+                .arg(&output)
+                .arg("--decrypt")
+                .arg(file);
+            Some(output)
+        }
+        Operation::Sign => {
+            let output = dir.join(crate::unique_file_name(dir, &format!("{}.asc", name)));
+            command
+                .args(["--armor", "--detach-sign", "--output"])
+                .arg(&output)
+                .arg(file);
+            Some(output)
+        }
+        Operation::Verify => {
+            command.arg("--verify").arg(file);
+            // A detached signature "x.sig" belongs to "x" next to it
+            if let Some(data) = signed_file(file) {
+                command.arg(data);
+            }
+            None
+        }
+    };
+
+    let output = command.output().map_err(|e| e.to_string())?;
+    let status = String::from_utf8_lossy(&output.stdout);
+    if let Operation::Verify = operation {
+        return verdict(&status).unwrap_or_else(|| Err(last_line(&output.stderr)));
+    }
+    if !output.status.success() {
+        // Reason 10 is "key not trusted"
+        let untrusted = status_lines(&status)
+            .any(|(keyword, args)| keyword == "INV_RECP" && args.starts_with("10 "));
+        if untrusted {
+            return Err(String::from(
+                "The key isn't trusted. Certify it with gpg --edit-key, or choose to encrypt anyway.",
+            ));
+        }
+        return Err(last_line(&output.stderr));
+    }
+    Ok(match output_file {
+        Some(path) => format!("Wrote {}", file_name(&path)),
+        None => String::new(),
+    })
+}
+
+// "[GNUPG:] GOODSIG 1234ABCD Jane <jane@example.org>" → ("GOODSIG", "1234...")
+fn status_lines(status: &str) -> impl Iterator<Item = (&str, &str)> {
+    status.lines().filter_map(|line| {
+        let line = line.strip_prefix("[GNUPG:] ")?;
+        Some(line.split_once(' ').unwrap_or((line, "")))
+    })
+}
+
+// What a verification found, or None when gpg couldn't get as far as a
+// signature. Only a VALIDSIG line means the signature checked out.
+fn verdict(status: &str) -> Option<Result<String, String>> {
+    let mut signer = None;
+    let mut valid = false;
+    let mut problem = None;
+    for (keyword, args) in status_lines(status) {
+        // Key id, then the user id
+        let user_id = || {
+            args.split_once(' ')
+                .map_or(args, |(_, user_id)| user_id)
+                .to_string()
+        };
+        match keyword {
+            "GOODSIG" => signer = Some(user_id()),
+            "VALIDSIG" => valid = true,
+            "BADSIG" => problem = Some(format!("Bad signature from {}", user_id())),
+            "EXPKEYSIG" => problem = Some(format!("Signed by {} with an expired key", user_id())),
+            "REVKEYSIG" => problem = Some(format!("Signed by {} with a revoked key", user_id())),
+            "EXPSIG" => problem = Some(String::from("The signature has expired")),
+            "NO_PUBKEY" => {
+                problem = Some(format!(
+                    "Can't check: the signer's key {} isn't in the keyring",
+                    args.trim()
+                ))
+            }
+            "ERRSIG" if problem.is_none() => {
+                problem = Some(String::from("Can't check the signature"))
+            }
+            _ => {}
+        }
+    }
+    match (problem, signer) {
+        (Some(problem), _) => Some(Err(problem)),
+        (None, Some(signer)) if valid => Some(Ok(format!("Good signature from {}", signer))),
+        _ => None,
+    }
+}
+
+fn signed_file(signature: &Path) -> Option<PathBuf> {
+    let data = signature.with_extension("");
+    (is_signature(signature) && data.is_file()).then_some(data)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn last_line(stderr: &[u8]) -> String {
+    String::from_utf8_lossy(stderr)
+        .lines()
+        .last()
+        .map(|l| l.trim_start_matches("gpg: ").to_string())
+        .unwrap_or_else(|| String::from("gpg failed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_verification_status() {
+        let good = "[GNUPG:] NEWSIG\n\
+                    [GNUPG:] GOODSIG 1234ABCD Jane Doe <jane@example.org>\n\
+                    [GNUPG:] VALIDSIG 0123456789ABCDEF0123456789ABCDEF1234ABCD 2024-01-01\n\
+                    [GNUPG:] TRUST_UNDEFINED 0 pgp\n";
+        assert_eq!(
+            verdict(good),
+            Some(Ok(String::from(
+                "Good signature from Jane Doe <jane@example.org>"
+            )))
+        );
+
+        let bad = "[GNUPG:] NEWSIG\n[GNUPG:] BADSIG 1234ABCD Jane Doe <jane@example.org>\n";
+        assert_eq!(
+            verdict(bad),
+            Some(Err(String::from(
+                "Bad signature from Jane Doe <jane@example.org>"
+            )))
+        );
+
+        let unknown = "[GNUPG:] NEWSIG\n\
+                       [GNUPG:] ERRSIG 1234ABCD 1 8 00 1700000000 9 -\n\
+                       [GNUPG:] NO_PUBKEY 1234ABCD\n";
+        assert!(matches!(verdict(unknown), Some(Err(e)) if e.contains("1234ABCD")));
+
+        // A GOODSIG alone isn't enough
+        assert_eq!(verdict("[GNUPG:] GOODSIG 1234ABCD Jane\n"), None);
+        assert_eq!(verdict(""), None);
+    }
+}
@@ -0,0 +1,327 @@
+// A record of the file operations done through the app, for "where did I
+// move that file?" moments. Each finished operation is appended to
+// <data dir>/history.toml as an `[[entry]]` table, so writing never has to
+// read the file back; the History window loads it when opened.
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Older entries are dropped once the file holds more than this
+const KEPT: usize = 5000;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Operation {
+    Copy,
+    Move,
+    Trash,
+    Restore,
+    Delete,
+    Shred,
+    Link,
+    Create,
+    Rename,
+    Extract,
+    Compress,
+}
+
+impl Operation {
+    const ALL: [Operation; 11] = [
+        Operation::Copy,
+        Operation::Move,
+        Operation::Trash,
+        Operation::Restore,
+        Operation::Delete,
+        Operation::Shred,
+        Operation::Link,
+        Operation::Create,
+        Operation::Rename,
+        Operation::Extract,
+        Operation::Compress,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Operation::Copy => "Copied",
+            Operation::Move => "Moved",
+            Operation::Trash => "Moved to trash",
+            Operation::Restore => "Restored",
+            Operation::Delete => "Deleted",
+            Operation::Shred => "Shredded",
+            Operation::Link => "Linked",
+            Operation::Create => "Created",
+            Operation::Rename => "Renamed",
+            Operation::Extract => "Extracted",
+            Operation::Compress => "Compressed",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    // Local time, "2024-05-01 14:03:22"
+    pub time: String,
+    pub operation: Operation,
+    pub sources: Vec<PathBuf>,
+    // Where things went, for operations that put them somewhere
+    pub destination: Option<PathBuf>,
+    // Why it failed, partly or wholly
+    pub error: Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct HistoryFile {
+    entry: Vec<Record>,
+}
+
+pub fn history_file() -> Option<PathBuf> {
+    crate::user_dirs::data_dir().map(|d| d.join("history.toml"))
+}
+
+// Jobs finish on their own threads; this keeps their appends whole
+static WRITING: Mutex<()> = Mutex::new(());
+
+pub fn record(
+    operation: Operation,
+    sources: &[PathBuf],
+    destination: Option<&Path>,
+    result: &Result<(), String>,
+) {
+    let Some(path) = history_file() else {
+        return;
+    };
+    let file = HistoryFile {
+        entry: vec![Record {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            operation,
+            sources: sources.to_vec(),
+            destination: destination.map(Path::to_path_buf),
+            error: result.as_ref().err().cloned(),
+        }],
+    };
+    let _writing = WRITING.lock().unwrap();
+    let result = toml::to_string(&file)
+        .map_err(io::Error::other)
+        .and_then(|text| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?
+                .write_all(format!("{}\n", text).as_bytes())
+        });
+    if let Err(e) = result {
+        tracing::warn!("Failed to add to the history: {}", e);
+    }
+}
+
+// Records an operation as it returns from its job
+pub fn logged(
+    operation: Operation,
+    sources: &[PathBuf],
+    destination: Option<&Path>,
+    result: Result<(), String>,
+) -> Result<(), String> {
+    record(operation, sources, destination, &result);
+    result
+}
+
+pub fn logged_transfer(
+    transfer: &crate::ops::Transfer,
+    result: Result<(), String>,
+) -> Result<(), String> {
+    let operation = match transfer.mode {
+        crate::ops::TransferMode::Copy => Operation::Copy,
+        crate::ops::TransferMode::Move => Operation::Move,
+    };
+    logged(
+        operation,
+        &transfer.sources,
+        Some(&transfer.destination),
+        result,
+    )
+}
+
+// Oldest first
+pub fn load() -> Result<Vec<Record>, String> {
+    let Some(path) = history_file() else {
+        return Ok(Vec::new());
+    };
+    let _writing = WRITING.lock().unwrap();
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+    let mut records = toml::from_str::<HistoryFile>(&text)
+        .map_err(|e| e.to_string())?
+        .entry;
+    if records.len() > KEPT {
+        records.drain(..records.len() - KEPT);
+        let kept = HistoryFile { entry: records };
+        if let Err(e) = toml::to_string(&kept)
+            .map_err(io::Error::other)
+            .and_then(|text| fs::write(&path, text))
+        {
+            tracing::warn!("Failed to trim the history: {}", e);
+        }
+        records = kept.entry;
+    }
+    Ok(records)
+}
+
+pub fn clear() -> io::Result<()> {
+    let _writing = WRITING.lock().unwrap();
+    match history_file().map(fs::remove_file) {
+        Some(Err(e)) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Default)]
+pub struct HistoryWindow {
+    pub open: bool,
+    search: String,
+    operation: Option<Operation>,
+    // Loaded on opening and on Refresh
+    records: Option<Result<Vec<Record>, String>>,
+}
+
+impl HistoryWindow {
+    pub fn open(&mut self) {
+        self.open = true;
+        self.records = None;
+    }
+
+    // Returns a folder to go to
+    // `salt` is the id of the browser showing it
+    pub fn show(&mut self, ctx: &egui::Context, salt: egui::Id) -> Option<PathBuf> {
+        if !self.open {
+            return None;
+        }
+        let mut open = self.open;
+        let mut go_to = None;
+        let records = self.records.get_or_insert_with(load);
+
+        egui::Window::new("History")
+            .id(salt.with("history"))
+            .open(&mut open)
+            .default_size([700.0, 350.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("history_operation")
+                        .selected_text(self.operation.map_or("All operations", |o| o.label()))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.operation, None, "All operations");
+                            for operation in Operation::ALL {
+                                ui.selectable_value(
+                                    &mut self.operation,
+                                    Some(operation),
+                                    operation.label(),
+                                );
+                            }
+                        });
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.search).hint_text("File or folder"),
+                    );
+                    if ui.button("Refresh").clicked() {
+                        *records = load();
+                    }
+                    if ui.button("Clear").clicked() {
+                        match clear() {
+                            Ok(()) => *records = Ok(Vec::new()),
+                            Err(e) => tracing::error!("Failed to clear the history: {}", e),
+                        }
+                    }
+                });
+                ui.separator();
+
+                let records = match records {
+                    Ok(records) => records,
+                    Err(e) => {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("Failed to read the history: {}", e),
+                        );
+                        return;
+                    }
+                };
+                let search = self.search.to_lowercase();
+                let matches =
+                    |path: &PathBuf| path.to_string_lossy().to_lowercase().contains(&search);
+                let shown: Vec<&Record> = records
+                    .iter()
+                    .rev()
+                    .filter(|r| self.operation.is_none_or(|o| o == r.operation))
+                    .filter(|r| {
+                        search.is_empty()
+                            || r.sources.iter().any(matches)
+                            || r.destination.as_ref().is_some_and(matches)
+                    })
+                    .collect();
+                if shown.is_empty() {
+                    ui.weak("Nothing recorded yet");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        egui::Grid::new("history")
+                            .num_columns(5)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for record in shown {
+                                    ui.label(&record.time);
+                                    ui.label(record.operation.label());
+                                    let sources = match record.sources.as_slice() {
+                                        [source] => source.display().to_string(),
+                                        sources => crate::ops::plural(sources.len(), "item"),
+                                    };
+                                    let all: Vec<String> = record
+                                        .sources
+                                        .iter()
+                                        .map(|s| s.display().to_string())
+                                        .collect();
+                                    ui.label(sources).on_hover_text(all.join("\n"));
+                                    match &record.destination {
+                                        Some(destination) => {
+                                            if ui
+                                                .link(format!("→ {}", destination.display()))
+                                                .on_hover_text("Go to this folder")
+                                                .clicked()
+                                            {
+                                                // Archives and the like open
+                                                // in the folder holding them
+                                                go_to = if destination.is_dir() {
+                                                    Some(destination.clone())
+                                                } else {
+                                                    destination.parent().map(Path::to_path_buf)
+                                                };
+                                            }
+                                        }
+                                        None => {
+                                            ui.label("");
+                                        }
+                                    }
+                                    match &record.error {
+                                        Some(error) => ui.colored_label(
+                                            ui.visuals().error_fg_color,
+                                            format!("Failed: {}", error),
+                                        ),
+                                        None => ui.label("Done"),
+                                    };
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        self.open = open;
+        go_to
+    }
+}
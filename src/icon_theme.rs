@@ -0,0 +1,399 @@
+// File type and folder icons. On Linux and the BSDs they come from the
+// desktop's freedesktop icon theme (GTK or KDE setting, falling back through
+// the themes it inherits from to hicolor); elsewhere, or for types the theme
+// has nothing for, from a few generic icons bundled with the app. Only PNG
+// icons are used, so themes that ship SVG alone fall back to the bundled set.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+// Rows show icons at 16 points; this looks sharp on high-DPI screens too
+const SIZE: u32 = 32;
+
+const BUNDLED: &[(&str, &[u8])] = &[
+    ("folder", include_bytes!("../data/icons/folder.png")),
+    (
+        "text-x-generic",
+        include_bytes!("../data/icons/text-x-generic.png"),
+    ),
+    (
+        "image-x-generic",
+        include_bytes!("../data/icons/image-x-generic.png"),
+    ),
+    (
+        "audio-x-generic",
+        include_bytes!("../data/icons/audio-x-generic.png"),
+    ),
+    (
+        "video-x-generic",
+        include_bytes!("../data/icons/video-x-generic.png"),
+    ),
+    (
+        "package-x-generic",
+        include_bytes!("../data/icons/package-x-generic.png"),
+    ),
+    (
+        "application-x-executable",
+        include_bytes!("../data/icons/application-x-executable.png"),
+    ),
+    (
+        "application-x-generic",
+        include_bytes!("../data/icons/application-x-generic.png"),
+    ),
+    (
+        "text-x-script",
+        include_bytes!("../data/icons/text-x-script.png"),
+    ),
+    (
+        "x-office-document",
+        include_bytes!("../data/icons/x-office-document.png"),
+    ),
+    (
+        "x-office-spreadsheet",
+        include_bytes!("../data/icons/x-office-spreadsheet.png"),
+    ),
+    (
+        "x-office-presentation",
+        include_bytes!("../data/icons/x-office-presentation.png"),
+    ),
+    (
+        "font-x-generic",
+        include_bytes!("../data/icons/font-x-generic.png"),
+    ),
+    ("text-html", include_bytes!("../data/icons/text-html.png")),
+];
+
+// Icons from the bundled set are named with this prefix
+const BUNDLED_PREFIX: &str = "bundled:";
+
+pub fn bundled(icon: &str) -> Option<&'static [u8]> {
+    let name = icon.strip_prefix(BUNDLED_PREFIX)?;
+    BUNDLED
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, bytes)| *bytes)
+}
+
+// The icon for an item as something `load_icon` in the browser understands:
+// an image path, or a bundled icon's name. Looked up once per file type.
+pub fn for_item(name: &str, is_dir: bool) -> Option<String> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+    let mime = if is_dir {
+        String::from("inode/directory")
+    } else {
+        mime_guess::from_path(name)
+            .first()
+            .map(|m| m.essence_str().to_string())
+            .unwrap_or_else(|| String::from("application/octet-stream"))
+    };
+    let mut cache = CACHE.get_or_init(Default::default).lock().unwrap();
+    cache
+        .entry(mime)
+        .or_insert_with_key(|mime| {
+            let names = icon_names(mime);
+            names
+                .iter()
+                .find_map(|name| system::find(name))
+                .map(|path| path.to_string_lossy().into_owned())
+                .or_else(|| {
+                    names
+                        .iter()
+                        .find(|name| bundled(&format!("{}{}", BUNDLED_PREFIX, name)).is_some())
+                        .map(|name| format!("{}{}", BUNDLED_PREFIX, name))
+                })
+        })
+        .clone()
+}
+
+// Icon names to try for a type, most specific first, as the freedesktop
+// icon naming spec lays them out
+fn icon_names(mime: &str) -> Vec<String> {
+    if mime == "inode/directory" {
+        return vec![String::from("folder"), String::from("inode-directory")];
+    }
+    let mut names = vec![mime.replace('/', "-")];
+    names.extend(system::generic_icon(mime));
+    let (top, sub) = mime.split_once('/').unwrap_or((mime, ""));
+    names.push(format!("{}-x-generic", top));
+    let generic = match sub {
+        _ if top == "text" && (sub.contains("script") || sub.contains("python")) => "text-x-script",
+        "zip" | "gzip" | "x-tar" | "x-7z-compressed" | "x-rar" | "vnd.rar" | "x-bzip2" | "x-xz"
+        | "zstd" | "x-zstd" => "package-x-generic",
+        "pdf" | "msword" | "rtf" => "x-office-document",
+        "vnd.ms-excel" => "x-office-spreadsheet",
+        "vnd.ms-powerpoint" => "x-office-presentation",
+        "x-executable" | "x-msdownload" | "x-sharedlib" | "vnd.microsoft.portable-executable" => {
+            "application-x-executable"
+        }
+        _ if sub.contains("wordprocessingml") || sub.contains("opendocument.text") => {
+            "x-office-document"
+        }
+        _ if sub.contains("spreadsheetml") || sub.contains("opendocument.spreadsheet") => {
+            "x-office-spreadsheet"
+        }
+        _ if sub.contains("presentationml") || sub.contains("opendocument.presentation") => {
+            "x-office-presentation"
+        }
+        _ if top == "text" || sub == "json" || sub == "xml" || sub == "javascript" => {
+            "text-x-generic"
+        }
+        _ => "application-x-generic",
+    };
+    names.push(String::from(generic));
+    names
+}
+
+// Also finds icons named by shortcuts and folder icon files
+pub fn find(icon: &str) -> Option<PathBuf> {
+    system::find(icon)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod system {
+    use super::SIZE;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Mutex, OnceLock};
+
+    struct Directory {
+        name: String,
+        size: u32,
+        min: u32,
+        max: u32,
+    }
+
+    struct Theme {
+        // The theme's folder under every icon base directory
+        roots: Vec<PathBuf>,
+        directories: Vec<Directory>,
+        inherits: Vec<String>,
+    }
+
+    fn bases() -> Vec<PathBuf> {
+        let mut bases = Vec::new();
+        if let Some(home) = crate::user_dirs::home_dir() {
+            bases.push(home.join(".icons"));
+        }
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| crate::user_dirs::home_dir().map(|home| home.join(".local/share")));
+        bases.extend(data_home.map(|dir| dir.join("icons")));
+        let data_dirs = std::env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| String::from("/usr/local/share:/usr/share"));
+        bases.extend(data_dirs.split(':').map(|dir| Path::new(dir).join("icons")));
+        bases
+    }
+
+    fn load_theme(name: &str, bases: &[PathBuf]) -> Option<Theme> {
+        let roots: Vec<PathBuf> = bases
+            .iter()
+            .map(|base| base.join(name))
+            .filter(|root| root.is_dir())
+            .collect();
+        let index = roots
+            .iter()
+            .find_map(|root| fs::read_to_string(root.join("index.theme")).ok())?;
+        let mut theme = Theme {
+            roots,
+            directories: Vec::new(),
+            inherits: Vec::new(),
+        };
+        let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut section = String::new();
+        for line in index.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.to_string();
+            } else if let Some((key, value)) = line.split_once('=') {
+                sections
+                    .entry(section.clone())
+                    .or_default()
+                    .insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+        let main = sections.get("Icon Theme")?;
+        let list = |key: &str| -> Vec<String> {
+            main.get(key)
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        theme.inherits = list("Inherits");
+        for name in list("Directories") {
+            let Some(keys) = sections.get(&name) else {
+                continue;
+            };
+            let number = |key: &str| keys.get(key).and_then(|v| v.parse::<u32>().ok());
+            let Some(size) = number("Size") else {
+                continue;
+            };
+            let (min, max) = match keys.get("Type").map(String::as_str) {
+                Some("Fixed") => (size, size),
+                Some("Scalable") => (
+                    number("MinSize").unwrap_or(size),
+                    number("MaxSize").unwrap_or(size),
+                ),
+                _ => {
+                    let threshold = number("Threshold").unwrap_or(2);
+                    (size.saturating_sub(threshold), size + threshold)
+                }
+            };
+            theme.directories.push(Directory {
+                name,
+                size,
+                min,
+                max,
+            });
+        }
+        Some(theme)
+    }
+
+    // The user's theme, the ones it inherits from, then hicolor
+    fn themes() -> &'static [Theme] {
+        static THEMES: OnceLock<Vec<Theme>> = OnceLock::new();
+        THEMES.get_or_init(|| {
+            let bases = bases();
+            let mut names = vec![current_theme().unwrap_or_else(|| String::from("hicolor"))];
+            let mut themes = Vec::new();
+            let mut i = 0;
+            while i < names.len() {
+                if let Some(theme) = load_theme(&names[i], &bases) {
+                    for inherited in &theme.inherits {
+                        if !names.contains(inherited) {
+                            names.push(inherited.clone());
+                        }
+                    }
+                    themes.push(theme);
+                }
+                i += 1;
+                if i == names.len() && !names.iter().any(|n| n == "hicolor") {
+                    names.push(String::from("hicolor"));
+                }
+            }
+            themes
+        })
+    }
+
+    // GTK's setting, then KDE's, then GNOME's, which lives in dconf
+    fn current_theme() -> Option<String> {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| crate::user_dirs::home_dir().map(|home| home.join(".config")))?;
+        let setting = |file: &str, section: &str, key: &str| -> Option<String> {
+            let contents = fs::read_to_string(config.join(file)).ok()?;
+            let mut in_section = false;
+            contents.lines().map(str::trim).find_map(|line| {
+                if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                    in_section = name == section;
+                    return None;
+                }
+                let (k, v) = line.split_once('=')?;
+                (in_section && k.trim() == key).then(|| v.trim().trim_matches('"').to_string())
+            })
+        };
+        setting("gtk-4.0/settings.ini", "Settings", "gtk-icon-theme-name")
+            .or_else(|| setting("gtk-3.0/settings.ini", "Settings", "gtk-icon-theme-name"))
+            .or_else(|| setting("kdeglobals", "Icons", "Theme"))
+            .or_else(|| {
+                let output = std::process::Command::new("gsettings")
+                    .args(["get", "org.gnome.desktop.interface", "icon-theme"])
+                    .output()
+                    .ok()?;
+                let name = String::from_utf8_lossy(&output.stdout)
+                    .trim()
+                    .trim_matches('\'')
+                    .to_string();
+                (output.status.success() && !name.is_empty()).then_some(name)
+            })
+            .filter(|name| !name.is_empty())
+    }
+
+    fn size_distance(directory: &Directory) -> u32 {
+        if SIZE < directory.min {
+            directory.min - SIZE
+        } else {
+            SIZE.saturating_sub(directory.max)
+        }
+    }
+
+    // The exact size if a theme has it, else its closest; PNGs only
+    fn lookup(icon: &str) -> Option<PathBuf> {
+        let file_name = format!("{}.png", icon);
+        for theme in themes() {
+            let mut closest: Option<(u32, PathBuf)> = None;
+            for directory in &theme.directories {
+                let distance = size_distance(directory);
+                if closest.as_ref().is_some_and(|(best, _)| *best <= distance) {
+                    continue;
+                }
+                let found = theme
+                    .roots
+                    .iter()
+                    .map(|root| root.join(&directory.name).join(&file_name))
+                    .find(|path| path.is_file());
+                if let Some(path) = found {
+                    if distance == 0 && directory.size == SIZE {
+                        return Some(path);
+                    }
+                    closest = Some((distance, path));
+                }
+            }
+            if let Some((_, path)) = closest {
+                return Some(path);
+            }
+        }
+        let pixmap = PathBuf::from("/usr/share/pixmaps").join(&file_name);
+        pixmap.is_file().then_some(pixmap)
+    }
+
+    pub fn find(icon: &str) -> Option<PathBuf> {
+        static CACHE: OnceLock<Mutex<HashMap<String, Option<PathBuf>>>> = OnceLock::new();
+        let cache = CACHE.get_or_init(Default::default);
+        if let Some(found) = cache.lock().unwrap().get(icon) {
+            return found.clone();
+        }
+        let found = lookup(icon);
+        cache
+            .lock()
+            .unwrap()
+            .insert(icon.to_string(), found.clone());
+        found
+    }
+
+    // shared-mime-info's generic icon for types the theme has no icon for
+    pub fn generic_icon(mime: &str) -> Option<String> {
+        static GENERIC: OnceLock<HashMap<String, String>> = OnceLock::new();
+        GENERIC
+            .get_or_init(|| {
+                let contents =
+                    fs::read_to_string("/usr/share/mime/generic-icons").unwrap_or_default();
+                contents
+                    .lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .map(|(mime, icon)| (mime.to_string(), icon.to_string()))
+                    .collect()
+            })
+            .get(mime)
+            .cloned()
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+mod system {
+    use std::path::PathBuf;
+
+    pub fn find(_icon: &str) -> Option<PathBuf> {
+        None
+    }
+
+    pub fn generic_icon(_mime: &str) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,275 @@
+// Live read and write throughput of the disk a folder is on, shown while jobs
+// run so it's clear whether a copy is held up by the disk. Comes from
+// /proc/diskstats on Linux and the volume's performance counters on Windows;
+// other systems show nothing.
+use crate::format_file_size;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// Counters are read at most this often
+const INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, PartialEq)]
+struct Device {
+    // As shown, e.g. "sda2" or "C:"
+    name: String,
+    // What the counters are read from
+    key: String,
+}
+
+// Bytes moved since the device came up
+#[derive(Clone, Copy)]
+struct Counters {
+    read: u64,
+    written: u64,
+}
+
+// Bytes per second over the last interval
+#[derive(Clone)]
+pub struct Throughput {
+    pub device: String,
+    pub read: u64,
+    pub written: u64,
+}
+
+impl Throughput {
+    pub fn label(&self) -> String {
+        format!(
+            "{} ↓ {}/s ↑ {}/s",
+            self.device,
+            format_file_size(self.read),
+            format_file_size(self.written)
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct Monitor {
+    // The folder the device was looked up for, and the device
+    device: Option<(PathBuf, Option<Device>)>,
+    last: Option<(Instant, Counters)>,
+    current: Option<Throughput>,
+}
+
+impl Monitor {
+    // Needs two readings before there's anything to show
+    pub fn sample(&mut self, dir: &Path) -> Option<&Throughput> {
+        if self.device.as_ref().is_none_or(|(d, _)| d != dir) {
+            self.device = Some((dir.to_path_buf(), device(dir)));
+            self.last = None;
+            self.current = None;
+        }
+        let device = self.device.as_ref()?.1.clone()?;
+        let now = Instant::now();
+        if self
+            .last
+            .is_some_and(|(time, _)| now.duration_since(time) < INTERVAL)
+        {
+            return self.current.as_ref();
+        }
+        let counters = counters(&device)?;
+        if let Some((time, last)) = self.last {
+            let seconds = now.duration_since(time).as_secs_f64();
+            let rate = |now: u64, then: u64| (now.saturating_sub(then) as f64 / seconds) as u64;
+            self.current = Some(Throughput {
+                device: device.name,
+                read: rate(counters.read, last.read),
+                written: rate(counters.written, last.written),
+            });
+        }
+        self.last = Some((now, counters));
+        self.current.as_ref()
+    }
+
+    // Forgets the last reading, so an idle stretch isn't averaged in
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.current = None;
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn device(dir: &Path) -> Option<Device> {
+    use std::os::unix::fs::MetadataExt;
+    let dev = std::fs::metadata(dir).ok()?.dev();
+    let (major, minor) = (libc::major(dev), libc::minor(dev));
+    let stats = std::fs::read_to_string("/proc/diskstats").ok()?;
+    let by_number = stats.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let matches = fields.next()?.parse() == Ok(major) && fields.next()?.parse() == Ok(minor);
+        matches.then(|| fields.next().map(str::to_string))?
+    });
+    // Btrfs and the like report a device number of their own, so go by what
+    // is mounted there instead
+    let name = match by_number {
+        Some(name) => name,
+        None => {
+            let source = mount_source(dir)?;
+            let source = std::fs::canonicalize(source).ok()?;
+            let name = source.file_name()?.to_string_lossy().into_owned();
+            stats
+                .lines()
+                .any(|line| line.split_whitespace().nth(2) == Some(name.as_str()))
+                .then_some(name)?
+        }
+    };
+    Some(Device {
+        key: name.clone(),
+        name,
+    })
+}
+
+// The device mounted where `dir` is, e.g. "/dev/nvme0n1p2"
+#[cfg(target_os = "linux")]
+fn mount_source(dir: &Path) -> Option<PathBuf> {
+    let dir = std::fs::canonicalize(dir).ok()?;
+    let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = fields.next()?;
+            let mount_point = PathBuf::from(fields.next()?.replace("\\040", " "));
+            source
+                .starts_with("/dev/")
+                .then(|| (mount_point, PathBuf::from(source)))
+        })
+        .filter(|(mount_point, _)| dir.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+        .map(|(_, source)| source)
+}
+
+// Sectors read and written are the 6th and 10th fields after the name, in
+// 512-byte units whatever the disk's own sector size
+#[cfg(target_os = "linux")]
+fn counters(device: &Device) -> Option<Counters> {
+    let stats = std::fs::read_to_string("/proc/diskstats").ok()?;
+    stats.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.get(2) != Some(&device.key.as_str()) {
+            return None;
+        }
+        Some(Counters {
+            read: fields.get(5)?.parse::<u64>().ok()? * 512,
+            written: fields.get(9)?.parse::<u64>().ok()? * 512,
+        })
+    })
+}
+
+#[cfg(windows)]
+fn device(dir: &Path) -> Option<Device> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetVolumePathNameW(file: *const u16, volume: *mut u16, length: u32) -> i32;
+    }
+
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut root = [0u16; 261];
+    if unsafe { GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root.len() as u32) } == 0 {
+        return None;
+    }
+    let len = root.iter().position(|&c| c == 0).unwrap_or(root.len());
+    let root = String::from_utf16_lossy(&root[..len]);
+    // Drive letters only; mounted folders and shares have no counters
+    let name = root.strip_suffix('\\')?;
+    (name.len() == 2 && name.ends_with(':')).then(|| Device {
+        name: name.to_string(),
+        key: format!(r"\\.\{}", name),
+    })
+}
+
+#[cfg(windows)]
+fn counters(device: &Device) -> Option<Counters> {
+    #[repr(C)]
+    struct DiskPerformance {
+        bytes_read: i64,
+        bytes_written: i64,
+        read_time: i64,
+        write_time: i64,
+        idle_time: i64,
+        read_count: u32,
+        write_count: u32,
+        queue_depth: u32,
+        split_count: u32,
+        query_time: i64,
+        storage_device_number: u32,
+        storage_manager_name: [u16; 8],
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateFileW(
+            name: *const u16,
+            access: u32,
+            share_mode: u32,
+            security: *mut std::ffi::c_void,
+            disposition: u32,
+            flags: u32,
+            template: isize,
+        ) -> isize;
+        fn DeviceIoControl(
+            device: isize,
+            code: u32,
+            input: *mut std::ffi::c_void,
+            input_size: u32,
+            output: *mut std::ffi::c_void,
+            output_size: u32,
+            returned: *mut u32,
+            overlapped: *mut std::ffi::c_void,
+        ) -> i32;
+        fn CloseHandle(handle: isize) -> i32;
+    }
+    const FILE_SHARE_READ: u32 = 0x1;
+    const FILE_SHARE_WRITE: u32 = 0x2;
+    const OPEN_EXISTING: u32 = 3;
+    const INVALID_HANDLE_VALUE: isize = -1;
+    const IOCTL_DISK_PERFORMANCE: u32 = 0x70020;
+
+    let name: Vec<u16> = device.key.encode_utf16().chain(Some(0)).collect();
+    // No access rights are needed just to query
+    let volume = unsafe {
+        CreateFileW(
+            name.as_ptr(),
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            std::ptr::null_mut(),
+            OPEN_EXISTING,
+            0,
+            0,
+        )
+    };
+    if volume == INVALID_HANDLE_VALUE {
+        return None;
+    }
+    let mut performance: DiskPerformance = unsafe { std::mem::zeroed() };
+    let mut returned = 0;
+    let ok = unsafe {
+        DeviceIoControl(
+            volume,
+            IOCTL_DISK_PERFORMANCE,
+            std::ptr::null_mut(),
+            0,
+            &mut performance as *mut DiskPerformance as *mut std::ffi::c_void,
+            std::mem::size_of::<DiskPerformance>() as u32,
+            &mut returned,
+            std::ptr::null_mut(),
+        )
+    } != 0;
+    unsafe { CloseHandle(volume) };
+    ok.then_some(Counters {
+        read: performance.bytes_read as u64,
+        written: performance.bytes_written as u64,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn device(_dir: &Path) -> Option<Device> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn counters(_device: &Device) -> Option<Counters> {
+    None
+}
@@ -0,0 +1,327 @@
+// Read-only access to ISO 9660 images (CDs, DVDs, most .iso downloads),
+// including Joliet and Rock Ridge long names. UDF-only images can't be
+// browsed, but can still be mounted by the OS.
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const SECTOR: u64 = 2048;
+
+#[derive(Clone)]
+pub struct IsoEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<chrono::NaiveDateTime>,
+    extent: u32,
+}
+
+pub struct IsoImage {
+    file: fs::File,
+    root: IsoEntry,
+    joliet: bool,
+}
+
+pub fn is_disk_image(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| matches!(e.as_str(), "iso" | "img" | "dmg"))
+}
+
+pub fn is_iso(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("iso"))
+}
+
+impl IsoImage {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = fs::File::open(path)?;
+        let mut primary = None;
+        let mut joliet = None;
+
+        // Volume descriptors start at sector 16 and end with type 255
+        for sector in 16..64 {
+            let mut descriptor = [0; SECTOR as usize];
+            file.seek(SeekFrom::Start(sector * SECTOR))?;
+            file.read_exact(&mut descriptor)?;
+            if &descriptor[1..6] != b"CD001" {
+                break;
+            }
+            match descriptor[0] {
+                1 => primary = Some(descriptor),
+                // A supplementary descriptor with a UCS-2 escape sequence is Joliet
+                2 if matches!(&descriptor[88..91], b"%/@" | b"%/C" | b"%/E") => {
+                    joliet = Some(descriptor)
+                }
+                255 => break,
+                _ => {}
+            }
+        }
+
+        let (descriptor, is_joliet) = match (joliet, primary) {
+            (Some(d), _) => (d, true),
+            (None, Some(d)) => (d, false),
+            (None, None) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "not an ISO 9660 image (UDF-only images can only be mounted)",
+                ))
+            }
+        };
+        let root = parse_record(&descriptor[156..190], is_joliet)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad root directory"))?;
+        Ok(Self {
+            file,
+            root,
+            joliet: is_joliet,
+        })
+    }
+
+    pub fn root(&self) -> IsoEntry {
+        self.root.clone()
+    }
+
+    pub fn read_dir(&mut self, dir: &IsoEntry) -> io::Result<Vec<IsoEntry>> {
+        // The size comes from the image, so it's checked against the image
+        // before anything that big is allocated
+        let start = dir.extent as u64 * SECTOR;
+        let image_len = self.file.metadata()?.len();
+        if start.saturating_add(dir.size) > image_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "directory extends past the end of the image",
+            ));
+        }
+        let mut data = vec![0; dir.size as usize];
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.read_exact(&mut data)?;
+
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let len = data[offset] as usize;
+            if len == 0 {
+                // Records never cross sectors; the rest of this one is padding
+                offset = (offset / SECTOR as usize + 1) * SECTOR as usize;
+                continue;
+            }
+            let Some(record) = data.get(offset..offset + len).filter(|_| len > 33) else {
+                break;
+            };
+            // The first two records are "." and ".."
+            let id = &record[33..33 + (record[32] as usize).min(len - 33)];
+            if id != [0] && id != [1] {
+                if let Some(entry) = parse_record(record, self.joliet) {
+                    entries.push(entry);
+                }
+            }
+            offset += len;
+        }
+        entries.sort_by_key(|e| (!e.is_dir, e.name.to_lowercase()));
+        Ok(entries)
+    }
+
+    // Copies a file, or a folder with everything in it, out of the image
+    pub fn extract(&mut self, entry: &IsoEntry, destination: &Path) -> io::Result<()> {
+        if entry.is_dir {
+            fs::create_dir(destination)?;
+            for child in self.read_dir(entry)? {
+                self.extract(&child, &destination.join(&child.name))?;
+            }
+            return Ok(());
+        }
+        self.file
+            .seek(SeekFrom::Start(entry.extent as u64 * SECTOR))?;
+        let mut out = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(destination)?;
+        io::copy(&mut (&mut self.file).take(entry.size), &mut out)?;
+        out.flush()
+    }
+}
+
+fn parse_record(record: &[u8], joliet: bool) -> Option<IsoEntry> {
+    let u32_le = |at: usize| u32::from_le_bytes(record[at..at + 4].try_into().unwrap());
+    let id_len = *record.get(32)? as usize;
+    let id = record.get(33..33 + id_len)?;
+    let is_dir = record[25] & 0x02 != 0;
+
+    // Rock Ridge names live in the System Use area after the identifier
+    let system_use = record.get(33 + id_len + (1 - id_len % 2)..).unwrap_or(&[]);
+    let name = match rock_ridge_name(system_use) {
+        Some(name) => name,
+        None if joliet => {
+            let units: Vec<u16> = id
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => String::from_utf8_lossy(id).into_owned(),
+    };
+    // "NAME.TXT;1" → "NAME.TXT", and "README." → "README"
+    let name = name.split(';').next().unwrap_or("");
+    let name = if is_dir {
+        name
+    } else {
+        name.trim_end_matches('.')
+    };
+
+    let date = &record[18..25];
+    let modified =
+        chrono::NaiveDate::from_ymd_opt(1900 + date[0] as i32, date[1].into(), date[2].into())
+            .and_then(|d| d.and_hms_opt(date[3].into(), date[4].into(), date[5].into()));
+
+    Some(IsoEntry {
+        name: name.replace(['/', '\\'], "_"),
+        is_dir,
+        size: u32_le(10) as u64,
+        modified,
+        extent: u32_le(2),
+    })
+}
+
+// SUSP entries: two-letter signature, length, version, data. NM entries may
+// be split with the "continue" flag set.
+fn rock_ridge_name(mut system_use: &[u8]) -> Option<String> {
+    let mut name = Vec::new();
+    let mut found = false;
+    while system_use.len() >= 4 {
+        let len = system_use[2] as usize;
+        if len < 4 || len > system_use.len() {
+            break;
+        }
+        if &system_use[..2] == b"NM" && len >= 5 {
+            let flags = system_use[4];
+            // Flags 2 and 4 mark "." and ".."
+            if flags & 0x06 == 0 {
+                name.extend_from_slice(&system_use[5..len]);
+                found = true;
+            }
+        }
+        system_use = &system_use[len..];
+    }
+    found.then(|| String::from_utf8_lossy(&name).into_owned())
+}
+
+// Attaches the image with the OS's own loopback support and returns where
+// it got mounted
+#[cfg(target_os = "linux")]
+pub fn mount(image: &Path) -> Result<PathBuf, String> {
+    let output = std::process::Command::new("udisksctl")
+        .args(["loop-setup", "--read-only", "--no-user-interaction", "-f"])
+        .arg(image)
+        .output()
+        .map_err(|e| format!("udisksctl: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // "Mapped file x.iso as /dev/loop0."
+    let device = stdout
+        .split_whitespace()
+        .last()
+        .map(|d| d.trim_end_matches('.').to_string())
+        .filter(|d| output.status.success() && d.starts_with("/dev/"))
+        .ok_or_else(|| String::from_utf8_lossy(&output.stderr).trim().to_string())?;
+
+    let output = std::process::Command::new("udisksctl")
+        .args(["mount", "--no-user-interaction", "-b", &device])
+        .output()
+        .map_err(|e| format!("udisksctl: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // "Mounted /dev/loop0 at /media/user/LABEL", or "... is already mounted
+    // at `/media/user/LABEL'" when the desktop mounted it first
+    let text = if output.status.success() {
+        &stdout
+    } else {
+        &stderr
+    };
+    text.split(" at ")
+        .nth(1)
+        .map(|at| at.trim().trim_matches(['`', '\'', '.']).to_string())
+        .map(PathBuf::from)
+        .filter(|p| p.is_dir())
+        .ok_or_else(|| stderr.trim().to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn mount(image: &Path) -> Result<PathBuf, String> {
+    let output = std::process::Command::new("hdiutil")
+        .args(["attach", "-readonly"])
+        .arg(image)
+        .output()
+        .map_err(|e| format!("hdiutil: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    // Each line is "device<TAB>type<TAB>mount point"; only volumes have one
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split('\t').nth(2))
+        .map(|m| PathBuf::from(m.trim()))
+        .find(|m| m.is_dir())
+        .ok_or_else(|| String::from("the image has no mountable volume"))
+}
+
+#[cfg(target_os = "windows")]
+pub fn mount(image: &Path) -> Result<PathBuf, String> {
+    let script = format!(
+        "(Mount-DiskImage -ImagePath '{}' -PassThru | Get-Volume).DriveLetter",
+        image.display().to_string().replace('\'', "''")
+    );
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .output()
+        .map_err(|e| format!("powershell: {}", e))?;
+    let letter = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || letter.is_empty() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(PathBuf::from(format!("{}:\\", letter)))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn mount(_image: &Path) -> Result<PathBuf, String> {
+    Err(String::from(
+        "mounting images isn't supported on this system",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A primary volume descriptor whose root directory, in an empty sector
+    // after the terminator, claims `size` bytes
+    fn image_with_root(size: u32) -> tempfile::NamedTempFile {
+        let mut image = vec![0u8; 19 * SECTOR as usize];
+        let descriptor = &mut image[16 * SECTOR as usize..17 * SECTOR as usize];
+        descriptor[0] = 1;
+        descriptor[1..6].copy_from_slice(b"CD001");
+        let root = &mut descriptor[156..190];
+        root[0] = 34;
+        root[2..6].copy_from_slice(&18u32.to_le_bytes());
+        root[10..14].copy_from_slice(&size.to_le_bytes());
+        root[25] = 0x02;
+        root[32] = 1;
+        image[17 * SECTOR as usize] = 255;
+        image[17 * SECTOR as usize + 1..17 * SECTOR as usize + 6].copy_from_slice(b"CD001");
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&image).unwrap();
+        file
+    }
+
+    #[test]
+    fn rejects_directories_larger_than_the_image() {
+        let file = image_with_root(u32::MAX);
+        let mut image = IsoImage::open(file.path()).unwrap();
+        let root = image.root();
+        let error = image.read_dir(&root).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let file = image_with_root(SECTOR as u32);
+        let mut image = IsoImage::open(file.path()).unwrap();
+        let root = image.root();
+        assert!(image.read_dir(&root).unwrap().is_empty());
+    }
+}
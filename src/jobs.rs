@@ -0,0 +1,404 @@
+// Long-running work (downloads, copies, ...) runs on background threads and
+// reports progress here so the UI can show it in the jobs panel
+use crate::locks;
+use crate::repaint::Repaint;
+use std::any::Any;
+use std::io;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+type Work = Arc<dyn Fn(&JobHandle) -> Result<(), String> + Send + Sync>;
+
+// Work over a list of items, which can be run again for only some of them
+pub type Batch = Arc<dyn Fn(&JobHandle, &[PathBuf]) -> Result<(), String> + Send + Sync>;
+
+// An item a batch job couldn't handle
+#[derive(Clone)]
+pub struct Failure {
+    // The item the job was given, which a retry runs on again
+    pub target: PathBuf,
+    // What failed, which can be inside `target`
+    pub path: PathBuf,
+    pub error: String,
+    // Programs with it open, when that's what stopped it
+    pub held_by: Vec<String>,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum JobState {
+    Running,
+    Finished,
+    Failed(String),
+    Cancelled,
+}
+
+// What `done` and `total` count
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProgressUnit {
+    Bytes,
+    Items,
+}
+
+#[derive(Clone)]
+pub struct JobStatus {
+    pub message: String,
+    pub unit: ProgressUnit,
+    pub done: u64,
+    // None while the total isn't known (e.g. a download without Content-Length)
+    pub total: Option<u64>,
+    pub state: JobState,
+}
+
+// Given to the worker thread to report progress and check for cancellation
+#[derive(Clone)]
+pub struct JobHandle {
+    status: Arc<Mutex<JobStatus>>,
+    cancel: Arc<AtomicBool>,
+    repaint: Repaint,
+    failures: Arc<Mutex<Vec<Failure>>>,
+}
+
+impl JobHandle {
+    // Noted for the partial failure report while the job carries on
+    pub fn item_failed(&self, target: &Path, path: &Path, error: &io::Error) {
+        let held_by = if locks::is_lock_error(error) {
+            locks::holders(path)
+                .iter()
+                .map(locks::Holder::describe)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.failures.lock().unwrap().push(Failure {
+            target: target.to_path_buf(),
+            path: path.to_path_buf(),
+            error: error.to_string(),
+            held_by,
+        });
+    }
+
+    pub fn set_message(&self, message: impl Into<String>) {
+        self.status.lock().unwrap().message = message.into();
+        self.repaint.request();
+    }
+
+    pub fn set_progress(&self, done: u64, total: Option<u64>) {
+        let mut status = self.status.lock().unwrap();
+        status.done = done;
+        status.total = total;
+        self.repaint.request();
+    }
+
+    pub fn set_unit(&self, unit: ProgressUnit) {
+        self.status.lock().unwrap().unit = unit;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    // Not part of any job list, for running work directly in tests
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        Self {
+            status: Arc::new(Mutex::new(JobStatus {
+                message: String::new(),
+                unit: ProgressUnit::Bytes,
+                done: 0,
+                total: None,
+                state: JobState::Running,
+            })),
+            cancel: Arc::new(AtomicBool::new(false)),
+            repaint: Repaint::default(),
+            failures: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+pub struct Job {
+    pub title: String,
+    // Where its results end up, for "Show in folder"
+    folder: Option<PathBuf>,
+    handle: JobHandle,
+    work: Work,
+    // Set for jobs started with spawn_batch
+    batch: Option<Batch>,
+    // Whether the UI has already reacted to this job ending
+    reported: bool,
+    started: Instant,
+    // How long it ran, once it ended
+    took: Arc<Mutex<Duration>>,
+}
+
+// A job that ended since the UI last looked
+pub struct Ended {
+    pub title: String,
+    pub folder: Option<PathBuf>,
+    pub state: JobState,
+    pub took: Duration,
+    // Items a batch job skipped, and how to run it again on them
+    pub failures: Vec<Failure>,
+    pub batch: Option<Batch>,
+}
+
+impl Job {
+    pub fn status(&self) -> JobStatus {
+        self.handle.status.lock().unwrap().clone()
+    }
+
+    fn start(&mut self) {
+        self.reported = false;
+        self.started = Instant::now();
+        self.handle.cancel.store(false, Ordering::Relaxed);
+        self.handle.failures.lock().unwrap().clear();
+        *self.handle.status.lock().unwrap() = JobStatus {
+            message: String::new(),
+            unit: ProgressUnit::Bytes,
+            done: 0,
+            total: None,
+            state: JobState::Running,
+        };
+        let handle = self.handle.clone();
+        let work = self.work.clone();
+        let (started, took) = (self.started, self.took.clone());
+        #[cfg(feature = "profiling")]
+        let title = self.title.clone();
+        std::thread::spawn(move || {
+            profile_scope!(format!("job: {}", title));
+            // A bug in one job shouldn't leave it "running" forever
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| work(&handle)));
+            let state = match result {
+                _ if handle.is_cancelled() => JobState::Cancelled,
+                Ok(Ok(())) => JobState::Finished,
+                Ok(Err(e)) => JobState::Failed(e),
+                Err(panic) => JobState::Failed(format!("crashed: {}", panic_message(&*panic))),
+            };
+            *took.lock().unwrap() = started.elapsed();
+            handle.status.lock().unwrap().state = state;
+            handle.repaint.request();
+        });
+    }
+}
+
+// How far the running jobs of all windows are together, from 0 to 1, for the
+// taskbar; None when nothing runs
+pub fn overall<'a>(all: impl IntoIterator<Item = &'a Jobs>) -> Option<f64> {
+    let fractions: Vec<f64> = all.into_iter().flat_map(Jobs::fractions).collect();
+    (!fractions.is_empty()).then(|| fractions.iter().sum::<f64>() / fractions.len() as f64)
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown error")
+    }
+}
+
+#[derive(Default)]
+pub struct Jobs {
+    jobs: Vec<Job>,
+    // Woken by progress updates; left unset by the terminal interface,
+    // which redraws on its own
+    repaint: Repaint,
+}
+
+impl Jobs {
+    pub fn with_repaint(repaint: Repaint) -> Self {
+        Self {
+            jobs: Vec::new(),
+            repaint,
+        }
+    }
+
+    // The work closure is kept so failed jobs can be retried
+    pub fn spawn(
+        &mut self,
+        title: impl Into<String>,
+        work: impl Fn(&JobHandle) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        self.spawn_in(title, None, work);
+    }
+
+    // For jobs whose results land in `folder`
+    pub fn spawn_in(
+        &mut self,
+        title: impl Into<String>,
+        folder: Option<PathBuf>,
+        work: impl Fn(&JobHandle) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        self.start(title.into(), folder, Arc::new(work), None);
+    }
+
+    // For work over several items that carries on past those that fail,
+    // noting them with `item_failed` so they can be retried on their own
+    pub fn spawn_batch(
+        &mut self,
+        title: impl Into<String>,
+        folder: Option<PathBuf>,
+        targets: Vec<PathBuf>,
+        batch: Batch,
+    ) {
+        let work = {
+            let batch = batch.clone();
+            Arc::new(move |job: &JobHandle| batch(job, &targets))
+        };
+        self.start(title.into(), folder, work, Some(batch));
+    }
+
+    fn start(&mut self, title: String, folder: Option<PathBuf>, work: Work, batch: Option<Batch>) {
+        let mut job = Job {
+            title,
+            folder,
+            handle: JobHandle {
+                status: Arc::new(Mutex::new(JobStatus {
+                    message: String::new(),
+                    unit: ProgressUnit::Bytes,
+                    done: 0,
+                    total: None,
+                    state: JobState::Running,
+                })),
+                cancel: Arc::new(AtomicBool::new(false)),
+                repaint: self.repaint.clone(),
+                failures: Arc::new(Mutex::new(Vec::new())),
+            },
+            work,
+            batch,
+            reported: false,
+            started: Instant::now(),
+            took: Arc::new(Mutex::new(Duration::ZERO)),
+        };
+        job.start();
+        self.jobs.push(job);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    pub fn any_running(&self) -> bool {
+        self.jobs
+            .iter()
+            .any(|job| job.status().state == JobState::Running)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.iter()
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.jobs
+            .retain(|job| job.status().state == JobState::Running);
+    }
+
+    // Returns every job that ended since the last call once, so the caller
+    // knows to refresh the listing
+    pub fn take_ended(&mut self) -> Vec<Ended> {
+        let mut ended = Vec::new();
+        for job in &mut self.jobs {
+            let state = job.status().state;
+            if !job.reported && state != JobState::Running {
+                job.reported = true;
+                ended.push(Ended {
+                    title: job.title.clone(),
+                    folder: job.folder.clone(),
+                    state,
+                    took: *job.took.lock().unwrap(),
+                    failures: job.handle.failures.lock().unwrap().clone(),
+                    batch: job.batch.clone(),
+                });
+            }
+        }
+        ended
+    }
+
+    // Each running job's share done, from 0 to 1
+    fn fractions(&self) -> impl Iterator<Item = f64> + '_ {
+        self.jobs
+            .iter()
+            .map(Job::status)
+            .filter(|status| status.state == JobState::Running)
+            .map(|status| match status.total {
+                Some(total) if total > 0 => (status.done as f64 / total as f64).min(1.0),
+                // Not known yet counts as not started
+                _ => 0.0,
+            })
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.strong("Jobs");
+            if ui.small_button("Clear Finished").clicked() {
+                self.clear_finished();
+            }
+        });
+
+        let mut remove = None;
+        for (i, job) in self.jobs.iter_mut().enumerate() {
+            let status = job.status();
+            ui.horizontal(|ui| {
+                ui.label(&job.title);
+                match &status.state {
+                    JobState::Running => {
+                        let progress = match status.total {
+                            Some(total) if total > 0 => status.done as f32 / total as f32,
+                            _ => 0.0,
+                        };
+                        let format = |n| match status.unit {
+                            ProgressUnit::Bytes => crate::format_file_size(n),
+                            ProgressUnit::Items => n.to_string(),
+                        };
+                        let text = match status.total {
+                            Some(total) => format!("{} / {}", format(status.done), format(total)),
+                            None => format(status.done),
+                        };
+                        ui.add(
+                            egui::ProgressBar::new(progress)
+                                .desired_width(200.0)
+                                .text(text)
+                                .animate(status.total.is_none()),
+                        );
+                        if ui.small_button("Cancel").clicked() {
+                            job.handle.cancel.store(true, Ordering::Relaxed);
+                        }
+                    }
+                    JobState::Finished => {
+                        ui.label("Done");
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(i);
+                        }
+                    }
+                    JobState::Failed(error) => {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
+                        if ui.small_button("Retry").clicked() {
+                            job.start();
+                        }
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(i);
+                        }
+                    }
+                    JobState::Cancelled => {
+                        ui.weak("Cancelled");
+                        if ui.small_button("Retry").clicked() {
+                            job.start();
+                        }
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(i);
+                        }
+                    }
+                }
+                if !status.message.is_empty() {
+                    ui.weak(&status.message);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            self.jobs.remove(i);
+        }
+    }
+}
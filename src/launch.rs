@@ -0,0 +1,245 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[cfg(unix)]
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "windows")]
+pub fn is_executable(path: &Path) -> bool {
+    const EXTENSIONS: &[&str] = &["exe", "com", "bat", "cmd", "ps1", "msi", "vbs"];
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| EXTENSIONS.contains(&e.as_str()))
+}
+
+// Text files that can be opened in an editor instead of being run: anything
+// starting with a shebang or using a well-known script extension
+pub fn is_script(path: &Path) -> bool {
+    const EXTENSIONS: &[&str] = &[
+        "sh", "bash", "zsh", "fish", "py", "pl", "rb", "js", "lua", "php", "bat", "cmd", "ps1",
+        "vbs",
+    ];
+    let by_extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| EXTENSIONS.contains(&e.as_str()));
+    by_extension || starts_with_shebang(path)
+}
+
+fn starts_with_shebang(path: &Path) -> bool {
+    let mut magic = [0u8; 2];
+    fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_ok()
+        && &magic == b"#!"
+}
+
+// Where the file was downloaded from, if the browser tagged it. Browsers set
+// `user.xdg.origin.url` on Linux, macOS quarantines downloads, and Windows
+// records a Zone.Identifier stream.
+pub fn download_origin(path: &Path) -> Option<String> {
+    #[cfg(unix)]
+    {
+        if let Ok(Some(url)) = xattr::get(path, "user.xdg.origin.url") {
+            return Some(String::from_utf8_lossy(&url).into_owned());
+        }
+        if let Ok(Some(_)) = xattr::get(path, "com.apple.quarantine") {
+            return Some(String::from("the internet"));
+        }
+        None
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let mut stream = path.as_os_str().to_owned();
+        stream.push(":Zone.Identifier");
+        let zone = fs::read_to_string(stream).ok()?;
+        // Zone 3 is "Internet", 4 is "Restricted sites"
+        let internet = zone
+            .lines()
+            .any(|l| matches!(l.trim(), "ZoneId=3" | "ZoneId=4"));
+        if !internet {
+            return None;
+        }
+        let url = zone
+            .lines()
+            .find_map(|l| l.trim().strip_prefix("HostUrl="))
+            .unwrap_or("the internet");
+        Some(url.to_string())
+    }
+}
+
+pub fn run(path: &Path) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    #[cfg(target_os = "windows")]
+    return shell_execute(path.as_os_str(), None, Some(dir));
+
+    #[cfg(not(target_os = "windows"))]
+    Command::new(path).current_dir(dir).spawn().map(|_| ())
+}
+
+pub fn run_in_terminal(path: &Path) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    // Quoted, so cmd takes characters like & in the name literally; paths
+    // can't contain quotes themselves
+    #[cfg(target_os = "windows")]
+    let result = {
+        let mut parameters = std::ffi::OsString::from("/K \"");
+        parameters.push(path);
+        parameters.push("\"");
+        shell_execute(OsStr::new("cmd.exe"), Some(&parameters), Some(dir))
+    };
+
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open")
+        .args(["-a", "Terminal"])
+        .arg(path)
+        .current_dir(dir)
+        .spawn();
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let result = {
+        // $TERMINAL first, then the Debian alternative and common emulators
+        let mut candidates: Vec<String> = std::env::var("TERMINAL").into_iter().collect();
+        candidates.extend(
+            [
+                "x-terminal-emulator",
+                "gnome-terminal",
+                "konsole",
+                "xfce4-terminal",
+                "xterm",
+            ]
+            .map(String::from),
+        );
+        candidates
+            .iter()
+            .find_map(|terminal| {
+                let mut command = Command::new(terminal);
+                if terminal == "gnome-terminal" {
+                    command.arg("--");
+                } else {
+                    command.arg("-e");
+                }
+                command.arg(path).current_dir(dir).spawn().ok()
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no terminal emulator found"))
+    };
+
+    result.map(|_| ())
+}
+
+// Hands a file or URL to the desktop's default handler, like double-clicking
+// it in Explorer or Finder
+pub fn open_with_system(target: &OsStr) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    return shell_execute(target, None, None);
+
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open").arg(target).spawn();
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let result = Command::new("xdg-open").arg(target).spawn();
+
+    #[cfg(not(target_os = "windows"))]
+    result.map(|_| ())
+}
+
+// Opens a file, folder, URL or program with its default verb, as a double
+// click in Explorer would. Unlike `cmd /C start` nothing reparses the name,
+// so & | ^ in it are just characters.
+#[cfg(target_os = "windows")]
+fn shell_execute(file: &OsStr, parameters: Option<&OsStr>, dir: Option<&Path>) -> io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "shell32")]
+    extern "system" {
+        fn ShellExecuteW(
+            window: isize,
+            operation: *const u16,
+            file: *const u16,
+            parameters: *const u16,
+            directory: *const u16,
+            show: i32,
+        ) -> isize;
+    }
+    const SW_SHOWNORMAL: i32 = 1;
+
+    let wide = |text: &OsStr| -> Vec<u16> { text.encode_wide().chain(Some(0)).collect() };
+    let file = wide(file);
+    let parameters = parameters.map(wide);
+    let dir = dir.map(|d| wide(d.as_os_str()));
+    let pointer = |text: &Option<Vec<u16>>| text.as_ref().map_or(std::ptr::null(), |t| t.as_ptr());
+    let result = unsafe {
+        ShellExecuteW(
+            0,
+            std::ptr::null(),
+            file.as_ptr(),
+            pointer(&parameters),
+            pointer(&dir),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Anything above 32 is success
+    if result > 32 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+pub fn open_in_editor(path: &Path) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let result = Command::new("notepad").arg(path).spawn();
+
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open").arg("-t").arg(path).spawn();
+
+    // xdg-open picks the editor associated with text/x-script types and never
+    // executes the file
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let result = Command::new("xdg-open").arg(path).spawn();
+
+    result.map(|_| ())
+}
+
+// Opens a new message in the mail client with the files attached where the
+// platform allows it. Plain mailto: links can't carry attachments, so on
+// Windows the paths are listed in the body instead.
+pub fn send_by_email(paths: &[PathBuf]) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        let body: Vec<String> = paths
+            .iter()
+            .map(|p| crate::uri::to_file_uri(p).replace('%', "%25"))
+            .collect();
+        let mailto = format!("mailto:?body={}", body.join("%0D%0A"));
+        return shell_execute(OsStr::new(&mailto), None, None);
+    }
+
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open")
+        .args(["-a", "Mail"])
+        .args(paths)
+        .spawn();
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let result = {
+        let mut command = Command::new("xdg-email");
+        for path in paths {
+            command.arg("--attach").arg(path);
+        }
+        command.spawn()
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    result.map(|_| ())
+}
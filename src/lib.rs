@@ -0,0 +1,90 @@
+// A file browser for egui. `FileBrowserWidget` is the whole file manager as
+// a widget other egui applications can embed; the file_manager binary is a
+// thin window around it.
+//
+//     let mut browser = FileBrowserWidget::new()
+//         .on_open(|path| {
+//             println!("open {}", path.display());
+//             true
+//         });
+//     egui::CentralPanel::default().show(ctx, |ui| browser.show(ui));
+
+// Times the rest of the enclosing block for Debug → Profiler. Compiled out
+// without the `profiling` feature.
+macro_rules! profile_scope {
+    ($name:expr) => {
+        #[cfg(feature = "profiling")]
+        let _profile_scope = crate::profiler::Scope::new($name);
+    };
+}
+
+mod acl;
+mod actions;
+mod archive;
+mod badges;
+pub mod bench;
+mod browser;
+mod capabilities;
+mod checksum;
+mod clipboard;
+mod config;
+mod convert;
+mod dates;
+mod dnd;
+mod download;
+mod drives;
+mod fastcopy;
+mod filters;
+mod folder_icons;
+mod gpg;
+mod history;
+mod icon_theme;
+mod iostats;
+mod iso;
+mod jobs;
+mod launch;
+mod links;
+mod listing;
+mod locks;
+pub mod logging;
+mod network;
+mod notify;
+mod ops;
+mod owners;
+pub mod picker;
+mod plugins;
+#[cfg(target_os = "linux")]
+pub mod portal;
+mod processes;
+#[cfg(feature = "profiling")]
+mod profiler;
+mod properties;
+mod queue;
+mod recent;
+pub mod recovery;
+mod repaint;
+mod resume;
+mod scripting;
+mod settings;
+mod share;
+mod shortcuts;
+mod snapshots;
+mod split;
+mod streams;
+mod taskbar;
+mod templates;
+mod thumbnails;
+mod timestamps;
+mod toasts;
+mod trash;
+#[cfg(target_os = "linux")]
+pub mod tray;
+pub mod tui;
+mod udisks;
+mod uri;
+mod user_dirs;
+mod xattrs;
+
+pub(crate) use browser::unique_file_name;
+pub use browser::FileBrowserWidget;
+pub(crate) use file_manager_core::model::format_file_size;
@@ -0,0 +1,146 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LinkKind {
+    Symbolic,
+    Hard,
+    // Explorer .lnk shortcut, created through the WScript.Shell COM object
+    #[cfg(target_os = "windows")]
+    Shortcut,
+}
+
+impl LinkKind {
+    pub fn all() -> &'static [LinkKind] {
+        &[
+            LinkKind::Symbolic,
+            LinkKind::Hard,
+            #[cfg(target_os = "windows")]
+            LinkKind::Shortcut,
+        ]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LinkKind::Symbolic => "Symbolic link",
+            LinkKind::Hard => "Hard link",
+            #[cfg(target_os = "windows")]
+            LinkKind::Shortcut => "Shortcut (.lnk)",
+        }
+    }
+
+    // Hard links point at the inode itself, so there's no target path to
+    // make relative
+    pub fn supports_relative(self) -> bool {
+        self == LinkKind::Symbolic
+    }
+
+    pub fn link_name(self, target: &Path) -> String {
+        let name = target
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("link"));
+        match self {
+            #[cfg(target_os = "windows")]
+            LinkKind::Shortcut => format!("{}.lnk", name),
+            _ => name,
+        }
+    }
+}
+
+pub fn create_link(target: &Path, link: &Path, kind: LinkKind, relative: bool) -> io::Result<()> {
+    match kind {
+        LinkKind::Symbolic => {
+            let link_target = match link.parent() {
+                Some(dir) if relative => relative_path(dir, target),
+                _ => target.to_path_buf(),
+            };
+            symlink(&link_target, target.is_dir(), link)
+        }
+        LinkKind::Hard => {
+            if target.is_dir() {
+                return Err(io::Error::other(
+                    "hard links to directories are not supported",
+                ));
+            }
+            std::fs::hard_link(target, link)
+        }
+        #[cfg(target_os = "windows")]
+        LinkKind::Shortcut => create_shortcut(target, link),
+    }
+}
+
+#[cfg(unix)]
+fn symlink(link_target: &Path, _is_dir: bool, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(link_target, link)
+}
+
+#[cfg(target_os = "windows")]
+fn symlink(link_target: &Path, is_dir: bool, link: &Path) -> io::Result<()> {
+    if is_dir {
+        std::os::windows::fs::symlink_dir(link_target, link)
+    } else {
+        std::os::windows::fs::symlink_file(link_target, link)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn create_shortcut(target: &Path, link: &Path) -> io::Result<()> {
+    let quote = |p: &Path| p.display().to_string().replace('\'', "''");
+    let script = format!(
+        "$s = (New-Object -ComObject WScript.Shell).CreateShortcut('{}'); $s.TargetPath = '{}'; $s.Save()",
+        quote(link),
+        quote(target)
+    );
+    let status = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other("PowerShell failed to create the shortcut"))
+    }
+}
+
+// Path to `to` as seen from inside `from_dir`, e.g. ../../docs/file.txt
+pub fn relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to_components: Vec<Component> = to.components().collect();
+
+    // Different roots (e.g. another drive letter) can't be expressed relatively
+    if from.first() != to_components.first() {
+        return to.to_path_buf();
+    }
+
+    let common = from
+        .iter()
+        .zip(&to_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to_components[common..] {
+        relative.push(component);
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+// How many names the file has. Editing or shredding it affects them all.
+#[cfg(unix)]
+pub fn hard_link_count(meta: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.nlink())
+}
+
+// std only exposes the link count on Windows through an unstable API
+#[cfg(not(unix))]
+pub fn hard_link_count(_meta: &fs::Metadata) -> Option<u64> {
+    None
+}
@@ -0,0 +1,274 @@
+// Folders are listed on a background thread and handed over in chunks, so
+// huge folders show up progressively. Sizes, dates and badges are then
+// looked up on a pool of threads, because each lookup is a network round
+// trip on NFS and SMB shares. Rows fill in as the results arrive.
+use crate::badges::{self, Badge};
+use crate::folder_icons::{self, FolderIcon};
+use crate::owners::{self, Ownership};
+use crate::repaint::Repaint;
+use crate::shortcuts::{self, Shortcut};
+use file_manager_core::search;
+use file_manager_core::vfs::long_path;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime};
+use walkdir::WalkDir;
+
+// The threads mostly wait on the network rather than use the CPU, so there
+// are more of them than cores
+const THREADS: usize = 16;
+// Entries handed to the UI at a time
+const CHUNK: usize = 2000;
+// Searches hand over what they've found at least this often
+const SEARCH_BATCH: Duration = Duration::from_millis(200);
+
+// What the listing itself says about an entry
+pub struct Listed {
+    pub name: String,
+    // Links to folders count as files until their details arrive
+    pub is_dir: bool,
+}
+
+pub struct Details {
+    // Follows symlinks so links to folders can be entered
+    pub is_dir: bool,
+    // Bytes for files, number of entries for folders
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    // Not every platform and filesystem records these
+    pub created: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub owner: Option<Ownership>,
+    pub badges: Vec<Badge>,
+    pub shortcut: Option<Shortcut>,
+    pub folder_icon: Option<FolderIcon>,
+}
+
+pub enum Loaded {
+    // The next entries, in listing order
+    Entries(Vec<Listed>),
+    // Details for the entry at `index` among all entries sent so far
+    Details(usize, Box<Details>),
+    Failed(String),
+}
+
+fn pool() -> &'static rayon::ThreadPool {
+    static POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(THREADS)
+            .thread_name(|i| format!("metadata-{}", i))
+            .panic_handler(crate::recovery::pool_panicked)
+            .build()
+            .unwrap()
+    })
+}
+
+// The receiver disconnects once everything has been listed and looked up.
+// Dropping it stops the work early.
+pub fn load(dir: PathBuf, repaint: Repaint) -> Receiver<Loaded> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        profile_scope!("read_dir");
+        let entries = match fs::read_dir(long_path(&dir)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                let _ = sender.send(Loaded::Failed(e.to_string()));
+                repaint.request();
+                return;
+            }
+        };
+        let mut sent = 0;
+        let mut chunk = Vec::with_capacity(CHUNK);
+        for entry in entries.flatten() {
+            chunk.push(listed(&entry));
+            if chunk.len() == CHUNK && !send_chunk(&dir, &mut chunk, &mut sent, &sender, &repaint) {
+                return;
+            }
+        }
+        send_chunk(&dir, &mut chunk, &mut sent, &sender, &repaint);
+    });
+    receiver
+}
+
+fn listed(entry: &fs::DirEntry) -> Listed {
+    Listed {
+        name: entry.file_name().to_string_lossy().into_owned(),
+        is_dir: entry.file_type().is_ok_and(|t| t.is_dir()),
+    }
+}
+
+// The two halves of `load` done in one go rather than streamed, so each can
+// be timed on its own; for --bench
+pub fn read(dir: &Path) -> std::io::Result<Vec<Listed>> {
+    Ok(fs::read_dir(long_path(dir))?
+        .flatten()
+        .map(|entry| listed(&entry))
+        .collect())
+}
+
+pub fn resolve(dir: &Path, listed: &[Listed]) -> Vec<Details> {
+    pool().install(|| {
+        listed
+            .par_iter()
+            .map(|l| details(&dir.join(&l.name)))
+            .collect()
+    })
+}
+
+// Items from anywhere rather than a folder's contents, such as recently used
+// files, named by their whole path. `gather` runs on the background thread.
+pub fn paths(
+    gather: impl FnOnce() -> Vec<PathBuf> + Send + 'static,
+    repaint: Repaint,
+) -> Receiver<Loaded> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut chunk: Vec<Listed> = gather()
+            .into_iter()
+            .map(|path| Listed {
+                is_dir: path.is_dir(),
+                name: path.to_string_lossy().into_owned(),
+            })
+            .collect();
+        // Joining a whole path onto the empty one leaves it as it is
+        send_chunk(Path::new(""), &mut chunk, &mut 0, &sender, &repaint);
+    });
+    receiver
+}
+
+// Where to look for what
+pub struct Search {
+    pub roots: Vec<PathBuf>,
+    // Results under it are named by their path relative to it, others by
+    // their full path
+    pub base: PathBuf,
+    pub patterns: String,
+    // Folders not to look into, by name
+    pub exclude: String,
+    // Paths to skip, see `search::path_excluded`
+    pub skip: String,
+    // Just the roots' own entries, not what's inside their folders
+    pub shallow: bool,
+    // Don't cross into other drives mounted below a root
+    pub one_filesystem: bool,
+}
+
+// Like `load`, but for everything the search finds. Links to folders aren't
+// followed, as they could loop.
+pub fn search(search: Search, repaint: Repaint) -> Receiver<Loaded> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut sent = 0;
+        let mut chunk = Vec::new();
+        let mut last_sent = Instant::now();
+        // Roots can be inside one another, such as nested bookmarks
+        let mut found = HashSet::new();
+        for root in &search.roots {
+            let walk = WalkDir::new(root)
+                .min_depth(1)
+                .max_depth(if search.shallow { 1 } else { usize::MAX })
+                .same_file_system(search.one_filesystem)
+                .into_iter()
+                .filter_entry(|e| {
+                    let is_dir = e.file_type().is_dir();
+                    let name = e.file_name().to_string_lossy();
+                    let skipped = (is_dir && search::matches_any(&search.exclude, &name, false))
+                        || search::path_excluded(&search.skip, e.path(), is_dir);
+                    !skipped
+                });
+            for entry in walk.flatten() {
+                let name = entry.file_name().to_string_lossy();
+                if !search::matches_any(&search.patterns, &name, false)
+                    || !found.insert(entry.path().to_path_buf())
+                {
+                    continue;
+                }
+                let name = entry
+                    .path()
+                    .strip_prefix(&search.base)
+                    .unwrap_or(entry.path());
+                chunk.push(Listed {
+                    name: name.to_string_lossy().into_owned(),
+                    is_dir: entry.file_type().is_dir(),
+                });
+                if chunk.len() == CHUNK || last_sent.elapsed() >= SEARCH_BATCH {
+                    if !send_chunk(&search.base, &mut chunk, &mut sent, &sender, &repaint) {
+                        return;
+                    }
+                    last_sent = Instant::now();
+                }
+            }
+        }
+        send_chunk(&search.base, &mut chunk, &mut sent, &sender, &repaint);
+    });
+    receiver
+}
+
+// Returns false once nobody is listening any more
+fn send_chunk(
+    dir: &Path,
+    chunk: &mut Vec<Listed>,
+    sent: &mut usize,
+    sender: &Sender<Loaded>,
+    repaint: &Repaint,
+) -> bool {
+    if chunk.is_empty() {
+        return true;
+    }
+    let paths: Vec<PathBuf> = chunk.iter().map(|l| dir.join(&l.name)).collect();
+    if sender.send(Loaded::Entries(std::mem::take(chunk))).is_err() {
+        return false;
+    }
+    repaint.request();
+    let first = *sent;
+    *sent += paths.len();
+    let sender = sender.clone();
+    let repaint = repaint.clone();
+    pool().spawn(move || {
+        let _ = paths
+            .par_iter()
+            .enumerate()
+            .try_for_each_with(sender, |sender, (i, path)| {
+                let result = sender.send(Loaded::Details(first + i, Box::new(details(path))));
+                repaint.request();
+                result.map_err(|_| ())
+            });
+    });
+    true
+}
+
+fn details(path: &Path) -> Details {
+    profile_scope!("entry details");
+    let path = &*long_path(path);
+    let meta = fs::metadata(path).or_else(|_| fs::symlink_metadata(path));
+    let is_dir = meta.as_ref().is_ok_and(|m| m.is_dir());
+    Details {
+        is_dir,
+        size: match &meta {
+            Ok(_) if is_dir => fs::read_dir(path)
+                .map(|entries| entries.count())
+                .unwrap_or(0) as u64,
+            Ok(m) => m.len(),
+            Err(_) => 0,
+        },
+        modified: meta.as_ref().ok().and_then(|m| m.modified().ok()),
+        created: meta.as_ref().ok().and_then(|m| m.created().ok()),
+        accessed: meta.as_ref().ok().and_then(|m| m.accessed().ok()),
+        owner: meta.as_ref().ok().and_then(owners::ownership),
+        badges: meta
+            .as_ref()
+            .map(|m| badges::badges(path, m))
+            .unwrap_or_default(),
+        shortcut: if is_dir { None } else { shortcuts::read(path) },
+        folder_icon: if is_dir {
+            folder_icons::read(path)
+        } else {
+            None
+        },
+    }
+}
@@ -0,0 +1,153 @@
+// Which programs have a file open, so a "file in use" failure on Windows
+// can say what to close. Asks the Restart Manager, which Explorer's own
+// "file in use" dialog relies on. Other systems don't refuse to delete or
+// move open files, so nothing is looked up there.
+use std::io;
+use std::path::Path;
+
+pub struct Holder {
+    pub pid: u32,
+    pub name: String,
+}
+
+impl Holder {
+    pub fn describe(&self) -> String {
+        format!("{} (process {})", self.name, self.pid)
+    }
+}
+
+// Sharing and lock violations, the errors another program's handle causes
+pub fn is_lock_error(error: &io::Error) -> bool {
+    cfg!(windows) && matches!(error.raw_os_error(), Some(32 | 33))
+}
+
+// The error, followed by what holds the file open when that's known
+pub fn describe_error(path: &Path, error: &io::Error) -> String {
+    let holders = if is_lock_error(error) {
+        holders(path)
+    } else {
+        Vec::new()
+    };
+    if holders.is_empty() {
+        return error.to_string();
+    }
+    let names: Vec<String> = holders.iter().map(Holder::describe).collect();
+    format!("{}. In use by {}", error, names.join(", "))
+}
+
+#[cfg(not(windows))]
+pub fn holders(_path: &Path) -> Vec<Holder> {
+    Vec::new()
+}
+
+#[cfg(windows)]
+pub fn holders(path: &Path) -> Vec<Holder> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[repr(C)]
+    struct UniqueProcess {
+        process_id: u32,
+        // FILETIME
+        start_time: [u32; 2],
+    }
+
+    #[repr(C)]
+    struct ProcessInfo {
+        process: UniqueProcess,
+        app_name: [u16; 256],
+        service_short_name: [u16; 64],
+        application_type: u32,
+        app_status: u32,
+        ts_session_id: u32,
+        restartable: i32,
+    }
+
+    #[link(name = "rstrtmgr")]
+    extern "system" {
+        fn RmStartSession(session: *mut u32, flags: u32, key: *mut u16) -> u32;
+        fn RmRegisterResources(
+            session: u32,
+            file_count: u32,
+            files: *const *const u16,
+            app_count: u32,
+            apps: *const UniqueProcess,
+            service_count: u32,
+            services: *const *const u16,
+        ) -> u32;
+        fn RmGetList(
+            session: u32,
+            needed: *mut u32,
+            count: *mut u32,
+            info: *mut ProcessInfo,
+            reasons: *mut u32,
+        ) -> u32;
+        fn RmEndSession(session: u32) -> u32;
+    }
+    const ERROR_SUCCESS: u32 = 0;
+    const ERROR_MORE_DATA: u32 = 234;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut session = 0;
+    let mut key = [0u16; 33];
+    if unsafe { RmStartSession(&mut session, 0, key.as_mut_ptr()) } != ERROR_SUCCESS {
+        return Vec::new();
+    }
+    let files = [wide.as_ptr()];
+    let mut infos: Vec<ProcessInfo> = Vec::new();
+    let mut listed = false;
+    let registered = unsafe {
+        RmRegisterResources(
+            session,
+            1,
+            files.as_ptr(),
+            0,
+            std::ptr::null(),
+            0,
+            std::ptr::null(),
+        )
+    };
+    if registered == ERROR_SUCCESS {
+        // The first call says how many there are; more can appear in between
+        for _ in 0..3 {
+            let mut needed = 0;
+            let mut count = infos.len() as u32;
+            let mut reasons = 0;
+            let result = unsafe {
+                RmGetList(
+                    session,
+                    &mut needed,
+                    &mut count,
+                    infos.as_mut_ptr(),
+                    &mut reasons,
+                )
+            };
+            match result {
+                ERROR_SUCCESS => {
+                    infos.truncate(count as usize);
+                    listed = true;
+                    break;
+                }
+                ERROR_MORE_DATA => {
+                    infos.clear();
+                    infos.extend((0..needed + 2).map(|_| unsafe { std::mem::zeroed() }));
+                }
+                _ => break,
+            }
+        }
+    }
+    unsafe { RmEndSession(session) };
+    if !listed {
+        return Vec::new();
+    }
+
+    infos
+        .iter()
+        .map(|info| {
+            let end = info.app_name.iter().position(|&c| c == 0).unwrap_or(256);
+            Holder {
+                pid: info.process.process_id,
+                name: String::from_utf16_lossy(&info.app_name[..end]),
+            }
+        })
+        .collect()
+}
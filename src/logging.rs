@@ -0,0 +1,201 @@
+// Diagnostics go through `tracing`: to stderr, to a daily log file in the
+// data directory (the last week is kept), and to an in-memory buffer behind
+// the Logs window
+use eframe::egui;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+// How many records the Logs window can scroll back through
+const KEPT: usize = 2000;
+
+#[derive(Clone)]
+pub struct LogRecord {
+    pub time: chrono::DateTime<chrono::Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+fn recent() -> &'static Mutex<VecDeque<LogRecord>> {
+    static RECENT: OnceLock<Mutex<VecDeque<LogRecord>>> = OnceLock::new();
+    RECENT.get_or_init(|| Mutex::new(VecDeque::with_capacity(KEPT)))
+}
+
+pub fn log_dir() -> Option<PathBuf> {
+    crate::user_dirs::data_dir().map(|d| d.join("logs"))
+}
+
+// Installs the global subscriber. Keep the guard alive until exit so the
+// file writer gets flushed. The terminal interface passes `to_stderr: false`
+// since anything printed would garble its screen.
+pub fn init(to_stderr: bool) -> Option<WorkerGuard> {
+    let (file_layer, guard) = match log_dir().map(|dir| {
+        tracing_appender::rolling::Builder::new()
+            .rotation(tracing_appender::rolling::Rotation::DAILY)
+            .filename_prefix("file_manager")
+            .filename_suffix("log")
+            .max_log_files(7)
+            .build(dir)
+    }) {
+        Some(Ok(appender)) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer);
+            (Some(layer), Some(guard))
+        }
+        Some(Err(e)) => {
+            eprintln!("Failed to open the log file: {}", e);
+            (None, None)
+        }
+        None => (None, None),
+    };
+    let stderr_layer =
+        to_stderr.then(|| tracing_subscriber::fmt::layer().with_writer(std::io::stderr));
+
+    let result = tracing_subscriber::registry()
+        // Debug messages only from this crate; dependencies are chatty
+        .with(
+            MemoryLayer.with_filter(
+                Targets::new()
+                    .with_target("file_manager", Level::DEBUG)
+                    .with_default(Level::INFO),
+            ),
+        )
+        .with(file_layer.with_filter(LevelFilter::INFO))
+        .with(stderr_layer.with_filter(LevelFilter::INFO))
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("Logging was already set up: {}", e);
+    }
+    guard
+}
+
+struct MemoryLayer;
+
+impl<S: Subscriber> Layer<S> for MemoryLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+        let mut recent = recent().lock().unwrap();
+        if recent.len() == KEPT {
+            recent.pop_front();
+        }
+        recent.push_back(LogRecord {
+            time: chrono::Local::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message: visitor.0,
+        });
+    }
+}
+
+// The message first, then any other fields as `name=value`
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.0);
+            let _ = write!(self.0, "{:?}{}", value, fields);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+pub struct LogsWindow {
+    pub open: bool,
+    // Least severe level shown
+    level: Level,
+    search: String,
+}
+
+impl Default for LogsWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            level: Level::INFO,
+            search: String::new(),
+        }
+    }
+}
+
+impl LogsWindow {
+    // `salt` is the id of the browser showing it
+    pub fn show(&mut self, ctx: &egui::Context, salt: egui::Id) {
+        let mut open = self.open;
+        egui::Window::new("Logs")
+            .id(salt.with("logs"))
+            .open(&mut open)
+            .default_size([600.0, 300.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("log_level")
+                        .selected_text(self.level.as_str())
+                        .show_ui(ui, |ui| {
+                            for level in [Level::ERROR, Level::WARN, Level::INFO, Level::DEBUG] {
+                                ui.selectable_value(&mut self.level, level, level.as_str());
+                            }
+                        });
+                    ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("Filter"));
+                    if ui.button("Clear").clicked() {
+                        recent().lock().unwrap().clear();
+                    }
+                    if let Some(dir) = log_dir().filter(|d| d.is_dir()) {
+                        if ui.button("Open Log Folder").clicked() {
+                            if let Err(e) = crate::launch::open_with_system(dir.as_os_str()) {
+                                tracing::error!("Failed to open the log folder: {}", e);
+                            }
+                        }
+                    }
+                });
+                ui.separator();
+
+                let search = self.search.to_lowercase();
+                let records: Vec<LogRecord> = recent()
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    // Level ordering is by verbosity: ERROR < WARN < INFO
+                    .filter(|r| r.level <= self.level)
+                    .filter(|r| search.is_empty() || r.message.to_lowercase().contains(&search))
+                    .cloned()
+                    .collect();
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::both()
+                    .stick_to_bottom(true)
+                    .auto_shrink([false, false])
+                    .show_rows(ui, row_height, records.len(), |ui, range| {
+                        for record in &records[range] {
+                            let color = match record.level {
+                                Level::ERROR => ui.visuals().error_fg_color,
+                                Level::WARN => ui.visuals().warn_fg_color,
+                                _ => ui.visuals().text_color(),
+                            };
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "{} {:5} {}: {}",
+                                    record.time.format("%H:%M:%S"),
+                                    record.level,
+                                    record.target,
+                                    record.message
+                                ))
+                                .monospace()
+                                .color(color),
+                            );
+                        }
+                    });
+            });
+        self.open = open;
+    }
+}
@@ -1,212 +1,182 @@
 use eframe::egui;
-use std::fs;
+use file_manager::{logging, picker, recovery, FileBrowserWidget};
 use std::path::PathBuf;
-use std::process::Command;
 
-struct FileManager {
-    current_dir: PathBuf,
-    entries: Vec<FileEntry>,
-}
-
-struct FileEntry {
-    name: String,
-    is_dir: bool,
-    size: u64,
-    modified: String,
+struct App {
+    browser: FileBrowserWidget,
+    #[cfg(target_os = "linux")]
+    tray: TrayIcon,
 }
 
-impl Default for FileManager {
-    fn default() -> Self {
-        Self {
-            current_dir: std::env::current_dir().unwrap(),
-            entries: Vec::new(),
-        }
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none())
+            .show(ctx, |ui| self.browser.show(ui));
+        #[cfg(target_os = "linux")]
+        self.tray.update(ctx, &mut self.browser);
     }
 }
 
-impl FileManager {
-    fn read_dir(&mut self) {
-        self.entries.clear();
-        if let Ok(entries) = fs::read_dir(&self.current_dir) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let file_type = entry.file_type().unwrap();
-                    let name = entry.file_name().into_string().unwrap();
-                    self.entries.push(FileEntry {
-                        name,
-                        is_dir: file_type.is_dir(),
-                        size: if file_type.is_dir() {
-                            fs::read_dir(entry.path())
-                                .map(|entries| entries.count())
-                                .unwrap_or(0) as u64
-                        } else {
-                            entry.metadata().map(|m| m.len()).unwrap_or(0)
-                        },
-                        modified: entry
-                            .metadata()
-                            .and_then(|m| m.modified())
-                            .map(|t| {
-                                let datetime: chrono::DateTime<chrono::Local> = t.into();
-                                let now = chrono::Local::now();
-                                let today = now.date_naive();
-                                let yesterday = today.pred_opt();
+// The tray icon while Settings has it on, see tray.rs
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct TrayIcon {
+    tray: Option<file_manager::tray::Tray>,
+    // The setting the tray was last started or stopped for, so a desktop
+    // without a tray isn't asked again every frame
+    enabled: bool,
+    // Folders visited most recently first, for the tray menu
+    recent: Vec<PathBuf>,
+    // Quit from the tray, so the close isn't turned into minimising
+    quitting: bool,
+}
 
-                                if datetime.date_naive() == today {
-                                    format!("Today at {}", datetime.format("%H:%M"))
-                                } else if Some(datetime.date_naive()) == yesterday {
-                                    format!("Yesterday at {}", datetime.format("%H:%M"))
-                                } else {
-                                    datetime.format("%d/%m/%Y at %H:%M").to_string()
-                                }
-                            })
-                            .unwrap_or_else(|_| String::from("Unknown")),
-                    });
+#[cfg(target_os = "linux")]
+impl TrayIcon {
+    fn update(&mut self, ctx: &egui::Context, browser: &mut FileBrowserWidget) {
+        use file_manager::tray::{Command, Tray};
+
+        if browser.wants_tray() != self.enabled {
+            self.enabled = browser.wants_tray();
+            self.tray = None;
+            if self.enabled {
+                match Tray::start(ctx) {
+                    Ok(tray) => self.tray = Some(tray),
+                    Err(e) => tracing::warn!("Failed to show the tray icon: {}", e),
                 }
             }
-            self.entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir));
         }
-    }
-
-    fn can_navigate_up(&self) -> bool {
-        self.current_dir.parent().is_some()
-    }
-}
-
-impl eframe::App for FileManager {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                let current_dir_clone = self.current_dir.clone();
-                let mut path_so_far = PathBuf::new();
-                for (i, component) in current_dir_clone.components().enumerate() {
-                    if let Some(component_str) = component.as_os_str().to_str() {
-                        if i > 0 && i != 1 {
-                            ui.label("/");
-                        }
-                        path_so_far.push(component_str);
-                        let btn = ui.selectable_label(false, component_str);
-                        if btn.clicked() {
-                            self.current_dir = path_so_far.clone();
-                            self.read_dir();
-                        }
-                    }
+        let Some(tray) = &self.tray else {
+            return;
+        };
+        let current = browser.current_dir();
+        if self.recent.first().map(PathBuf::as_path) != Some(current) {
+            self.recent.retain(|dir| dir != current);
+            self.recent.insert(0, current.to_path_buf());
+            self.recent.truncate(10);
+        }
+        tray.set_places(browser.bookmarks(), &self.recent);
+
+        let restore = || {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        };
+        while let Some(command) = tray.try_recv() {
+            match command {
+                Command::Show => restore(),
+                Command::Open(dir) => {
+                    browser.navigate(dir);
+                    restore();
                 }
-            });
-            ui.separator();
-
-            // Navigate to the parent directory
-            if self.can_navigate_up() && ui.button("Up").clicked() {
-                if let Some(parent) = self.current_dir.parent() {
-                    self.current_dir = parent.to_path_buf();
-                    self.read_dir();
+                Command::Quit => {
+                    self.quitting = true;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                 }
             }
-
-            egui::ScrollArea::vertical()
-                .auto_shrink([false; 2])
-                .show(ui, |ui| {
-                    egui::Grid::new("file_manager_grid")
-                        .striped(true)
-                        .min_col_width(ui.available_width() / 3.0)
-                        .show(ui, |ui| {
-                            ui.label("Name");
-                            ui.label("Size");
-                            ui.label("Modified");
-                            ui.end_row();
-
-                            let mut clicked_dir: Option<PathBuf> = None;
-
-                            for entry in &self.entries {
-                                let icon = if entry.is_dir { "📁" } else { "📄" };
-                                let label = format!("{} {}", icon, entry.name);
-                                let nme_lbl = ui.selectable_label(false, label);
-
-                                if nme_lbl.clicked() {
-                                    if entry.is_dir {
-                                        clicked_dir = Some(self.current_dir.join(&entry.name));
-                                    }
-                                }
-
-                                if nme_lbl.double_clicked() {
-                                    if !entry.is_dir {
-                                        println!("Opening file: {}", entry.name);
-                                        let result = open_file(&self.current_dir, &entry.name);
-                                        if let Err(e) = result {
-                                            eprintln!("Failed to open file: {}", e);
-                                        }
-                                    }
-                                }
-
-                                // File or directory size
-                                if entry.is_dir {
-                                    ui.label(format!("{} items", entry.size));
-                                } else {
-                                    ui.label(format!("{}", format_file_size(entry.size)));
-                                }
-
-                                // Modified date
-                                ui.label(&entry.modified);
-                                ui.end_row();
-                            }
-
-                            if let Some(new_dir) = clicked_dir {
-                                self.current_dir = new_dir;
-                                self.read_dir();
-                            }
-                        });
-                });
-        });
-
-        if self.entries.is_empty() {
-            self.read_dir();
+        }
+        // Stays running in the tray
+        if ctx.input(|i| i.viewport().close_requested()) && !self.quitting {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
         }
     }
 }
 
-fn format_file_size(size: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-    const TB: u64 = GB * 1024;
-
-    if size < KB {
-        format!("{} bytes", size)
-    } else if size < MB {
-        format!("{:.2} KiB", size as f64 / KB as f64)
-    } else if size < GB {
-        format!("{:.2} MiB", size as f64 / MB as f64)
-    } else if size < TB {
-        format!("{:.2} GiB", size as f64 / GB as f64)
-    } else {
-        format!("{:.2} TiB", size as f64 / TB as f64)
-    }
+// The folder the app was started from, which may have been deleted since
+fn working_dir() -> Result<PathBuf, String> {
+    std::env::current_dir().map_err(|e| format!("Can't open the current folder: {}", e))
 }
 
-fn open_file(current_dir: &PathBuf, file_name: &str) -> std::io::Result<()> {
-    let file_path = current_dir.join(file_name);
-
-    #[cfg(target_os = "windows")]
-    let result = Command::new("notepad").arg(file_path).spawn();
-
-    #[cfg(target_os = "macos")]
-    let result = Command::new("open").arg("-t").arg(file_path).spawn();
+fn main() -> eframe::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let tui = args.first().is_some_and(|a| a == "--tui");
+    let read_only = args.iter().any(|a| a == "--read-only");
+    let log_guard = logging::init(!tui);
+    recovery::install_panic_hook();
 
     #[cfg(target_os = "linux")]
-    let result = Command::new("xdg-open")
-        .arg(file_path.to_str().unwrap())
-        .spawn();
+    if args.first().is_some_and(|a| a == "--portal") {
+        if let Err(e) = file_manager::portal::serve() {
+            tracing::error!("Failed to start the file chooser portal: {}", e);
+            drop(log_guard);
+            std::process::exit(1);
+        }
+    }
 
-    result.map(|_| ())
-}
+    // Times the listing pipeline, for catching slowdowns; see bench.rs
+    if let Some(i) = args.iter().position(|a| a == "--bench") {
+        // Other options may come between --bench and the folder
+        let root = match args[i + 1..].iter().find(|a| !a.starts_with("--")) {
+            Some(root) => Ok(PathBuf::from(root)),
+            None => working_dir(),
+        };
+        if let Err(e) = root.and_then(|root| file_manager::bench::run(&root)) {
+            eprintln!("{}", e);
+            drop(log_guard);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
-fn main() -> eframe::Result {
+    if tui {
+        let start = match args.iter().skip(1).find(|a| !a.starts_with("--")) {
+            Some(start) => Ok(PathBuf::from(start)),
+            None => working_dir(),
+        };
+        let result = start
+            .and_then(|start| file_manager::tui::run(start, read_only).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            tracing::error!("Terminal interface failed: {}", e);
+            drop(log_guard);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let picker = picker::Picker::from_args(&args);
+    // A folder given on the command line, otherwise where Settings says. A
+    // file, or a path after --select, is opened in its folder and selected.
+    let select = args
+        .iter()
+        .position(|a| a == "--select")
+        .and_then(|i| args.get(i + 1))
+        .map(|a| std::path::absolute(a).unwrap_or_else(|_| PathBuf::from(a)));
+    let start = args
+        .iter()
+        .enumerate()
+        .find(|(i, a)| !a.starts_with("--") && (*i == 0 || args[i - 1] != "--select"))
+        .map(|(_, a)| std::path::absolute(a).unwrap_or_else(|_| PathBuf::from(a)));
+    let title = match &picker {
+        Some(picker) if !picker.title.is_empty() => picker.title.clone(),
+        _ => String::from("File Manager"),
+    };
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([600.0, 400.0]),
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([600.0, 400.0])
+            .with_title(&title),
         ..Default::default()
     };
     eframe::run_native(
-        "File Manager",
+        &title,
         options,
-        Box::new(|_cc| Ok(Box::<FileManager>::default())),
+        Box::new(move |_cc| {
+            let mut browser = FileBrowserWidget::new();
+            browser = match (picker, select, start) {
+                (Some(picker), _, _) => browser.with_picker(picker),
+                (None, Some(path), _) => browser.with_revealed(path),
+                (None, None, Some(dir)) if dir.is_dir() => browser.with_directory(dir),
+                (None, None, Some(path)) => browser.with_revealed(path),
+                (None, None, None) => browser.with_startup(),
+            };
+            if read_only {
+                browser = browser.with_read_only();
+            }
+            Ok(Box::new(App {
+                browser,
+                #[cfg(target_os = "linux")]
+                tray: TrayIcon::default(),
+            }))
+        }),
     )
 }
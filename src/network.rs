@@ -0,0 +1,163 @@
+// Finds file sharing services on the local network through mDNS / DNS-SD
+// (what Avahi and Bonjour advertise), so shares can be opened without
+// knowing host names
+use mdns_sd::{Receiver, ServiceDaemon, ServiceEvent};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ServiceKind {
+    Smb,
+    Sftp,
+    WebDav,
+    WebDavSecure,
+    Afp,
+}
+
+impl ServiceKind {
+    const ALL: [ServiceKind; 5] = [
+        ServiceKind::Smb,
+        ServiceKind::Sftp,
+        ServiceKind::WebDav,
+        ServiceKind::WebDavSecure,
+        ServiceKind::Afp,
+    ];
+
+    fn service_type(self) -> &'static str {
+        match self {
+            ServiceKind::Smb => "_smb._tcp.local.",
+            ServiceKind::Sftp => "_sftp-ssh._tcp.local.",
+            ServiceKind::WebDav => "_webdav._tcp.local.",
+            ServiceKind::WebDavSecure => "_webdavs._tcp.local.",
+            ServiceKind::Afp => "_afpovertcp._tcp.local.",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ServiceKind::Smb => "Windows share (SMB)",
+            ServiceKind::Sftp => "SFTP",
+            ServiceKind::WebDav => "WebDAV",
+            ServiceKind::WebDavSecure => "WebDAV (HTTPS)",
+            ServiceKind::Afp => "Apple Filing Protocol",
+        }
+    }
+
+    // URL schemes understood by GVFS, KIO and Finder
+    fn scheme(self) -> &'static str {
+        match self {
+            ServiceKind::Smb => "smb",
+            ServiceKind::Sftp => "sftp",
+            ServiceKind::WebDav => "dav",
+            ServiceKind::WebDavSecure => "davs",
+            ServiceKind::Afp => "afp",
+        }
+    }
+
+    fn default_port(self) -> u16 {
+        match self {
+            ServiceKind::Smb => 445,
+            ServiceKind::Sftp => 22,
+            ServiceKind::WebDav => 80,
+            ServiceKind::WebDavSecure => 443,
+            ServiceKind::Afp => 548,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Service {
+    pub kind: ServiceKind,
+    // The advertised instance name, e.g. "Living Room NAS"
+    pub name: String,
+    pub host: String,
+    port: u16,
+    // WebDAV services can advertise a path in their TXT record
+    path: String,
+}
+
+impl Service {
+    pub fn url(&self) -> String {
+        let port = if self.port == self.kind.default_port() {
+            String::new()
+        } else {
+            format!(":{}", self.port)
+        };
+        format!(
+            "{}://{}{}/{}",
+            self.kind.scheme(),
+            self.host,
+            port,
+            self.path.trim_start_matches('/')
+        )
+    }
+}
+
+pub struct Discovery {
+    daemon: ServiceDaemon,
+    receivers: Vec<(ServiceKind, Receiver<ServiceEvent>)>,
+    // Keyed by the full DNS-SD name so updates replace older entries
+    services: BTreeMap<String, Service>,
+}
+
+impl Discovery {
+    pub fn start() -> Result<Self, String> {
+        let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+        let mut receivers = Vec::new();
+        for kind in ServiceKind::ALL {
+            let receiver = daemon
+                .browse(kind.service_type())
+                .map_err(|e| e.to_string())?;
+            receivers.push((kind, receiver));
+        }
+        Ok(Self {
+            daemon,
+            receivers,
+            services: BTreeMap::new(),
+        })
+    }
+
+    // Takes in whatever the daemon found since the last call
+    pub fn poll(&mut self) {
+        for (kind, receiver) in &self.receivers {
+            while let Ok(event) = receiver.try_recv() {
+                match event {
+                    ServiceEvent::ServiceResolved(info) => {
+                        let fullname = info.get_fullname().to_string();
+                        let name = fullname
+                            .strip_suffix(kind.service_type())
+                            .unwrap_or(&fullname)
+                            .trim_end_matches('.')
+                            .replace("\\032", " ");
+                        let service = Service {
+                            kind: *kind,
+                            name,
+                            host: info.get_hostname().trim_end_matches('.').to_string(),
+                            port: info.get_port(),
+                            path: info.get_property_val_str("path").unwrap_or("").to_string(),
+                        };
+                        self.services.insert(fullname, service);
+                    }
+                    ServiceEvent::ServiceRemoved(_, fullname) => {
+                        self.services.remove(&fullname);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Grouped by host, in name order
+    pub fn hosts(&self) -> BTreeMap<&str, Vec<&Service>> {
+        let mut hosts: BTreeMap<&str, Vec<&Service>> = BTreeMap::new();
+        for service in self.services.values() {
+            hosts.entry(&service.host).or_default().push(service);
+        }
+        hosts
+    }
+}
+
+impl Drop for Discovery {
+    fn drop(&mut self) {
+        let _ = self.daemon.shutdown();
+    }
+}
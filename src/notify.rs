@@ -0,0 +1,129 @@
+// Desktop notifications for jobs that end while nobody is looking at the
+// window. On Linux they go to the notification server over D-Bus and offer
+// "Show in Folder", which brings a window to the job's folder; macOS shows
+// them with osascript, without the button. Windows has none yet.
+use crate::repaint::Repaint;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// Folders whose "Show in Folder" was clicked, waiting for a window to open them
+static SHOW: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+pub fn take_shown() -> Option<PathBuf> {
+    let mut shown = SHOW.lock().unwrap();
+    (!shown.is_empty()).then(|| shown.remove(0))
+}
+
+// Doesn't wait for the desktop; failures are only logged
+pub fn send(summary: String, body: String, folder: Option<PathBuf>, repaint: Repaint) {
+    std::thread::spawn(move || {
+        if let Err(e) = show(&summary, &body, folder, &repaint) {
+            tracing::warn!("Failed to show a notification: {}", e);
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+fn show(
+    summary: &str,
+    body: &str,
+    folder: Option<PathBuf>,
+    repaint: &Repaint,
+) -> Result<(), String> {
+    use std::collections::HashMap;
+    use zbus::message::Type;
+    use zbus::zvariant::Value;
+    use zbus::MatchRule;
+
+    let connection = zbus::blocking::Connection::session().map_err(|e| e.to_string())?;
+    // Listening before sending, so a quick click isn't missed
+    let rule = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .interface("org.freedesktop.Notifications")
+        .map_err(|e| e.to_string())?
+        .build();
+    let signals = zbus::blocking::MessageIterator::for_match_rule(rule, &connection, Some(16))
+        .map_err(|e| e.to_string())?;
+    let actions: &[&str] = match folder {
+        Some(_) => &["default", "Show in Folder"],
+        None => &[],
+    };
+    let reply = connection
+        .call_method(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            Some("org.freedesktop.Notifications"),
+            "Notify",
+            &(
+                "File Manager",
+                0u32,
+                "system-file-manager",
+                summary,
+                body,
+                actions,
+                HashMap::<&str, Value>::new(),
+                -1i32,
+            ),
+        )
+        .map_err(|e| e.to_string())?;
+    let id: u32 = reply.body().deserialize().map_err(|e| e.to_string())?;
+    let Some(folder) = folder else {
+        return Ok(());
+    };
+
+    // Until the notification is clicked, dismissed or expires
+    for message in signals.flatten() {
+        let header = message.header();
+        let body = message.body();
+        let member = header.member().map(|m| m.as_str());
+        let clicked = member == Some("ActionInvoked")
+            && body
+                .deserialize::<(u32, String)>()
+                .is_ok_and(|(n, _)| n == id);
+        let closed = member == Some("NotificationClosed")
+            && body.deserialize::<(u32, u32)>().is_ok_and(|(n, _)| n == id);
+        if clicked {
+            SHOW.lock().unwrap().push(folder);
+            repaint.request();
+            break;
+        }
+        if closed {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn show(
+    summary: &str,
+    body: &str,
+    _folder: Option<PathBuf>,
+    _repaint: &Repaint,
+) -> Result<(), String> {
+    // AppleScript string literals escape \ and "
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let script = format!(
+        "display notification {} with title {}",
+        quote(body),
+        quote(summary)
+    );
+    let status = std::process::Command::new("osascript")
+        .args(["-e", &script])
+        .status()
+        .map_err(|e| e.to_string())?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(format!("osascript exited with {}", status)),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn show(
+    _summary: &str,
+    _body: &str,
+    _folder: Option<PathBuf>,
+    _repaint: &Repaint,
+) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,792 @@
+use crate::checksum;
+use crate::config::TransferOptions;
+use crate::fastcopy;
+use crate::jobs::{JobHandle, ProgressUnit};
+use crate::resume::{self, Checkpoint};
+use file_manager_core::model::format_file_size;
+pub use file_manager_core::ops::plural;
+use file_manager_core::vfs::{display_path, long_path};
+use file_manager_core::{LocalFs, Vfs};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+// Copies a file or folder tree into `destination_dir`, keeping its name and
+// preserving symlinks as links. Returns the path of the new copy.
+pub fn copy_into(source: &Path, destination_dir: &Path) -> io::Result<PathBuf> {
+    file_manager_core::ops::copy_into(&LocalFs, source, destination_dir)
+}
+
+pub fn copy_recursive(source: &Path, destination: &Path) -> io::Result<()> {
+    file_manager_core::ops::copy_recursive(&LocalFs, source, destination)
+}
+
+// Whether two paths are on the same filesystem, where moving is a rename
+pub fn same_device(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        // Same drive letter or share
+        a.components().next() == b.components().next()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TransferMode {
+    Copy,
+    Move,
+}
+
+// A copy or move of several items into one folder, run as a background job
+#[derive(Clone)]
+pub struct Transfer {
+    pub sources: Vec<PathBuf>,
+    pub destination: PathBuf,
+    pub mode: TransferMode,
+    pub options: TransferOptions,
+    // How far it got before being interrupted, when picking it up again
+    pub resume: Option<resume::Progress>,
+}
+
+// Running totals shared by every file of a transfer
+struct Progress<'a> {
+    job: &'a JobHandle,
+    done: u64,
+    total: u64,
+    // Copies whose contents didn't match the source after writing
+    mismatches: Vec<PathBuf>,
+    // Copies that lost some metadata the destination couldn't store
+    incomplete: usize,
+    // The current item may be partly there from an interrupted run
+    resuming: bool,
+}
+
+impl Transfer {
+    pub fn title(&self) -> String {
+        let verb = match self.mode {
+            TransferMode::Copy => "Copying",
+            TransferMode::Move => "Moving",
+        };
+        match self.sources.as_slice() {
+            [source] => format!(
+                "{} {}",
+                verb,
+                source.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            sources => format!("{} {} items", verb, sources.len()),
+        }
+    }
+
+    // Carries on past failing items and reports all of them at the end
+    pub fn run(&self, job: &JobHandle) -> Result<(), String> {
+        // Even adding up sizes reads the disk, so that waits too
+        let mut drives: Vec<&Path> = self.sources.iter().map(PathBuf::as_path).collect();
+        drives.push(&self.destination);
+        let Some(_turn) = crate::queue::wait_turn(&drives, self.options.per_disk, job) else {
+            return Err(String::from("cancelled"));
+        };
+        let mut checkpoint = Checkpoint::start(self);
+        let result = self.run_checkpointed(job, checkpoint.as_mut());
+        if let Some(checkpoint) = checkpoint {
+            checkpoint.remove();
+        }
+        result
+    }
+
+    fn finished(&self, source: &Path) -> bool {
+        self.resume
+            .as_ref()
+            .is_some_and(|r| r.finished.iter().any(|f| f == source))
+    }
+
+    fn run_checkpointed(
+        &self,
+        job: &JobHandle,
+        mut checkpoint: Option<&mut Checkpoint>,
+    ) -> Result<(), String> {
+        let sizes: Vec<u64> = self
+            .sources
+            .iter()
+            .map(|s| match self.finished(s) {
+                true => 0,
+                false => tree_size(s, !self.options.preserve_symlinks),
+            })
+            .collect();
+        self.check_space(&sizes)?;
+        let mut progress = Progress {
+            job,
+            done: 0,
+            total: sizes.iter().sum(),
+            mismatches: Vec::new(),
+            incomplete: 0,
+            resuming: false,
+        };
+        job.set_progress(0, Some(progress.total));
+        let mut errors = Vec::new();
+
+        for source in &self.sources {
+            if job.is_cancelled() {
+                return Err(String::from("cancelled"));
+            }
+            if self.finished(source) {
+                continue;
+            }
+            match self.transfer_one(source, &mut progress, checkpoint.as_deref_mut()) {
+                Ok(()) => {
+                    if let Some(checkpoint) = checkpoint.as_deref_mut() {
+                        checkpoint.progress.finished.push(source.clone());
+                        checkpoint.save();
+                    }
+                }
+                Err(e) => {
+                    job.item_failed(source, source, &e);
+                    errors.push(format!("{}: {}", source.display(), e));
+                }
+            }
+        }
+
+        if job.is_cancelled() {
+            return Err(String::from("cancelled"));
+        }
+        if !progress.mismatches.is_empty() {
+            errors.insert(
+                0,
+                format!(
+                    "{} failed verification: {}",
+                    plural(progress.mismatches.len(), "file"),
+                    summarize(&progress.mismatches)
+                ),
+            );
+        }
+        if progress.incomplete > 0 {
+            job.set_message(format!(
+                "Metadata of {} couldn't be kept, see the log",
+                plural(progress.incomplete, "item")
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    // Refuses up front what won't fit, rather than failing halfway with the
+    // destination full. Moves within a drive take no room, and what an
+    // interrupted run already copied is there.
+    fn check_space(&self, sizes: &[u64]) -> Result<(), String> {
+        let needed: u64 = self
+            .sources
+            .iter()
+            .zip(sizes)
+            .filter(|(source, _)| {
+                self.mode == TransferMode::Copy || !same_device(source, &self.destination)
+            })
+            .map(|(source, size)| {
+                let copied = self
+                    .resume
+                    .as_ref()
+                    .and_then(|r| r.target(source))
+                    .map_or(0, |target| tree_size(target, false));
+                size.saturating_sub(copied)
+            })
+            .sum();
+        let Ok(space) = crate::drives::space(&self.destination) else {
+            return Ok(());
+        };
+        if needed > space.available {
+            return Err(format!(
+                "Not enough space in {}: {} needed, {} free",
+                display_path(&self.destination).display(),
+                format_file_size(needed),
+                format_file_size(space.available)
+            ));
+        }
+        Ok(())
+    }
+
+    fn transfer_one(
+        &self,
+        source: &Path,
+        progress: &mut Progress,
+        checkpoint: Option<&mut Checkpoint>,
+    ) -> io::Result<()> {
+        let name = source
+            .file_name()
+            .ok_or_else(|| io::Error::other("cannot copy a filesystem root"))?;
+        // Where an interrupted run was putting it, if it got that far
+        let recorded = self.resume.as_ref().and_then(|r| r.target(source));
+        progress.resuming = recorded.is_some();
+        let mut target = self.destination.join(name);
+        if let Some(recorded) = recorded {
+            target = recorded.to_path_buf();
+        } else if self.mode == TransferMode::Copy
+            && source.parent() == Some(self.destination.as_path())
+        {
+            // A copy next to its original gets a name of its own
+            target = self.destination.join(file_manager_core::ops::copy_name(
+                &LocalFs,
+                &self.destination,
+                &name.to_string_lossy(),
+                source.is_dir(),
+                &self.options.copy_name,
+                &self.options.numbered_copy_name,
+            ));
+        }
+        let taken = target.file_name().is_some_and(|name| {
+            file_manager_core::ops::name_taken(&LocalFs, &self.destination, &name.to_string_lossy())
+        });
+        if taken && !progress.resuming {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", display_path(&target).display()),
+            ));
+        }
+        if target.starts_with(source) {
+            return Err(io::Error::other("cannot copy a folder into itself"));
+        }
+        // Only once the target is known to be ours: resuming treats whatever
+        // is at a recorded target as a partial copy and writes over it
+        if let Some(checkpoint) = checkpoint {
+            if checkpoint.progress.target(source).is_none() {
+                checkpoint
+                    .progress
+                    .targets
+                    .push((source.to_path_buf(), target.clone()));
+                checkpoint.save();
+            }
+        }
+        let source = &*long_path(source);
+        let destination = long_path(&target).into_owned();
+
+        // A rename could replace a partial copy, so resumed moves copy
+        if self.mode == TransferMode::Move
+            && !progress.resuming
+            && fs::rename(source, &destination).is_ok()
+        {
+            // Same filesystem: nothing was rewritten, so nothing to verify
+            progress.done += tree_size(&destination, !self.options.preserve_symlinks);
+            progress
+                .job
+                .set_progress(progress.done, Some(progress.total));
+            return Ok(());
+        }
+
+        let mismatches_before = progress.mismatches.len();
+        self.copy_tree(source, &destination, progress)?;
+        if self.mode == TransferMode::Move
+            && progress.mismatches.len() == mismatches_before
+            && !progress.job.is_cancelled()
+        {
+            // Only drop the originals once every copy is known to be good
+            if fs::symlink_metadata(source)?.is_dir() {
+                fs::remove_dir_all(source)?;
+            } else {
+                fs::remove_file(source)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn copy_tree(
+        &self,
+        source: &Path,
+        destination: &Path,
+        progress: &mut Progress,
+    ) -> io::Result<()> {
+        if progress.job.is_cancelled() {
+            return Ok(());
+        }
+        let mut meta = fs::symlink_metadata(source)?;
+        if meta.file_type().is_symlink() {
+            let linked = fs::symlink_metadata(destination).is_ok_and(|m| m.is_symlink());
+            if self.options.preserve_symlinks && progress.resuming && linked {
+                return Ok(());
+            }
+            if self.options.preserve_symlinks {
+                match LocalFs
+                    .read_link(source)
+                    .and_then(|target| LocalFs.symlink(&target, destination))
+                {
+                    Ok(()) => return Ok(()),
+                    // e.g. FAT32 and exFAT have no symlinks
+                    Err(e) => {
+                        log_lost(destination, "symbolic link", &e);
+                        progress.incomplete += 1;
+                    }
+                }
+            }
+            meta = fs::metadata(source)?;
+        }
+
+        if meta.is_dir() {
+            if !(progress.resuming && destination.is_dir()) {
+                fs::create_dir(destination)?;
+            }
+            for entry in fs::read_dir(source)? {
+                let entry = entry?;
+                self.copy_tree(
+                    &entry.path(),
+                    &destination.join(entry.file_name()),
+                    progress,
+                )?;
+            }
+            // After the children, since adding them bumps the folder's mtime
+            self.preserve_metadata(source, destination, &meta, progress);
+            Ok(())
+        } else {
+            let from = match progress.resuming {
+                true => resume_point(source, destination, &meta)?,
+                false => 0,
+            };
+            if from > 0 && from == meta.len() {
+                // Finished before the interruption
+                progress.done += from;
+                progress
+                    .job
+                    .set_progress(progress.done, Some(progress.total));
+                self.preserve_metadata(source, destination, &meta, progress);
+                return Ok(());
+            }
+            copy_file(source, destination, from, progress)?;
+            self.preserve_metadata(source, destination, &meta, progress);
+            if self.options.verify && !progress.job.is_cancelled() {
+                progress.job.set_message(format!(
+                    "Verifying {}",
+                    destination
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                ));
+                if checksum::sha256_file(source)? != checksum::sha256_file(destination)? {
+                    progress.mismatches.push(destination.to_path_buf());
+                }
+                progress.job.set_message("");
+            }
+            Ok(())
+        }
+    }
+
+    // Best effort: the data is what matters, so failures here only get logged
+    fn preserve_metadata(
+        &self,
+        source: &Path,
+        destination: &Path,
+        meta: &fs::Metadata,
+        progress: &mut Progress,
+    ) {
+        let mut failed = false;
+
+        if self.options.preserve_xattrs {
+            if let Err(e) = copy_xattrs(source, destination) {
+                log_lost(destination, "extended attributes", &e);
+                failed = true;
+            }
+        }
+        // Before permissions, since a read-only copy can no longer be opened
+        // for writing to set them
+        if self.options.preserve_timestamps {
+            if let Err(e) = set_times(destination, meta) {
+                log_lost(destination, "timestamps", &e);
+                failed = true;
+            }
+        }
+        if self.options.preserve_permissions {
+            if let Err(e) = fs::set_permissions(destination, meta.permissions()) {
+                log_lost(destination, "permissions", &e);
+                failed = true;
+            }
+        }
+        if failed {
+            progress.incomplete += 1;
+        }
+    }
+}
+
+fn log_lost(destination: &Path, what: &str, error: &io::Error) {
+    tracing::error!(
+        "Couldn't keep {} on {}: {}",
+        what,
+        display_path(destination).display(),
+        error
+    );
+}
+
+fn set_times(destination: &Path, meta: &fs::Metadata) -> io::Result<()> {
+    let mut times = fs::FileTimes::new().set_modified(meta.modified()?);
+    if let Ok(accessed) = meta.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    if meta.is_dir() {
+        set_dir_times(destination, times)
+    } else {
+        fs::OpenOptions::new()
+            .write(true)
+            .open(destination)?
+            .set_times(times)
+    }
+}
+
+#[cfg(unix)]
+fn set_dir_times(destination: &Path, times: fs::FileTimes) -> io::Result<()> {
+    fs::File::open(destination)?.set_times(times)
+}
+
+// std can't open folders on Windows without FILE_FLAG_BACKUP_SEMANTICS
+#[cfg(not(unix))]
+fn set_dir_times(_destination: &Path, _times: fs::FileTimes) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_xattrs(source: &Path, destination: &Path) -> io::Result<()> {
+    if !xattr::SUPPORTED_PLATFORM {
+        return Ok(());
+    }
+    for name in xattr::list(source)? {
+        if let Some(value) = xattr::get(source, &name)? {
+            xattr::set(destination, &name, &value)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_xattrs(_source: &Path, _destination: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// How much of a file an interrupted run left can stay: all of it when it
+// matches the source, the start when that matches. Anything else is removed
+// to be copied again.
+fn resume_point(source: &Path, destination: &Path, meta: &fs::Metadata) -> io::Result<u64> {
+    let Ok(copied) = fs::symlink_metadata(destination) else {
+        return Ok(0);
+    };
+    let have = copied.len();
+    // Copies get the source's modification time once they're complete
+    let complete = have == meta.len()
+        && copied
+            .modified()
+            .ok()
+            .is_some_and(|t| Some(t) == meta.modified().ok());
+    let keep = copied.is_file()
+        && have > 0
+        && have <= meta.len()
+        && (complete
+            || checksum::sha256_prefix(source, have)? == checksum::sha256_file(destination)?);
+    if keep {
+        return Ok(have);
+    }
+    fs::remove_file(destination)?;
+    Ok(0)
+}
+
+// Copies from `from` on, keeping what's already in `destination` before it
+fn copy_file(
+    source: &Path,
+    destination: &Path,
+    from: u64,
+    progress: &mut Progress,
+) -> io::Result<()> {
+    let len = fs::metadata(source)?.len();
+    if from == 0 && fastcopy::reflink(source, destination).is_ok() {
+        progress.done += len;
+        progress
+            .job
+            .set_progress(progress.done, Some(progress.total));
+        return Ok(());
+    }
+
+    let mut reader = fs::File::open(source)?;
+    let mut writer = fs::OpenOptions::new()
+        .write(true)
+        .create_new(from == 0)
+        .open(destination)?;
+    // Only the parts of sparse files holding data get written; skipping
+    // over the rest leaves holes in the copy too
+    let ranges = fastcopy::data_ranges(&reader, len).unwrap_or_else(|| vec![(0, len)]);
+    let mut buffer = vec![0; 256 * 1024];
+    let mut position = from;
+    progress.done += from;
+
+    for (start, end) in ranges {
+        if end <= from {
+            continue;
+        }
+        let start = start.max(from);
+        reader.seek(SeekFrom::Start(start))?;
+        writer.seek(SeekFrom::Start(start))?;
+        progress.done += start - position;
+        let mut remaining = end - start;
+        while remaining > 0 {
+            if progress.job.is_cancelled() {
+                drop(writer);
+                // A half-written file is worse than none
+                return fs::remove_file(destination);
+            }
+            let chunk = buffer.len().min(remaining as usize);
+            let read = match reader.read(&mut buffer[..chunk]) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            writer.write_all(&buffer[..read])?;
+            remaining -= read as u64;
+            progress.done += read as u64;
+            progress
+                .job
+                .set_progress(progress.done, Some(progress.total));
+        }
+        position = end;
+    }
+    progress.done += len.saturating_sub(position);
+    // Covers a trailing hole, which no write reached
+    writer.set_len(len)?;
+    // Make sure the data reached the disk before it gets verified
+    writer.sync_all()
+}
+
+// Total size of the regular files below `path`
+pub fn tree_size(path: &Path, follow_links: bool) -> u64 {
+    WalkDir::new(long_path(path))
+        .follow_links(follow_links)
+        .into_iter()
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+fn summarize(paths: &[PathBuf]) -> String {
+    const SHOWN: usize = 3;
+    let names: Vec<String> = paths
+        .iter()
+        .take(SHOWN)
+        .map(|p| display_path(p).display().to_string())
+        .collect();
+    if paths.len() > SHOWN {
+        format!("{} and {} more", names.join(", "), paths.len() - SHOWN)
+    } else {
+        names.join(", ")
+    }
+}
+
+// Deletes files and whole trees for good, deepest entries first. Items that
+// can't be removed are skipped and listed at the end.
+pub fn delete(targets: &[PathBuf], job: &JobHandle) -> Result<(), String> {
+    remove_all(targets, job, false)
+}
+
+// Like delete, but file contents get overwritten before the files are
+// unlinked. Symlinks are removed without touching what they point to.
+pub fn shred(targets: &[PathBuf], job: &JobHandle) -> Result<(), String> {
+    remove_all(targets, job, true)
+}
+
+// Shredding writes random data, then zeros
+const SHRED_PASSES: u64 = 2;
+
+fn remove_all(targets: &[PathBuf], job: &JobHandle, secure: bool) -> Result<(), String> {
+    // Shredding time depends on bytes written, deleting on the item count
+    let total: u64 = if secure {
+        job.set_unit(ProgressUnit::Bytes);
+        targets.iter().map(|t| tree_size(t, false)).sum::<u64>() * SHRED_PASSES
+    } else {
+        job.set_unit(ProgressUnit::Items);
+        targets
+            .iter()
+            .map(|t| WalkDir::new(long_path(t)).into_iter().count() as u64)
+            .sum()
+    };
+    job.set_progress(0, Some(total));
+
+    let mut done = 0;
+    let mut removed = 0;
+    let mut failed: Vec<(PathBuf, io::Error)> = Vec::new();
+    for given in targets {
+        let target = long_path(given);
+        for entry in WalkDir::new(&target).contents_first(true) {
+            if job.is_cancelled() {
+                return Err(String::from("cancelled"));
+            }
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    let path = e.path().unwrap_or(&target).to_path_buf();
+                    let e = io::Error::from(e);
+                    job.item_failed(given, &display_path(&path), &e);
+                    failed.push((path, e));
+                    continue;
+                }
+            };
+            let path = entry.path();
+            job.set_message(display_path(path).display().to_string());
+            let result = if entry.file_type().is_dir() {
+                // A folder that kept some of its contents can't go either;
+                // that's already reported through the contents
+                if failed.iter().any(|(p, _)| p.starts_with(path)) {
+                    continue;
+                }
+                fs::remove_dir(path)
+            } else if secure && entry.file_type().is_file() {
+                overwrite(path, job, &mut done, total).and_then(|()| unlink_hidden(path))
+            } else {
+                fs::remove_file(path)
+            };
+            match result {
+                Ok(()) => {
+                    removed += 1;
+                    if !secure {
+                        done += 1;
+                    }
+                }
+                Err(_) if job.is_cancelled() => return Err(String::from("cancelled")),
+                Err(e) => {
+                    job.item_failed(given, &display_path(path), &e);
+                    failed.push((path.to_path_buf(), e));
+                }
+            }
+            job.set_progress(done, Some(total));
+        }
+    }
+
+    if failed.is_empty() {
+        job.set_message(format!("Deleted {}", plural(removed, "item")));
+        return Ok(());
+    }
+    job.set_message("");
+    let details: Vec<String> = failed
+        .iter()
+        .take(3)
+        .map(|(path, e)| format!("{}: {}", display_path(path).display(), e))
+        .collect();
+    let more = match failed.len() {
+        n if n > 3 => format!(" and {} more", n - 3),
+        _ => String::new(),
+    };
+    Err(format!(
+        "{} couldn't be deleted: {}{}",
+        plural(failed.len(), "item"),
+        details.join("; "),
+        more
+    ))
+}
+
+fn overwrite(path: &Path, job: &JobHandle, done: &mut u64, total: u64) -> io::Result<()> {
+    let len = fs::metadata(path)?.len();
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    let mut buffer = vec![0; 256 * 1024];
+    let mut random = seed();
+
+    for pass in 0..SHRED_PASSES {
+        file.seek(SeekFrom::Start(0))?;
+        let mut remaining = len;
+        while remaining > 0 {
+            if job.is_cancelled() {
+                return Err(io::Error::other("cancelled"));
+            }
+            let chunk = buffer.len().min(remaining as usize);
+            if pass == 0 {
+                for bytes in buffer[..chunk].chunks_mut(8) {
+                    let value = next_random(&mut random).to_le_bytes();
+                    bytes.copy_from_slice(&value[..bytes.len()]);
+                }
+            } else {
+                buffer[..chunk].fill(0);
+            }
+            file.write_all(&buffer[..chunk])?;
+            remaining -= chunk as u64;
+            *done += chunk as u64;
+            job.set_progress(*done, Some(total));
+        }
+        // Each pass has to reach the disk, not just the page cache
+        file.sync_all()?;
+    }
+    file.set_len(0)?;
+    file.sync_all()
+}
+
+// The name is sensitive too, so it's replaced before the final unlink
+fn unlink_hidden(path: &Path) -> io::Result<()> {
+    let mut random = seed();
+    let hidden = path.with_file_name(format!("{:016x}", next_random(&mut random)));
+    match fs::rename(path, &hidden) {
+        Ok(()) => fs::remove_file(hidden),
+        Err(_) => fs::remove_file(path),
+    }
+}
+
+// xorshift64: overwriting needs noise, not cryptographic randomness
+fn seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    nanos | 1
+}
+
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resuming_leaves_existing_destinations_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        let target = dir.path().join("target");
+        fs::create_dir_all(source.join("folder")).unwrap();
+        fs::create_dir(&target).unwrap();
+        fs::write(source.join("report.txt"), "new report").unwrap();
+        fs::write(source.join("folder/inner.txt"), "new").unwrap();
+        // Unrelated items already at the destination
+        fs::write(target.join("report.txt"), "old").unwrap();
+        fs::create_dir(target.join("folder")).unwrap();
+        fs::write(target.join("folder/kept.txt"), "kept").unwrap();
+
+        let transfer = Transfer {
+            sources: vec![source.join("report.txt"), source.join("folder")],
+            destination: target.clone(),
+            mode: TransferMode::Copy,
+            options: TransferOptions::default(),
+            resume: None,
+        };
+        let mut checkpoint = Checkpoint::new(&transfer, dir.path().join("checkpoint.toml"));
+        let job = JobHandle::detached();
+        assert!(transfer
+            .run_checkpointed(&job, Some(&mut checkpoint))
+            .is_err());
+        assert!(checkpoint.progress.targets.is_empty());
+
+        // As if the app had closed partway and the transfer was resumed
+        let resumed = checkpoint.transfer();
+        assert!(resumed.run_checkpointed(&job, None).is_err());
+        assert_eq!(
+            fs::read_to_string(target.join("report.txt")).unwrap(),
+            "old"
+        );
+        assert!(!target.join("folder/inner.txt").exists());
+        assert_eq!(
+            fs::read_to_string(target.join("folder/kept.txt")).unwrap(),
+            "kept"
+        );
+    }
+}
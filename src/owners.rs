@@ -0,0 +1,127 @@
+// Who owns a file. IDs are mapped to names through the local users and
+// groups databases (which includes LDAP and the like through NSS). Network
+// filesystems such as NFS and CIFS pass the server's IDs through, and those
+// may have no local name, so the raw ID is shown instead.
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Ownership {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+#[cfg(unix)]
+pub fn ownership(meta: &fs::Metadata) -> Option<Ownership> {
+    use std::os::unix::fs::MetadataExt;
+    Some(Ownership {
+        uid: meta.uid(),
+        gid: meta.gid(),
+    })
+}
+
+#[cfg(not(unix))]
+pub fn ownership(_meta: &fs::Metadata) -> Option<Ownership> {
+    None
+}
+
+// Lookups can go over the network (LDAP, NIS), so each ID is only asked once
+fn cached(
+    table: &'static OnceLock<Mutex<HashMap<u32, Option<String>>>>,
+    id: u32,
+    lookup: fn(u32) -> Option<String>,
+) -> Option<String> {
+    let mut names = table.get_or_init(Default::default).lock().unwrap();
+    names.entry(id).or_insert_with(|| lookup(id)).clone()
+}
+
+pub fn user_name(uid: u32) -> Option<String> {
+    static USERS: OnceLock<Mutex<HashMap<u32, Option<String>>>> = OnceLock::new();
+    cached(&USERS, uid, lookup_user)
+}
+
+pub fn group_name(gid: u32) -> Option<String> {
+    static GROUPS: OnceLock<Mutex<HashMap<u32, Option<String>>>> = OnceLock::new();
+    cached(&GROUPS, gid, lookup_group)
+}
+
+// The name, or the ID itself when there's none
+pub fn user_label(uid: u32) -> String {
+    user_name(uid).unwrap_or_else(|| uid.to_string())
+}
+
+pub fn group_label(gid: u32) -> String {
+    group_name(gid).unwrap_or_else(|| gid.to_string())
+}
+
+// "alice (1000)", or "1001 (no local user)" for properties
+pub fn describe_user(uid: u32) -> String {
+    match user_name(uid) {
+        Some(name) => format!("{} ({})", name, uid),
+        None => format!("{} (no local user)", uid),
+    }
+}
+
+pub fn describe_group(gid: u32) -> String {
+    match group_name(gid) {
+        Some(name) => format!("{} ({})", name, gid),
+        None => format!("{} (no local group)", gid),
+    }
+}
+
+// Calls a getpwuid_r-style function, growing the buffer until the entry fits
+#[cfg(unix)]
+fn lookup_reentrant(
+    mut call: impl FnMut(&mut [libc::c_char]) -> (libc::c_int, Option<*const libc::c_char>),
+) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let (result, name) = call(&mut buf);
+        if result == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        let name = name.filter(|_| result == 0)?;
+        let name = unsafe { std::ffi::CStr::from_ptr(name) };
+        return Some(name.to_string_lossy().into_owned());
+    }
+}
+
+#[cfg(unix)]
+fn lookup_user(uid: u32) -> Option<String> {
+    lookup_reentrant(|buf| {
+        let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut found = std::ptr::null_mut();
+        let result =
+            unsafe { libc::getpwuid_r(uid, &mut entry, buf.as_mut_ptr(), buf.len(), &mut found) };
+        (
+            result,
+            (!found.is_null()).then_some(entry.pw_name as *const _),
+        )
+    })
+}
+
+#[cfg(unix)]
+fn lookup_group(gid: u32) -> Option<String> {
+    lookup_reentrant(|buf| {
+        let mut entry: libc::group = unsafe { std::mem::zeroed() };
+        let mut found = std::ptr::null_mut();
+        let result =
+            unsafe { libc::getgrgid_r(gid, &mut entry, buf.as_mut_ptr(), buf.len(), &mut found) };
+        (
+            result,
+            (!found.is_null()).then_some(entry.gr_name as *const _),
+        )
+    })
+}
+
+#[cfg(not(unix))]
+fn lookup_user(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn lookup_group(_gid: u32) -> Option<String> {
+    None
+}
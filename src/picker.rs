@@ -0,0 +1,153 @@
+// Running as another app's open/save dialog. The portal service starts a
+// copy of the file manager with `--pick ...`; the chosen items are printed
+// to stdout as file:// URIs, one per line, and nothing is printed when the
+// user cancels.
+use std::path::{Path, PathBuf};
+
+#[derive(Clone)]
+pub enum PickMode {
+    Open { multiple: bool, directory: bool },
+    // Pick a folder and a name for one new file
+    Save { name: String },
+    // Pick a folder to save several already-named files into
+    SaveFiles { names: Vec<String> },
+}
+
+#[derive(Clone)]
+pub struct Picker {
+    pub mode: PickMode,
+    pub title: String,
+    pub accept_label: Option<String>,
+    pub folder: Option<PathBuf>,
+}
+
+impl Picker {
+    // --pick open|save|save-files [--multiple] [--directory] [--title T]
+    // [--accept LABEL] [--folder DIR] [--name NAME]...
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let mut args = args.iter();
+        let mut kind = None;
+        let mut multiple = false;
+        let mut directory = false;
+        let mut title = String::new();
+        let mut accept_label = None;
+        let mut folder = None;
+        let mut names = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--pick" => kind = args.next().cloned(),
+                "--multiple" => multiple = true,
+                "--directory" => directory = true,
+                "--title" => title = args.next().cloned().unwrap_or_default(),
+                "--accept" => accept_label = args.next().cloned(),
+                "--folder" => folder = args.next().map(PathBuf::from),
+                "--name" => names.extend(args.next().cloned()),
+                _ => {}
+            }
+        }
+        let mode = match kind?.as_str() {
+            "open" => PickMode::Open {
+                multiple,
+                directory,
+            },
+            "save" => PickMode::Save {
+                name: names.into_iter().next().unwrap_or_default(),
+            },
+            "save-files" => PickMode::SaveFiles { names },
+            _ => return None,
+        };
+        Some(Self {
+            mode,
+            title,
+            accept_label,
+            folder,
+        })
+    }
+
+    // The arguments `from_args` reads back
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec![String::from("--pick")];
+        match &self.mode {
+            PickMode::Open {
+                multiple,
+                directory,
+            } => {
+                args.push(String::from("open"));
+                if *multiple {
+                    args.push(String::from("--multiple"));
+                }
+                if *directory {
+                    args.push(String::from("--directory"));
+                }
+            }
+            PickMode::Save { name } => {
+                args.extend([String::from("save"), String::from("--name"), name.clone()]);
+            }
+            PickMode::SaveFiles { names } => {
+                args.push(String::from("save-files"));
+                for name in names {
+                    args.extend([String::from("--name"), name.clone()]);
+                }
+            }
+        }
+        args.extend([String::from("--title"), self.title.clone()]);
+        if let Some(label) = &self.accept_label {
+            args.extend([String::from("--accept"), label.clone()]);
+        }
+        if let Some(folder) = &self.folder {
+            args.extend([String::from("--folder"), folder.display().to_string()]);
+        }
+        args
+    }
+
+    pub fn accept_label(&self) -> &str {
+        match (&self.accept_label, &self.mode) {
+            (Some(label), _) => label.trim_start_matches('_'),
+            (None, PickMode::Open { .. }) => "Open",
+            (None, _) => "Save",
+        }
+    }
+
+    // What accepting right now would return, or None if nothing fits yet
+    pub fn result(
+        &self,
+        current_dir: &Path,
+        selected: &[PathBuf],
+        name: &str,
+    ) -> Option<Vec<PathBuf>> {
+        match &self.mode {
+            PickMode::Open {
+                multiple,
+                directory,
+            } => {
+                let picked: Vec<PathBuf> = selected
+                    .iter()
+                    .filter(|p| p.is_dir() == *directory)
+                    .cloned()
+                    .collect();
+                match picked.len() {
+                    // With no folder selected, the one being shown is picked
+                    0 if *directory => Some(vec![current_dir.to_path_buf()]),
+                    0 => None,
+                    1 => Some(picked),
+                    _ if *multiple => Some(picked),
+                    _ => None,
+                }
+            }
+            PickMode::Save { .. } => {
+                let name = name.trim();
+                let valid = !name.is_empty() && !name.contains(['/', '\\']);
+                valid.then(|| vec![current_dir.join(name)])
+            }
+            PickMode::SaveFiles { names } => {
+                Some(names.iter().map(|n| current_dir.join(n)).collect())
+            }
+        }
+    }
+
+    pub fn finish(paths: &[PathBuf]) {
+        for path in paths {
+            println!("{}", crate::uri::to_file_uri(path));
+        }
+    }
+}
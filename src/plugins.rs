@@ -0,0 +1,469 @@
+// Plugins are folders under <config dir>/plugins, each with a plugin.toml
+// manifest. They extend the app through external commands, so they can be
+// written in any language and never share the process (or its ABI):
+//
+//   name = "Image tools"
+//   description = "Dimensions column and optimizer"
+//   version = "1.0"
+//
+//   [[actions]]
+//   name = "Optimize PNG"
+//   command = "optipng %f"
+//   mime_types = ["image/png"]
+//
+//   [[columns]]
+//   name = "Dimensions"
+//   command = "%p/dimensions.sh %f"
+//   mime_types = ["image/*"]
+//
+//   [[previewers]]
+//   name = "EXIF"
+//   command = "exiftool %f"
+//   mime_types = ["image/jpeg"]
+//
+//   [[filesystems]]
+//   name = "Backup server"
+//   command = "%p/backup-fs"
+//
+// Commands use the same placeholders as custom actions, plus %p for the
+// plugin's own folder. A column command prints the cell value on stdout, and
+// a previewer prints the text shown in the Preview window.
+//
+// A filesystem command is run with an operation and the paths it applies to,
+// all inside the filesystem and starting with `/`:
+//
+//   list <dir>              one entry name per line
+//   stat <path>             `dir` or `file <size> [<modified, Unix seconds>]`
+//   read <path>             the contents on stdout
+//   write <path>            the contents on stdin
+//   mkdir <path>
+//   rename <from> <to>
+//   remove <path>           files and whole folders
+//
+// A non-zero exit status fails the operation, with stderr's first line as the
+// error. Previewers and filesystems are loaded as `PreviewRenderer` and
+// `VfsBackend` hooks, which is all the rest of the app sees of them. Plugins
+// aren't loaded as dynamic libraries or WASM modules; those would need an
+// in-process ABI, which this design avoids.
+
+use crate::actions;
+use crate::config::CustomAction;
+use crate::repaint::Repaint;
+use file_manager_core::{Metadata, Vfs};
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+// Previews longer than this are cut short
+const MAX_PREVIEW: usize = 1 << 20;
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    pub actions: Vec<CustomAction>,
+    pub columns: Vec<PluginColumn>,
+    pub previewers: Vec<PluginPreviewer>,
+    pub filesystems: Vec<PluginFilesystem>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PluginColumn {
+    pub name: String,
+    pub command: String,
+    pub mime_types: Vec<String>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PluginPreviewer {
+    pub name: String,
+    pub command: String,
+    pub mime_types: Vec<String>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PluginFilesystem {
+    pub name: String,
+    pub command: String,
+}
+
+// Turns a file into the text shown in the Preview window
+pub trait PreviewRenderer: Send + Sync {
+    fn name(&self) -> &str;
+    fn handles(&self, path: &Path) -> bool;
+    fn render(&self, path: &Path) -> Result<String, String>;
+}
+
+// A folder's entries as names and what they are
+pub type FsEntries = Vec<(String, Metadata)>;
+
+// A filesystem other than the local disk, browsed in a window of its own and
+// copied out of
+pub trait VfsBackend: Vfs + Send + Sync {
+    fn name(&self) -> &str;
+
+    // A folder's entries sorted folders first, then by name
+    fn list(&self, dir: &Path) -> io::Result<FsEntries> {
+        let mut entries = Vec::new();
+        for name in self.read_dir(dir)? {
+            let metadata = self.metadata(&dir.join(&name))?;
+            entries.push((name.to_string_lossy().into_owned(), metadata));
+        }
+        entries.sort_by(|a, b| {
+            b.1.is_dir
+                .cmp(&a.1.is_dir)
+                .then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase()))
+        });
+        Ok(entries)
+    }
+}
+
+pub struct Plugin {
+    // Folder name, used to remember whether the plugin is enabled
+    pub id: String,
+    pub dir: PathBuf,
+    pub manifest: Manifest,
+    pub enabled: bool,
+    pub previewers: Vec<Arc<dyn PreviewRenderer>>,
+    pub filesystems: Vec<Arc<dyn VfsBackend>>,
+}
+
+pub fn plugins_dir() -> Option<PathBuf> {
+    crate::user_dirs::config_dir().map(|d| d.join("plugins"))
+}
+
+pub fn load_all(disabled: &[String]) -> Vec<Plugin> {
+    let Some(Ok(read)) = plugins_dir().map(fs::read_dir) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<Plugin> = read
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let id = entry.file_name().to_string_lossy().into_owned();
+            let manifest_path = dir.join("plugin.toml");
+            let contents = fs::read_to_string(&manifest_path).ok()?;
+            let mut manifest: Manifest = match toml::from_str(&contents) {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!("Invalid plugin manifest {}: {}", manifest_path.display(), e);
+                    return None;
+                }
+            };
+            if manifest.name.is_empty() {
+                manifest.name = id.clone();
+            }
+            let plugin_dir = actions::quote(&dir.to_string_lossy());
+            for action in &mut manifest.actions {
+                action.command = action.command.replace("%p", &plugin_dir);
+            }
+            for column in &mut manifest.columns {
+                column.command = column.command.replace("%p", &plugin_dir);
+            }
+            let previewers = manifest
+                .previewers
+                .iter()
+                .map(|previewer| {
+                    Arc::new(PluginPreviewer {
+                        command: previewer.command.replace("%p", &plugin_dir),
+                        ..previewer.clone()
+                    }) as Arc<dyn PreviewRenderer>
+                })
+                .collect();
+            let filesystems = manifest
+                .filesystems
+                .iter()
+                .map(|filesystem| {
+                    Arc::new(CommandFs {
+                        name: filesystem.name.clone(),
+                        command: filesystem.command.replace("%p", &plugin_dir),
+                        dir: dir.clone(),
+                    }) as Arc<dyn VfsBackend>
+                })
+                .collect();
+            Some(Plugin {
+                enabled: !disabled.contains(&id),
+                id,
+                dir,
+                manifest,
+                previewers,
+                filesystems,
+            })
+        })
+        .collect();
+    plugins.sort_by_key(|p| p.manifest.name.to_lowercase());
+    plugins
+}
+
+impl PluginColumn {
+    fn value(&self, path: &Path, current_dir: &Path) -> Option<String> {
+        let targets = [path.to_path_buf()];
+        if !actions::mime_filter_matches(&self.mime_types, &targets) {
+            return None;
+        }
+        let command_line = actions::expand(&self.command, Some(path), &targets, current_dir);
+        let output = actions::shell_command(&command_line)
+            .current_dir(current_dir)
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout.lines().next().map(|l| l.trim().to_string())
+    }
+}
+
+impl PreviewRenderer for PluginPreviewer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn handles(&self, path: &Path) -> bool {
+        path.is_file() && actions::mime_filter_matches(&self.mime_types, &[path.to_path_buf()])
+    }
+
+    fn render(&self, path: &Path) -> Result<String, String> {
+        let folder = path.parent().unwrap_or(path);
+        let command_line =
+            actions::expand(&self.command, Some(path), &[path.to_path_buf()], folder);
+        let output = actions::shell_command(&command_line)
+            .current_dir(folder)
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(first_line(&output.stderr, "the previewer failed"));
+        }
+        let shown = &output.stdout[..output.stdout.len().min(MAX_PREVIEW)];
+        Ok(String::from_utf8_lossy(shown).into_owned())
+    }
+}
+
+fn first_line(output: &[u8], fallback: &str) -> String {
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or(fallback)
+        .to_string()
+}
+
+// A filesystem provided by a plugin command, as described at the top
+struct CommandFs {
+    name: String,
+    command: String,
+    // The plugin's folder, where its commands run
+    dir: PathBuf,
+}
+
+impl CommandFs {
+    fn run(&self, operation: &str, paths: &[&Path], input: Option<&[u8]>) -> io::Result<Vec<u8>> {
+        let mut command_line = format!("{} {}", self.command, operation);
+        for path in paths {
+            command_line.push(' ');
+            command_line.push_str(&actions::quote(&path.to_string_lossy()));
+        }
+        let mut child = actions::shell_command(&command_line)
+            .current_dir(&self.dir)
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input)?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let fallback = format!("{} failed", operation);
+            return Err(io::Error::other(first_line(&output.stderr, &fallback)));
+        }
+        Ok(output.stdout)
+    }
+}
+
+fn parse_stat(output: &[u8]) -> io::Result<Metadata> {
+    let output = String::from_utf8_lossy(output);
+    let mut fields = output.split_whitespace();
+    let is_dir = match fields.next() {
+        Some("dir") => true,
+        Some("file") => false,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected stat output \"{}\"", output.trim()),
+            ))
+        }
+    };
+    let len = fields.next().and_then(|f| f.parse().ok()).unwrap_or(0);
+    let modified = fields
+        .next()
+        .and_then(|f| f.parse().ok())
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+    Ok(Metadata {
+        is_dir,
+        is_symlink: false,
+        len,
+        modified,
+    })
+}
+
+impl Vfs for CommandFs {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        parse_stat(&self.run("stat", &[path], None)?)
+    }
+
+    // Plugin filesystems have no symlinks
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.metadata(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        let output = self.run("list", &[path], None)?;
+        Ok(String::from_utf8_lossy(&output)
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(OsString::from)
+            .collect())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.run("read", &[path], None)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.run("write", &[path], Some(contents)).map(|_| ())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.run("mkdir", &[path], None).map(|_| ())
+    }
+
+    fn copy_file(&self, source: &Path, destination: &Path) -> io::Result<u64> {
+        let contents = self.read(source)?;
+        self.write(destination, &contents)?;
+        Ok(contents.len() as u64)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.run("rename", &[from, to], None).map(|_| ())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.run("remove", &[path], None).map(|_| ())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.run("remove", &[path], None).map(|_| ())
+    }
+}
+
+impl VfsBackend for CommandFs {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+// Copies `from` on the backend, and everything under it, to `to` on disk
+pub fn copy_out(backend: &dyn VfsBackend, from: &Path, to: &Path) -> io::Result<()> {
+    if backend.metadata(from)?.is_dir {
+        fs::create_dir(to)?;
+        for name in backend.read_dir(from)? {
+            copy_out(backend, &from.join(&name), &to.join(&name))?;
+        }
+        Ok(())
+    } else {
+        fs::write(to, backend.read(from)?)
+    }
+}
+
+pub struct ColumnValue {
+    pub column: usize,
+    pub path: PathBuf,
+    pub value: String,
+}
+
+// Runs every column command for every path on a background thread, sending
+// the cells back as they are produced
+pub fn compute_columns(
+    columns: Vec<PluginColumn>,
+    paths: Vec<PathBuf>,
+    current_dir: PathBuf,
+    repaint: Repaint,
+) -> Receiver<ColumnValue> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for path in paths {
+            for (column, definition) in columns.iter().enumerate() {
+                if let Some(value) = definition.value(&path, &current_dir) {
+                    let cell = ColumnValue {
+                        column,
+                        path: path.clone(),
+                        value,
+                    };
+                    if sender.send(cell).is_err() {
+                        // The listing changed and nobody is waiting any more
+                        return;
+                    }
+                    repaint.request();
+                }
+            }
+        }
+    });
+    receiver
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_filesystems() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("fs.sh");
+        fs::write(
+            &script,
+            r#"case "$1" in
+list) [ "$2" = / ] && printf 'docs\nnotes.txt\n' ;;
+stat) case "$2" in /docs) echo dir ;; /docs/*|/notes.txt) echo file 5 60 ;; *) echo "no such file: $2" >&2; exit 1 ;; esac ;;
+read) printf hello ;;
+*) exit 1 ;;
+esac
+"#,
+        )
+        .unwrap();
+        let backend = CommandFs {
+            name: String::from("Test"),
+            command: format!("sh {}", actions::quote(&script.to_string_lossy())),
+            dir: dir.path().to_path_buf(),
+        };
+
+        let entries = backend.list(Path::new("/")).unwrap();
+        let names: Vec<&str> = entries.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["docs", "notes.txt"]);
+        assert!(entries[0].1.is_dir);
+        assert_eq!(entries[1].1.len, 5);
+        assert_eq!(
+            entries[1].1.modified,
+            Some(UNIX_EPOCH + Duration::from_secs(60))
+        );
+
+        let error = backend.metadata(Path::new("/missing")).unwrap_err();
+        assert_eq!(error.to_string(), "no such file: /missing");
+
+        let copy = dir.path().join("copy.txt");
+        copy_out(&backend, Path::new("/notes.txt"), &copy).unwrap();
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "hello");
+    }
+}
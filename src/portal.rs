@@ -0,0 +1,168 @@
+// xdg-desktop-portal FileChooser backend, so sandboxed (Flatpak, Snap) and
+// portal-aware apps can use this file manager as their open and save
+// dialog. Each request opens a separate picker window (see picker.rs), since
+// a process can only run one GUI event loop. Installing is described in the
+// readme.
+use crate::picker::{PickMode, Picker};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+const BUS_NAME: &str = "org.freedesktop.impl.portal.desktop.file_manager";
+const OBJECT_PATH: &str = "/org/freedesktop/portal/desktop";
+
+// Portal response codes
+const SUCCESS: u32 = 0;
+const CANCELLED: u32 = 1;
+const FAILED: u32 = 2;
+
+struct FileChooser;
+
+type Options<'a> = HashMap<&'a str, Value<'a>>;
+type Response = (u32, HashMap<String, OwnedValue>);
+
+fn flag(options: &Options, key: &str) -> bool {
+    matches!(options.get(key), Some(Value::Bool(true)))
+}
+
+fn string(options: &Options, key: &str) -> Option<String> {
+    match options.get(key) {
+        Some(Value::Str(s)) => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+// Paths are passed as NUL-terminated byte arrays
+fn path(options: &Options, key: &str) -> Option<PathBuf> {
+    let bytes: Vec<u8> = options.get(key)?.try_clone().ok()?.try_into().ok()?;
+    let bytes = bytes.strip_suffix(&[0]).unwrap_or(&bytes);
+    use std::os::unix::ffi::OsStrExt;
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(bytes)))
+}
+
+fn paths(options: &Options, key: &str) -> Vec<String> {
+    let Some(value) = options.get(key).and_then(|v| v.try_clone().ok()) else {
+        return Vec::new();
+    };
+    let arrays: Vec<Vec<u8>> = value.try_into().unwrap_or_default();
+    arrays
+        .iter()
+        .map(|bytes| {
+            String::from_utf8_lossy(bytes.strip_suffix(&[0]).unwrap_or(bytes)).into_owned()
+        })
+        .collect()
+}
+
+// Shows the picker and waits for the user without holding up other calls
+async fn pick(picker: Picker) -> Response {
+    let result = blocking::unblock(move || {
+        std::process::Command::new(std::env::current_exe()?)
+            .args(picker.to_args())
+            .stderr(std::process::Stdio::inherit())
+            .output()
+    })
+    .await;
+    let output = match result {
+        Ok(output) => output,
+        Err(e) => {
+            tracing::error!("Failed to start the file picker: {}", e);
+            return (FAILED, HashMap::new());
+        }
+    };
+    let uris: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect();
+    if uris.is_empty() {
+        return (CANCELLED, HashMap::new());
+    }
+    let mut results = HashMap::new();
+    if let Ok(uris) = OwnedValue::try_from(Value::from(uris)) {
+        results.insert(String::from("uris"), uris);
+    }
+    (SUCCESS, results)
+}
+
+#[zbus::interface(name = "org.freedesktop.impl.portal.FileChooser")]
+impl FileChooser {
+    async fn open_file(
+        &self,
+        _handle: ObjectPath<'_>,
+        _app_id: &str,
+        _parent_window: &str,
+        title: &str,
+        options: Options<'_>,
+    ) -> Response {
+        pick(Picker {
+            mode: PickMode::Open {
+                multiple: flag(&options, "multiple"),
+                directory: flag(&options, "directory"),
+            },
+            title: title.to_string(),
+            accept_label: string(&options, "accept_label"),
+            folder: path(&options, "current_folder"),
+        })
+        .await
+    }
+
+    async fn save_file(
+        &self,
+        _handle: ObjectPath<'_>,
+        _app_id: &str,
+        _parent_window: &str,
+        title: &str,
+        options: Options<'_>,
+    ) -> Response {
+        // Saving over an existing file starts out in its folder
+        let current_file = path(&options, "current_file");
+        pick(Picker {
+            mode: PickMode::Save {
+                name: string(&options, "current_name")
+                    .or_else(|| {
+                        current_file
+                            .as_ref()
+                            .and_then(|f| f.file_name())
+                            .map(|n| n.to_string_lossy().into_owned())
+                    })
+                    .unwrap_or_default(),
+            },
+            title: title.to_string(),
+            accept_label: string(&options, "accept_label"),
+            folder: path(&options, "current_folder")
+                .or_else(|| current_file.and_then(|f| f.parent().map(PathBuf::from))),
+        })
+        .await
+    }
+
+    async fn save_files(
+        &self,
+        _handle: ObjectPath<'_>,
+        _app_id: &str,
+        _parent_window: &str,
+        title: &str,
+        options: Options<'_>,
+    ) -> Response {
+        pick(Picker {
+            mode: PickMode::SaveFiles {
+                names: paths(&options, "files"),
+            },
+            title: title.to_string(),
+            accept_label: string(&options, "accept_label"),
+            folder: path(&options, "current_folder"),
+        })
+        .await
+    }
+}
+
+// Runs until the process is killed; D-Bus activation starts it on demand
+pub fn serve() -> Result<(), String> {
+    let _connection = zbus::blocking::connection::Builder::session()
+        .and_then(|b| b.name(BUS_NAME))
+        .and_then(|b| b.serve_at(OBJECT_PATH, FileChooser))
+        .and_then(|b| b.build())
+        .map_err(|e| e.to_string())?;
+    loop {
+        std::thread::park();
+    }
+}
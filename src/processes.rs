@@ -0,0 +1,247 @@
+// Running processes and where their executables live, for the "Open Process
+// Folder" tool that jumps to the folder holding a program's binary. Read from
+// /proc on Linux, `ps` on other Unix systems and a Toolhelp snapshot on
+// Windows; processes whose executable can't be seen are still listed.
+use eframe::egui;
+use std::path::PathBuf;
+
+pub struct Process {
+    pub pid: u32,
+    pub name: String,
+    pub exe: Option<PathBuf>,
+}
+
+#[derive(Default)]
+pub struct ProcessesWindow {
+    pub open: bool,
+    filter: String,
+    processes: Option<Vec<Process>>,
+}
+
+impl ProcessesWindow {
+    pub fn open(&mut self) {
+        self.open = true;
+        self.processes = None;
+    }
+
+    // Returns an executable to reveal in its folder. `salt` is the id of the
+    // browser showing it.
+    pub fn show(&mut self, ctx: &egui::Context, salt: egui::Id) -> Option<PathBuf> {
+        if !self.open {
+            return None;
+        }
+        let mut open = self.open;
+        let mut reveal = None;
+        let processes = self.processes.get_or_insert_with(list);
+
+        egui::Window::new("Open Process Folder")
+            .id(salt.with("processes"))
+            .open(&mut open)
+            .default_size([600.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.filter)
+                            .hint_text("Name, path or process id"),
+                    );
+                    if ui.button("Refresh").clicked() {
+                        *processes = list();
+                    }
+                });
+                ui.separator();
+
+                let filter = self.filter.to_lowercase();
+                let shown: Vec<&Process> = processes
+                    .iter()
+                    .filter(|p| {
+                        filter.is_empty()
+                            || p.name.to_lowercase().contains(&filter)
+                            || p.pid.to_string() == filter
+                            || p.exe.as_ref().is_some_and(|exe| {
+                                exe.to_string_lossy().to_lowercase().contains(&filter)
+                            })
+                    })
+                    .collect();
+                if shown.is_empty() {
+                    ui.weak("No matching processes");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        egui::Grid::new("processes")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for process in shown {
+                                    ui.label(process.pid.to_string());
+                                    ui.label(&process.name);
+                                    match &process.exe {
+                                        Some(exe) => {
+                                            if ui
+                                                .link(exe.display().to_string())
+                                                .on_hover_text("Open the folder holding it")
+                                                .clicked()
+                                            {
+                                                reveal = Some(exe.clone());
+                                            }
+                                        }
+                                        None => {
+                                            ui.weak("Not accessible").on_hover_text(
+                                                "Owned by another user or the system",
+                                            );
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        self.open = open;
+        reveal
+    }
+}
+
+// Sorted by name, then process id
+fn list() -> Vec<Process> {
+    let mut processes = running();
+    processes.sort_by(|a, b| {
+        a.name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then(a.pid.cmp(&b.pid))
+    });
+    processes
+}
+
+#[cfg(target_os = "linux")]
+fn running() -> Vec<Process> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+            let dir = entry.path();
+            // Kernel threads have no executable
+            let exe = std::fs::read_link(dir.join("exe"))
+                .ok()
+                .filter(|exe| exe.is_absolute());
+            let name = std::fs::read_to_string(dir.join("comm"))
+                .map(|comm| comm.trim_end().to_string())
+                .ok()
+                .or_else(|| Some(exe.as_ref()?.file_name()?.to_string_lossy().into_owned()))?;
+            Some(Process { pid, name, exe })
+        })
+        .collect()
+}
+
+// macOS and the BSDs give the executable's full path as the command name
+#[cfg(all(unix, not(target_os = "linux")))]
+fn running() -> Vec<Process> {
+    let output = match std::process::Command::new("ps")
+        .args(["-axo", "pid=,comm="])
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            tracing::warn!("Failed to list processes: {}", e);
+            return Vec::new();
+        }
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, command) = line.trim().split_once(char::is_whitespace)?;
+            let command = PathBuf::from(command.trim());
+            let name = command.file_name()?.to_string_lossy().into_owned();
+            let exe = command.is_absolute().then_some(command);
+            Some(Process {
+                pid: pid.parse().ok()?,
+                name,
+                exe,
+            })
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn running() -> Vec<Process> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+
+    #[repr(C)]
+    struct ProcessEntry {
+        size: u32,
+        usage: u32,
+        process_id: u32,
+        default_heap_id: usize,
+        module_id: u32,
+        threads: u32,
+        parent_process_id: u32,
+        priority: i32,
+        flags: u32,
+        exe_file: [u16; 260],
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateToolhelp32Snapshot(flags: u32, process_id: u32) -> isize;
+        fn Process32FirstW(snapshot: isize, entry: *mut ProcessEntry) -> i32;
+        fn Process32NextW(snapshot: isize, entry: *mut ProcessEntry) -> i32;
+        fn OpenProcess(access: u32, inherit: i32, process_id: u32) -> isize;
+        fn QueryFullProcessImageNameW(
+            process: isize,
+            flags: u32,
+            name: *mut u16,
+            size: *mut u32,
+        ) -> i32;
+        fn CloseHandle(handle: isize) -> i32;
+    }
+    const TH32CS_SNAPPROCESS: u32 = 0x2;
+    const INVALID_HANDLE_VALUE: isize = -1;
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+
+    // Works for other users' processes, short of protected ones
+    let exe = |pid: u32| -> Option<PathBuf> {
+        let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid) };
+        if process == 0 {
+            return None;
+        }
+        let mut name = [0u16; 1024];
+        let mut size = name.len() as u32;
+        let found =
+            unsafe { QueryFullProcessImageNameW(process, 0, name.as_mut_ptr(), &mut size) } != 0;
+        unsafe { CloseHandle(process) };
+        found.then(|| PathBuf::from(OsString::from_wide(&name[..size as usize])))
+    };
+
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
+    if snapshot == INVALID_HANDLE_VALUE {
+        tracing::warn!(
+            "Failed to list processes: {}",
+            std::io::Error::last_os_error()
+        );
+        return Vec::new();
+    }
+    let mut processes = Vec::new();
+    let mut entry: ProcessEntry = unsafe { std::mem::zeroed() };
+    entry.size = std::mem::size_of::<ProcessEntry>() as u32;
+    let mut more = unsafe { Process32FirstW(snapshot, &mut entry) } != 0;
+    while more {
+        let end = entry.exe_file.iter().position(|&c| c == 0).unwrap_or(260);
+        // The idle "process" 0 isn't a program
+        if entry.process_id != 0 {
+            processes.push(Process {
+                pid: entry.process_id,
+                name: String::from_utf16_lossy(&entry.exe_file[..end]),
+                exe: exe(entry.process_id),
+            });
+        }
+        more = unsafe { Process32NextW(snapshot, &mut entry) } != 0;
+    }
+    unsafe { CloseHandle(snapshot) };
+    processes
+}
@@ -0,0 +1,232 @@
+// Frame-time profiler for finding slow spots, built with `--features
+// profiling`. Debug → Profiler graphs how long recent frames took and, for a
+// chosen frame, which `profile_scope!` blocks the time went to, on the UI
+// thread as well as job and listing threads. Nothing is recorded while the
+// window is closed.
+use eframe::egui;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Frames kept for the graph
+const FRAMES: usize = 240;
+// What a frame has at 60 frames a second
+const BUDGET: Duration = Duration::from_micros(16_667);
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+// Scopes ended since the frame began, from every thread
+static ENDED: Mutex<Vec<Span>> = Mutex::new(Vec::new());
+
+struct Span {
+    name: Cow<'static, str>,
+    thread: String,
+    took: Duration,
+}
+
+struct Frame {
+    took: Duration,
+    spans: Vec<Span>,
+}
+
+// Records the time until it's dropped, see `profile_scope!`
+pub struct Scope {
+    name: Option<Cow<'static, str>>,
+    started: Instant,
+}
+
+impl Scope {
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: RECORDING.load(Ordering::Relaxed).then(|| name.into()),
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let Some(name) = self.name.take() else {
+            return;
+        };
+        let thread = std::thread::current();
+        let thread = match thread.name() {
+            Some(name) => name.to_string(),
+            None => format!("{:?}", thread.id()),
+        };
+        ENDED.lock().unwrap().push(Span {
+            name,
+            thread,
+            took: self.started.elapsed(),
+        });
+    }
+}
+
+#[derive(Default)]
+pub struct ProfilerWindow {
+    pub open: bool,
+    paused: bool,
+    frames: VecDeque<Frame>,
+    began: Option<Instant>,
+    // Index into `frames`; the latest when None
+    selected: Option<usize>,
+}
+
+impl ProfilerWindow {
+    pub fn begin_frame(&mut self) {
+        let recording = self.open && !self.paused;
+        RECORDING.store(recording, Ordering::Relaxed);
+        self.began = recording.then(Instant::now);
+        if !recording {
+            ENDED.lock().unwrap().clear();
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        let Some(began) = self.began.take() else {
+            return;
+        };
+        let spans = std::mem::take(&mut *ENDED.lock().unwrap());
+        self.frames.push_back(Frame {
+            took: began.elapsed(),
+            spans,
+        });
+        if self.frames.len() > FRAMES {
+            self.frames.pop_front();
+            self.selected = self.selected.and_then(|i| i.checked_sub(1));
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        let mut open = self.open;
+        egui::Window::new("Profiler")
+            .open(&mut open)
+            .default_size([560.0, 420.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.paused, "Pause");
+                    if ui.button("Clear").clicked() {
+                        self.frames.clear();
+                        self.selected = None;
+                    }
+                    if self.selected.is_some() && ui.button("Follow Latest").clicked() {
+                        self.selected = None;
+                    }
+                });
+                self.graph(ui);
+                ui.separator();
+                self.scopes(ui);
+            });
+        self.open = open;
+    }
+
+    // A bar per frame, taller for slower ones, with the 60 fps budget marked;
+    // clicking one picks it
+    fn graph(&mut self, ui: &mut egui::Ui) {
+        let size = egui::vec2(ui.available_width(), 80.0);
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::click());
+        let rect = response.rect;
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        let slowest = self
+            .frames
+            .iter()
+            .map(|f| f.took)
+            .max()
+            .unwrap_or_default()
+            .max(BUDGET * 2);
+        let height = |took: Duration| rect.height() * took.as_secs_f32() / slowest.as_secs_f32();
+        let width = rect.width() / FRAMES as f32;
+        let selected = self.selected.unwrap_or(self.frames.len().saturating_sub(1));
+        for (i, frame) in self.frames.iter().enumerate() {
+            let x = rect.left() + i as f32 * width;
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(x, rect.bottom() - height(frame.took)),
+                egui::pos2(x + width.max(1.0), rect.bottom()),
+            );
+            let color = if i == selected {
+                ui.visuals().selection.bg_fill
+            } else if frame.took > BUDGET {
+                ui.visuals().warn_fg_color
+            } else {
+                ui.visuals().weak_text_color()
+            };
+            painter.rect_filled(bar, 0.0, color);
+        }
+        let budget = rect.bottom() - height(BUDGET);
+        painter.hline(
+            rect.x_range(),
+            budget,
+            egui::Stroke::new(1.0, ui.visuals().error_fg_color),
+        );
+        if let Some(pos) = response.interact_pointer_pos() {
+            if response.clicked() {
+                let i = ((pos.x - rect.left()) / width) as usize;
+                if i < self.frames.len() {
+                    self.selected = Some(i);
+                }
+            }
+        }
+    }
+
+    // The chosen frame's scopes by thread and name, slowest first
+    fn scopes(&self, ui: &mut egui::Ui) {
+        let frame = match self.selected {
+            Some(i) => self.frames.get(i),
+            None => self.frames.back(),
+        };
+        let Some(frame) = frame else {
+            ui.weak("No frames recorded yet");
+            return;
+        };
+        ui.label(format!(
+            "Frame took {:.2} ms",
+            frame.took.as_secs_f64() * 1e3
+        ));
+
+        // (thread, name) → (calls, total, slowest)
+        let mut totals: Vec<(&str, &str, usize, Duration, Duration)> = Vec::new();
+        for span in &frame.spans {
+            match totals
+                .iter_mut()
+                .find(|t| t.0 == span.thread && t.1 == span.name)
+            {
+                Some(total) => {
+                    total.2 += 1;
+                    total.3 += span.took;
+                    total.4 = total.4.max(span.took);
+                }
+                None => totals.push((&span.thread, &span.name, 1, span.took, span.took)),
+            }
+        }
+        totals.sort_by_key(|t| std::cmp::Reverse(t.3));
+
+        let ms = |time: Duration| format!("{:.2} ms", time.as_secs_f64() * 1e3);
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                egui::Grid::new("profiler_scopes")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Scope");
+                        ui.strong("Thread");
+                        ui.strong("Calls");
+                        ui.strong("Total");
+                        ui.strong("Slowest");
+                        ui.end_row();
+                        for (thread, name, calls, total, slowest) in totals {
+                            ui.label(name);
+                            ui.weak(thread);
+                            ui.label(calls.to_string());
+                            ui.label(ms(total));
+                            ui.label(ms(slowest));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
@@ -0,0 +1,1174 @@
+use crate::acl;
+use crate::capabilities::Capabilities;
+use crate::folder_icons::{self, FolderIcon};
+use crate::repaint::Repaint;
+use crate::shortcuts::{self, Shortcut};
+use crate::snapshots::{self, Version};
+use crate::streams::{self, Stream};
+use crate::xattrs;
+use eframe::egui;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+#[derive(PartialEq)]
+enum Tab {
+    General,
+    Attributes,
+    Streams,
+    Versions,
+    Permissions,
+}
+
+// One extended attribute as shown in the editor
+struct Xattr {
+    name: String,
+    value: String,
+    // Set for values that aren't text; `value` then holds their hex
+    binary: bool,
+    // The value as last read, to tell whether it was edited
+    original: String,
+}
+
+pub struct PropertiesDialog {
+    pub path: PathBuf,
+    tab: Tab,
+    // (label, value) rows for the General section
+    general: Vec<(&'static str, String)>,
+    shortcut: Vec<(&'static str, String)>,
+    // Custom icon and emblem being edited, for folders
+    folder_icon: Option<FolderIcon>,
+    folder_icon_error: Option<String>,
+    // Set when the icon or emblem was saved, so the list shows it
+    pub folder_icon_changed: bool,
+    xattrs: Result<Vec<Xattr>, String>,
+    selinux: Option<String>,
+    new_name: String,
+    new_value: String,
+    xattr_error: Option<String>,
+    streams: Result<Vec<Stream>, String>,
+    // Where a Zone.Identifier stream says the file came from
+    zone: Option<String>,
+    stream_error: Option<String>,
+    // Whether snapshots cover the item at all
+    has_versions: bool,
+    // What the filesystem can store; tabs for the rest are greyed out
+    capabilities: Capabilities,
+    // Looked up on a background thread when the tab is first shown, since
+    // asking Windows for shadow copies takes seconds
+    versions: Option<Vec<Version>>,
+    finding_versions: Option<Receiver<Vec<Version>>>,
+    repaint: Repaint,
+    // Version the user clicked Restore on, waiting for confirmation
+    confirm_restore: Option<usize>,
+    // Outcome of the last restore
+    restore_result: Option<Result<String, String>>,
+    // POSIX ACL as read, and the copy being edited; read when the tab is
+    // first shown
+    acl: Option<Result<Vec<acl::Entry>, String>>,
+    acl_edited: Vec<acl::Entry>,
+    // NTFS entries on Windows, which are changed one at a time instead
+    ntfs: Option<Result<Vec<acl::Ace>, String>>,
+    new_acl_tag: acl::Tag,
+    new_acl_name: String,
+    new_acl_default: bool,
+    new_ntfs_rights: &'static str,
+    acl_error: Option<String>,
+    // Everything is shown but nothing can be changed
+    read_only: bool,
+}
+
+impl PropertiesDialog {
+    pub fn new(path: PathBuf, repaint: Repaint, read_only: bool) -> Self {
+        let mut general = Vec::new();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        general.push(("Name", name));
+        if let Some(parent) = path.parent() {
+            general.push(("Location", parent.display().to_string()));
+        }
+        general.push(("Type", crate::actions::mime_type(&path)));
+
+        if let Ok(meta) = fs::metadata(&path) {
+            if meta.is_dir() {
+                let items = fs::read_dir(&path).map(|r| r.count()).unwrap_or(0);
+                general.push(("Contents", format!("{} items", items)));
+            } else {
+                general.push((
+                    "Size",
+                    format!(
+                        "{} ({} bytes)",
+                        crate::format_file_size(meta.len()),
+                        meta.len()
+                    ),
+                ));
+            }
+            if let Ok(modified) = meta.modified() {
+                let datetime: chrono::DateTime<chrono::Local> = modified.into();
+                general.push(("Modified", datetime.format("%d/%m/%Y %H:%M:%S").to_string()));
+            }
+            if let Some(owner) = crate::owners::ownership(&meta) {
+                general.push(("Owner", crate::owners::describe_user(owner.uid)));
+                general.push(("Group", crate::owners::describe_group(owner.gid)));
+            }
+            general.extend(inode_rows(&meta));
+        }
+        let capabilities = Capabilities::detect(&path);
+        general.push(("File system", capabilities.describe()));
+        if let Ok(target) = fs::read_link(&path) {
+            general.push(("Link target", target.display().to_string()));
+        }
+
+        let mut shortcut = Vec::new();
+        match shortcuts::read(&path) {
+            Some(Shortcut::Desktop(entry)) => {
+                let fields = [
+                    ("Name", entry.name),
+                    ("Comment", entry.comment),
+                    ("Command", entry.exec),
+                    ("URL", entry.url),
+                    ("Icon", entry.icon),
+                ];
+                shortcut.extend(fields.into_iter().filter_map(|(k, v)| Some((k, v?))));
+                if entry.terminal {
+                    shortcut.push(("Terminal", String::from("Runs in a terminal")));
+                }
+            }
+            Some(Shortcut::WindowsLink(link)) => {
+                let fields = [
+                    ("Target", link.target),
+                    ("Arguments", link.arguments),
+                    ("Start in", link.working_dir),
+                    ("Comment", link.description),
+                    ("Icon", link.icon_location),
+                ];
+                shortcut.extend(fields.into_iter().filter_map(|(k, v)| Some((k, v?))));
+            }
+            Some(Shortcut::Internet(internet)) => {
+                shortcut.push(("URL", internet.url));
+                if let Some(icon) = internet.icon {
+                    shortcut.push(("Icon", icon));
+                }
+            }
+            None => {}
+        }
+
+        let folder_icon = path
+            .is_dir()
+            .then(|| folder_icons::read(&path).unwrap_or_default());
+
+        let mut dialog = Self {
+            selinux: xattrs::selinux_context(&path),
+            has_versions: snapshots::available(&path),
+            capabilities,
+            path,
+            tab: Tab::General,
+            general,
+            shortcut,
+            folder_icon,
+            folder_icon_error: None,
+            folder_icon_changed: false,
+            xattrs: Ok(Vec::new()),
+            new_name: String::from("user."),
+            new_value: String::new(),
+            xattr_error: None,
+            streams: Ok(Vec::new()),
+            zone: None,
+            stream_error: None,
+            versions: None,
+            finding_versions: None,
+            repaint,
+            confirm_restore: None,
+            restore_result: None,
+            acl: None,
+            acl_edited: Vec::new(),
+            ntfs: None,
+            new_acl_tag: acl::Tag::User,
+            new_acl_name: String::new(),
+            new_acl_default: false,
+            new_ntfs_rights: "RX",
+            acl_error: None,
+            read_only,
+        };
+        dialog.load_xattrs();
+        dialog.load_streams();
+        dialog
+    }
+
+    fn load_streams(&mut self) {
+        if !streams::supported() {
+            return;
+        }
+        self.streams = streams::list(&self.path).map_err(|e| e.to_string());
+        self.zone = streams::read(&self.path, streams::ZONE_IDENTIFIER)
+            .ok()
+            .map(|zone| streams::describe_zone(&String::from_utf8_lossy(&zone)));
+    }
+
+    fn load_xattrs(&mut self) {
+        self.xattrs = xattrs::list_user(&self.path)
+            .map(|list| {
+                list.into_iter()
+                    .map(|(name, value)| {
+                        let (value, binary) = xattrs::display_value(&value);
+                        Xattr {
+                            name,
+                            original: value.clone(),
+                            value,
+                            binary,
+                        }
+                    })
+                    .collect()
+            })
+            .map_err(|e| e.to_string());
+    }
+
+    // Returns false once the window has been closed. `salt` is the id of the
+    // browser showing it.
+    pub fn show(&mut self, ctx: &egui::Context, salt: egui::Id) -> bool {
+        let mut open = true;
+        egui::Window::new("Properties")
+            .id(salt.with(("properties", &self.path)))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                let has_versions = self.has_versions;
+                let has_acl = acl::supported();
+                if xattrs::supported() || streams::supported() || has_versions || has_acl {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.tab, Tab::General, "General");
+                        if xattrs::supported() {
+                            ui.selectable_value(
+                                &mut self.tab,
+                                Tab::Attributes,
+                                "Extended Attributes",
+                            );
+                        }
+                        if streams::supported() {
+                            ui.selectable_value(&mut self.tab, Tab::Streams, "Data Streams");
+                        }
+                        if has_versions {
+                            ui.selectable_value(&mut self.tab, Tab::Versions, "Previous Versions");
+                        }
+                        if has_acl {
+                            ui.selectable_value(&mut self.tab, Tab::Permissions, "Permissions");
+                        }
+                    });
+                    ui.separator();
+                }
+                match self.tab {
+                    Tab::General => {
+                        rows(ui, "properties_general", &self.general);
+                        if !self.shortcut.is_empty() {
+                            ui.separator();
+                            ui.strong("Shortcut");
+                            rows(ui, "properties_shortcut", &self.shortcut);
+                        }
+                        if self.folder_icon.is_some() {
+                            ui.separator();
+                            ui.add_enabled_ui(!self.read_only, |ui| self.folder_icon_section(ui));
+                        }
+                    }
+                    Tab::Attributes => {
+                        let supported = self.capabilities.xattrs;
+                        if !supported {
+                            ui.weak(format!(
+                                "{} don't store extended attributes.",
+                                self.capabilities.drive_label()
+                            ));
+                        }
+                        ui.add_enabled_ui(supported && !self.read_only, |ui| {
+                            self.attributes_tab(ui)
+                        });
+                    }
+                    Tab::Streams => {
+                        ui.add_enabled_ui(!self.read_only, |ui| self.streams_tab(ui));
+                    }
+                    Tab::Versions => self.versions_tab(ui),
+                    Tab::Permissions => {
+                        let supported = self.capabilities.permissions;
+                        if !supported {
+                            ui.weak(format!(
+                                "{} don't store permissions.",
+                                self.capabilities.drive_label()
+                            ));
+                        }
+                        ui.add_enabled_ui(supported && !self.read_only, |ui| {
+                            if cfg!(windows) {
+                                self.ntfs_tab(ui)
+                            } else {
+                                self.acl_tab(ui)
+                            }
+                        });
+                    }
+                }
+            });
+        open
+    }
+
+    fn folder_icon_section(&mut self, ui: &mut egui::Ui) {
+        let Some(folder) = &mut self.folder_icon else {
+            return;
+        };
+        ui.strong("Folder icon");
+        let mut icon = folder.icon.clone().unwrap_or_default();
+        let mut emblem = folder.emblem.clone().unwrap_or_default();
+        egui::Grid::new(("properties_folder_icon", &self.path))
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Icon");
+                ui.add(
+                    egui::TextEdit::singleline(&mut icon).hint_text("folder-music or image path"),
+                );
+                ui.end_row();
+                ui.label("Emblem");
+                ui.add(
+                    egui::TextEdit::singleline(&mut emblem)
+                        .hint_text("⭐")
+                        .desired_width(40.0),
+                );
+                ui.end_row();
+            });
+        let trimmed = |text: String| Some(text.trim().to_string()).filter(|t| !t.is_empty());
+        folder.icon = trimmed(icon);
+        folder.emblem = trimmed(emblem);
+
+        let mut save = None;
+        ui.horizontal(|ui| {
+            if ui.button("Apply").clicked() {
+                save = Some(folder.clone());
+            }
+            if ui.button("Reset").clicked() {
+                save = Some(FolderIcon::default());
+            }
+            ui.weak(format!(
+                "Saved in {} in the folder",
+                folder_icons::FILE_NAME
+            ));
+        });
+        if let Some(folder) = save {
+            match folder_icons::write(&self.path, &folder) {
+                Ok(()) => {
+                    self.folder_icon = Some(folder);
+                    self.folder_icon_error = None;
+                    self.folder_icon_changed = true;
+                }
+                Err(e) => self.folder_icon_error = Some(e.to_string()),
+            }
+        }
+        if let Some(error) = &self.folder_icon_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn attributes_tab(&mut self, ui: &mut egui::Ui) {
+        if let Some(context) = &self.selinux {
+            ui.horizontal(|ui| {
+                ui.label("SELinux context:");
+                ui.monospace(context);
+            });
+            ui.separator();
+        }
+
+        let mut save = None;
+        let mut delete = None;
+        let mut added = false;
+        match &mut self.xattrs {
+            Err(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+            }
+            Ok(list) => {
+                if list.is_empty() {
+                    ui.weak("No extended attributes");
+                }
+                egui::Grid::new(("properties_xattrs", &self.path))
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for (i, xattr) in list.iter_mut().enumerate() {
+                            ui.monospace(&xattr.name);
+                            let edit = ui.text_edit_singleline(&mut xattr.value);
+                            if xattr.binary {
+                                edit.on_hover_text(
+                                    "Binary value, shown in hex. Saving stores the text as typed.",
+                                );
+                            }
+                            ui.horizontal(|ui| {
+                                let edited = xattr.value != xattr.original;
+                                if ui.add_enabled(edited, egui::Button::new("Save")).clicked() {
+                                    save = Some(i);
+                                }
+                                if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                                    delete = Some(i);
+                                }
+                            });
+                            ui.end_row();
+                        }
+
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.new_name).hint_text("user.name"),
+                        );
+                        ui.add(egui::TextEdit::singleline(&mut self.new_value).hint_text("value"));
+                        let valid = xattrs::is_user_name(self.new_name.trim());
+                        if ui.add_enabled(valid, egui::Button::new("Add")).clicked() {
+                            let result = xattrs::set(
+                                &self.path,
+                                self.new_name.trim(),
+                                self.new_value.as_bytes(),
+                            );
+                            self.xattr_error = result.err().map(|e| e.to_string());
+                            if self.xattr_error.is_none() {
+                                self.new_name = String::from("user.");
+                                self.new_value.clear();
+                                added = true;
+                            }
+                        }
+                        ui.end_row();
+                    });
+            }
+        }
+        if cfg!(target_os = "linux") {
+            ui.weak("Only user.* attributes can be added or changed here.");
+        }
+        if let Some(error) = &self.xattr_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        let result = match (&self.xattrs, save, delete) {
+            (Ok(list), Some(i), _) => {
+                xattrs::set(&self.path, &list[i].name, list[i].value.as_bytes()).map(|()| true)
+            }
+            (Ok(list), _, Some(i)) => xattrs::remove(&self.path, &list[i].name).map(|()| true),
+            _ => Ok(added),
+        };
+        match result {
+            Ok(true) => {
+                self.xattr_error = None;
+                self.load_xattrs();
+            }
+            Ok(false) => {}
+            Err(e) => self.xattr_error = Some(e.to_string()),
+        }
+    }
+
+    fn acl_tab(&mut self, ui: &mut egui::Ui) {
+        if self.acl.is_none() {
+            let entries = acl::read(&self.path);
+            self.acl_edited = entries.clone().unwrap_or_default();
+            self.acl = Some(entries);
+        }
+        if let Some(Err(e)) = &self.acl {
+            ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+            return;
+        }
+
+        let is_dir = self.path.is_dir();
+        let mut remove = None;
+        egui::Grid::new(("properties_acl", &self.path))
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Applies to");
+                ui.strong("Name");
+                ui.strong("Read");
+                ui.strong("Write");
+                ui.strong("Execute");
+                ui.end_row();
+                for (i, entry) in self.acl_edited.iter_mut().enumerate() {
+                    let applies = if entry.default {
+                        format!("{} (new items)", entry.tag.label())
+                    } else {
+                        entry.tag.label().to_string()
+                    };
+                    ui.label(applies);
+                    match (entry.tag, entry.is_base()) {
+                        (acl::Tag::User, true) => ui.weak("Owner"),
+                        (acl::Tag::Group, true) => ui.weak("Owning group"),
+                        (_, true) => ui.weak(""),
+                        (_, false) => ui.label(&entry.qualifier),
+                    };
+                    ui.checkbox(&mut entry.read, "");
+                    ui.checkbox(&mut entry.write, "");
+                    ui.checkbox(&mut entry.execute, "");
+                    if !entry.is_base() && ui.small_button("🗑").on_hover_text("Remove").clicked()
+                    {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = remove {
+            self.acl_edited.remove(i);
+        }
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source(("acl_tag", &self.path))
+                .selected_text(self.new_acl_tag.label())
+                .show_ui(ui, |ui| {
+                    for tag in [acl::Tag::User, acl::Tag::Group] {
+                        ui.selectable_value(&mut self.new_acl_tag, tag, tag.label());
+                    }
+                });
+            ui.add(egui::TextEdit::singleline(&mut self.new_acl_name).hint_text("name"));
+            if is_dir {
+                ui.checkbox(&mut self.new_acl_default, "For new items")
+                    .on_hover_text("A default entry, inherited by items created inside");
+            }
+            let name = self.new_acl_name.trim();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Add"))
+                .clicked()
+            {
+                self.acl_edited.push(acl::Entry {
+                    default: self.new_acl_default && is_dir,
+                    tag: self.new_acl_tag,
+                    qualifier: name.to_string(),
+                    read: true,
+                    write: false,
+                    execute: is_dir,
+                });
+                self.new_acl_name.clear();
+            }
+        });
+
+        let loaded = self.acl.as_ref().and_then(|a| a.as_ref().ok()).cloned();
+        let edited = loaded.as_ref() != Some(&self.acl_edited);
+        ui.horizontal(|ui| {
+            if ui.add_enabled(edited, egui::Button::new("Apply")).clicked() {
+                match acl::write(&self.path, &self.acl_edited) {
+                    Ok(()) => {
+                        self.acl_error = None;
+                        // Read back, as setfacl recalculates the mask
+                        self.acl = None;
+                    }
+                    Err(e) => self.acl_error = Some(e),
+                }
+            }
+            if ui
+                .add_enabled(edited, egui::Button::new("Revert"))
+                .clicked()
+            {
+                self.acl_edited = loaded.clone().unwrap_or_default();
+                self.acl_error = None;
+            }
+        });
+        ui.weak("The mask caps what named users and groups get; it's recalculated on Apply.");
+        if let Some(error) = &self.acl_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn ntfs_tab(&mut self, ui: &mut egui::Ui) {
+        let aces = self.ntfs.get_or_insert_with(|| acl::read_ntfs(&self.path));
+        let aces = match aces {
+            Ok(aces) => aces,
+            Err(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+                return;
+            }
+        };
+
+        let mut remove = None;
+        egui::Grid::new(("properties_ntfs", &self.path))
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for ace in aces.iter() {
+                    ui.label(&ace.principal);
+                    ui.monospace(&ace.rights);
+                    let button = ui.add_enabled(!ace.inherited, egui::Button::new("🗑").small());
+                    if button
+                        .on_hover_text("Remove")
+                        .on_disabled_hover_text("Inherited from the parent folder")
+                        .clicked()
+                    {
+                        remove = Some(ace.principal.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+
+        let mut grant = None;
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_acl_name).hint_text("DOMAIN\\user"));
+            let levels = [
+                ("R", "Read"),
+                ("RX", "Read & execute"),
+                ("M", "Modify"),
+                ("F", "Full control"),
+            ];
+            let selected = levels
+                .iter()
+                .find(|(code, _)| *code == self.new_ntfs_rights)
+                .map_or("", |(_, label)| label);
+            egui::ComboBox::from_id_source(("ntfs_rights", &self.path))
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (code, label) in levels {
+                        ui.selectable_value(&mut self.new_ntfs_rights, code, label);
+                    }
+                });
+            let name = self.new_acl_name.trim();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Grant"))
+                .clicked()
+            {
+                grant = Some(name.to_string());
+            }
+        });
+        ui.weak(
+            "(OI) and (CI) are inherited by files and folders inside, (I) comes from the parent.",
+        );
+
+        let result = match (grant, remove) {
+            (Some(principal), _) => {
+                acl::grant_ntfs(&self.path, &principal, self.new_ntfs_rights).map(|()| true)
+            }
+            (_, Some(principal)) => acl::remove_ntfs(&self.path, &principal).map(|()| true),
+            _ => Ok(false),
+        };
+        match result {
+            Ok(true) => {
+                self.acl_error = None;
+                self.new_acl_name.clear();
+                self.ntfs = None;
+            }
+            Ok(false) => {}
+            Err(e) => self.acl_error = Some(e),
+        }
+        if let Some(error) = &self.acl_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn versions_tab(&mut self, ui: &mut egui::Ui) {
+        if self.versions.is_none() && self.finding_versions.is_none() {
+            self.finding_versions = Some(find_versions(self.path.clone(), self.repaint.clone()));
+        }
+        if let Some(finding) = &self.finding_versions {
+            match finding.try_recv() {
+                Ok(found) => self.versions = Some(found),
+                Err(mpsc::TryRecvError::Disconnected) => self.versions = Some(Vec::new()),
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+            if self.versions.is_some() {
+                self.finding_versions = None;
+            }
+        }
+        let Some(versions) = &self.versions else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.weak("Looking for previous versions…");
+            });
+            return;
+        };
+        if versions.is_empty() {
+            ui.weak("No snapshot holds a different version of this item.");
+        }
+
+        let mut restore = None;
+        let mut restore_copy = None;
+        egui::Grid::new(("properties_versions", &self.path))
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, version) in versions.iter().enumerate() {
+                    ui.label(&version.snapshot);
+                    ui.label(
+                        version
+                            .modified
+                            .map(|m| {
+                                let datetime: chrono::DateTime<chrono::Local> = m.into();
+                                datetime.format("%d/%m/%Y %H:%M").to_string()
+                            })
+                            .unwrap_or_default(),
+                    );
+                    ui.label(crate::format_file_size(version.size));
+                    ui.horizontal(|ui| {
+                        if ui.small_button("Open").clicked() {
+                            if let Err(e) =
+                                crate::launch::open_with_system(version.path.as_os_str())
+                            {
+                                tracing::error!("Failed to open {}: {}", version.path.display(), e);
+                            }
+                        }
+                        if self.read_only {
+                            return;
+                        }
+                        if ui
+                            .small_button("Restore Copy")
+                            .on_hover_text("Saves this version next to the current file")
+                            .clicked()
+                        {
+                            restore_copy = Some(i);
+                        }
+                        // Folders are only restored as copies
+                        if version.is_dir {
+                            return;
+                        }
+                        if self.confirm_restore == Some(i) {
+                            if ui.small_button("Replace current file?").clicked() {
+                                restore = Some(i);
+                            }
+                        } else if ui.small_button("Restore").clicked() {
+                            self.confirm_restore = Some(i);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        match &self.restore_result {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+            None => {}
+        }
+
+        if let Some(i) = restore {
+            self.confirm_restore = None;
+            self.restore_result = Some(
+                snapshots::restore(&versions[i], &self.path)
+                    .map(|()| format!("Restored the version from {}", versions[i].snapshot))
+                    .map_err(|e| e.to_string()),
+            );
+            self.versions = None;
+        } else if let Some(i) = restore_copy {
+            self.restore_result = Some(
+                snapshots::restore_copy(&versions[i], &self.path)
+                    .map(|p| format!("Saved as {}", p.display()))
+                    .map_err(|e| e.to_string()),
+            );
+        }
+    }
+
+    fn streams_tab(&mut self, ui: &mut egui::Ui) {
+        let mut remove = None;
+        if let Some(zone) = &self.zone {
+            ui.horizontal(|ui| {
+                ui.label(format!("⚠ Blocked: {}", zone));
+                if ui
+                    .button("Unblock")
+                    .on_hover_text("Removes the Zone.Identifier stream so Windows trusts the file")
+                    .clicked()
+                {
+                    remove = Some(streams::ZONE_IDENTIFIER.to_string());
+                }
+            });
+            ui.separator();
+        }
+
+        match &self.streams {
+            Err(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+            }
+            Ok(list) if list.is_empty() => {
+                ui.weak("No alternate data streams");
+            }
+            Ok(list) => {
+                egui::Grid::new(("properties_streams", &self.path))
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for stream in list {
+                            ui.monospace(&stream.name);
+                            ui.label(crate::format_file_size(stream.size));
+                            if ui
+                                .small_button("🗑")
+                                .on_hover_text("Delete stream")
+                                .clicked()
+                            {
+                                remove = Some(stream.name.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+            }
+        }
+        if let Some(error) = &self.stream_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        if let Some(name) = remove {
+            self.stream_error = streams::remove(&self.path, &name)
+                .err()
+                .map(|e| e.to_string());
+            self.load_streams();
+        }
+    }
+}
+
+// Everything below the selected items, counted so far
+#[derive(Clone, Copy, Default)]
+struct Totals {
+    files: u64,
+    folders: u64,
+    size: u64,
+    done: bool,
+}
+
+// Properties of several items at once: what they add up to, and the
+// permissions they share, which can be changed on all of them together
+pub struct BulkPropertiesDialog {
+    targets: Vec<PathBuf>,
+    general: Vec<(&'static str, String)>,
+    totals: Totals,
+    // Disconnects once everything is counted; dropping it stops the count
+    counting: Option<Receiver<Totals>>,
+    // Each target's permission bits, None where they couldn't be read and
+    // for links, which are left alone
+    bits: Vec<Option<u32>>,
+    links: usize,
+    // Bits ticked or unticked since, for every target
+    set: u32,
+    cleared: u32,
+    error: Option<String>,
+    read_only: bool,
+}
+
+impl BulkPropertiesDialog {
+    pub fn new(targets: Vec<PathBuf>, repaint: Repaint, read_only: bool) -> Self {
+        let metas: Vec<Option<fs::Metadata>> = targets
+            .iter()
+            .map(|t| fs::symlink_metadata(t).ok())
+            .collect();
+        let folders = metas.iter().flatten().filter(|m| m.is_dir()).count();
+        let files = targets.len() - folders;
+        let mut selected = Vec::new();
+        if files > 0 {
+            selected.push(crate::ops::plural(files, "file"));
+        }
+        if folders > 0 {
+            selected.push(crate::ops::plural(folders, "folder"));
+        }
+        let mut general = vec![("Selected", selected.join(", "))];
+        let parent = targets[0].parent();
+        if targets.iter().all(|t| t.parent() == parent) {
+            if let Some(parent) = parent {
+                general.push(("Location", parent.display().to_string()));
+            }
+        } else {
+            general.push(("Location", String::from("Various folders")));
+        }
+
+        Self {
+            bits: targets.iter().map(|t| own_permission_bits(t)).collect(),
+            links: metas.iter().flatten().filter(|m| m.is_symlink()).count(),
+            counting: Some(count(targets.clone(), repaint)),
+            targets,
+            general,
+            totals: Totals::default(),
+            set: 0,
+            cleared: 0,
+            error: None,
+            read_only,
+        }
+    }
+
+    // Returns false once the window has been closed. `salt` is the id of the
+    // browser showing it.
+    pub fn show(&mut self, ctx: &egui::Context, salt: egui::Id) -> bool {
+        if let Some(counting) = &self.counting {
+            if let Some(totals) = counting.try_iter().last() {
+                self.totals = totals;
+            }
+            if self.totals.done {
+                self.counting = None;
+            }
+        }
+
+        let mut open = true;
+        egui::Window::new("Properties")
+            .id(salt.with(("bulk_properties", &self.targets[0], self.targets.len())))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                let totals = self.totals;
+                let mut general = self.general.clone();
+                let so_far = if totals.done { "" } else { " so far" };
+                general.push((
+                    "Size",
+                    format!(
+                        "{} ({} bytes){}",
+                        crate::format_file_size(totals.size),
+                        totals.size,
+                        so_far
+                    ),
+                ));
+                general.push((
+                    "Contains",
+                    format!(
+                        "{}, {}{}",
+                        crate::ops::plural(totals.files as usize, "file"),
+                        crate::ops::plural(totals.folders as usize, "folder"),
+                        so_far
+                    ),
+                ));
+                rows(ui, "bulk_properties_general", &general);
+                if !totals.done {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.weak("Adding up the contents…");
+                    });
+                }
+                ui.separator();
+                ui.strong("Permissions");
+                ui.add_enabled_ui(!self.read_only, |ui| self.permissions(ui));
+            });
+        open
+    }
+
+    // One checkbox per bit, shown as mixed where the items disagree and
+    // nothing was picked for it yet
+    fn permissions(&mut self, ui: &mut egui::Ui) {
+        let readable: Vec<u32> = self.bits.iter().flatten().copied().collect();
+        if self.links > 0 {
+            ui.weak(format!(
+                "{} left as they are, they take the permissions of what they point to",
+                crate::ops::plural(self.links, "link")
+            ));
+        }
+        if readable.is_empty() {
+            if self.links < self.targets.len() {
+                ui.weak("The permissions of these items can't be read.");
+            }
+            return;
+        }
+        let (set, cleared) = (&mut self.set, &mut self.cleared);
+        let mut checkbox = |ui: &mut egui::Ui, bit: u32, text: &str| {
+            let all = readable.iter().all(|b| b & bit != 0);
+            let none = readable.iter().all(|b| b & bit == 0);
+            let mut checked = *set & bit != 0 || (*cleared & bit == 0 && all);
+            let mixed = (*set | *cleared) & bit == 0 && !all && !none;
+            let mut response = ui.add(egui::Checkbox::new(&mut checked, text).indeterminate(mixed));
+            if mixed {
+                response = response.on_hover_text("Differs between the selected items");
+            }
+            // Once picked it's on or off for all of them; picking what they
+            // all had already counts as no change
+            if response.clicked() {
+                *set &= !bit;
+                *cleared &= !bit;
+                if checked && !all {
+                    *set |= bit;
+                } else if !checked && !none {
+                    *cleared |= bit;
+                }
+            }
+        };
+        permission_grid(ui, &mut checkbox);
+
+        let edited = self.set | self.cleared != 0;
+        ui.horizontal(|ui| {
+            if ui.add_enabled(edited, egui::Button::new("Apply")).clicked() {
+                self.apply();
+            }
+            if ui
+                .add_enabled(edited, egui::Button::new("Revert"))
+                .clicked()
+            {
+                self.set = 0;
+                self.cleared = 0;
+                self.error = None;
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn apply(&mut self) {
+        let changed = self.set | self.cleared;
+        let mut failed = Vec::new();
+        // Setting them on a link would change what it points to instead
+        let targets = self
+            .targets
+            .iter()
+            .filter(|t| !fs::symlink_metadata(t).is_ok_and(|m| m.is_symlink()));
+        for target in targets {
+            if let Err(e) = set_permission_bits(target, self.set, changed) {
+                failed.push(format!("{}: {}", target.display(), e));
+            }
+        }
+        self.error = match failed.as_slice() {
+            [] => None,
+            [error] => Some(format!("Failed to change {}", error)),
+            [first, ..] => Some(format!(
+                "Failed to change {}, first {}",
+                crate::ops::plural(failed.len(), "item"),
+                first
+            )),
+        };
+        if failed.is_empty() {
+            tracing::info!(
+                "Changed the permissions of {}",
+                crate::ops::plural(self.targets.len(), "item")
+            );
+        }
+        self.bits = self
+            .targets
+            .iter()
+            .map(|t| own_permission_bits(t))
+            .collect();
+        self.set = 0;
+        self.cleared = 0;
+    }
+}
+
+fn find_versions(path: PathBuf, repaint: Repaint) -> Receiver<Vec<Version>> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(snapshots::versions(&path));
+        repaint.request();
+    });
+    receiver
+}
+
+// Walks everything below the targets on a background thread, sending the
+// running totals every so often and once more when done
+fn count(targets: Vec<PathBuf>, repaint: Repaint) -> Receiver<Totals> {
+    const EVERY: Duration = Duration::from_millis(200);
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut totals = Totals::default();
+        let mut sent = Instant::now();
+        for target in &targets {
+            // The selected folders themselves aren't part of what's inside
+            for entry in WalkDir::new(target).min_depth(1).into_iter().flatten() {
+                if entry.file_type().is_dir() {
+                    totals.folders += 1;
+                } else {
+                    totals.files += 1;
+                }
+                if entry.file_type().is_file() {
+                    totals.size += entry.metadata().map_or(0, |m| m.len());
+                }
+                if sent.elapsed() >= EVERY {
+                    if sender.send(totals).is_err() {
+                        return;
+                    }
+                    repaint.request();
+                    sent = Instant::now();
+                }
+            }
+            // Files and links that were selected count once each
+            if !target.is_dir() {
+                totals.files += 1;
+                totals.size += fs::symlink_metadata(target).map_or(0, |m| m.len());
+            }
+        }
+        totals.done = true;
+        let _ = sender.send(totals);
+        repaint.request();
+    });
+    receiver
+}
+
+// None for links, whose own bits mean nothing
+fn own_permission_bits(path: &Path) -> Option<u32> {
+    let meta = fs::symlink_metadata(path).ok()?;
+    (!meta.is_symlink()).then(|| permission_bits(&meta))
+}
+
+// rwx for the owner, group and others
+#[cfg(unix)]
+fn permission_bits(meta: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o777
+}
+
+// Only the read-only attribute, as bit 1
+#[cfg(not(unix))]
+fn permission_bits(meta: &fs::Metadata) -> u32 {
+    meta.permissions().readonly() as u32
+}
+
+// Sets the bits in `mask` to how they are in `bits`, leaving the others
+#[cfg(unix)]
+fn set_permission_bits(path: &Path, bits: u32, mask: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(path)?.permissions().mode();
+    fs::set_permissions(
+        path,
+        fs::Permissions::from_mode((mode & !mask) | (bits & mask)),
+    )
+}
+
+#[cfg(not(unix))]
+fn set_permission_bits(path: &Path, bits: u32, mask: u32) -> io::Result<()> {
+    if mask & 1 == 0 {
+        return Ok(());
+    }
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_readonly(bits & 1 != 0);
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(unix)]
+fn permission_grid(ui: &mut egui::Ui, checkbox: &mut impl FnMut(&mut egui::Ui, u32, &str)) {
+    egui::Grid::new("bulk_permissions")
+        .num_columns(4)
+        .show(ui, |ui| {
+            for (who, shift) in [("Owner", 6), ("Group", 3), ("Others", 0)] {
+                ui.label(format!("{}:", who));
+                checkbox(ui, 0o4 << shift, "Read");
+                checkbox(ui, 0o2 << shift, "Write");
+                checkbox(ui, 0o1 << shift, "Execute");
+                ui.end_row();
+            }
+        });
+}
+
+#[cfg(not(unix))]
+fn permission_grid(ui: &mut egui::Ui, checkbox: &mut impl FnMut(&mut egui::Ui, u32, &str)) {
+    checkbox(ui, 1, "Read-only");
+}
+
+#[cfg(unix)]
+fn inode_rows(meta: &fs::Metadata) -> Vec<(&'static str, String)> {
+    use std::os::unix::fs::MetadataExt;
+    let mut rows = vec![
+        ("Inode", meta.ino().to_string()),
+        ("Device", device_label(meta.dev())),
+    ];
+    let links = meta.nlink();
+    if meta.is_file() && links > 1 {
+        rows.push((
+            "Hard links",
+            format!("{} (changes show up under every name)", links),
+        ));
+    } else {
+        rows.push(("Hard links", links.to_string()));
+    }
+    rows
+}
+
+#[cfg(not(unix))]
+fn inode_rows(_meta: &fs::Metadata) -> Vec<(&'static str, String)> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+fn device_label(dev: u64) -> String {
+    format!("{}:{}", libc::major(dev), libc::minor(dev))
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn device_label(dev: u64) -> String {
+    format!("{:#x}", dev)
+}
+
+fn rows(ui: &mut egui::Ui, id: &str, rows: &[(&'static str, String)]) {
+    egui::Grid::new(id).num_columns(2).show(ui, |ui| {
+        for (label, value) in rows {
+            ui.label(format!("{}:", label));
+            ui.add(egui::Label::new(value).wrap());
+            ui.end_row();
+        }
+    });
+}
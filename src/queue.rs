@@ -0,0 +1,138 @@
+// Transfers to and from the same hard disk take turns: several at once make
+// its heads jump back and forth, and all of them end up slower. Different
+// drives, and SSDs, which don't seek, still work in parallel.
+use crate::jobs::JobHandle;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+// Transfers running per drive, by the disk's device number
+static RUNNING: Mutex<Option<HashMap<u64, usize>>> = Mutex::new(None);
+static FREED: Condvar = Condvar::new();
+
+// Held while a transfer runs; gives its places back when dropped
+pub struct Turn {
+    drives: Vec<u64>,
+}
+
+impl Drop for Turn {
+    fn drop(&mut self) {
+        if self.drives.is_empty() {
+            return;
+        }
+        let mut running = RUNNING.lock().unwrap();
+        let running = running.get_or_insert_with(HashMap::new);
+        for drive in &self.drives {
+            if let Some(count) = running.get_mut(drive) {
+                *count = count.saturating_sub(1);
+            }
+        }
+        FREED.notify_all();
+    }
+}
+
+// Waits until every hard disk among `paths` runs fewer than `limit`
+// transfers (0 for no limit), then takes a place on all of them at once so
+// two transfers can't each hold a drive the other waits for. None if the job
+// was cancelled while waiting.
+pub fn wait_turn(paths: &[&Path], limit: usize, job: &JobHandle) -> Option<Turn> {
+    let mut drives: Vec<u64> = if limit == 0 {
+        Vec::new()
+    } else {
+        paths
+            .iter()
+            .filter_map(|p| drive_id(p))
+            .filter(|&d| spinning(d))
+            .collect()
+    };
+    drives.sort_unstable();
+    drives.dedup();
+    if drives.is_empty() {
+        return Some(Turn { drives });
+    }
+
+    let mut waiting = false;
+    let mut running = RUNNING.lock().unwrap();
+    loop {
+        let counts = running.get_or_insert_with(HashMap::new);
+        if drives
+            .iter()
+            .all(|d| counts.get(d).copied().unwrap_or(0) < limit)
+        {
+            for drive in &drives {
+                *counts.entry(*drive).or_default() += 1;
+            }
+            if waiting {
+                job.set_message("");
+            }
+            return Some(Turn { drives });
+        }
+        if !waiting {
+            job.set_message("Waiting for another transfer on the same disk");
+            waiting = true;
+        }
+        // Woken by finishing transfers; the timeout notices cancelling
+        running = FREED
+            .wait_timeout(running, Duration::from_millis(250))
+            .unwrap()
+            .0;
+        if job.is_cancelled() {
+            return None;
+        }
+    }
+}
+
+// The device number of the disk `path` is on. Filesystems are numbered per
+// partition, so two partitions of one disk are mapped to the same disk.
+#[cfg(unix)]
+fn drive_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|m| disk_of(m.dev()))
+}
+
+// A partition's sysfs folder sits inside its disk's, whose `dev` file holds
+// the disk's "major:minor". Anything else (whole disks, LVM, tmpfs, network
+// filesystems) is left as it is.
+#[cfg(target_os = "linux")]
+fn disk_of(dev: u64) -> u64 {
+    let block = format!("/sys/dev/block/{}:{}", libc::major(dev), libc::minor(dev));
+    let block = Path::new(&block);
+    if !block.join("partition").exists() {
+        return dev;
+    }
+    std::fs::read_to_string(block.join("../dev"))
+        .ok()
+        .and_then(|numbers| {
+            let (major, minor) = numbers.trim().split_once(':')?;
+            Some(libc::makedev(major.parse().ok()?, minor.parse().ok()?))
+        })
+        .unwrap_or(dev)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn disk_of(dev: u64) -> u64 {
+    dev
+}
+
+#[cfg(not(unix))]
+fn drive_id(_path: &Path) -> Option<u64> {
+    None
+}
+
+// Whether the kernel reports the disk as rotational. Partitions have no
+// queue of their own, so it's looked up on their disk.
+#[cfg(target_os = "linux")]
+fn spinning(dev: u64) -> bool {
+    let block = format!("/sys/dev/block/{}:{}", libc::major(dev), libc::minor(dev));
+    ["queue/rotational", "../queue/rotational"]
+        .iter()
+        .find_map(|file| std::fs::read_to_string(Path::new(&block).join(file)).ok())
+        .is_some_and(|value| value.trim() == "1")
+}
+
+// Other systems can't tell, so nothing waits there
+#[cfg(not(target_os = "linux"))]
+fn spinning(_dev: u64) -> bool {
+    false
+}
@@ -0,0 +1,141 @@
+// Crash recovery: the browser keeps a snapshot of where it is, which tabs are
+// open and which jobs are running, and the panic hook writes it out so the
+// next launch can say what was in flight when things went wrong
+use crate::jobs::{JobState, Jobs};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub current_dir: PathBuf,
+    // Every tab, including those of detached windows
+    pub tabs: Vec<SavedTab>,
+    pub jobs: Vec<PendingJob>,
+    // Filled in by the panic hook
+    pub crashed_at: String,
+    pub panic: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedTab {
+    pub dir: PathBuf,
+    pub pinned: bool,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingJob {
+    pub title: String,
+    pub message: String,
+    pub done: u64,
+    pub total: Option<u64>,
+}
+
+fn snapshot() -> &'static Mutex<Session> {
+    static SNAPSHOT: OnceLock<Mutex<Session>> = OnceLock::new();
+    SNAPSHOT.get_or_init(|| Mutex::new(Session::default()))
+}
+
+fn crash_file() -> Option<PathBuf> {
+    crate::user_dirs::data_dir().map(|d| d.join("crashed-session.toml"))
+}
+
+// Called every frame with the jobs of every window; cheap when nothing
+// changed
+pub fn update<'a>(
+    current_dir: &Path,
+    tabs: Vec<SavedTab>,
+    jobs: impl IntoIterator<Item = &'a Jobs>,
+) {
+    let pending: Vec<PendingJob> = jobs
+        .into_iter()
+        .flat_map(Jobs::iter)
+        .map(|job| (job, job.status()))
+        .filter(|(_, status)| status.state == JobState::Running)
+        .map(|(job, status)| PendingJob {
+            title: job.title.clone(),
+            message: status.message,
+            done: status.done,
+            total: status.total,
+        })
+        .collect();
+    let mut session = snapshot().lock().unwrap();
+    if session.current_dir != current_dir {
+        session.current_dir = current_dir.to_path_buf();
+    }
+    if session.tabs != tabs {
+        session.tabs = tabs;
+    }
+    if session.jobs != pending {
+        session.jobs = pending;
+    }
+}
+
+thread_local! {
+    // What the last panic on this thread said, for `pool_panicked`
+    static LAST_PANIC: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+// Saves the snapshot when the main thread panics. Panics inside background
+// jobs are caught by the job runner and only fail that job; those in tasks
+// spawned on a rayon pool end the process, see `pool_panicked`.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        tracing::error!("{}", info);
+        if std::thread::current().name() == Some("main") {
+            save_crash(info.to_string());
+        } else {
+            LAST_PANIC.with(|last| *last.borrow_mut() = info.to_string());
+        }
+        default_hook(info);
+    }));
+}
+
+// Panic handler for the rayon pools (metadata-*, thumbnails-*). A panic in a
+// spawned task has nobody to go back to, so the session is saved and the
+// process aborted, as rayon would do without a handler.
+pub fn pool_panicked(_payload: Box<dyn Any + Send>) {
+    save_crash(LAST_PANIC.with(|last| last.take()));
+    std::process::abort();
+}
+
+fn save_crash(panic: String) {
+    let Some(path) = crash_file() else {
+        return;
+    };
+    // The panic may have happened while the snapshot was locked
+    let mut session = match snapshot().try_lock() {
+        Ok(session) => session.clone(),
+        Err(_) => return,
+    };
+    session.crashed_at = chrono::Local::now().format("%d/%m/%Y at %H:%M").to_string();
+    session.panic = panic;
+    let result = toml::to_string_pretty(&session)
+        .map_err(std::io::Error::other)
+        .and_then(|contents| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, contents)
+        });
+    if let Err(e) = result {
+        eprintln!("Failed to save the session: {}", e);
+    }
+}
+
+// The session saved by the last crash, if any; it's only offered once
+pub fn take_crashed() -> Option<Session> {
+    let path = crash_file()?;
+    let contents = fs::read_to_string(&path).ok()?;
+    if let Err(e) = fs::remove_file(&path) {
+        tracing::error!("Failed to remove {}: {}", path.display(), e);
+    }
+    toml::from_str(&contents)
+        .map_err(|e| tracing::warn!("Invalid crashed session {}: {}", path.display(), e))
+        .ok()
+}
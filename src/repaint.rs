@@ -0,0 +1,24 @@
+// Lets background threads wake the UI when they have something new to show,
+// so nothing has to poll on a timer. Handles are made before the first frame,
+// when there's no egui context yet, and start working once it's set.
+use eframe::egui;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+// Requests within this window are merged into one repaint
+const COALESCE: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Default)]
+pub struct Repaint(Arc<OnceLock<egui::Context>>);
+
+impl Repaint {
+    pub fn set(&self, ctx: &egui::Context) {
+        self.0.get_or_init(|| ctx.clone());
+    }
+
+    pub fn request(&self) {
+        if let Some(ctx) = self.0.get() {
+            ctx.request_repaint_after(COALESCE);
+        }
+    }
+}
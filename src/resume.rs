@@ -0,0 +1,169 @@
+// Transfers cut short by the app closing or crashing can be picked up where
+// they stopped. While a transfer runs, a checkpoint in <data dir>/transfers
+// records what it's doing; it goes away when the transfer ends, so any left
+// at startup belong to interrupted transfers. Resuming skips items that were
+// finished and continues partly copied files once their start matches.
+use crate::config::TransferOptions;
+use crate::ops::{Transfer, TransferMode};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// How far a transfer got
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progress {
+    // Where each item started so far goes; copies made next to their
+    // original get a new name, which has to be the same one on resuming
+    pub targets: Vec<(PathBuf, PathBuf)>,
+    // Items that were completely transferred
+    pub finished: Vec<PathBuf>,
+}
+
+impl Progress {
+    pub fn target(&self, source: &Path) -> Option<&Path> {
+        self.targets
+            .iter()
+            .find(|(s, _)| s == source)
+            .map(|(_, t)| t.as_path())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub sources: Vec<PathBuf>,
+    pub destination: PathBuf,
+    pub mode: TransferMode,
+    pub options: TransferOptions,
+    pub progress: Progress,
+    // Local time it started, "2024-05-01 14:03"
+    pub started: String,
+    // The app instance running it; another one still running isn't
+    // interrupted
+    pub pid: u32,
+    #[serde(skip)]
+    file: PathBuf,
+}
+
+fn checkpoint_dir() -> Option<PathBuf> {
+    crate::user_dirs::data_dir().map(|d| d.join("transfers"))
+}
+
+impl Checkpoint {
+    // Writes the first checkpoint for a transfer that's starting
+    pub fn start(transfer: &Transfer) -> Option<Self> {
+        let dir = checkpoint_dir()?;
+        let now = chrono::Local::now();
+        let file = dir.join(format!(
+            "{}-{}.toml",
+            std::process::id(),
+            now.timestamp_nanos_opt().unwrap_or_default()
+        ));
+        if let Err(e) = fs::create_dir_all(&dir) {
+            tracing::warn!("Failed to create {}: {}", dir.display(), e);
+            return None;
+        }
+        let checkpoint = Self::new(transfer, file);
+        checkpoint.save();
+        Some(checkpoint)
+    }
+
+    // Kept in `file`, which isn't written until the first save
+    pub fn new(transfer: &Transfer, file: PathBuf) -> Self {
+        Self {
+            sources: transfer.sources.clone(),
+            destination: transfer.destination.clone(),
+            mode: transfer.mode,
+            options: transfer.options.clone(),
+            progress: transfer.resume.clone().unwrap_or_default(),
+            started: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            pid: std::process::id(),
+            file,
+        }
+    }
+
+    pub fn save(&self) {
+        let result = toml::to_string(self)
+            .map_err(std::io::Error::other)
+            .and_then(|text| fs::write(&self.file, text));
+        if let Err(e) = result {
+            tracing::warn!("Failed to save the transfer checkpoint: {}", e);
+        }
+    }
+
+    // For the transfer ending, or the user not wanting it resumed
+    pub fn remove(&self) {
+        if let Err(e) = fs::remove_file(&self.file) {
+            tracing::warn!("Failed to remove {}: {}", self.file.display(), e);
+        }
+    }
+
+    pub fn transfer(&self) -> Transfer {
+        Transfer {
+            sources: self.sources.clone(),
+            destination: self.destination.clone(),
+            mode: self.mode,
+            options: self.options.clone(),
+            resume: Some(self.progress.clone()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let verb = match self.mode {
+            TransferMode::Copy => "Copying",
+            TransferMode::Move => "Moving",
+        };
+        format!(
+            "{} {} to {} ({} done), started {}",
+            verb,
+            crate::ops::plural(self.sources.len(), "item"),
+            self.destination.display(),
+            self.progress.finished.len(),
+            self.started
+        )
+    }
+}
+
+// Checkpoints left behind by earlier runs. Only the first window asking gets
+// them, so they're offered once.
+pub fn interrupted() -> Vec<Checkpoint> {
+    static OFFERED: AtomicBool = AtomicBool::new(false);
+    if OFFERED.swap(true, Ordering::Relaxed) {
+        return Vec::new();
+    }
+    let Some(entries) = checkpoint_dir().and_then(|d| fs::read_dir(d).ok()) else {
+        return Vec::new();
+    };
+    let mut checkpoints: Vec<Checkpoint> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let text = fs::read_to_string(&path).ok()?;
+            match toml::from_str::<Checkpoint>(&text) {
+                Ok(checkpoint) => Some(Checkpoint {
+                    file: path,
+                    ..checkpoint
+                }),
+                Err(e) => {
+                    tracing::warn!("Invalid transfer checkpoint {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .filter(|checkpoint| !still_running(checkpoint.pid))
+        .collect();
+    checkpoints.sort_by(|a, b| a.started.cmp(&b.started));
+    checkpoints
+}
+
+#[cfg(unix)]
+fn still_running(pid: u32) -> bool {
+    pid != std::process::id() && unsafe { libc::kill(pid as libc::pid_t, 0) } == 0
+}
+
+// Can't tell; another instance's transfers get offered too
+#[cfg(not(unix))]
+fn still_running(_pid: u32) -> bool {
+    false
+}
@@ -0,0 +1,352 @@
+// Rhai scripts from <config dir>/scripts/*.rhai are loaded at startup. A
+// script can define event hooks:
+//
+//   fn on_open_directory(dir) { ... }     // dir: string
+//   fn on_select(paths) { ... }           // paths: array of strings
+//
+// Any other public function without parameters is listed under
+// Tools → Scripts, so batch workflows can be run on demand. Scripts talk to
+// the app through these functions:
+//
+//   current_dir()          selection()           list_dir(path)
+//   navigate(path)         select(pattern)       clear_selection()
+//   run(command)           rename(from, to)      copy_file(from, to)
+//   create_dir(path)       exists(path)          is_dir(path)
+//
+// rename, copy_file and create_dir never replace anything and are logged in
+// the history like the app's own changes. In read-only mode they fail and
+// run does nothing. Scripts run on the UI thread, so they're stopped once
+// they take too many steps, recurse too deep or build huge values.
+
+use crate::history;
+use file_manager_core::rename;
+use file_manager_core::LocalFs;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope, AST};
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Roughly a second of work before a script is stopped
+const MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_STRING_SIZE: usize = 1 << 20;
+const MAX_COLLECTION_SIZE: usize = 100_000;
+
+pub enum ScriptCommand {
+    Navigate(PathBuf),
+    Select(String),
+    ClearSelection,
+    Run(String),
+    // A script touched the filesystem, so the listing may be stale
+    Refresh,
+}
+
+#[derive(Default)]
+struct State {
+    read_only: bool,
+    current_dir: PathBuf,
+    selection: Vec<PathBuf>,
+    commands: Vec<ScriptCommand>,
+}
+
+pub struct Script {
+    pub name: String,
+    ast: AST,
+}
+
+impl Script {
+    // Public parameterless functions, shown in the Scripts menu
+    pub fn menu_functions(&self) -> Vec<String> {
+        self.ast
+            .iter_functions()
+            .filter(|f| f.params.is_empty() && !f.access.is_private())
+            .filter(|f| !f.name.starts_with("on_"))
+            .map(|f| f.name.to_string())
+            .collect()
+    }
+
+    fn defines(&self, name: &str, arity: usize) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|f| f.name == name && f.params.len() == arity)
+    }
+}
+
+pub struct ScriptHost {
+    engine: Engine,
+    state: Rc<RefCell<State>>,
+    pub scripts: Vec<Script>,
+    pub read_only: bool,
+}
+
+pub fn scripts_dir() -> Option<PathBuf> {
+    crate::user_dirs::config_dir().map(|d| d.join("scripts"))
+}
+
+fn to_strings(paths: &[PathBuf]) -> Array {
+    paths
+        .iter()
+        .map(|p| Dynamic::from(p.to_string_lossy().into_owned()))
+        .collect()
+}
+
+// Records the change in the history, as the app's own are
+fn logged(
+    operation: history::Operation,
+    source: &Path,
+    destination: Option<&Path>,
+    result: io::Result<()>,
+) -> Result<(), Box<EvalAltResult>> {
+    let result = result.map_err(|e| e.to_string());
+    history::logged(operation, &[source.to_path_buf()], destination, result).map_err(Into::into)
+}
+
+// Fails rather than replace what's at `to`
+fn copy_file(from: &Path, to: &Path) -> io::Result<()> {
+    if to.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("\"{}\" already exists", to.display()),
+        ));
+    }
+    crate::ops::copy_recursive(from, to)
+}
+
+// Checked before a script changes anything on disk
+fn writable(state: &Rc<RefCell<State>>) -> Result<(), Box<EvalAltResult>> {
+    let mut state = state.borrow_mut();
+    if state.read_only {
+        return Err("read-only mode".into());
+    }
+    state.commands.push(ScriptCommand::Refresh);
+    Ok(())
+}
+
+impl ScriptHost {
+    pub fn load(read_only: bool) -> Self {
+        let state = Rc::new(RefCell::new(State::default()));
+        let engine = build_engine(&state);
+
+        let mut scripts = Vec::new();
+        if let Some(Ok(read)) = scripts_dir().map(fs::read_dir) {
+            let mut paths: Vec<PathBuf> = read
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|e| e == "rhai"))
+                .collect();
+            paths.sort();
+            for path in paths {
+                match engine.compile_file(path.clone()) {
+                    Ok(ast) => scripts.push(Script {
+                        name: path
+                            .file_stem()
+                            .map(|s| s.to_string_lossy().into_owned())
+                            .unwrap_or_default(),
+                        ast,
+                    }),
+                    Err(e) => tracing::error!("Failed to load script {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        Self {
+            engine,
+            state,
+            scripts,
+            read_only,
+        }
+    }
+
+    fn prepare(&self, current_dir: &Path, selection: &[PathBuf]) {
+        let mut state = self.state.borrow_mut();
+        state.read_only = self.read_only;
+        state.current_dir = current_dir.to_path_buf();
+        state.selection = selection.to_vec();
+    }
+
+    fn take_commands(&self) -> Vec<ScriptCommand> {
+        std::mem::take(&mut self.state.borrow_mut().commands)
+    }
+
+    pub fn on_open_directory(
+        &self,
+        current_dir: &Path,
+        selection: &[PathBuf],
+    ) -> Vec<ScriptCommand> {
+        let arg = Dynamic::from(current_dir.to_string_lossy().into_owned());
+        self.dispatch("on_open_directory", current_dir, selection, arg)
+    }
+
+    pub fn on_select(&self, current_dir: &Path, selection: &[PathBuf]) -> Vec<ScriptCommand> {
+        let arg = Dynamic::from_array(to_strings(selection));
+        self.dispatch("on_select", current_dir, selection, arg)
+    }
+
+    fn dispatch(
+        &self,
+        hook: &str,
+        current_dir: &Path,
+        selection: &[PathBuf],
+        arg: Dynamic,
+    ) -> Vec<ScriptCommand> {
+        self.prepare(current_dir, selection);
+        for script in self.scripts.iter().filter(|s| s.defines(hook, 1)) {
+            let result = self.engine.call_fn::<Dynamic>(
+                &mut Scope::new(),
+                &script.ast,
+                hook,
+                (arg.clone(),),
+            );
+            if let Err(e) = result {
+                tracing::error!("Script {} failed in {}: {}", script.name, hook, e);
+            }
+        }
+        self.take_commands()
+    }
+
+    pub fn run_function(
+        &self,
+        script: usize,
+        function: &str,
+        current_dir: &Path,
+        selection: &[PathBuf],
+    ) -> Vec<ScriptCommand> {
+        self.prepare(current_dir, selection);
+        let script = &self.scripts[script];
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &script.ast, function, ());
+        if let Err(e) = result {
+            tracing::error!("Script {} failed in {}: {}", script.name, function, e);
+        }
+        self.take_commands()
+    }
+
+    // Runs `source` as a script would be, without it being saved anywhere
+    #[cfg(test)]
+    pub fn run_source(&self, source: &str, current_dir: &Path) -> Result<(), String> {
+        self.prepare(current_dir, &[]);
+        let result = self.engine.run(source).map_err(|e| e.to_string());
+        self.take_commands();
+        result
+    }
+}
+
+fn build_engine(state: &Rc<RefCell<State>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE);
+
+    let s = state.clone();
+    engine.register_fn("current_dir", move || {
+        s.borrow().current_dir.to_string_lossy().into_owned()
+    });
+    let s = state.clone();
+    engine.register_fn("selection", move || to_strings(&s.borrow().selection));
+
+    let s = state.clone();
+    engine.register_fn("navigate", move |path: &str| {
+        s.borrow_mut()
+            .commands
+            .push(ScriptCommand::Navigate(PathBuf::from(path)));
+    });
+    let s = state.clone();
+    engine.register_fn("select", move |pattern: &str| {
+        s.borrow_mut()
+            .commands
+            .push(ScriptCommand::Select(pattern.to_string()));
+    });
+    let s = state.clone();
+    engine.register_fn("clear_selection", move || {
+        s.borrow_mut().commands.push(ScriptCommand::ClearSelection);
+    });
+    let s = state.clone();
+    engine.register_fn("run", move |command: &str| {
+        s.borrow_mut()
+            .commands
+            .push(ScriptCommand::Run(command.to_string()));
+    });
+
+    engine.register_fn("list_dir", |path: &str| -> Array {
+        fs::read_dir(path)
+            .map(|read| {
+                read.flatten()
+                    .map(|e| Dynamic::from(e.path().to_string_lossy().into_owned()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    });
+    engine.register_fn("exists", |path: &str| Path::new(path).exists());
+    engine.register_fn("is_dir", |path: &str| Path::new(path).is_dir());
+
+    let s = state.clone();
+    engine.register_fn("rename", move |from: &str, to: &str| {
+        writable(&s)?;
+        let (from, to) = (Path::new(from), Path::new(to));
+        let result = rename::rename(&LocalFs, from, to);
+        logged(history::Operation::Rename, from, Some(to), result)
+    });
+    let s = state.clone();
+    engine.register_fn("copy_file", move |from: &str, to: &str| {
+        writable(&s)?;
+        let (from, to) = (Path::new(from), Path::new(to));
+        logged(
+            history::Operation::Copy,
+            from,
+            Some(to),
+            copy_file(from, to),
+        )
+    });
+    let s = state.clone();
+    engine.register_fn("create_dir", move |path: &str| {
+        writable(&s)?;
+        let path = Path::new(path);
+        logged(
+            history::Operation::Create,
+            path,
+            None,
+            fs::create_dir_all(path),
+        )
+    });
+
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runaway_scripts_are_stopped() {
+        let engine = build_engine(&Rc::new(RefCell::new(State::default())));
+        for script in [
+            "loop {}",
+            "fn f(n) { f(n + 1) } f(0)",
+            "let s = \"x\"; loop { s += s; }",
+        ] {
+            assert!(engine.run(script).is_err(), "{} wasn't stopped", script);
+        }
+        assert!(engine
+            .run("let n = 0; for i in 0..1000 { n += i; }")
+            .is_ok());
+    }
+
+    #[test]
+    fn copies_never_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let (from, to) = (dir.path().join("a"), dir.path().join("b"));
+        fs::write(&from, "new").unwrap();
+        fs::write(&to, "old").unwrap();
+        assert!(copy_file(&from, &to).is_err());
+        assert_eq!(fs::read_to_string(&to).unwrap(), "old");
+        fs::remove_file(&to).unwrap();
+        copy_file(&from, &to).unwrap();
+        assert_eq!(fs::read_to_string(&to).unwrap(), "new");
+    }
+}
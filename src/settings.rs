@@ -0,0 +1,329 @@
+use crate::config::{Config, StartupFolder};
+use crate::{format_file_size, thumbnails, trash};
+use eframe::egui;
+use std::path::PathBuf;
+
+#[derive(Default)]
+pub struct SettingsWindow {
+    pub open: bool,
+    new_extension: String,
+    new_command: String,
+}
+
+impl SettingsWindow {
+    // Returns true when the config was changed and should be saved
+    pub fn show(&mut self, ctx: &egui::Context, salt: egui::Id, config: &mut Config) -> bool {
+        let mut open = self.open;
+        let mut changed = false;
+
+        egui::Window::new("Settings")
+            .id(salt.with("settings"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                egui::CollapsingHeader::new("File openers")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        changed |= self.openers(ui, config);
+                    });
+                egui::CollapsingHeader::new("Copying and moving")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        changed |= ui
+                            .checkbox(
+                                &mut config.transfers.verify,
+                                "Verify copied files against the source",
+                            )
+                            .on_hover_text(
+                                "Slower, but catches bad writes to failing or flaky drives",
+                            )
+                            .changed();
+                        ui.label("Keep on copies:");
+                        let transfers = &mut config.transfers;
+                        changed |= ui
+                            .checkbox(&mut transfers.preserve_timestamps, "Timestamps")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut transfers.preserve_permissions, "Permissions")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut transfers.preserve_xattrs, "Extended attributes")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut transfers.preserve_symlinks, "Symbolic links as links")
+                            .on_hover_text("When off, the files links point to are copied instead")
+                            .changed();
+                        ui.weak(
+                            "Anything the destination can't store (e.g. on FAT32) is skipped and logged.",
+                        );
+                        ui.horizontal(|ui| {
+                            ui.label("Transfers at a time per hard disk:");
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut transfers.per_disk).range(0..=8))
+                                .on_hover_text(
+                                    "Others wait their turn, as a disk seeking between several is slower. 0 for no limit; SSDs are never limited.",
+                                )
+                                .changed();
+                        });
+                        ui.label("Names of copies pasted next to their original:")
+                            .on_hover_text(
+                                "{name} is the name without its extension, {ext} the extension and {n} the number",
+                            );
+                        egui::Grid::new("copy_names").num_columns(2).show(ui, |ui| {
+                            ui.label("First");
+                            changed |= ui.text_edit_singleline(&mut transfers.copy_name).changed();
+                            ui.end_row();
+                            ui.label("Then");
+                            changed |= ui
+                                .text_edit_singleline(&mut transfers.numbered_copy_name)
+                                .changed();
+                            ui.end_row();
+                        });
+                    });
+                egui::CollapsingHeader::new("Safety")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.label("Ask before:");
+                        let confirmations = &mut config.confirmations;
+                        changed |= ui
+                            .checkbox(&mut confirmations.trash, "Moving to the trash")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut confirmations.permanent_delete, "Deleting permanently")
+                            .on_hover_text("Also covers secure deletion")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut confirmations.overwrite, "Replacing existing files")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut confirmations.run_executables, "Running programs and scripts")
+                            .on_hover_text("Files downloaded from the internet always ask")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut confirmations.empty_trash, "Emptying the trash")
+                            .changed();
+                    });
+                egui::CollapsingHeader::new("Notifications")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            let options = &mut config.notifications;
+                            changed |= ui
+                                .checkbox(&mut options.enabled, "Notify when jobs taking over")
+                                .on_hover_text("Only while the window isn't in focus")
+                                .changed();
+                            changed |= ui
+                                .add_enabled(
+                                    options.enabled,
+                                    egui::DragValue::new(&mut options.after_secs).range(0..=3600),
+                                )
+                                .changed();
+                            ui.label("seconds end");
+                        });
+                    });
+                if cfg!(target_os = "linux") {
+                    egui::CollapsingHeader::new("Tray")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            changed |= ui
+                                .checkbox(&mut config.tray, "Show a tray icon")
+                                .on_hover_text(
+                                    "With bookmarks and recent folders. Closing the window minimises it instead of quitting.",
+                                )
+                                .changed();
+                        });
+                }
+                egui::CollapsingHeader::new("Startup")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        let startup = &mut config.startup;
+                        ui.label("Open new windows in:");
+                        for (folder, label) in [
+                            (StartupFolder::Overview, "This Computer"),
+                            (StartupFolder::Home, "The home folder"),
+                            (StartupFolder::Last, "The last folder open"),
+                            (StartupFolder::Path, "This folder:"),
+                        ] {
+                            changed |= ui.radio_value(&mut startup.folder, folder, label).changed();
+                        }
+                        let mut path = startup.path.display().to_string();
+                        let edited = ui
+                            .add_enabled(
+                                startup.folder == StartupFolder::Path,
+                                egui::TextEdit::singleline(&mut path),
+                            )
+                            .changed();
+                        if edited {
+                            startup.path = PathBuf::from(path);
+                            changed = true;
+                        }
+                    });
+                egui::CollapsingHeader::new("Accessibility")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        let accessibility = &mut config.accessibility;
+                        changed |= ui
+                            .checkbox(&mut accessibility.high_contrast, "High contrast")
+                            .changed();
+                        changed |= ui
+                            .checkbox(
+                                &mut accessibility.shape_indicators,
+                                "Don't rely on colour alone",
+                            )
+                            .on_hover_text(
+                                "Ticks on selected items, ⏺ for today and ○ for this week \
+                                 beside recent dates, and ■ to ■■■ beside large file sizes",
+                            )
+                            .changed();
+                    });
+                egui::CollapsingHeader::new("Scans")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.label("Paths searches skip, separated by ; (e.g. */node_modules/*; *.o):");
+                        changed |= ui.text_edit_singleline(&mut config.scan_exclude).changed();
+                    });
+                egui::CollapsingHeader::new("Search")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.label("Folders not to look into, separated by ; (wildcards allowed):");
+                        let exclude = &mut config.search.exclude;
+                        egui::Grid::new("search_excludes").num_columns(2).show(ui, |ui| {
+                            for (label, patterns) in [
+                                ("Subfolders", &mut exclude.subfolders),
+                                ("This drive", &mut exclude.drive),
+                                ("Bookmarks", &mut exclude.bookmarks),
+                                ("Everywhere", &mut exclude.everywhere),
+                            ] {
+                                ui.label(label);
+                                changed |= ui.text_edit_singleline(patterns).changed();
+                                ui.end_row();
+                            }
+                        });
+                    });
+                if trash::browsable() {
+                    egui::CollapsingHeader::new("Trash")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                let options = &mut config.trash;
+                                changed |= ui
+                                    .checkbox(&mut options.auto_empty, "Delete items trashed over")
+                                    .changed();
+                                changed |= ui
+                                    .add_enabled(
+                                        options.auto_empty,
+                                        egui::DragValue::new(&mut options.auto_empty_days)
+                                            .range(1..=3650),
+                                    )
+                                    .changed();
+                                ui.label("days ago");
+                            });
+                            ui.weak("Checked when the app starts.");
+                        });
+                }
+                egui::CollapsingHeader::new("File list")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        changed |= ui
+                            .checkbox(
+                                &mut config.view.highlight_recent,
+                                "Highlight recently modified items",
+                            )
+                            .on_hover_text("Today in a strong colour, the past week in a fainter one")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut config.view.relative_dates, "Show today and yesterday by name")
+                            .on_hover_text("\"Today at 14:05\" rather than the full date")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut config.view.theme_icons, "Use the icon theme for items")
+                            .on_hover_text(if cfg!(any(windows, target_os = "macos")) {
+                                "Generic file type icons instead of emoji"
+                            } else {
+                                "File type and folder icons from the desktop's icon theme instead of emoji"
+                            })
+                            .changed();
+                        ui.horizontal(|ui| {
+                            ui.label("Show item details after:");
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut config.view.details_delay_ms)
+                                        .range(0..=5000)
+                                        .speed(10)
+                                        .suffix(" ms"),
+                                )
+                                .on_hover_text("How long the pointer rests on an item before its full name, path, size and dates show")
+                                .changed();
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Thumbnail cache:");
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut config.view.thumbnail_cache_mb)
+                                        .range(16..=16384)
+                                        .suffix(" MB"),
+                                )
+                                .on_hover_text("The least recently used thumbnails are deleted past this size")
+                                .changed();
+                            ui.weak(format!("{} used", format_file_size(thumbnails::cache_size())));
+                        });
+                        if ui.button("Clear thumbnail cache").clicked() {
+                            if let Err(e) = thumbnails::clear_cache() {
+                                tracing::error!("Failed to clear the thumbnail cache: {}", e);
+                            }
+                        }
+                    });
+            });
+
+        self.open = open;
+        changed
+    }
+
+    fn openers(&mut self, ui: &mut egui::Ui, config: &mut Config) -> bool {
+        let mut changed = false;
+        ui.label(
+            "Files without an entry open in the system default application. %f is the file path.",
+        );
+
+        let mut removed = None;
+        egui::Grid::new("settings_openers")
+            .num_columns(3)
+            .show(ui, |ui| {
+                ui.strong("Extension");
+                ui.strong("Command");
+                ui.end_row();
+                for (extension, command) in config.openers.iter_mut() {
+                    ui.label(format!(".{}", extension));
+                    changed |= ui.text_edit_singleline(command).lost_focus();
+                    if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                        removed = Some(extension.clone());
+                    }
+                    ui.end_row();
+                }
+
+                ui.add(egui::TextEdit::singleline(&mut self.new_extension).hint_text("mp4"));
+                ui.add(egui::TextEdit::singleline(&mut self.new_command).hint_text("mpv %f"));
+                let extension = self
+                    .new_extension
+                    .trim()
+                    .trim_start_matches('.')
+                    .to_lowercase();
+                let valid = !extension.is_empty() && !self.new_command.trim().is_empty();
+                if ui.add_enabled(valid, egui::Button::new("Add")).clicked() {
+                    config
+                        .openers
+                        .insert(extension, self.new_command.trim().to_string());
+                    self.new_extension.clear();
+                    self.new_command.clear();
+                    changed = true;
+                }
+                ui.end_row();
+            });
+
+        if let Some(extension) = removed {
+            config.openers.remove(&extension);
+            changed = true;
+        }
+        changed
+    }
+}
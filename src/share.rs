@@ -0,0 +1,266 @@
+// "Share this folder": a temporary read-only HTTP server so files can be
+// fetched from a phone or another machine on the same network
+use base64::Engine;
+use std::ffi::OsStr;
+use std::fs;
+use std::net::{IpAddr, UdpSocket};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tiny_http::{Header, Request, Response, Server};
+
+pub struct Share {
+    pub url: String,
+    stop: Arc<AtomicBool>,
+    server: Arc<Server>,
+}
+
+impl Share {
+    pub fn start(root: PathBuf, password: Option<String>) -> Result<Self, String> {
+        let server = Server::http("0.0.0.0:0").map_err(|e| e.to_string())?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|a| a.port())
+            .ok_or("the server has no TCP address")?;
+        let host = local_ip()
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| String::from("localhost"));
+
+        let server = Arc::new(server);
+        let stop = Arc::new(AtomicBool::new(false));
+        {
+            let server = server.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match server.recv_timeout(Duration::from_millis(500)) {
+                        Ok(Some(request)) => {
+                            let root = root.clone();
+                            let password = password.clone();
+                            // One thread per request so a slow download
+                            // doesn't block everyone else
+                            std::thread::spawn(move || handle(request, &root, password.as_deref()));
+                        }
+                        Ok(None) => {}
+                        Err(_) => break,
+                    }
+                }
+            });
+        }
+
+        Ok(Self {
+            url: format!("http://{}:{}/", host, port),
+            stop,
+            server,
+        })
+    }
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.server.unblock();
+    }
+}
+
+// The address other machines reach us on: connecting a UDP socket sends no
+// packets but makes the OS pick the outgoing interface
+fn local_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    socket.local_addr().ok().map(|a| a.ip())
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+fn authorized(request: &Request, password: Option<&str>) -> bool {
+    let Some(password) = password else {
+        return true;
+    };
+    request
+        .headers()
+        .iter()
+        .filter(|h| h.field.equiv("Authorization"))
+        .filter_map(|h| h.value.as_str().strip_prefix("Basic "))
+        .filter_map(|encoded| {
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .ok()
+        })
+        .filter_map(|decoded| String::from_utf8(decoded).ok())
+        // Any user name is accepted; only the password matters
+        .any(|credentials| credentials.split_once(':').map(|(_, p)| p) == Some(password))
+}
+
+// Maps the request path onto the shared folder, refusing anything that
+// would escape it, and hidden files (.ssh, .env) the listing leaves out
+fn resolve(root: &Path, url: &str) -> Option<PathBuf> {
+    let path = url.split(['?', '#']).next().unwrap_or("");
+    let decoded = crate::uri::percent_decode(path);
+    let mut resolved = root.to_path_buf();
+    for component in Path::new(decoded.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) if !is_hidden(part) => resolved.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    // Symlinks inside the folder must not point outside of it, or at
+    // something hidden, either
+    let canonical = fs::canonicalize(&resolved).ok()?;
+    let inside = canonical.strip_prefix(fs::canonicalize(root).ok()?).ok()?;
+    if inside.iter().any(is_hidden) {
+        return None;
+    }
+    Some(canonical)
+}
+
+fn is_hidden(name: &OsStr) -> bool {
+    name.to_string_lossy().starts_with('.')
+}
+
+fn handle(request: Request, root: &Path, password: Option<&str>) {
+    if !authorized(&request, password) {
+        let response = Response::from_string("Password required")
+            .with_status_code(401)
+            .with_header(header("WWW-Authenticate", "Basic realm=\"Shared folder\""));
+        let _ = request.respond(response);
+        return;
+    }
+    if request.method() != &tiny_http::Method::Get {
+        let _ = request.respond(Response::empty(405));
+        return;
+    }
+
+    let url = request.url().to_string();
+    let Some(path) = resolve(root, &url) else {
+        let _ = request.respond(Response::from_string("Not found").with_status_code(404));
+        return;
+    };
+
+    if path.is_dir() {
+        // Relative links below only work when directory URLs end with /
+        if !url.split('?').next().unwrap_or("").ends_with('/') {
+            let response =
+                Response::empty(301).with_header(header("Location", &format!("{}/", url)));
+            let _ = request.respond(response);
+            return;
+        }
+        let html = listing_html(&path, &url);
+        let response = Response::from_string(html)
+            .with_header(header("Content-Type", "text/html; charset=utf-8"));
+        let _ = request.respond(response);
+    } else {
+        match fs::File::open(&path) {
+            Ok(file) => {
+                let mime = mime_guess::from_path(&path).first_or_octet_stream();
+                let response = Response::from_file(file)
+                    .with_header(header("Content-Type", mime.essence_str()));
+                let _ = request.respond(response);
+            }
+            Err(_) => {
+                let _ = request.respond(Response::from_string("Forbidden").with_status_code(403));
+            }
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn listing_html(dir: &Path, url: &str) -> String {
+    let mut entries: Vec<(String, bool, u64)> = fs::read_dir(dir)
+        .map(|read| {
+            read.flatten()
+                .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+                .map(|e| {
+                    let meta = fs::metadata(e.path()).ok();
+                    (
+                        e.file_name().to_string_lossy().into_owned(),
+                        meta.as_ref().is_some_and(|m| m.is_dir()),
+                        meta.map(|m| m.len()).unwrap_or(0),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort_by_key(|(name, is_dir, _)| (!is_dir, name.to_lowercase()));
+
+    let title = escape_html(&crate::uri::percent_decode(url));
+    let mut html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <title>{0}</title><style>body{{font-family:sans-serif;margin:1em}}\
+         a{{display:block;padding:.6em 0;text-decoration:none}}\
+         span{{color:#888;float:right}}</style></head><body><h2>{0}</h2>",
+        title
+    );
+    if url != "/" {
+        html.push_str("<a href=\"../\">⬆ Parent folder</a>");
+    }
+    for (name, is_dir, size) in entries {
+        let href = crate::uri::percent_encode_path(&name);
+        if is_dir {
+            html.push_str(&format!(
+                "<a href=\"{}/\">📁 {}</a>",
+                href,
+                escape_html(&name)
+            ));
+        } else {
+            html.push_str(&format!(
+                "<a href=\"{}\" download>📄 {}<span>{}</span></a>",
+                href,
+                escape_html(&name),
+                crate::format_file_size(size)
+            ));
+        }
+    }
+    html.push_str("</body></html>");
+    html
+}
+
+// QR code modules as rows of booleans (true = dark)
+pub fn qr_modules(text: &str) -> Option<(usize, Vec<bool>)> {
+    let code = qrcode::QrCode::new(text.as_bytes()).ok()?;
+    let width = code.width();
+    let modules = code
+        .to_colors()
+        .into_iter()
+        .map(|c| c == qrcode::Color::Dark)
+        .collect();
+    Some((width, modules))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_files_are_not_served() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".ssh")).unwrap();
+        fs::write(root.join(".ssh/id_ed25519"), "").unwrap();
+        fs::write(root.join(".env"), "").unwrap();
+        fs::write(root.join("notes.txt"), "").unwrap();
+
+        assert!(resolve(root, "/notes.txt").is_some());
+        assert!(resolve(root, "/").is_some());
+        assert!(resolve(root, "/.env").is_none());
+        assert!(resolve(root, "/%2Essh/id_ed25519").is_none());
+        assert!(resolve(root, "/../etc/passwd").is_none());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join(".ssh"), root.join("keys")).unwrap();
+            assert!(resolve(root, "/keys/id_ed25519").is_none());
+        }
+    }
+}
@@ -0,0 +1,456 @@
+// Freedesktop .desktop entries, Windows .lnk shell links and .url / .webloc
+// internet shortcuts, shown with their declared name and icon and opened by
+// launching their target
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub enum Shortcut {
+    Desktop(DesktopEntry),
+    WindowsLink(WindowsLink),
+    Internet(InternetShortcut),
+}
+
+#[derive(Default)]
+pub struct DesktopEntry {
+    pub name: Option<String>,
+    pub comment: Option<String>,
+    pub icon: Option<String>,
+    pub exec: Option<String>,
+    pub url: Option<String>,
+    pub terminal: bool,
+}
+
+#[derive(Default)]
+pub struct WindowsLink {
+    pub target: Option<String>,
+    pub arguments: Option<String>,
+    pub working_dir: Option<String>,
+    pub description: Option<String>,
+    pub icon_location: Option<String>,
+}
+
+// A web address saved by a browser: .url on Windows, .webloc on macOS
+#[derive(Default)]
+pub struct InternetShortcut {
+    pub url: String,
+    // .url files may name an icon file
+    pub icon: Option<String>,
+}
+
+impl InternetShortcut {
+    // Opened without asking. Other schemes, file:// included, could start
+    // a program, so those shortcuts open like any other file.
+    pub fn is_web(&self) -> bool {
+        let scheme = self
+            .url
+            .split_once(':')
+            .map(|(s, _)| s.to_ascii_lowercase());
+        matches!(scheme.as_deref(), Some("http" | "https" | "ftp" | "mailto"))
+    }
+}
+
+pub fn read(path: &Path) -> Option<Shortcut> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "desktop" => parse_desktop(&fs::read_to_string(path).ok()?).map(Shortcut::Desktop),
+        "lnk" => parse_lnk(&fs::read(path).ok()?).map(Shortcut::WindowsLink),
+        "url" => parse_url(&String::from_utf8_lossy(&fs::read(path).ok()?)).map(Shortcut::Internet),
+        "webloc" => parse_webloc(&fs::read(path).ok()?).map(Shortcut::Internet),
+        _ => None,
+    }
+}
+
+impl Shortcut {
+    pub fn display_name(&self) -> Option<&str> {
+        match self {
+            Shortcut::Desktop(entry) => entry.name.as_deref(),
+            Shortcut::WindowsLink(_) | Shortcut::Internet(_) => None,
+        }
+    }
+
+    pub fn icon(&self) -> Option<&str> {
+        match self {
+            Shortcut::Desktop(entry) => entry.icon.as_deref(),
+            Shortcut::WindowsLink(link) => link.icon_location.as_deref(),
+            Shortcut::Internet(shortcut) => shortcut.icon.as_deref(),
+        }
+    }
+
+    // Human-readable description of what the shortcut opens
+    pub fn target(&self) -> Option<String> {
+        match self {
+            Shortcut::Desktop(entry) => entry.url.clone().or_else(|| entry.exec.clone()),
+            Shortcut::WindowsLink(link) => {
+                link.target.as_ref().map(|target| match &link.arguments {
+                    Some(args) => format!("{} {}", target, args),
+                    None => target.clone(),
+                })
+            }
+            Shortcut::Internet(shortcut) => Some(shortcut.url.clone()),
+        }
+    }
+
+    pub fn launch(&self, shortcut_path: &Path) -> io::Result<()> {
+        let dir = shortcut_path.parent().unwrap_or(Path::new("."));
+        match self {
+            Shortcut::Desktop(entry) => {
+                if let Some(url) = &entry.url {
+                    return crate::launch::open_with_system(url.as_ref());
+                }
+                let exec = entry
+                    .exec
+                    .as_deref()
+                    .ok_or_else(|| io::Error::other("the entry has no Exec line"))?;
+                let command_line = strip_field_codes(exec);
+                if entry.terminal {
+                    let script = std::env::temp_dir()
+                        .join(format!("file_manager-launch-{}.sh", std::process::id()));
+                    fs::write(&script, format!("#!/bin/sh\n{}\n", command_line))?;
+                    set_executable(&script)?;
+                    crate::launch::run_in_terminal(&script)
+                } else {
+                    crate::actions::shell_command(&command_line)
+                        .current_dir(dir)
+                        .spawn()
+                        .map(|_| ())
+                }
+            }
+            Shortcut::WindowsLink(link) => {
+                let target = link
+                    .target
+                    .as_deref()
+                    .ok_or_else(|| io::Error::other("the shortcut has no target"))?;
+                let working_dir = link
+                    .working_dir
+                    .as_deref()
+                    .map(PathBuf::from)
+                    .filter(|d| d.is_dir())
+                    .unwrap_or_else(|| dir.to_path_buf());
+                let mut command = Command::new(target);
+                if let Some(args) = &link.arguments {
+                    command.args(args.split_whitespace());
+                }
+                command.current_dir(working_dir).spawn().map(|_| ())
+            }
+            Shortcut::Internet(shortcut) => crate::launch::open_with_system(shortcut.url.as_ref()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o700))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// Drops %f, %U and friends: the entry is launched without files. %% becomes
+// a literal percent sign.
+fn strip_field_codes(exec: &str) -> String {
+    let mut out = String::new();
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        if let Some('%') = chars.next() {
+            out.push('%');
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Locale suffixes to try for localized keys, most specific first: for
+// LANG=de_DE.UTF-8 that's [de_DE], then [de]
+fn locale_suffixes() -> Vec<String> {
+    let lang = std::env::var("LC_ALL")
+        .or_else(|_| std::env::var("LC_MESSAGES"))
+        .or_else(|_| std::env::var("LANG"))
+        .unwrap_or_default();
+    let lang = lang.split(['.', '@']).next().unwrap_or_default();
+    let mut suffixes = Vec::new();
+    if !lang.is_empty() && lang != "C" && lang != "POSIX" {
+        suffixes.push(format!("[{}]", lang));
+        if let Some((language, _)) = lang.split_once('_') {
+            suffixes.push(format!("[{}]", language));
+        }
+    }
+    suffixes.push(String::new());
+    suffixes
+}
+
+fn parse_desktop(contents: &str) -> Option<DesktopEntry> {
+    let mut in_entry = false;
+    let mut pairs = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+        } else if in_entry && !line.starts_with('#') {
+            if let Some((key, value)) = line.split_once('=') {
+                pairs.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+    }
+    if pairs.is_empty() {
+        return None;
+    }
+
+    let suffixes = locale_suffixes();
+    let localized = |key: &str| {
+        suffixes.iter().find_map(|suffix| {
+            let wanted = format!("{}{}", key, suffix);
+            pairs
+                .iter()
+                .find(|(k, _)| *k == wanted)
+                .map(|(_, v)| v.clone())
+        })
+    };
+    let plain = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+
+    Some(DesktopEntry {
+        name: localized("Name"),
+        comment: localized("Comment"),
+        icon: localized("Icon"),
+        exec: plain("Exec"),
+        url: plain("URL"),
+        terminal: plain("Terminal").is_some_and(|v| v == "true"),
+    })
+}
+
+// [InternetShortcut]
+// URL=https://example.com/
+// IconFile=C:\Windows\web.ico
+fn parse_url(contents: &str) -> Option<InternetShortcut> {
+    let mut in_section = false;
+    let mut shortcut = InternetShortcut::default();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_section = line.eq_ignore_ascii_case("[InternetShortcut]");
+        } else if let (true, Some((key, value))) = (in_section, line.split_once('=')) {
+            match key.trim() {
+                "URL" => shortcut.url = value.trim().to_string(),
+                "IconFile" => shortcut.icon = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+    }
+    (!shortcut.url.is_empty()).then_some(shortcut)
+}
+
+// A property list with a URL entry, as XML or, as newer macOS versions
+// write it, binary. Binary ones aren't fully decoded: the URL is the ASCII
+// string in them that has a scheme.
+fn parse_webloc(data: &[u8]) -> Option<InternetShortcut> {
+    let url = if data.starts_with(b"bplist00") {
+        binary_plist_url(data)?
+    } else {
+        let text = String::from_utf8_lossy(data);
+        let after_key = &text[text.find("<key>URL</key>")?..];
+        let start = after_key.find("<string>")? + "<string>".len();
+        let end = start + after_key[start..].find("</string>")?;
+        after_key[start..end]
+            .trim()
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&")
+    };
+    Some(InternetShortcut { url, icon: None })
+}
+
+fn binary_plist_url(data: &[u8]) -> Option<String> {
+    let mut i = 8;
+    while i < data.len() {
+        // ASCII string objects: 0x5N with N the length, or 0x5F and the
+        // length as a following integer object of one or two bytes
+        if data[i] & 0xF0 != 0x50 {
+            i += 1;
+            continue;
+        }
+        let (len, start) = match data[i] & 0x0F {
+            0x0F => match data.get(i + 1) {
+                Some(0x10) => (*data.get(i + 2)? as usize, i + 3),
+                Some(0x11) => (
+                    u16::from_be_bytes([*data.get(i + 2)?, *data.get(i + 3)?]) as usize,
+                    i + 4,
+                ),
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            },
+            len => (len as usize, i + 1),
+        };
+        if let Some(text) = data
+            .get(start..start + len)
+            .and_then(|b| std::str::from_utf8(b).ok())
+        {
+            if text.contains("://") || text.starts_with("mailto:") {
+                return Some(text.to_string());
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+// Minimal reader for the MS-SHLLINK format: enough to find the target path,
+// arguments, working directory, description and icon location. Every size
+// and offset comes from the file, so each is checked against its length.
+fn parse_lnk(data: &[u8]) -> Option<WindowsLink> {
+    const HEADER_SIZE: usize = 0x4C;
+    const HAS_LINK_TARGET_ID_LIST: u32 = 1 << 0;
+    const HAS_LINK_INFO: u32 = 1 << 1;
+    const HAS_NAME: u32 = 1 << 2;
+    const HAS_RELATIVE_PATH: u32 = 1 << 3;
+    const HAS_WORKING_DIR: u32 = 1 << 4;
+    const HAS_ARGUMENTS: u32 = 1 << 5;
+    const HAS_ICON_LOCATION: u32 = 1 << 6;
+    const IS_UNICODE: u32 = 1 << 7;
+
+    let bytes_at = |offset: usize, len: usize| data.get(offset..offset.checked_add(len)?);
+    let u16_at = |offset: usize| -> Option<usize> {
+        Some(u16::from_le_bytes(bytes_at(offset, 2)?.try_into().ok()?) as usize)
+    };
+    let u32_at = |offset: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(bytes_at(offset, 4)?.try_into().ok()?) as usize)
+    };
+
+    if u32_at(0)? != HEADER_SIZE {
+        return None;
+    }
+    let flags = u32_at(0x14)? as u32;
+    let mut offset = HEADER_SIZE;
+    let mut link = WindowsLink::default();
+
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        offset = offset.checked_add(2 + u16_at(offset)?)?;
+    }
+    if flags & HAS_LINK_INFO != 0 {
+        let info = offset;
+        let info_size = u32_at(info)?;
+        let local_base_path = u32_at(info.checked_add(0x10)?)?;
+        // The path lies within the LinkInfo structure
+        if local_base_path != 0 && local_base_path < info_size {
+            let path = data.get(info.checked_add(local_base_path)?..)?;
+            let end = path.iter().position(|&b| b == 0)?;
+            link.target = Some(String::from_utf8_lossy(&path[..end]).into_owned());
+        }
+        offset = offset.checked_add(info_size)?;
+    }
+
+    // StringData: each entry is a character count followed by the text
+    let read_string = |offset: &mut usize| -> Option<String> {
+        let count = u16_at(*offset)?;
+        *offset += 2;
+        let text = if flags & IS_UNICODE != 0 {
+            let bytes = bytes_at(*offset, count * 2)?;
+            *offset += count * 2;
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        } else {
+            let bytes = bytes_at(*offset, count)?;
+            *offset += count;
+            String::from_utf8_lossy(bytes).into_owned()
+        };
+        Some(text)
+    };
+
+    if flags & HAS_NAME != 0 {
+        link.description = read_string(&mut offset);
+    }
+    if flags & HAS_RELATIVE_PATH != 0 {
+        let relative = read_string(&mut offset);
+        if link.target.is_none() {
+            link.target = relative;
+        }
+    }
+    if flags & HAS_WORKING_DIR != 0 {
+        link.working_dir = read_string(&mut offset);
+    }
+    if flags & HAS_ARGUMENTS != 0 {
+        link.arguments = read_string(&mut offset).filter(|a| !a.is_empty());
+    }
+    if flags & HAS_ICON_LOCATION != 0 {
+        link.icon_location = read_string(&mut offset).filter(|i| !i.is_empty());
+    }
+    Some(link)
+}
+
+// Desktop entries can run arbitrary commands, so like GNOME and KDE only
+// launch those that are marked executable or installed as applications.
+// Others open as plain text.
+pub fn is_trusted_desktop_file(path: &Path) -> bool {
+    let installed = ["/usr/share/applications", "/usr/local/share/applications"]
+        .iter()
+        .map(PathBuf::from)
+        .chain(crate::user_dirs::home_dir().map(|h| h.join(".local/share/applications")))
+        .any(|dir| path.starts_with(dir));
+    installed || crate::launch::is_executable(path)
+}
+
+// Resolves a freedesktop icon name to an image file through the desktop's
+// icon theme. Absolute paths are used as they are.
+pub fn find_icon_file(icon: &str) -> Option<PathBuf> {
+    let as_path = Path::new(icon);
+    if as_path.is_absolute() {
+        return as_path.is_file().then(|| as_path.to_path_buf());
+    }
+    crate::icon_theme::find(icon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A link to C:\Tools\app.exe with "--help" as its arguments
+    fn lnk() -> Vec<u8> {
+        let mut data = vec![0; 0x4C];
+        data[0] = 0x4C;
+        // HasLinkInfo, HasArguments, IsUnicode
+        data[0x14] = 0b1010_0010;
+        let path = b"C:\\Tools\\app.exe\0";
+        let mut info = vec![0; 0x1C];
+        info[0..4].copy_from_slice(&(0x1C + path.len() as u32).to_le_bytes());
+        info[0x10..0x14].copy_from_slice(&0x1Cu32.to_le_bytes());
+        info.extend_from_slice(path);
+        data.extend(info);
+        let arguments: Vec<u16> = "--help".encode_utf16().collect();
+        data.extend((arguments.len() as u16).to_le_bytes());
+        data.extend(arguments.iter().flat_map(|c| c.to_le_bytes()));
+        data
+    }
+
+    #[test]
+    fn reads_links() {
+        let link = parse_lnk(&lnk()).unwrap();
+        assert_eq!(link.target.as_deref(), Some("C:\\Tools\\app.exe"));
+        assert_eq!(link.arguments.as_deref(), Some("--help"));
+    }
+
+    #[test]
+    fn survives_truncated_and_corrupt_links() {
+        let data = lnk();
+        for len in 0..data.len() {
+            parse_lnk(&data[..len]);
+        }
+        // A local base path offset past the end of the file
+        let mut corrupt = data.clone();
+        corrupt[0x4C + 0x10..0x4C + 0x14].copy_from_slice(&0x1000u32.to_le_bytes());
+        corrupt[0x4C..0x4C + 4].copy_from_slice(&0x2000u32.to_le_bytes());
+        assert!(parse_lnk(&corrupt).is_none());
+    }
+}
@@ -0,0 +1,181 @@
+// Older copies of a file kept in filesystem snapshots: ZFS exposes them
+// under `<dataset>/.zfs/snapshot/<name>/`, snapper (Btrfs) under
+// `<subvolume>/.snapshots/<number>/snapshot/`, and Windows through Volume
+// Shadow Copy devices
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub struct Version {
+    pub snapshot: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub modified: Option<SystemTime>,
+    pub size: u64,
+}
+
+// Whether snapshots could hold older versions of `path`. On Windows that's
+// any local drive; asking VSS is slow, so it waits for `versions`.
+pub fn available(path: &Path) -> bool {
+    if cfg!(windows) {
+        return drive_relative(path).is_some();
+    }
+    !snapshot_dirs(path).is_empty()
+}
+
+// Snapshot roots that could hold `path`, each paired with the path relative
+// to the snapshotted filesystem
+fn snapshot_dirs(path: &Path) -> Vec<(String, PathBuf)> {
+    let mut dirs = Vec::new();
+    for root in path.ancestors().skip(1) {
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        if let Ok(entries) = fs::read_dir(root.join(".zfs").join("snapshot")) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                dirs.push((name, entry.path().join(relative)));
+            }
+        }
+        if let Ok(entries) = fs::read_dir(root.join(".snapshots")) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let copy = entry.path().join("snapshot").join(relative);
+                dirs.push((format!("Snapshot {}", name), copy));
+            }
+        }
+    }
+    dirs.extend(shadow_copies(path));
+    dirs
+}
+
+// "C:\Users\me\a.txt" → ("C:", "Users\me\a.txt")
+fn drive_relative(path: &Path) -> Option<(String, PathBuf)> {
+    use std::path::{Component, Prefix};
+    let mut components = path.components();
+    let drive = match components.next()? {
+        Component::Prefix(prefix) => match prefix.kind() {
+            Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+                format!("{}:", letter as char)
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let relative = components
+        .filter(|c| !matches!(c, Component::RootDir))
+        .collect();
+    Some((drive, relative))
+}
+
+// Each shadow copy of the drive is a device the path can be looked up in
+#[cfg(windows)]
+fn shadow_copies(path: &Path) -> Vec<(String, PathBuf)> {
+    let Some((drive, relative)) = drive_relative(path) else {
+        return Vec::new();
+    };
+    let script = format!(
+        "$v = (Get-CimInstance Win32_Volume | Where-Object DriveLetter -eq '{}').DeviceID; \
+         Get-CimInstance Win32_ShadowCopy | Where-Object VolumeName -eq $v | \
+         ForEach-Object {{ $_.DeviceObject + '|' + $_.InstallDate.ToString('yyyy-MM-dd HH:mm') }}",
+        drive
+    );
+    let output = match std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            tracing::error!("Failed to list shadow copies: {}", e);
+            return Vec::new();
+        }
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().split_once('|'))
+        .map(|(device, date)| {
+            let mut copy = std::ffi::OsString::from(device);
+            copy.push("\\");
+            copy.push(&relative);
+            (format!("Shadow copy {}", date), PathBuf::from(copy))
+        })
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn shadow_copies(_path: &Path) -> Vec<(String, PathBuf)> {
+    Vec::new()
+}
+
+// Distinct older versions, newest first. Snapshots where the file didn't
+// change are collapsed into the earliest one holding that version.
+pub fn versions(path: &Path) -> Vec<Version> {
+    let mut versions: Vec<Version> = snapshot_dirs(path)
+        .into_iter()
+        .filter_map(|(snapshot, copy)| {
+            let meta = fs::metadata(&copy).ok()?;
+            Some(Version {
+                snapshot,
+                path: copy,
+                is_dir: meta.is_dir(),
+                modified: meta.modified().ok(),
+                size: meta.len(),
+            })
+        })
+        .collect();
+    versions.sort_by(|a, b| {
+        a.modified
+            .cmp(&b.modified)
+            .then(a.snapshot.cmp(&b.snapshot))
+    });
+    versions.dedup_by(|later, earlier| {
+        later.modified == earlier.modified && later.size == earlier.size
+    });
+
+    // The live file itself isn't a previous version
+    let current = fs::metadata(path).ok();
+    versions.retain(|v| {
+        current
+            .as_ref()
+            .is_none_or(|m| m.modified().ok() != v.modified || m.len() != v.size)
+    });
+    versions.reverse();
+    versions
+}
+
+// Puts the old version back in place of the current file. The copy is made
+// next to the file first so a failure leaves the current one untouched.
+// Folders are only restored as copies, since merging them is ambiguous.
+pub fn restore(version: &Version, target: &Path) -> io::Result<()> {
+    if version.is_dir {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "folders can only be restored as a copy",
+        ));
+    }
+    let dir = target.parent().unwrap_or(Path::new("."));
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    let temp = dir.join(format!(".{}.restoring", name));
+    fs::copy(&version.path, &temp)?;
+    fs::rename(&temp, target).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+// Copies the old version next to the item, leaving the current one alone
+pub fn restore_copy(version: &Version, target: &Path) -> io::Result<PathBuf> {
+    let dir = target.parent().unwrap_or(Path::new("."));
+    let stem = target.file_stem().unwrap_or_default().to_string_lossy();
+    // Labels hold times like 14:05, and NTFS and FAT don't allow colons
+    let label = version
+        .snapshot
+        .replace(|c: char| r#"<>:"/\|?*"#.contains(c), ".");
+    let name = match target.extension().filter(|_| !version.is_dir) {
+        Some(ext) => format!("{} ({}).{}", stem, label, ext.to_string_lossy()),
+        None => format!("{} ({})", stem, label),
+    };
+    let destination = dir.join(crate::unique_file_name(dir, &name));
+    crate::ops::copy_recursive(&version.path, &destination)?;
+    Ok(destination)
+}
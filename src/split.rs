@@ -0,0 +1,181 @@
+// Splitting a file into numbered chunks (name.part01, name.part02, ...) and
+// joining them back. A sha256sum-style file written next to the chunks lets
+// the joined file be checked against the original.
+use crate::checksum;
+use crate::jobs::JobHandle;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+// Largest file FAT32 can hold
+pub const FAT32_LIMIT: u64 = 4 * 1024 * 1024 * 1024 - 1;
+
+const BUFFER: usize = 1024 * 1024;
+
+// "movie.mkv.part03" → ("movie.mkv", 3)
+pub fn part_number(path: &Path) -> Option<(String, u32)> {
+    let name = path.file_name()?.to_str()?;
+    let (base, number) = name.rsplit_once(".part")?;
+    if base.is_empty() || number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((base.to_string(), number.parse().ok()?))
+}
+
+fn part_path(dir: &Path, base: &str, number: u32, width: usize) -> PathBuf {
+    dir.join(format!("{}.part{:0width$}", base, number, width = width))
+}
+
+fn checksum_path(dir: &Path, base: &str) -> PathBuf {
+    dir.join(format!("{}.sha256", base))
+}
+
+pub fn split(path: &Path, chunk_size: u64, job: &JobHandle) -> Result<(), String> {
+    if chunk_size == 0 {
+        return Err(String::from("the chunk size must be above zero"));
+    }
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let base = path
+        .file_name()
+        .ok_or("not a file")?
+        .to_string_lossy()
+        .into_owned();
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let total = file.metadata().map_err(|e| e.to_string())?.len();
+    let count = total.div_ceil(chunk_size).max(1);
+    let width = count.to_string().len().max(2);
+
+    for number in 1..=count {
+        let part = part_path(dir, &base, number as u32, width);
+        if part.exists() {
+            return Err(format!("{} already exists", part.display()));
+        }
+    }
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BUFFER];
+    let mut done = 0;
+    let mut written = Vec::new();
+    let result = (|| {
+        for number in 1..=count {
+            let part = part_path(dir, &base, number as u32, width);
+            job.set_message(part.file_name().unwrap_or_default().to_string_lossy());
+            let mut out = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&part)?;
+            written.push(part);
+            let mut left = chunk_size;
+            while left > 0 {
+                if job.is_cancelled() {
+                    return Err(io::Error::other("cancelled"));
+                }
+                let n = file.read(&mut buffer[..left.min(BUFFER as u64) as usize])?;
+                if n == 0 {
+                    break;
+                }
+                out.write_all(&buffer[..n])?;
+                hasher.update(&buffer[..n]);
+                left -= n as u64;
+                done += n as u64;
+                job.set_progress(done, Some(total));
+            }
+            out.sync_all()?;
+        }
+        let digest = checksum::to_hex(&hasher.finalize_reset());
+        fs::write(checksum_path(dir, &base), format!("{}  {}\n", digest, base))
+    })();
+
+    // Don't leave half a set behind
+    if let Err(e) = result {
+        for part in written {
+            let _ = fs::remove_file(part);
+        }
+        return Err(e.to_string());
+    }
+    Ok(())
+}
+
+// Joins the set `part` belongs to into a file named after it, next to the
+// chunks, and returns its path
+pub fn join(part: &Path, job: &JobHandle) -> Result<PathBuf, String> {
+    let dir = part.parent().unwrap_or(Path::new("."));
+    let (base, _) = part_number(part).ok_or("not a numbered part")?;
+    let width = part.extension().map_or(2, |e| e.len() - "part".len());
+
+    // Parts have to run from 1 without gaps
+    let mut parts = Vec::new();
+    loop {
+        let path = part_path(dir, &base, parts.len() as u32 + 1, width);
+        if !path.is_file() {
+            break;
+        }
+        parts.push(path);
+    }
+    if parts.is_empty() {
+        return Err(format!(
+            "{}.part{:0width$} is missing",
+            base,
+            1,
+            width = width
+        ));
+    }
+    let total = parts
+        .iter()
+        .map(|p| fs::metadata(p).map(|m| m.len()).unwrap_or(0))
+        .sum();
+
+    let destination = dir.join(crate::unique_file_name(dir, &base));
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BUFFER];
+    let mut done = 0;
+    let result = (|| {
+        let mut out = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&destination)?;
+        for path in &parts {
+            job.set_message(path.file_name().unwrap_or_default().to_string_lossy());
+            let mut input = fs::File::open(path)?;
+            loop {
+                if job.is_cancelled() {
+                    return Err(io::Error::other("cancelled"));
+                }
+                let n = input.read(&mut buffer)?;
+                if n == 0 {
+                    break;
+                }
+                out.write_all(&buffer[..n])?;
+                hasher.update(&buffer[..n]);
+                done += n as u64;
+                job.set_progress(done, Some(total));
+            }
+        }
+        out.sync_all()?;
+
+        let digest = checksum::to_hex(&hasher.finalize_reset());
+        match fs::read_to_string(checksum_path(dir, &base)) {
+            Ok(expected) => {
+                let expected = expected.split_whitespace().next().unwrap_or("");
+                if !expected.eq_ignore_ascii_case(&digest) {
+                    return Err(io::Error::other(
+                        "checksum mismatch: a part is damaged or missing",
+                    ));
+                }
+                job.set_message(format!("Joined {} parts, checksum verified", parts.len()));
+            }
+            Err(_) => job.set_message(format!(
+                "Joined {} parts (no checksum file to verify against)",
+                parts.len()
+            )),
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&destination);
+        return Err(e.to_string());
+    }
+    Ok(destination)
+}
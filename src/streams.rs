@@ -0,0 +1,126 @@
+// NTFS alternate data streams: extra named contents attached to a file,
+// most commonly the Zone.Identifier that marks downloads as untrusted
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const ZONE_IDENTIFIER: &str = "Zone.Identifier";
+
+pub struct Stream {
+    pub name: String,
+    pub size: u64,
+}
+
+pub fn supported() -> bool {
+    cfg!(windows)
+}
+
+// "file.txt" + "Zone.Identifier" → "file.txt:Zone.Identifier"
+fn stream_path(path: &Path, name: &str) -> PathBuf {
+    let mut stream = path.as_os_str().to_owned();
+    stream.push(":");
+    stream.push(name);
+    PathBuf::from(stream)
+}
+
+pub fn read(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+    std::fs::read(stream_path(path, name))
+}
+
+pub fn remove(path: &Path, name: &str) -> io::Result<()> {
+    std::fs::remove_file(stream_path(path, name))
+}
+
+// What a Zone.Identifier says about where the file came from
+pub fn describe_zone(contents: &str) -> String {
+    let zone = contents
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("ZoneId="))
+        .and_then(|z| z.parse::<u32>().ok());
+    let zone = match zone {
+        Some(0) => "Local computer",
+        Some(1) => "Local intranet",
+        Some(2) => "Trusted sites",
+        Some(3) => "Internet",
+        Some(4) => "Restricted sites",
+        _ => "Unknown zone",
+    };
+    match contents
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("HostUrl="))
+    {
+        Some(url) => format!("{}, downloaded from {}", zone, url),
+        None => zone.to_string(),
+    }
+}
+
+#[cfg(windows)]
+pub fn list(path: &Path) -> io::Result<Vec<Stream>> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[repr(C)]
+    struct FindStreamData {
+        size: i64,
+        // MAX_PATH + 36
+        name: [u16; 296],
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn FindFirstStreamW(
+            file_name: *const u16,
+            info_level: u32,
+            data: *mut FindStreamData,
+            flags: u32,
+        ) -> isize;
+        fn FindNextStreamW(handle: isize, data: *mut FindStreamData) -> i32;
+        fn FindClose(handle: isize) -> i32;
+    }
+    const INVALID_HANDLE_VALUE: isize = -1;
+    const ERROR_HANDLE_EOF: i32 = 38;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut data = FindStreamData {
+        size: 0,
+        name: [0; 296],
+    };
+    let handle = unsafe { FindFirstStreamW(wide.as_ptr(), 0, &mut data, 0) };
+    if handle == INVALID_HANDLE_VALUE {
+        let error = io::Error::last_os_error();
+        // Folders and non-NTFS drives simply have no streams
+        return match error.raw_os_error() {
+            Some(ERROR_HANDLE_EOF) => Ok(Vec::new()),
+            _ => Err(error),
+        };
+    }
+
+    let mut streams = Vec::new();
+    loop {
+        let len = data
+            .name
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(data.name.len());
+        let full = String::from_utf16_lossy(&data.name[..len]);
+        // ":name:$DATA"; the unnamed "::$DATA" is the file's main contents
+        let name = full
+            .trim_start_matches(':')
+            .trim_end_matches(":$DATA")
+            .to_string();
+        if !name.is_empty() {
+            streams.push(Stream {
+                name,
+                size: data.size as u64,
+            });
+        }
+        if unsafe { FindNextStreamW(handle, &mut data) } == 0 {
+            break;
+        }
+    }
+    unsafe { FindClose(handle) };
+    Ok(streams)
+}
+
+#[cfg(not(windows))]
+pub fn list(_path: &Path) -> io::Result<Vec<Stream>> {
+    Ok(Vec::new())
+}
@@ -0,0 +1,50 @@
+use std::fs;
+use std::path::PathBuf;
+
+pub enum Template {
+    File { label: String, path: PathBuf },
+    // Sub-folders of the Templates directory become submenus
+    Group { label: String, items: Vec<Template> },
+}
+
+pub fn load() -> Vec<Template> {
+    crate::user_dirs::templates_dir()
+        .map(|dir| list(&dir, 0))
+        .unwrap_or_default()
+}
+
+fn list(dir: &std::path::Path, depth: usize) -> Vec<Template> {
+    const MAX_DEPTH: usize = 4;
+
+    let Ok(read) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut items: Vec<Template> = read
+        .flatten()
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| {
+            let path = e.path();
+            if path.is_dir() {
+                if depth >= MAX_DEPTH {
+                    return None;
+                }
+                let items = list(&path, depth + 1);
+                (!items.is_empty()).then(|| Template::Group {
+                    label: e.file_name().to_string_lossy().into_owned(),
+                    items,
+                })
+            } else {
+                let label = path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                Some(Template::File { label, path })
+            }
+        })
+        .collect();
+    items.sort_by_key(|t| match t {
+        Template::Group { label, .. } => (0, label.to_lowercase()),
+        Template::File { label, .. } => (1, label.to_lowercase()),
+    });
+    items
+}
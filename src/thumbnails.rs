@@ -0,0 +1,303 @@
+// Thumbnails for images in the file list. Generated ones are saved as PNGs in
+// <cache dir>/thumbnails, named after a hash of the source's path, size and
+// modification time so edited files get a fresh one. Reading a thumbnail
+// bumps its modification time, and once the folder grows past the limit set
+// in Settings the least recently used ones are deleted.
+//
+// Rows on screen come first: requests wait in a queue stamped with the frame
+// they were last asked for in, workers take the most recently seen one, and
+// ones that scrolled out of view are dropped before they start.
+use crate::checksum;
+use crate::repaint::Repaint;
+use eframe::egui;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+// Longest side in pixels, the freedesktop "normal" size
+const SIZE: u32 = 128;
+// Decoding is CPU bound, so only a few at a time
+const THREADS: usize = 4;
+
+pub fn cache_dir() -> Option<PathBuf> {
+    crate::user_dirs::cache_dir().map(|d| d.join("thumbnails"))
+}
+
+// Bytes in the cache folder, counted on first use and kept up to date after
+fn used() -> &'static Mutex<Option<u64>> {
+    static USED: OnceLock<Mutex<Option<u64>>> = OnceLock::new();
+    USED.get_or_init(|| Mutex::new(None))
+}
+
+fn cached_files(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(read) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    read.flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let used = meta.modified().ok()?;
+            meta.is_file().then(|| (entry.path(), meta.len(), used))
+        })
+        .collect()
+}
+
+pub fn cache_size() -> u64 {
+    let mut used = used().lock().unwrap();
+    *used.get_or_insert_with(|| {
+        cache_dir()
+            .map(|dir| cached_files(&dir).iter().map(|(_, len, _)| len).sum())
+            .unwrap_or(0)
+    })
+}
+
+pub fn clear_cache() -> io::Result<()> {
+    let mut used = used().lock().unwrap();
+    if let Some(dir) = cache_dir().filter(|d| d.exists()) {
+        fs::remove_dir_all(dir)?;
+    }
+    *used = Some(0);
+    Ok(())
+}
+
+// Deletes the least recently used thumbnails until the cache fits in `limit`
+fn evict(dir: &Path, limit: u64) {
+    let mut files = cached_files(dir);
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort_by_key(|(_, _, used)| *used);
+    for (path, len, _) in files {
+        if total <= limit {
+            break;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => total -= len,
+            Err(e) => tracing::warn!("Failed to remove {}: {}", path.display(), e),
+        }
+    }
+    *used().lock().unwrap() = Some(total);
+}
+
+fn cache_file(dir: &Path, source: &Path) -> Option<PathBuf> {
+    let meta = fs::metadata(source).ok()?;
+    let modified = meta
+        .modified()
+        .ok()?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?;
+    let mut hasher = Sha256::new();
+    hasher.update(source.as_os_str().as_encoded_bytes());
+    hasher.update(meta.len().to_le_bytes());
+    hasher.update(modified.as_nanos().to_le_bytes());
+    Some(dir.join(format!("{}.png", checksum::to_hex(&hasher.finalize()))))
+}
+
+fn load_or_generate(source: &Path, limit: u64) -> Option<image::RgbaImage> {
+    let dir = cache_dir();
+    let cached = dir.as_deref().and_then(|dir| cache_file(dir, source));
+    if let Some(cached) = &cached {
+        if let Ok(thumbnail) = image::open(cached) {
+            // Access times are often switched off, so mark it used by hand
+            if let Ok(file) = fs::File::options().write(true).open(cached) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            return Some(thumbnail.into_rgba8());
+        }
+    }
+
+    let thumbnail = image::open(source).ok()?.thumbnail(SIZE, SIZE).into_rgba8();
+    if let (Some(dir), Some(cached)) = (dir, cached) {
+        // Counted before saving so the new file isn't added twice
+        let before = cache_size();
+        let saved = fs::create_dir_all(&dir)
+            .map_err(image::ImageError::IoError)
+            .and_then(|_| thumbnail.save(&cached));
+        match saved {
+            Ok(()) => {
+                let len = fs::metadata(&cached).map(|m| m.len()).unwrap_or(0);
+                let total = before + len;
+                *used().lock().unwrap() = Some(total);
+                if total > limit {
+                    evict(&dir, limit);
+                }
+            }
+            Err(e) => tracing::warn!(
+                "Failed to cache the thumbnail of {}: {}",
+                source.display(),
+                e
+            ),
+        }
+    }
+    Some(thumbnail)
+}
+
+fn pool() -> &'static rayon::ThreadPool {
+    static POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(THREADS)
+            .thread_name(|i| format!("thumbnails-{}", i))
+            .panic_handler(crate::recovery::pool_panicked)
+            .build()
+            .unwrap()
+    })
+}
+
+enum Thumbnail {
+    Loading,
+    Ready(egui::TextureHandle),
+    // Not an image we can decode; don't try again
+    Failed,
+}
+
+type Generated = (PathBuf, Option<SystemTime>, Option<image::RgbaImage>);
+
+// A thumbnail waiting for a worker
+struct Request {
+    modified: Option<SystemTime>,
+    limit: u64,
+    // Frame it was last asked for in
+    seen: u64,
+    // When it was last asked for; rows higher up ask first within a frame
+    order: u64,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: HashMap<PathBuf, Request>,
+    frame: u64,
+    requested: u64,
+}
+
+impl Queue {
+    // The latest frame's requests, top row first
+    fn take_next(&mut self) -> Option<(PathBuf, Request)> {
+        let path = self
+            .pending
+            .iter()
+            .max_by_key(|(_, r)| (r.seen, std::cmp::Reverse(r.order)))
+            .map(|(path, _)| path.clone())?;
+        self.pending.remove_entry(&path)
+    }
+}
+
+// Textures for the thumbnails on screen, generated in the background
+pub struct Thumbnails {
+    // By source path, along with the modification time they were made from
+    textures: HashMap<PathBuf, (Option<SystemTime>, Thumbnail)>,
+    queue: Arc<Mutex<Queue>>,
+    sender: Sender<Generated>,
+    receiver: Receiver<Generated>,
+    repaint: Repaint,
+}
+
+impl Thumbnails {
+    pub fn new(repaint: Repaint) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            textures: HashMap::new(),
+            queue: Arc::default(),
+            sender,
+            receiver,
+            repaint,
+        }
+    }
+
+    // The thumbnail of `path` if it's ready; otherwise starts making it
+    pub fn get(
+        &mut self,
+        path: &Path,
+        modified: Option<SystemTime>,
+        limit: u64,
+    ) -> Option<egui::TextureHandle> {
+        let mut queue = self.queue.lock().unwrap();
+        let frame = queue.frame;
+        queue.requested += 1;
+        let order = queue.requested;
+        match self.textures.get(path) {
+            Some((made_from, thumbnail)) if *made_from == modified => match thumbnail {
+                Thumbnail::Ready(texture) => return Some(texture.clone()),
+                Thumbnail::Loading => {
+                    // Still on screen, so still wanted
+                    if let Some(request) = queue.pending.get_mut(path) {
+                        request.seen = frame;
+                        request.order = order;
+                    }
+                    return None;
+                }
+                Thumbnail::Failed => return None,
+            },
+            _ => {}
+        }
+
+        self.textures
+            .insert(path.to_path_buf(), (modified, Thumbnail::Loading));
+        queue.pending.insert(
+            path.to_path_buf(),
+            Request {
+                modified,
+                limit,
+                seen: frame,
+                order,
+            },
+        );
+        // Each task makes whichever thumbnail is most wanted when it starts
+        let queue = self.queue.clone();
+        let sender = self.sender.clone();
+        let repaint = self.repaint.clone();
+        pool().spawn(move || {
+            let Some((path, request)) = queue.lock().unwrap().take_next() else {
+                return;
+            };
+            let image = load_or_generate(&path, request.limit);
+            if sender.send((path, request.modified, image)).is_ok() {
+                repaint.request();
+            }
+        });
+        None
+    }
+
+    // Turns finished thumbnails into textures
+    pub fn poll(&mut self, ctx: &egui::Context) {
+        for (path, modified, image) in self.receiver.try_iter() {
+            let thumbnail = match image {
+                Some(image) => {
+                    let size = [image.width() as usize, image.height() as usize];
+                    let pixels = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+                    let name = path.to_string_lossy();
+                    Thumbnail::Ready(ctx.load_texture(name, pixels, egui::TextureOptions::LINEAR))
+                }
+                None => Thumbnail::Failed,
+            };
+            // Skip results for folders that were left in the meantime
+            if let Some(entry) = self.textures.get_mut(&path) {
+                *entry = (modified, thumbnail);
+            }
+        }
+
+        // Called before the rows are drawn, so what the last frame didn't
+        // ask for has scrolled away; it's asked for again if it comes back
+        let mut queue = self.queue.lock().unwrap();
+        let last = queue.frame;
+        let textures = &mut self.textures;
+        queue.pending.retain(|path, request| {
+            let wanted = request.seen >= last;
+            if !wanted {
+                textures.remove(path);
+            }
+            wanted
+        });
+        queue.frame += 1;
+    }
+
+    // Frees the textures of other folders after navigating away
+    pub fn retain_in(&mut self, dir: &Path) {
+        self.textures.retain(|path, _| path.parent() == Some(dir));
+        let mut queue = self.queue.lock().unwrap();
+        queue.pending.retain(|path, _| path.parent() == Some(dir));
+    }
+}
@@ -0,0 +1,131 @@
+// Setting or shifting the modified and created times of many files at
+// once, e.g. to fix photos taken with a camera whose clock was off
+use crate::jobs::{JobHandle, ProgressUnit};
+use crate::ops::plural;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Clone, Copy)]
+pub enum Change {
+    // The same local date and time for every file
+    Set(NaiveDateTime),
+    // Moves each file's own time by this much
+    Shift(chrono::Duration),
+}
+
+#[derive(Clone, Copy)]
+pub struct TimestampOptions {
+    pub change: Change,
+    pub modified: bool,
+    pub created: bool,
+}
+
+// Linux has no call for changing a file's birth time
+pub fn can_set_created() -> bool {
+    cfg!(any(windows, target_os = "macos"))
+}
+
+pub fn apply(
+    targets: &[PathBuf],
+    options: TimestampOptions,
+    job: &JobHandle,
+) -> Result<(), String> {
+    job.set_unit(ProgressUnit::Items);
+    let total = targets.len() as u64;
+    let mut failed = Vec::new();
+    for (done, target) in targets.iter().enumerate() {
+        if job.is_cancelled() {
+            return Err(String::from("cancelled"));
+        }
+        job.set_progress(done as u64, Some(total));
+        job.set_message(target.display().to_string());
+        if let Err(e) = change_times(target, options) {
+            failed.push(format!("{}: {}", target.display(), e));
+        }
+    }
+    job.set_progress(total, Some(total));
+
+    if failed.is_empty() {
+        job.set_message(format!("Changed {}", plural(targets.len(), "item")));
+        return Ok(());
+    }
+    job.set_message("");
+    let more = match failed.len() {
+        n if n > 3 => format!(" and {} more", n - 3),
+        _ => String::new(),
+    };
+    Err(format!(
+        "{} couldn't be changed: {}{}",
+        plural(failed.len(), "item"),
+        failed[..failed.len().min(3)].join("; "),
+        more
+    ))
+}
+
+fn change_times(path: &Path, options: TimestampOptions) -> io::Result<()> {
+    let meta = fs::metadata(path)?;
+    let mut times = fs::FileTimes::new();
+    if options.modified {
+        times = times.set_modified(new_time(meta.modified()?, options.change)?);
+    }
+    if options.created {
+        times = set_created(times, new_time(meta.created()?, options.change)?)?;
+    }
+    open_for_times(path, meta.is_dir())?.set_times(times)
+}
+
+fn new_time(old: SystemTime, change: Change) -> io::Result<SystemTime> {
+    let time = match change {
+        Change::Set(local) => Local
+            .from_local_datetime(&local)
+            .earliest()
+            .ok_or_else(|| io::Error::other("that time doesn't exist in the local time zone"))?,
+        Change::Shift(offset) => DateTime::<Local>::from(old)
+            .checked_add_signed(offset)
+            .ok_or_else(|| io::Error::other("the shifted time is out of range"))?,
+    };
+    Ok(time.into())
+}
+
+#[cfg(windows)]
+fn set_created(times: fs::FileTimes, created: SystemTime) -> io::Result<fs::FileTimes> {
+    use std::os::windows::fs::FileTimesExt;
+    Ok(times.set_created(created))
+}
+
+#[cfg(target_os = "macos")]
+fn set_created(times: fs::FileTimes, created: SystemTime) -> io::Result<fs::FileTimes> {
+    use std::os::macos::fs::FileTimesExt;
+    Ok(times.set_created(created))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn set_created(_times: fs::FileTimes, _created: SystemTime) -> io::Result<fs::FileTimes> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "creation times can't be changed on this system",
+    ))
+}
+
+// Folders need FILE_FLAG_BACKUP_SEMANTICS to be opened on Windows
+#[cfg(windows)]
+fn open_for_times(path: &Path, _is_dir: bool) -> io::Result<fs::File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+}
+
+#[cfg(not(windows))]
+fn open_for_times(path: &Path, is_dir: bool) -> io::Result<fs::File> {
+    if is_dir {
+        fs::File::open(path)
+    } else {
+        fs::OpenOptions::new().write(true).open(path)
+    }
+}
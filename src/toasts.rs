@@ -0,0 +1,121 @@
+// Short-lived messages in the window's corner confirming what just happened,
+// like "3 items copied", some with an Undo button to take it back
+use crate::jobs::{Ended, JobState};
+use eframe::egui;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// How long a toast stays unless the pointer is over it
+const SHOWN_FOR: Duration = Duration::from_secs(6);
+// Older ones go once there are more
+const MAX_SHOWN: usize = 3;
+
+// How to take back what a toast reports
+#[derive(Clone)]
+pub enum Undo {
+    // Put back from the trash, by original path
+    Trash(Vec<PathBuf>),
+    // Each item's (current, original) path
+    Move(Vec<(PathBuf, PathBuf)>),
+    Rename { from: PathBuf, to: PathBuf },
+}
+
+struct Toast {
+    text: String,
+    undo: Option<Undo>,
+    shown: Instant,
+}
+
+// A toast for a job, shown once the job with this title finishes
+struct Waiting {
+    title: String,
+    text: String,
+    undo: Option<Undo>,
+}
+
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    waiting: Vec<Waiting>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, text: impl Into<String>, undo: Option<Undo>) {
+        self.toasts.push(Toast {
+            text: text.into(),
+            undo,
+            shown: Instant::now(),
+        });
+        if self.toasts.len() > MAX_SHOWN {
+            self.toasts.remove(0);
+        }
+    }
+
+    // Failed and cancelled jobs show in the jobs panel instead
+    pub fn after_job(
+        &mut self,
+        title: impl Into<String>,
+        text: impl Into<String>,
+        undo: Option<Undo>,
+    ) {
+        self.waiting.push(Waiting {
+            title: title.into(),
+            text: text.into(),
+            undo,
+        });
+    }
+
+    pub fn job_ended(&mut self, job: &Ended) {
+        let Some(i) = self.waiting.iter().position(|w| w.title == job.title) else {
+            return;
+        };
+        let waiting = self.waiting.remove(i);
+        if job.state == JobState::Finished {
+            self.push(waiting.text, waiting.undo);
+        }
+    }
+
+    // Returns what to undo when an Undo button was clicked
+    pub fn show(&mut self, ctx: &egui::Context, salt: egui::Id) -> Option<Undo> {
+        self.toasts.retain(|t| t.shown.elapsed() < SHOWN_FOR);
+        if self.toasts.is_empty() {
+            return None;
+        }
+        let mut undo = None;
+        let mut closed = None;
+        let mut hovered = false;
+        egui::Area::new(salt.with("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (i, toast) in self.toasts.iter().enumerate() {
+                    let frame = egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(&toast.text);
+                            if toast.undo.is_some() && ui.button("Undo").clicked() {
+                                undo = toast.undo.clone();
+                                closed = Some(i);
+                            }
+                            if ui.small_button("✖").clicked() {
+                                closed = Some(i);
+                            }
+                        });
+                    });
+                    hovered |= frame.response.contains_pointer();
+                }
+            });
+        if let Some(i) = closed {
+            self.toasts.remove(i);
+        }
+        // Kept while being read
+        if hovered {
+            for toast in &mut self.toasts {
+                toast.shown = Instant::now();
+            }
+        }
+        if let Some(oldest) = self.toasts.iter().map(|t| t.shown).min() {
+            ctx.request_repaint_after(SHOWN_FOR.saturating_sub(oldest.elapsed()));
+        }
+        undo
+    }
+}
@@ -0,0 +1,533 @@
+// Moving to the trash rather than deleting for good. On Linux and the BSDs
+// this follows the freedesktop.org trash spec, so the desktop's own trash
+// sees the same items: the home trash in ~/.local/share/Trash, and a
+// .Trash-<uid> folder at the top of other drives so trashing there is a
+// rename rather than a copy. macOS and Windows hand the files to Finder and
+// the Recycle Bin, whose contents aren't listed here.
+use crate::history;
+use crate::jobs::{JobHandle, ProgressUnit};
+use crate::ops::plural;
+use crate::repaint::Repaint;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+// One item in the trash
+pub struct Trashed {
+    // The item itself, inside the trash's files folder
+    pub path: PathBuf,
+    // Its .trashinfo file
+    info: PathBuf,
+    pub original: PathBuf,
+    pub deleted: Option<chrono::NaiveDateTime>,
+}
+
+impl Trashed {
+    pub fn deleted_time(&self) -> Option<std::time::SystemTime> {
+        use chrono::TimeZone;
+        chrono::Local
+            .from_local_datetime(&self.deleted?)
+            .earliest()
+            .map(Into::into)
+    }
+
+    // Puts the item at `destination`, creating missing parent folders. An
+    // existing item there is never replaced.
+    pub fn restore(&self, destination: &Path) -> io::Result<()> {
+        if fs::symlink_metadata(destination).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", destination.display()),
+            ));
+        }
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match fs::rename(&self.path, destination) {
+            Ok(()) => {}
+            // From the home trash to another drive
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                crate::ops::copy_recursive(&self.path, destination)?;
+                match fs::symlink_metadata(&self.path) {
+                    Ok(meta) if meta.is_dir() => fs::remove_dir_all(&self.path)?,
+                    _ => fs::remove_file(&self.path)?,
+                }
+            }
+            Err(e) => return Err(e),
+        }
+        fs::remove_file(&self.info)
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Summary {
+    pub items: usize,
+    pub size: u64,
+}
+
+// Whether there's a trash to move things to
+pub fn supported() -> bool {
+    cfg!(any(unix, windows))
+}
+
+// Whether the trash can be listed, counted and emptied from here
+pub fn browsable() -> bool {
+    cfg!(all(unix, not(target_os = "macos")))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod freedesktop {
+    use super::Trashed;
+    use crate::uri;
+    use std::fs;
+    use std::io::{self, Write};
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    use std::path::{Path, PathBuf};
+
+    const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+    pub fn home_trash() -> Option<PathBuf> {
+        crate::user_dirs::data_home().map(|d| d.join("Trash"))
+    }
+
+    fn uid() -> u32 {
+        unsafe { libc::getuid() }
+    }
+
+    // The home trash plus any trash at the top of a mounted drive
+    pub fn trash_dirs() -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = home_trash().into_iter().collect();
+        for mount in crate::drives::mounts() {
+            let top = mount.mount_point;
+            for dir in [
+                top.join(".Trash").join(uid().to_string()),
+                top.join(format!(".Trash-{}", uid())),
+            ] {
+                if dir.join("files").is_dir() && !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        dirs
+    }
+
+    // The outermost folder above `path` that's still on its drive
+    fn top_dir(path: &Path, device: u64) -> PathBuf {
+        let mut top = path.to_path_buf();
+        for dir in path.ancestors().skip(1) {
+            match fs::metadata(dir) {
+                Ok(meta) if meta.dev() == device => top = dir.to_path_buf(),
+                _ => break,
+            }
+        }
+        top
+    }
+
+    // Where `path` goes: the home trash when it's on the same drive,
+    // otherwise a trash at the top of its own drive
+    fn trash_for(path: &Path) -> io::Result<PathBuf> {
+        let home = home_trash().ok_or_else(|| io::Error::other("no home folder"))?;
+        let device = fs::symlink_metadata(path)?.dev();
+        // The home trash may not exist yet
+        let home_device = home
+            .ancestors()
+            .find_map(|d| fs::metadata(d).ok())
+            .map(|m| m.dev());
+        if home_device == Some(device) {
+            return Ok(home);
+        }
+        let top = top_dir(path, device);
+        // An administrator-made .Trash with the sticky bit (0o1000), shared
+        // by users
+        let shared = top.join(".Trash");
+        let sticky =
+            fs::symlink_metadata(&shared).is_ok_and(|m| m.is_dir() && m.mode() & 0o1000 != 0);
+        if sticky {
+            let dir = shared.join(uid().to_string());
+            if create_private_dir(&dir).is_ok() {
+                return Ok(dir);
+            }
+        }
+        Ok(top.join(format!(".Trash-{}", uid())))
+    }
+
+    fn create_private_dir(dir: &Path) -> io::Result<()> {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+    }
+
+    pub fn move_to_trash(path: &Path) -> io::Result<()> {
+        let trash = trash_for(path)?;
+        let files = trash.join("files");
+        let info = trash.join("info");
+        create_private_dir(&files)?;
+        create_private_dir(&info)?;
+
+        let name = path
+            .file_name()
+            .ok_or_else(|| io::Error::other("cannot trash a filesystem root"))?
+            .to_string_lossy()
+            .into_owned();
+        let contents = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            uri::percent_encode_path(&path.to_string_lossy()),
+            chrono::Local::now().format(DATE_FORMAT)
+        );
+        let (stem, ext) = match name.rfind('.') {
+            Some(i) if i > 0 => name.split_at(i),
+            _ => (name.as_str(), ""),
+        };
+        // The info file is created first and claims the name, as the spec
+        // asks, so two programs trashing at once can't pick the same one
+        for n in 1.. {
+            let candidate = match n {
+                1 => name.clone(),
+                n => format!("{} ({}){}", stem, n, ext),
+            };
+            if fs::symlink_metadata(files.join(&candidate)).is_ok() {
+                continue;
+            }
+            let info_file = info.join(format!("{}.trashinfo", candidate));
+            let mut file = match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_file)
+            {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            };
+            let result = file
+                .write_all(contents.as_bytes())
+                .and_then(|()| fs::rename(path, files.join(&candidate)));
+            if result.is_err() {
+                let _ = fs::remove_file(&info_file);
+            }
+            return result;
+        }
+        unreachable!()
+    }
+
+    fn parse_info(text: &str) -> (Option<String>, Option<chrono::NaiveDateTime>) {
+        let mut path = None;
+        let mut deleted = None;
+        for line in text.lines() {
+            if let Some(value) = line.strip_prefix("Path=") {
+                path = Some(uri::percent_decode(value.trim()));
+            } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+                deleted = chrono::NaiveDateTime::parse_from_str(value.trim(), DATE_FORMAT).ok();
+            }
+        }
+        (path, deleted)
+    }
+
+    pub fn list_dir(trash: &Path) -> Vec<Trashed> {
+        // Paths in a drive's trash may be relative to the top of that drive
+        let top = match trash.parent() {
+            Some(parent) if parent.file_name() == Some(".Trash".as_ref()) => parent.parent(),
+            parent => parent,
+        }
+        .unwrap_or(Path::new("/"));
+        let Ok(entries) = fs::read_dir(trash.join("info")) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let info = entry.path();
+                let name = info.file_name()?.to_str()?.strip_suffix(".trashinfo")?;
+                let path = trash.join("files").join(name);
+                fs::symlink_metadata(&path).ok()?;
+                let (original, deleted) = parse_info(&fs::read_to_string(&info).ok()?);
+                Some(Trashed {
+                    path,
+                    info,
+                    original: top.join(original?),
+                    deleted,
+                })
+            })
+            .collect()
+    }
+}
+
+pub fn move_to_trash(path: &Path) -> io::Result<()> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return freedesktop::move_to_trash(path);
+
+    #[cfg(target_os = "macos")]
+    {
+        // Through Finder so "Put Back" works
+        let quoted = path
+            .display()
+            .to_string()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"");
+        let script = format!(
+            "tell application \"Finder\" to delete POSIX file \"{}\"",
+            quoted
+        );
+        let status = std::process::Command::new("osascript")
+            .args(["-e", &script])
+            .stdout(std::process::Stdio::null())
+            .status()?;
+        return if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other("Finder failed to move it to the trash"))
+        };
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let quoted = path.display().to_string().replace('\'', "''");
+        let method = if path.is_dir() {
+            "DeleteDirectory"
+        } else {
+            "DeleteFile"
+        };
+        let script = format!(
+            "Add-Type -AssemblyName Microsoft.VisualBasic; \
+             [Microsoft.VisualBasic.FileIO.FileSystem]::{}('{}', 'OnlyErrorDialogs', 'SendToRecycleBin')",
+            method, quoted
+        );
+        let status = std::process::Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .status()?;
+        return if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(
+                "PowerShell failed to move it to the Recycle Bin",
+            ))
+        };
+    }
+
+    #[allow(unreachable_code)]
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "there is no trash on this system",
+    ))
+}
+
+// Moves each target to the trash. Items that can't be moved are skipped and
+// listed at the end.
+pub fn trash(targets: &[PathBuf], job: &JobHandle) -> Result<(), String> {
+    job.set_unit(ProgressUnit::Items);
+    let total = targets.len() as u64;
+    let mut failed = Vec::new();
+    for (done, target) in targets.iter().enumerate() {
+        if job.is_cancelled() {
+            return Err(String::from("cancelled"));
+        }
+        job.set_message(target.display().to_string());
+        if let Err(e) = move_to_trash(target) {
+            job.item_failed(target, target, &e);
+            failed.push(format!("{}: {}", target.display(), e));
+        }
+        job.set_progress(done as u64 + 1, Some(total));
+    }
+    job.set_message("");
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} couldn't be moved to the trash: {}",
+            plural(failed.len(), "item"),
+            failed.join("; ")
+        ))
+    }
+}
+
+pub fn list() -> Vec<Trashed> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return freedesktop::trash_dirs()
+        .iter()
+        .flat_map(|dir| freedesktop::list_dir(dir))
+        .collect();
+
+    #[allow(unreachable_code)]
+    Vec::new()
+}
+
+// Whether `dir` is inside a trash, where files shouldn't be trashed again
+pub fn contains(dir: &Path) -> bool {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return freedesktop::trash_dirs()
+        .iter()
+        .any(|trash| dir.starts_with(trash));
+
+    #[allow(unreachable_code)]
+    {
+        let _ = dir;
+        false
+    }
+}
+
+// The folder the home trash keeps its items in, to browse
+pub fn files_dir() -> Option<PathBuf> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return freedesktop::home_trash().map(|t| t.join("files"));
+
+    #[allow(unreachable_code)]
+    None
+}
+
+// Restores the trashed items at `paths` (inside a trash's files folder) to
+// where they came from, or into `folder` if given
+pub fn restore(paths: &[PathBuf], folder: Option<&Path>, job: &JobHandle) -> Result<(), String> {
+    job.set_unit(ProgressUnit::Items);
+    let items: Vec<Trashed> = list()
+        .into_iter()
+        .filter(|t| paths.contains(&t.path))
+        .collect();
+    let total = items.len() as u64;
+    let mut failed = Vec::new();
+    for (done, item) in items.iter().enumerate() {
+        if job.is_cancelled() {
+            return Err(String::from("cancelled"));
+        }
+        let destination = match (folder, item.original.file_name()) {
+            (Some(folder), Some(name)) => folder.join(name),
+            _ => item.original.clone(),
+        };
+        job.set_message(destination.display().to_string());
+        let result = item.restore(&destination).map_err(|e| e.to_string());
+        history::record(
+            history::Operation::Restore,
+            std::slice::from_ref(&item.path),
+            Some(&destination),
+            &result,
+        );
+        if let Err(e) = result {
+            failed.push(format!("{}: {}", item.original.display(), e));
+        }
+        job.set_progress(done as u64 + 1, Some(total));
+    }
+    job.set_message("");
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} couldn't be restored: {}",
+            plural(failed.len(), "item"),
+            failed.join("; ")
+        ))
+    }
+}
+
+// Where the items last trashed from `originals` are now, for undoing a move
+// to the trash
+pub fn latest_trashed(originals: &[PathBuf]) -> Vec<PathBuf> {
+    let mut latest: Vec<Trashed> = Vec::new();
+    for item in list()
+        .into_iter()
+        .filter(|t| originals.contains(&t.original))
+    {
+        match latest.iter_mut().find(|t| t.original == item.original) {
+            Some(kept) if kept.deleted >= item.deleted => {}
+            Some(kept) => *kept = item,
+            None => latest.push(item),
+        }
+    }
+    latest.into_iter().map(|t| t.path).collect()
+}
+
+// The info of whatever went; anything left stays listed
+fn forget_removed(items: &[Trashed]) {
+    for item in items {
+        if fs::symlink_metadata(&item.path).is_err() {
+            if let Err(e) = fs::remove_file(&item.info) {
+                tracing::warn!("Failed to remove {}: {}", item.info.display(), e);
+            }
+        }
+    }
+}
+
+// Deletes the given items for good, as a job
+pub fn remove(items: &[Trashed], job: &JobHandle) -> Result<(), String> {
+    let paths: Vec<PathBuf> = items.iter().map(|t| t.path.clone()).collect();
+    let result = crate::ops::delete(&paths, job);
+    forget_removed(items);
+    result
+}
+
+// Deletes the items trashed more than `days` ago, without a job. Returns
+// the original paths of those that went.
+fn purge(days: u32) -> Vec<PathBuf> {
+    let Some(cutoff) = chrono::Local::now()
+        .naive_local()
+        .checked_sub_days(chrono::Days::new(days.into()))
+    else {
+        return Vec::new();
+    };
+    let old: Vec<Trashed> = list()
+        .into_iter()
+        .filter(|t| t.deleted.is_some_and(|d| d < cutoff))
+        .collect();
+    for item in &old {
+        let result = match fs::symlink_metadata(&item.path) {
+            Ok(meta) if meta.is_dir() => fs::remove_dir_all(&item.path),
+            _ => fs::remove_file(&item.path),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to remove {}: {}", item.path.display(), e);
+        }
+    }
+    forget_removed(&old);
+    old.into_iter()
+        .filter(|t| fs::symlink_metadata(&t.path).is_err())
+        .map(|t| t.original)
+        .collect()
+}
+
+pub fn summary() -> Summary {
+    let items = list();
+    Summary {
+        items: items.len(),
+        size: items
+            .iter()
+            .map(|t| crate::ops::tree_size(&t.path, false))
+            .sum(),
+    }
+}
+
+// Counts the trash on a background thread, after deleting the items older
+// than `purge_after` days if given
+pub fn summarize(purge_after: Option<u32>, repaint: Repaint) -> Receiver<Summary> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        if let Some(days) = purge_after {
+            let purged = purge(days);
+            if !purged.is_empty() {
+                tracing::info!(
+                    "Deleted {} trashed over {} days ago",
+                    plural(purged.len(), "item"),
+                    days
+                );
+                history::record(history::Operation::Delete, &purged, None, &Ok(()));
+            }
+        }
+        if sender.send(summary()).is_ok() {
+            repaint.request();
+        }
+    });
+    receiver
+}
+
+// For the Trash row in the sidebar, e.g. "12 items, 340 MB"
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.items {
+            0 => write!(f, "Empty"),
+            n => write!(
+                f,
+                "{}, {}",
+                plural(n, "item"),
+                crate::format_file_size(self.size)
+            ),
+        }
+    }
+}
@@ -0,0 +1,314 @@
+// Tray icon on Linux, as a StatusNotifierItem with a com.canonical.dbusmenu
+// menu of bookmarks and recently visited folders, which is what KDE, GNOME
+// with the AppIndicator extension and most other panels show. While it's on,
+// closing the main window minimises it instead of quitting, so folder
+// watchers, caches and running jobs stay warm. The binary's App runs it, as
+// minimising and quitting are up to the application rather than the browser.
+use eframe::egui;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Str, StructureBuilder, Type, Value};
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+
+pub enum Command {
+    Show,
+    Open(PathBuf),
+    Quit,
+}
+
+// What the menu lists
+#[derive(Default, PartialEq)]
+struct Places {
+    bookmarks: Vec<PathBuf>,
+    recent: Vec<PathBuf>,
+}
+
+// Menu item ids; bookmarks and recent folders are numbered from their base
+const SHOW: i32 = 1;
+const QUIT: i32 = 2;
+const RECENT_MENU: i32 = 3;
+const BOOKMARKS: i32 = 100;
+const RECENT: i32 = 1000;
+
+struct Item {
+    commands: Sender<Command>,
+    ctx: egui::Context,
+}
+
+impl Item {
+    fn send(&self, command: Command) {
+        let _ = self.commands.send(command);
+        self.ctx.request_repaint();
+    }
+}
+
+#[zbus::interface(name = "org.kde.StatusNotifierItem")]
+impl Item {
+    #[zbus(property)]
+    fn category(&self) -> &str {
+        "ApplicationStatus"
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> &str {
+        "file_manager"
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> &str {
+        "File Manager"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "Active"
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> &str {
+        "system-file-manager"
+    }
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn menu(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(MENU_PATH).unwrap()
+    }
+
+    fn activate(&self, _x: i32, _y: i32) {
+        self.send(Command::Show);
+    }
+
+    fn secondary_activate(&self, _x: i32, _y: i32) {}
+
+    fn scroll(&self, _delta: i32, _orientation: &str) {}
+}
+
+// One menu item and what's below it, as dbusmenu lays them out
+#[derive(Serialize, Type)]
+struct Layout {
+    id: i32,
+    properties: HashMap<String, OwnedValue>,
+    children: Vec<OwnedValue>,
+}
+
+impl Layout {
+    fn new(id: i32, label: &str) -> Self {
+        let mut properties = HashMap::new();
+        properties.insert(String::from("label"), OwnedValue::from(Str::from(label)));
+        Self {
+            id,
+            properties,
+            children: Vec::new(),
+        }
+    }
+
+    fn separator(id: i32) -> Self {
+        let mut layout = Self::new(id, "");
+        layout.properties.insert(
+            String::from("type"),
+            OwnedValue::from(Str::from("separator")),
+        );
+        layout
+    }
+
+    fn with_children(mut self, children: Vec<Layout>) -> Self {
+        self.properties.insert(
+            String::from("children-display"),
+            OwnedValue::from(Str::from("submenu")),
+        );
+        self.children = children
+            .into_iter()
+            .filter_map(Layout::into_value)
+            .collect();
+        self
+    }
+
+    // Children are variants holding the same structure
+    fn into_value(self) -> Option<OwnedValue> {
+        let structure = StructureBuilder::new()
+            .add_field(self.id)
+            .add_field(self.properties)
+            .add_field(self.children)
+            .build()
+            .ok()?;
+        OwnedValue::try_from(Value::from(structure)).ok()
+    }
+}
+
+fn place_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+fn layout(places: &Places) -> Layout {
+    let mut items = vec![Layout::new(SHOW, "Show File Manager")];
+    if !places.bookmarks.is_empty() {
+        items.push(Layout::separator(-1));
+    }
+    for (i, bookmark) in places.bookmarks.iter().enumerate() {
+        items.push(Layout::new(BOOKMARKS + i as i32, &place_name(bookmark)));
+    }
+    if !places.recent.is_empty() {
+        let recent = places
+            .recent
+            .iter()
+            .enumerate()
+            .map(|(i, dir)| Layout::new(RECENT + i as i32, &dir.display().to_string()))
+            .collect();
+        items.push(Layout::new(RECENT_MENU, "Recent").with_children(recent));
+    }
+    items.push(Layout::separator(-2));
+    items.push(Layout::new(QUIT, "Quit"));
+    Layout::new(0, "").with_children(items)
+}
+
+struct Menu {
+    item: Item,
+    places: Arc<Mutex<Places>>,
+    revision: Arc<Mutex<u32>>,
+}
+
+#[zbus::interface(name = "com.canonical.dbusmenu")]
+impl Menu {
+    // The whole menu whatever part is asked for; it's small
+    fn get_layout(
+        &self,
+        _parent_id: i32,
+        _recursion_depth: i32,
+        _property_names: Vec<String>,
+    ) -> (u32, Layout) {
+        let revision = *self.revision.lock().unwrap();
+        (revision, layout(&self.places.lock().unwrap()))
+    }
+
+    fn get_group_properties(
+        &self,
+        _ids: Vec<i32>,
+        _property_names: Vec<String>,
+    ) -> Vec<(i32, HashMap<String, OwnedValue>)> {
+        Vec::new()
+    }
+
+    fn event(&self, id: i32, event_id: &str, _data: Value<'_>, _timestamp: u32) {
+        if event_id != "clicked" {
+            return;
+        }
+        let places = self.places.lock().unwrap();
+        let command = match id {
+            SHOW => Command::Show,
+            QUIT => Command::Quit,
+            RECENT.. => match places.recent.get((id - RECENT) as usize) {
+                Some(dir) => Command::Open(dir.clone()),
+                None => return,
+            },
+            BOOKMARKS.. => match places.bookmarks.get((id - BOOKMARKS) as usize) {
+                Some(dir) => Command::Open(dir.clone()),
+                None => return,
+            },
+            _ => return,
+        };
+        self.item.send(command);
+    }
+
+    fn about_to_show(&self, _id: i32) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "normal"
+    }
+}
+
+// Removed from the panel when dropped
+pub struct Tray {
+    connection: zbus::blocking::Connection,
+    commands: Receiver<Command>,
+    places: Arc<Mutex<Places>>,
+    revision: Arc<Mutex<u32>>,
+}
+
+impl Tray {
+    pub fn start(ctx: &egui::Context) -> Result<Self, String> {
+        let (sender, commands) = mpsc::channel();
+        let places = Arc::new(Mutex::new(Places::default()));
+        let revision = Arc::new(Mutex::new(0));
+        let item = || Item {
+            commands: sender.clone(),
+            ctx: ctx.clone(),
+        };
+        let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+        let connection = zbus::blocking::connection::Builder::session()
+            .and_then(|b| b.name(name.as_str()))
+            .and_then(|b| b.serve_at(ITEM_PATH, item()))
+            .and_then(|b| {
+                b.serve_at(
+                    MENU_PATH,
+                    Menu {
+                        item: item(),
+                        places: places.clone(),
+                        revision: revision.clone(),
+                    },
+                )
+            })
+            .and_then(|b| b.build())
+            .map_err(|e| e.to_string())?;
+        connection
+            .call_method(
+                Some("org.kde.StatusNotifierWatcher"),
+                "/StatusNotifierWatcher",
+                Some("org.kde.StatusNotifierWatcher"),
+                "RegisterStatusNotifierItem",
+                &name,
+            )
+            .map_err(|e| format!("no tray on this desktop ({})", e))?;
+        Ok(Self {
+            connection,
+            commands,
+            places,
+            revision,
+        })
+    }
+
+    // Cheap when nothing changed
+    pub fn set_places(&self, bookmarks: &[PathBuf], recent: &[PathBuf]) {
+        let mut places = self.places.lock().unwrap();
+        if places.bookmarks == bookmarks && places.recent == recent {
+            return;
+        }
+        places.bookmarks = bookmarks.to_vec();
+        places.recent = recent.to_vec();
+        let mut revision = self.revision.lock().unwrap();
+        *revision += 1;
+        let result = self.connection.emit_signal(
+            None::<&str>,
+            MENU_PATH,
+            "com.canonical.dbusmenu",
+            "LayoutUpdated",
+            &(*revision, 0i32),
+        );
+        if let Err(e) = result {
+            tracing::warn!("Failed to update the tray menu: {}", e);
+        }
+    }
+
+    pub fn try_recv(&self) -> Option<Command> {
+        self.commands.try_recv().ok()
+    }
+}
@@ -0,0 +1,513 @@
+// Terminal frontend for machines without a display (`file_manager --tui`).
+// It shares the listing, the config and the job queue with the window, and
+// uses the same keys wherever a terminal can tell them apart.
+use crate::config::Config;
+use crate::jobs::{JobState, Jobs, ProgressUnit};
+use crate::{actions, history, launch, ops, trash};
+use file_manager_core::model::{self, format_file_size, format_file_size_column};
+use file_manager_core::{search, LocalFs};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const HELP: &str = "Enter open · Backspace up · Space select · ^A all · * invert · ^S pattern · \
+                    ^C copy · ^X cut · ^V paste · Del trash · ⇧Del delete · ^D bookmark · F5 refresh · b bookmarks · q quit";
+const HELP_READ_ONLY: &str = "Read-only · Enter open · Backspace up · Space select · ^A all · * invert · \
+                              ^S pattern · ^C copy · ^D bookmark · F5 refresh · b bookmarks · q quit";
+
+pub fn run(start: PathBuf, read_only: bool) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let mut tui = Tui::new(start, read_only);
+    let result = tui.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+enum Prompt {
+    SelectPattern(String),
+    ConfirmTrash(Vec<PathBuf>),
+    ConfirmDelete(Vec<PathBuf>),
+}
+
+struct Tui {
+    config: Config,
+    current_dir: PathBuf,
+    entries: Vec<model::Entry>,
+    table: TableState,
+    selected: HashSet<PathBuf>,
+    file_clipboard: Vec<PathBuf>,
+    clipboard_cut: bool,
+    jobs: Jobs,
+    prompt: Option<Prompt>,
+    // Cut, paste and delete are ignored
+    read_only: bool,
+    // Shown in the status line until the next key press
+    message: Option<String>,
+    quit: bool,
+}
+
+impl Tui {
+    fn new(start: PathBuf, read_only: bool) -> Self {
+        let config = Config::load();
+        let mut tui = Self {
+            read_only: read_only || config.read_only,
+            config,
+            current_dir: start,
+            entries: Vec::new(),
+            table: TableState::default(),
+            selected: HashSet::new(),
+            file_clipboard: Vec::new(),
+            clipboard_cut: false,
+            jobs: Jobs::default(),
+            prompt: None,
+            message: None,
+            quit: false,
+        };
+        tui.read_dir();
+        tui
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            // Wake up regularly so job progress keeps moving
+            if event::poll(Duration::from_millis(100))? {
+                match event::read()? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => {
+                        self.message = None;
+                        self.handle_key(key, terminal)?;
+                    }
+                    _ => {}
+                }
+            }
+            if !self.jobs.take_ended().is_empty() {
+                self.read_dir();
+            }
+        }
+        Ok(())
+    }
+
+    fn read_dir(&mut self) {
+        match model::list(&LocalFs, &self.current_dir) {
+            Ok(entries) => self.entries = entries,
+            Err(e) => {
+                self.entries.clear();
+                self.message = Some(format!("{}: {}", self.current_dir.display(), e));
+            }
+        }
+        let current: HashSet<PathBuf> = self
+            .entries
+            .iter()
+            .map(|e| self.current_dir.join(&e.name))
+            .collect();
+        self.selected.retain(|p| current.contains(p));
+        let cursor = self.table.selected().unwrap_or(0);
+        self.table
+            .select((!self.entries.is_empty()).then(|| cursor.min(self.entries.len() - 1)));
+    }
+
+    fn navigate(&mut self, dir: PathBuf) {
+        self.current_dir = dir;
+        self.selected.clear();
+        self.table.select(Some(0));
+        self.read_dir();
+    }
+
+    fn cursor_path(&self) -> Option<PathBuf> {
+        let entry = self.entries.get(self.table.selected()?)?;
+        Some(self.current_dir.join(&entry.name))
+    }
+
+    // The selection, or the entry under the cursor when nothing is selected
+    fn targets(&self) -> Vec<PathBuf> {
+        let selected: Vec<PathBuf> = self
+            .entries
+            .iter()
+            .map(|e| self.current_dir.join(&e.name))
+            .filter(|p| self.selected.contains(p))
+            .collect();
+        if selected.is_empty() {
+            self.cursor_path().into_iter().collect()
+        } else {
+            selected
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        if let Some(prompt) = &mut self.prompt {
+            match (prompt, key.code) {
+                (_, KeyCode::Esc) => self.prompt = None,
+                (Prompt::SelectPattern(pattern), KeyCode::Char(c)) => pattern.push(c),
+                (Prompt::SelectPattern(pattern), KeyCode::Backspace) => {
+                    pattern.pop();
+                }
+                (Prompt::SelectPattern(pattern), KeyCode::Enter) => {
+                    let pattern = std::mem::take(pattern);
+                    self.prompt = None;
+                    self.select_matching(&pattern);
+                }
+                (Prompt::ConfirmTrash(targets), KeyCode::Char('y' | 'Y') | KeyCode::Enter) => {
+                    let targets = std::mem::take(targets);
+                    self.prompt = None;
+                    self.trash(targets);
+                }
+                (Prompt::ConfirmDelete(targets), KeyCode::Char('y' | 'Y') | KeyCode::Enter) => {
+                    let targets = std::mem::take(targets);
+                    self.prompt = None;
+                    self.delete(targets);
+                }
+                (Prompt::ConfirmTrash(_) | Prompt::ConfirmDelete(_), _) => self.prompt = None,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        let cursor = self.table.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Char('q') if !ctrl => self.quit = true,
+            KeyCode::Up | KeyCode::Char('k') => self.table.select(Some(cursor.saturating_sub(1))),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.table
+                    .select(Some((cursor + 1).min(self.entries.len().saturating_sub(1))));
+            }
+            KeyCode::Home => self.table.select(Some(0)),
+            KeyCode::End => self
+                .table
+                .select(Some(self.entries.len().saturating_sub(1))),
+            KeyCode::Backspace | KeyCode::Left => {
+                if let Some(parent) = self.current_dir.parent() {
+                    self.navigate(parent.to_path_buf());
+                }
+            }
+            KeyCode::Enter | KeyCode::Right => self.open_cursor(terminal)?,
+            KeyCode::Char(' ') => {
+                if let Some(path) = self.cursor_path() {
+                    if !self.selected.remove(&path) {
+                        self.selected.insert(path);
+                    }
+                    self.table
+                        .select(Some((cursor + 1).min(self.entries.len().saturating_sub(1))));
+                }
+            }
+            KeyCode::Esc => self.selected.clear(),
+            KeyCode::Char('a') if ctrl => {
+                self.selected = self
+                    .entries
+                    .iter()
+                    .map(|e| self.current_dir.join(&e.name))
+                    .collect();
+            }
+            // Ctrl+Shift+I like the window where the terminal reports it
+            KeyCode::Char('*') => self.invert_selection(),
+            KeyCode::Char('i' | 'I') if ctrl && shift => self.invert_selection(),
+            KeyCode::Char('s') if ctrl => self.prompt = Some(Prompt::SelectPattern(String::new())),
+            KeyCode::Char('d') if ctrl => self.toggle_bookmark(),
+            KeyCode::F(5) => self.read_dir(),
+            KeyCode::Char('r') if ctrl => self.read_dir(),
+            KeyCode::Char('c') if ctrl => self.copy_selection(false),
+            KeyCode::Char('x' | 'v') if ctrl && self.read_only => self.refuse_change(),
+            KeyCode::Delete if self.read_only => self.refuse_change(),
+            KeyCode::Char('x') if ctrl => self.copy_selection(true),
+            KeyCode::Char('v') if ctrl => self.paste_files(),
+            // Shift+Delete skips the trash, and always asks first since a
+            // terminal has no undo
+            KeyCode::Delete => {
+                let targets = self.targets();
+                let can_trash = trash::supported() && !trash::contains(&self.current_dir);
+                if targets.is_empty() {
+                    return Ok(());
+                }
+                if shift || !can_trash {
+                    self.prompt = Some(Prompt::ConfirmDelete(targets));
+                } else if self.config.confirmations.trash {
+                    self.prompt = Some(Prompt::ConfirmTrash(targets));
+                } else {
+                    self.trash(targets);
+                }
+            }
+            KeyCode::Char('b') => {
+                // Cycle through the bookmarks
+                let bookmarks = &self.config.bookmarks;
+                let next = bookmarks
+                    .iter()
+                    .position(|b| *b == self.current_dir)
+                    .map_or(0, |i| i + 1);
+                if let Some(dir) = bookmarks.get(next % bookmarks.len().max(1)).cloned() {
+                    self.navigate(dir);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn invert_selection(&mut self) {
+        for entry in &self.entries {
+            let path = self.current_dir.join(&entry.name);
+            if !self.selected.remove(&path) {
+                self.selected.insert(path);
+            }
+        }
+    }
+
+    fn select_matching(&mut self, patterns: &str) {
+        for entry in &self.entries {
+            if search::matches_any(patterns, &entry.name, false) {
+                self.selected.insert(self.current_dir.join(&entry.name));
+            }
+        }
+    }
+
+    fn toggle_bookmark(&mut self) {
+        if let Some(index) = self
+            .config
+            .bookmarks
+            .iter()
+            .position(|b| *b == self.current_dir)
+        {
+            self.config.bookmarks.remove(index);
+            self.message = Some(String::from("Bookmark removed"));
+        } else {
+            self.config.bookmarks.push(self.current_dir.clone());
+            self.message = Some(String::from("Bookmarked"));
+        }
+        self.config.save();
+    }
+
+    fn trash(&mut self, targets: Vec<PathBuf>) {
+        self.jobs.spawn(
+            format!("Moving {} to the trash", ops::plural(targets.len(), "item")),
+            move |job| {
+                history::logged(
+                    history::Operation::Trash,
+                    &targets,
+                    None,
+                    trash::trash(&targets, job),
+                )
+            },
+        );
+    }
+
+    fn delete(&mut self, targets: Vec<PathBuf>) {
+        self.jobs.spawn(
+            format!("Deleting {}", ops::plural(targets.len(), "item")),
+            move |job| {
+                history::logged(
+                    history::Operation::Delete,
+                    &targets,
+                    None,
+                    ops::delete(&targets, job),
+                )
+            },
+        );
+    }
+
+    fn refuse_change(&mut self) {
+        self.message = Some(String::from("Read-only mode"));
+    }
+
+    fn copy_selection(&mut self, cut: bool) {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return;
+        }
+        self.message = Some(format!(
+            "{} {}",
+            if cut { "Cut" } else { "Copied" },
+            ops::plural(targets.len(), "item")
+        ));
+        self.file_clipboard = targets;
+        self.clipboard_cut = cut;
+    }
+
+    fn paste_files(&mut self) {
+        if self.file_clipboard.is_empty() {
+            return;
+        }
+        let transfer = ops::Transfer {
+            sources: self.file_clipboard.clone(),
+            destination: self.current_dir.clone(),
+            mode: if self.clipboard_cut {
+                ops::TransferMode::Move
+            } else {
+                ops::TransferMode::Copy
+            },
+            options: self.config.transfers.clone(),
+            resume: None,
+        };
+        // Cut files are gone from their old place after one paste
+        if self.clipboard_cut {
+            self.file_clipboard.clear();
+            self.clipboard_cut = false;
+        }
+        self.jobs.spawn(transfer.title(), move |job| {
+            history::logged_transfer(&transfer, transfer.run(job))
+        });
+    }
+
+    fn open_cursor(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        let Some(path) = self.cursor_path() else {
+            return Ok(());
+        };
+        if path.is_dir() {
+            self.navigate(path);
+            return Ok(());
+        }
+        // Openers are often terminal programs (less, vim), so hand them the
+        // terminal until they exit
+        ratatui::restore();
+        let result = open(&self.config, &self.current_dir, &path);
+        *terminal = ratatui::init();
+        if let Err(e) = result {
+            self.message = Some(format!("Failed to open file: {}", e));
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let jobs_height = self.jobs.iter().count().min(5) as u16;
+        let [header, list, jobs, status] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(jobs_height + u16::from(jobs_height > 0)),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let bookmarked = if self.config.bookmarks.contains(&self.current_dir) {
+            " ★"
+        } else {
+            ""
+        };
+        frame.render_widget(
+            Line::from(format!("{}{}", self.current_dir.display(), bookmarked)).bold(),
+            header,
+        );
+
+        let rows = self.entries.iter().map(|entry| {
+            let selected = self.selected.contains(&self.current_dir.join(&entry.name));
+            let name = if entry.is_dir {
+                format!("{}/", entry.name)
+            } else {
+                entry.name.clone()
+            };
+            let size = if entry.is_dir {
+                format!("{} items", entry.size)
+            } else {
+                format_file_size_column(entry.size)
+            };
+            let modified = entry
+                .modified
+                .map(|t| {
+                    chrono::DateTime::<chrono::Local>::from(t)
+                        .format("%d/%m/%Y %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            let row = Row::new([
+                Line::from(name),
+                Line::from(size).right_aligned(),
+                Line::from(modified),
+            ]);
+            if selected {
+                row.style(Style::new().add_modifier(Modifier::BOLD).yellow())
+            } else {
+                row
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(20),
+                Constraint::Length(12),
+                Constraint::Length(16),
+            ],
+        )
+        .header(
+            Row::new([
+                Line::from("Name"),
+                Line::from("Size").right_aligned(),
+                Line::from("Modified"),
+            ])
+            .underlined(),
+        )
+        .row_highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(table, list, &mut self.table);
+
+        if jobs_height > 0 {
+            let lines: Vec<Line> = self
+                .jobs
+                .iter()
+                .take(5)
+                .map(|job| {
+                    let status = job.status();
+                    let format = |n| match status.unit {
+                        ProgressUnit::Bytes => format_file_size(n),
+                        ProgressUnit::Items => n.to_string(),
+                    };
+                    let state = match &status.state {
+                        JobState::Running => match status.total {
+                            Some(total) => format!("{} / {}", format(status.done), format(total)),
+                            None => format(status.done),
+                        },
+                        JobState::Finished => String::from("Done"),
+                        JobState::Failed(error) => format!("Failed: {}", error),
+                        JobState::Cancelled => String::from("Cancelled"),
+                    };
+                    Line::from(format!("{}  {}  {}", job.title, state, status.message))
+                })
+                .collect();
+            frame.render_widget(
+                Paragraph::new(lines).block(Block::new().borders(Borders::TOP).title("Jobs")),
+                jobs,
+            );
+        }
+
+        let help = if self.read_only { HELP_READ_ONLY } else { HELP };
+        let status_line = match &self.prompt {
+            Some(Prompt::SelectPattern(pattern)) => format!("Select matching: {}_", pattern),
+            Some(Prompt::ConfirmTrash(targets)) => format!(
+                "Move {} to the trash? (y/n)",
+                ops::plural(targets.len(), "item")
+            ),
+            Some(Prompt::ConfirmDelete(targets)) => format!(
+                "Delete {} for good? (y/n)",
+                ops::plural(targets.len(), "item")
+            ),
+            None => match &self.message {
+                Some(message) => message.clone(),
+                None if !self.selected.is_empty() => {
+                    format!("{} selected · {}", self.selected.len(), help)
+                }
+                None => help.to_string(),
+            },
+        };
+        frame.render_widget(Line::from(status_line).dim(), status);
+    }
+}
+
+// Runs the opener from the settings for the file's extension, or the
+// system's default application, and waits for it
+fn open(config: &Config, current_dir: &Path, path: &Path) -> io::Result<()> {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+    match extension.and_then(|e| config.openers.get(&e)) {
+        Some(command) => {
+            let targets = [path.to_path_buf()];
+            let command_line = actions::expand(command, Some(path), &targets, current_dir);
+            actions::shell_command(&command_line)
+                .current_dir(current_dir)
+                .status()
+                .map(|_| ())
+        }
+        None => launch::open_with_system(path.as_os_str()),
+    }
+}
@@ -0,0 +1,349 @@
+// Mounting, unmounting, ejecting and unlocking drives through udisks2, the
+// same D-Bus service desktop file managers use, so no root is needed.
+// Devices are listed with lsblk and changed over the system bus. Changes wait
+// for the daemon, and for polkit when it asks for a password, so they're made
+// off the UI thread.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[derive(Clone, PartialEq)]
+pub struct BlockDevice {
+    pub path: String,
+    name: String,
+    // Kernel name of the device this one sits on (disk for a partition,
+    // partition for an unlocked LUKS volume)
+    parent: String,
+    // lsblk's TYPE: disk, part, crypt, loop, ...
+    kind: String,
+    pub label: String,
+    pub fs_type: String,
+    pub size: String,
+    pub mount_point: Option<PathBuf>,
+    // The whole disk can be powered off, e.g. a USB stick
+    pub removable: bool,
+    // A LUKS container that hasn't been unlocked yet
+    pub locked: bool,
+}
+
+impl BlockDevice {
+    pub fn display_name(&self) -> String {
+        if !self.label.is_empty() {
+            return self.label.clone();
+        }
+        match &self.mount_point {
+            Some(mount) if mount.as_os_str() == "/" => String::from("File System"),
+            Some(mount) => mount
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| mount.display().to_string()),
+            None => format!("{} Volume", self.size),
+        }
+    }
+}
+
+// lsblk -P escapes unusual characters as \xHH
+fn unescape(value: &str) -> String {
+    let mut out = Vec::with_capacity(value.len());
+    let bytes = value.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b'x') {
+            if let Some(byte) = value
+                .get(i + 2..i + 4)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                out.push(byte);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// KEY="value" KEY="value" ...
+fn parse_pairs(line: &str) -> HashMap<&str, String> {
+    let mut fields = HashMap::new();
+    let mut rest = line;
+    while let Some((key, value)) = rest.split_once("=\"") {
+        let Some(end) = value.find('"') else {
+            break;
+        };
+        fields.insert(key.trim(), unescape(&value[..end]));
+        rest = &value[end + 1..];
+    }
+    fields
+}
+
+fn list_all() -> Vec<BlockDevice> {
+    let output = Command::new("lsblk")
+        .args([
+            "-P",
+            "-o",
+            "PATH,NAME,PKNAME,LABEL,FSTYPE,MOUNTPOINT,RM,HOTPLUG,TYPE,SIZE",
+        ])
+        .output();
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<BlockDevice> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            let mut fields = parse_pairs(line);
+            let mut take = |key| fields.remove(key).unwrap_or_default();
+            BlockDevice {
+                path: take("PATH"),
+                name: take("NAME"),
+                parent: take("PKNAME"),
+                kind: take("TYPE"),
+                label: take("LABEL"),
+                size: take("SIZE"),
+                removable: take("RM") == "1" || take("HOTPLUG") == "1",
+                locked: false,
+                fs_type: take("FSTYPE"),
+                mount_point: Some(take("MOUNTPOINT"))
+                    .filter(|m| !m.is_empty() && m != "[SWAP]")
+                    .map(PathBuf::from),
+            }
+        })
+        .collect();
+
+    // Removability is reported on the disk, and a LUKS container is
+    // unlocked once something sits on top of it
+    let parents = parents(&devices);
+    let removable_disks: Vec<String> = devices
+        .iter()
+        .filter(|d| d.removable && d.parent.is_empty())
+        .map(|d| d.name.clone())
+        .collect();
+    let has_children: Vec<String> = devices.iter().map(|d| d.parent.clone()).collect();
+    for device in &mut devices {
+        device.removable = removable_disks.contains(&disk_of(&device.name, &parents));
+        device.locked = device.fs_type == "crypto_LUKS" && !has_children.contains(&device.name);
+    }
+    devices
+}
+
+// Every device holding a filesystem or a locked encrypted volume. Loop
+// devices only show up when mounted as user media (e.g. an attached disk
+// image).
+pub fn block_devices() -> Vec<BlockDevice> {
+    list_all()
+        .into_iter()
+        .filter(|d| {
+            let user_media = d
+                .mount_point
+                .as_ref()
+                .is_some_and(|m| m.starts_with("/media") || m.starts_with("/run/media"));
+            let unlocked_container = d.fs_type == "crypto_LUKS" && !d.locked;
+            !d.fs_type.is_empty()
+                && d.fs_type != "swap"
+                && !unlocked_container
+                && (d.kind != "loop" || user_media)
+        })
+        .collect()
+}
+
+fn parents(devices: &[BlockDevice]) -> HashMap<String, String> {
+    devices
+        .iter()
+        .map(|d| (d.name.clone(), d.parent.clone()))
+        .collect()
+}
+
+// Follows parents up to the whole disk: dm-0 → sdb1 → sdb
+fn disk_of(name: &str, parents: &HashMap<String, String>) -> String {
+    let mut current = name.to_string();
+    while let Some(parent) = parents.get(&current).filter(|p| !p.is_empty()) {
+        current = parent.clone();
+    }
+    current
+}
+
+#[cfg(target_os = "linux")]
+struct UDisks(zbus::blocking::Connection);
+
+#[cfg(target_os = "linux")]
+impl UDisks {
+    fn connect() -> Result<Self, String> {
+        zbus::blocking::Connection::system()
+            .map(Self)
+            .map_err(|e| format!("No system bus: {}", e))
+    }
+
+    fn call<B>(
+        &self,
+        object: &str,
+        interface: &str,
+        method: &str,
+        body: &B,
+    ) -> Result<zbus::Message, String>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        let interface = format!("org.freedesktop.UDisks2.{}", interface);
+        self.0
+            .call_method(
+                Some("org.freedesktop.UDisks2"),
+                object,
+                Some(interface.as_str()),
+                method,
+                body,
+            )
+            .map_err(|e| match e {
+                // udisks says what went wrong in the detail, e.g. "target is busy"
+                zbus::Error::MethodError(_, Some(detail), _) => detail,
+                e => e.to_string(),
+            })
+    }
+
+    // "/dev/sdb1" → /org/freedesktop/UDisks2/block_devices/sdb1
+    fn object(&self, device: &str) -> Result<String, String> {
+        use zbus::zvariant::{OwnedObjectPath, Value};
+        let spec = HashMap::from([("path", Value::from(device))]);
+        let reply = self.call(
+            "/org/freedesktop/UDisks2/Manager",
+            "Manager",
+            "ResolveDevice",
+            &(spec, options()),
+        )?;
+        let objects: Vec<OwnedObjectPath> =
+            reply.body().deserialize().map_err(|e| e.to_string())?;
+        objects
+            .first()
+            .map(|o| o.to_string())
+            .ok_or_else(|| format!("{} isn't known to udisks", device))
+    }
+
+    fn mount(&self, object: &str) -> Result<PathBuf, String> {
+        let reply = self.call(object, "Filesystem", "Mount", &(options(),))?;
+        let mount: String = reply.body().deserialize().map_err(|e| e.to_string())?;
+        Ok(PathBuf::from(mount))
+    }
+
+    fn unmount(&self, object: &str) -> Result<(), String> {
+        self.call(object, "Filesystem", "Unmount", &(options(),))
+            .map(|_| ())
+    }
+
+    fn lock(&self, object: &str) -> Result<(), String> {
+        self.call(object, "Encrypted", "Lock", &(options(),))
+            .map(|_| ())
+    }
+
+    // The drive a block device is on, and powering that off
+    fn power_off(&self, object: &str) -> Result<(), String> {
+        use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+        let reply = self
+            .0
+            .call_method(
+                Some("org.freedesktop.UDisks2"),
+                object,
+                Some("org.freedesktop.DBus.Properties"),
+                "Get",
+                &("org.freedesktop.UDisks2.Block", "Drive"),
+            )
+            .map_err(|e| e.to_string())?;
+        let drive: OwnedValue = reply.body().deserialize().map_err(|e| e.to_string())?;
+        let drive = OwnedObjectPath::try_from(drive).map_err(|e| e.to_string())?;
+        if drive.as_str() == "/" {
+            return Err(String::from("Not on a drive that can be powered off"));
+        }
+        self.call(drive.as_str(), "Drive", "PowerOff", &(options(),))
+            .map(|_| ())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn options() -> HashMap<&'static str, zbus::zvariant::Value<'static>> {
+    HashMap::new()
+}
+
+// Where the filesystem ended up, e.g. /media/me/STICK
+#[cfg(target_os = "linux")]
+pub fn mount(device: &str) -> Result<PathBuf, String> {
+    let udisks = UDisks::connect()?;
+    udisks.mount(&udisks.object(device)?)
+}
+
+#[cfg(target_os = "linux")]
+pub fn unmount(device: &str) -> Result<(), String> {
+    let udisks = UDisks::connect()?;
+    udisks.unmount(&udisks.object(device)?)
+}
+
+// Unlocks a LUKS volume and mounts the filesystem inside. The passphrase goes
+// to the daemon in the call, never on a command line.
+#[cfg(target_os = "linux")]
+pub fn unlock(device: &str, passphrase: &str) -> Result<PathBuf, String> {
+    use zbus::zvariant::OwnedObjectPath;
+    let udisks = UDisks::connect()?;
+    let reply = udisks.call(
+        &udisks.object(device)?,
+        "Encrypted",
+        "Unlock",
+        &(passphrase, options()),
+    )?;
+    let cleartext: OwnedObjectPath = reply.body().deserialize().map_err(|e| e.to_string())?;
+    udisks.mount(cleartext.as_str())
+}
+
+// Unmounts and locks everything on the disk holding `device`, then powers
+// the disk off so it can be unplugged
+#[cfg(target_os = "linux")]
+pub fn eject(device: &BlockDevice) -> Result<(), String> {
+    let udisks = UDisks::connect()?;
+    let all = list_all();
+    let parents = parents(&all);
+    let disk = disk_of(&device.name, &parents);
+    let depth = |d: &BlockDevice| {
+        let mut depth = 0;
+        let mut current = d.name.as_str();
+        while let Some(parent) = parents.get(current).filter(|p| !p.is_empty()) {
+            depth += 1;
+            current = parent;
+        }
+        depth
+    };
+
+    // Innermost first: a filesystem inside a LUKS volume has to be unmounted
+    // before the volume can be locked
+    let mut on_disk: Vec<&BlockDevice> = all
+        .iter()
+        .filter(|d| disk_of(&d.name, &parents) == disk)
+        .collect();
+    on_disk.sort_by_key(|d| std::cmp::Reverse(depth(d)));
+    for d in on_disk {
+        if d.mount_point.is_some() {
+            udisks.unmount(&udisks.object(&d.path)?)?;
+        }
+        if d.fs_type == "crypto_LUKS" && !d.locked {
+            udisks.lock(&udisks.object(&d.path)?)?;
+        }
+    }
+    udisks.power_off(&udisks.object(&format!("/dev/{}", disk))?)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn mount(_device: &str) -> Result<PathBuf, String> {
+    Err(String::from("Drives can only be mounted on Linux"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn unmount(_device: &str) -> Result<(), String> {
+    Err(String::from("Drives can only be unmounted on Linux"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn unlock(_device: &str, _passphrase: &str) -> Result<PathBuf, String> {
+    Err(String::from("Volumes can only be unlocked on Linux"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn eject(_device: &BlockDevice) -> Result<(), String> {
+    Err(String::from("Drives can only be removed safely on Linux"))
+}
@@ -0,0 +1,139 @@
+use std::path::{Path, PathBuf};
+
+// Characters that can appear unescaped in the path part of a file:// URI
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'/')
+}
+
+pub fn to_file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    // Windows paths need a leading slash and forward separators: file:///C:/Users
+    #[cfg(target_os = "windows")]
+    let path = format!("/{}", path.replace('\\', "/"));
+
+    let mut uri = String::from("file://");
+    for byte in path.bytes() {
+        if is_unreserved(byte) || (cfg!(target_os = "windows") && byte == b':') {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+// Decodes %XX escapes; malformed escapes are kept as they are
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(value) = u8::from_str_radix(hex, 16) {
+                out.push(value);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+pub fn percent_encode_path(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        if is_unreserved(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+// A local path from a file:// URI; None for other schemes and remote hosts
+// (which on Windows are shares: file://server/share is \\server\share)
+pub fn file_uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = strip_scheme(uri, "file")?;
+    let path = match rest.strip_prefix("//") {
+        Some(authority) => {
+            let (host, path) = authority.split_at(authority.find('/').unwrap_or(authority.len()));
+            if !host.is_empty() && !host.eq_ignore_ascii_case("localhost") {
+                if cfg!(windows) {
+                    return Some(PathBuf::from(format!(
+                        r"\\{}{}",
+                        host,
+                        percent_decode(path).replace('/', r"\")
+                    )));
+                }
+                return None;
+            }
+            path
+        }
+        None => rest,
+    };
+    let path = percent_decode(path);
+    // file:///C:/Users is C:\Users
+    if cfg!(windows) {
+        let drive = path
+            .strip_prefix('/')
+            .filter(|p| p.as_bytes().get(1) == Some(&b':'));
+        return Some(PathBuf::from(drive.unwrap_or(&path).replace('/', r"\")));
+    }
+    Some(PathBuf::from(path))
+}
+
+// The rest of `text` after `scheme:`, ignoring case
+fn strip_scheme<'a>(text: &'a str, scheme: &str) -> Option<&'a str> {
+    let (found, rest) = text.split_once(':')?;
+    found.eq_ignore_ascii_case(scheme).then_some(rest)
+}
+
+// Where a location typed or pasted into the path bar leads
+#[derive(Debug, PartialEq)]
+pub enum Address {
+    Path(PathBuf),
+    // Relative to the trash's files folder
+    Trash(PathBuf),
+    // Recently used files
+    Recent,
+}
+
+pub fn parse_address(text: &str) -> Result<Address, String> {
+    let text = text.trim();
+    if strip_scheme(text, "file").is_some() {
+        return file_uri_to_path(text)
+            .map(Address::Path)
+            .ok_or_else(|| String::from("Only files on this computer can be opened"));
+    }
+    if let Some(rest) = strip_scheme(text, "trash") {
+        return Ok(Address::Trash(PathBuf::from(percent_decode(
+            rest.trim_start_matches('/'),
+        ))));
+    }
+    if strip_scheme(text, "recent").is_some() {
+        return Ok(Address::Recent);
+    }
+    // Other schemes; a single letter is a Windows drive
+    if let Some((scheme, _)) = text.split_once("://") {
+        if scheme.len() > 1
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '+')
+        {
+            return Err(format!("{}:// locations aren't supported", scheme));
+        }
+    }
+    Ok(Address::Path(PathBuf::from(text)))
+}
+
+// How the path bar shows a location inside the trash
+pub fn trash_address(relative: &Path) -> String {
+    format!(
+        "trash:///{}",
+        percent_encode_path(&relative.to_string_lossy().replace('\\', "/"))
+    )
+}
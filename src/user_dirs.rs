@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+
+pub fn home_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let home = std::env::var_os("USERPROFILE");
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var_os("HOME");
+
+    home.filter(|h| !h.is_empty()).map(PathBuf::from)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn config_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|h| h.join(".config")))
+}
+
+// Per-user directory holding config.toml and other settings files
+pub fn config_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let base = std::env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(target_os = "macos")]
+    let base = home_dir().map(|h| h.join("Library/Application Support"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base = config_home();
+
+    base.map(|b| b.join("file_manager"))
+}
+
+// $XDG_DATA_HOME, shared with other applications (the trash lives there)
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn data_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|h| h.join(".local/share")))
+}
+
+// Per-user directory for files the app writes for itself, such as logs
+pub fn data_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let base = std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+    #[cfg(target_os = "macos")]
+    let base = home_dir().map(|h| h.join("Library/Application Support"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base = data_home();
+
+    base.map(|b| b.join("file_manager"))
+}
+
+// Per-user directory for files that can be regenerated, such as thumbnails
+pub fn cache_dir() -> Option<PathBuf> {
+    // Windows has no separate cache location, so it goes inside the data dir
+    #[cfg(target_os = "windows")]
+    return data_dir().map(|d| d.join("cache"));
+    #[cfg(target_os = "macos")]
+    let base = home_dir().map(|h| h.join("Library/Caches"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|h| h.join(".cache")));
+
+    #[cfg(not(target_os = "windows"))]
+    base.map(|b| b.join("file_manager"))
+}
+
+// Looks up an entry such as `XDG_TEMPLATES_DIR="$HOME/Templates"` in
+// ~/.config/user-dirs.dirs, as written by xdg-user-dirs-update
+#[cfg(not(target_os = "windows"))]
+fn xdg_user_dir(key: &str) -> Option<PathBuf> {
+    let contents = std::fs::read_to_string(config_home()?.join("user-dirs.dirs")).ok()?;
+    let home = home_dir()?;
+    contents.lines().find_map(|line| {
+        let value = line.trim().strip_prefix(key)?.strip_prefix('=')?;
+        let value = value.trim().trim_matches('"');
+        let path = match value.strip_prefix("$HOME") {
+            Some(rest) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(value),
+        };
+        // A directory set to $HOME itself means "disabled"
+        (path != home).then_some(path)
+    })
+}
+
+// One of the standard folders in the home folder, as moved or renamed in
+// user-dirs.dirs
+#[cfg_attr(target_os = "windows", allow(unused_variables))]
+fn user_dir(key: &str, name: &str) -> Option<PathBuf> {
+    #[cfg(not(target_os = "windows"))]
+    if let Some(dir) = xdg_user_dir(key) {
+        return Some(dir);
+    }
+    home_dir().map(|h| h.join(name))
+}
+
+pub fn desktop_dir() -> Option<PathBuf> {
+    user_dir("XDG_DESKTOP_DIR", "Desktop")
+}
+
+pub fn documents_dir() -> Option<PathBuf> {
+    user_dir("XDG_DOCUMENTS_DIR", "Documents")
+}
+
+pub fn downloads_dir() -> Option<PathBuf> {
+    user_dir("XDG_DOWNLOAD_DIR", "Downloads")
+}
+
+pub fn templates_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var_os("APPDATA").map(|d| PathBuf::from(d).join("Microsoft\\Windows\\Templates"))
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        xdg_user_dir("XDG_TEMPLATES_DIR").or_else(|| home_dir().map(|h| h.join("Templates")))
+    }
+}
@@ -0,0 +1,91 @@
+// Extended attributes the user can see and edit. On Linux those are the
+// `user.*` namespace (the others belong to the kernel and security
+// modules); other Unix systems have no namespaces, so everything is shown.
+
+pub fn supported() -> bool {
+    cfg!(unix) && imp::SUPPORTED
+}
+
+// Whether the user may create an attribute with this name
+pub fn is_user_name(name: &str) -> bool {
+    !cfg!(target_os = "linux") || (name.starts_with("user.") && name.len() > "user.".len())
+}
+
+pub use imp::{list_user, remove, selinux_context, set};
+
+#[cfg(unix)]
+mod imp {
+    use std::io;
+    use std::path::Path;
+
+    pub const SUPPORTED: bool = xattr::SUPPORTED_PLATFORM;
+
+    pub fn list_user(path: &Path) -> io::Result<Vec<(String, Vec<u8>)>> {
+        let mut attributes = Vec::new();
+        for name in xattr::list(path)? {
+            let name = name.to_string_lossy().into_owned();
+            if !super::is_user_name(&name) {
+                continue;
+            }
+            if let Some(value) = xattr::get(path, &name)? {
+                attributes.push((name, value));
+            }
+        }
+        attributes.sort();
+        Ok(attributes)
+    }
+
+    pub fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        xattr::set(path, name, value)
+    }
+
+    pub fn remove(path: &Path, name: &str) -> io::Result<()> {
+        xattr::remove(path, name)
+    }
+
+    pub fn selinux_context(path: &Path) -> Option<String> {
+        let value = xattr::get(path, "security.selinux").ok()??;
+        let value = value.strip_suffix(&[0]).unwrap_or(&value);
+        Some(String::from_utf8_lossy(value).into_owned())
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::io;
+    use std::path::Path;
+
+    pub const SUPPORTED: bool = false;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "extended attributes aren't supported on this system",
+        )
+    }
+
+    pub fn list_user(_path: &Path) -> io::Result<Vec<(String, Vec<u8>)>> {
+        Err(unsupported())
+    }
+
+    pub fn set(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn remove(_path: &Path, _name: &str) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn selinux_context(_path: &Path) -> Option<String> {
+        None
+    }
+}
+
+// Values are edited as text; anything else is shown in hex and kept as is
+// unless the user replaces it
+pub fn display_value(value: &[u8]) -> (String, bool) {
+    match std::str::from_utf8(value) {
+        Ok(text) if !text.contains('\0') => (text.to_string(), false),
+        _ => (crate::checksum::to_hex(value), true),
+    }
+}